@@ -0,0 +1,17 @@
+-- Create budgets table for monthly spending limits per transaction category
+
+CREATE TABLE IF NOT EXISTS budgets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    category TEXT NOT NULL,
+    monthly_amount DECIMAL(12,2) NOT NULL CHECK (monthly_amount >= 0),
+    -- Carry unspent budget into the following month
+    carry_over BOOLEAN NOT NULL DEFAULT FALSE,
+    -- First month that counts towards carry-over
+    starts_on DATE NOT NULL DEFAULT date_trunc('month', now())::date,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, category)
+);
+
+CREATE INDEX IF NOT EXISTS idx_budgets_user ON budgets(user_id);
@@ -0,0 +1,15 @@
+-- Create energy_journal table: one row per user and day with intake and body weight
+-- Expenditure is derived at read time from BMR formulas and completed workout sessions
+
+CREATE TABLE IF NOT EXISTS energy_journal (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    entry_date DATE NOT NULL,
+    intake_kcal DECIMAL(7,1) CHECK (intake_kcal >= 0),
+    body_weight_kg DECIMAL(6,2) CHECK (body_weight_kg > 0),
+    body_fat_percentage DECIMAL(4,1) CHECK (body_fat_percentage > 0 AND body_fat_percentage < 100),
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, entry_date)
+);
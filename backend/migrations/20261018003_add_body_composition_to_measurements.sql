@@ -0,0 +1,12 @@
+-- Circumferences and skinfold sites for body fat estimation (Navy, Jackson-Pollock)
+ALTER TABLE body_measurements
+    ADD COLUMN IF NOT EXISTS neck_cm DECIMAL(5,1),
+    ADD COLUMN IF NOT EXISTS waist_cm DECIMAL(5,1),
+    ADD COLUMN IF NOT EXISTS hip_cm DECIMAL(5,1),
+    ADD COLUMN IF NOT EXISTS skinfold_chest_mm DECIMAL(4,1),
+    ADD COLUMN IF NOT EXISTS skinfold_abdominal_mm DECIMAL(4,1),
+    ADD COLUMN IF NOT EXISTS skinfold_thigh_mm DECIMAL(4,1),
+    ADD COLUMN IF NOT EXISTS skinfold_triceps_mm DECIMAL(4,1),
+    ADD COLUMN IF NOT EXISTS skinfold_suprailiac_mm DECIMAL(4,1),
+    ADD COLUMN IF NOT EXISTS skinfold_subscapular_mm DECIMAL(4,1),
+    ADD COLUMN IF NOT EXISTS skinfold_midaxillary_mm DECIMAL(4,1);
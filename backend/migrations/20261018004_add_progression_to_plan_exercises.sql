@@ -0,0 +1,7 @@
+-- Progression scheme per plan exercise; the fixed targets become the starting prescription
+
+ALTER TABLE training_plan_exercises
+    ADD COLUMN IF NOT EXISTS progression_scheme TEXT NOT NULL DEFAULT 'fixed' CHECK (progression_scheme IN (
+        'fixed', 'linear', 'double_progression', 'rpe', 'percentage_wave'
+    )),
+    ADD COLUMN IF NOT EXISTS progression_config JSONB NOT NULL DEFAULT '{}'::jsonb;
@@ -0,0 +1,23 @@
+-- Prescribed sets of a session started from a plan; checked off by linking the logged set
+
+CREATE TABLE IF NOT EXISTS planned_sets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES workout_sessions(id) ON DELETE CASCADE,
+    plan_exercise_id UUID REFERENCES training_plan_exercises(id) ON DELETE SET NULL,
+    exercise_id UUID NOT NULL REFERENCES exercises(id) ON DELETE CASCADE,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    set_number INTEGER NOT NULL,
+    superset_group INTEGER,
+    target_weight_kg DECIMAL(6,2),
+    target_reps INTEGER NOT NULL,
+    target_rpe DECIMAL(3,1),
+    rest_seconds INTEGER,
+    is_amrap BOOLEAN NOT NULL DEFAULT FALSE,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'completed', 'skipped')),
+    workout_set_id UUID REFERENCES workout_sets(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_planned_sets_session ON planned_sets(session_id, sort_order, set_number);
+CREATE INDEX IF NOT EXISTS idx_planned_sets_workout_set ON planned_sets(workout_set_id);
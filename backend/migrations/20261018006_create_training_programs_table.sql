@@ -0,0 +1,32 @@
+-- Training programs schedule plans across weeks with volume/intensity ramps and a deload week
+
+CREATE TABLE IF NOT EXISTS training_programs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    start_date DATE NOT NULL,
+    weeks INTEGER NOT NULL CHECK (weeks BETWEEN 1 AND 52),
+    deload_week INTEGER CHECK (deload_week BETWEEN 1 AND weeks),
+    volume_ramp_percentage DECIMAL(5,2) NOT NULL DEFAULT 0,
+    intensity_ramp_percentage DECIMAL(5,2) NOT NULL DEFAULT 0,
+    deload_volume_percentage DECIMAL(5,2) NOT NULL DEFAULT 50,
+    deload_intensity_percentage DECIMAL(5,2) NOT NULL DEFAULT 90,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_training_programs_user ON training_programs(user_id, is_active);
+
+-- One row per plan in a weekday's rotation; week N trains rotation entry (N - 1) mod count
+CREATE TABLE IF NOT EXISTS program_schedule (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    program_id UUID NOT NULL REFERENCES training_programs(id) ON DELETE CASCADE,
+    day_of_week INTEGER NOT NULL CHECK (day_of_week BETWEEN 1 AND 7),
+    rotation_index INTEGER NOT NULL DEFAULT 0,
+    plan_id UUID NOT NULL REFERENCES training_plans(id) ON DELETE CASCADE,
+    UNIQUE (program_id, day_of_week, rotation_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_program_schedule_program ON program_schedule(program_id);
@@ -0,0 +1,33 @@
+-- Per-muscle share of each set's energy, split with the 60/30/10 involvement pools at log time
+
+CREATE TABLE IF NOT EXISTS workout_set_muscle_energy (
+    workout_set_id UUID NOT NULL REFERENCES workout_sets(id) ON DELETE CASCADE,
+    muscle_group_id UUID NOT NULL REFERENCES muscle_groups(id) ON DELETE CASCADE,
+    involvement TEXT NOT NULL CHECK (involvement IN ('primary', 'secondary', 'stabilizer')),
+    share_fraction DECIMAL(7,6) NOT NULL,
+    energy_kcal DECIMAL(10,4) NOT NULL,
+    PRIMARY KEY (workout_set_id, muscle_group_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_workout_set_muscle_energy_muscle ON workout_set_muscle_energy(muscle_group_id);
+
+-- Backfill existing sets with the same split as compute::attribute_muscle_energy:
+-- activation-weighted share of the involvement pool, normalised over the pools present
+WITH mapped AS (
+    SELECT wse.id AS set_id, wse.energy_kcal, em.muscle_group_id, em.involvement,
+           CASE em.involvement WHEN 'primary' THEN 0.60 WHEN 'secondary' THEN 0.30 ELSE 0.10 END
+               * em.activation_fraction
+               / NULLIF(SUM(em.activation_fraction) OVER (PARTITION BY wse.id, em.involvement), 0) AS share
+    FROM workout_sets wse
+    JOIN exercise_muscles em ON em.exercise_id = wse.exercise_id
+    WHERE wse.energy_kcal > 0
+), normalised AS (
+    SELECT set_id, energy_kcal, muscle_group_id, involvement,
+           COALESCE(share, 0) / NULLIF(SUM(COALESCE(share, 0)) OVER (PARTITION BY set_id), 0) AS share
+    FROM mapped
+)
+INSERT INTO workout_set_muscle_energy (workout_set_id, muscle_group_id, involvement, share_fraction, energy_kcal)
+SELECT set_id, muscle_group_id, involvement, share, energy_kcal * share
+FROM normalised
+WHERE share IS NOT NULL
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,4 @@
+-- Full-text index for exercise search over name and description
+
+CREATE INDEX IF NOT EXISTS idx_exercises_search ON exercises
+    USING GIN (to_tsvector('english', name || ' ' || coalesce(description, '')));
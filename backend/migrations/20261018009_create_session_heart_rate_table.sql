@@ -0,0 +1,15 @@
+-- Heart-rate stream imported from a wearable file (FIT, TCX or GPX) for a session
+
+CREATE TABLE IF NOT EXISTS session_heart_rate_samples (
+    session_id UUID NOT NULL REFERENCES workout_sessions(id) ON DELETE CASCADE,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    bpm INTEGER NOT NULL CHECK (bpm > 0),
+    PRIMARY KEY (session_id, recorded_at)
+);
+
+ALTER TABLE workout_sessions
+    ADD COLUMN IF NOT EXISTS heart_rate_source TEXT CHECK (heart_rate_source IN ('fit', 'tcx', 'gpx')),
+    ADD COLUMN IF NOT EXISTS heart_rate_energy_kcal DECIMAL(10,2),
+    -- Sex and age behind the energy, so it can be recomputed when the body weight changes
+    ADD COLUMN IF NOT EXISTS heart_rate_sex TEXT CHECK (heart_rate_sex IN ('male', 'female')),
+    ADD COLUMN IF NOT EXISTS heart_rate_age_years DECIMAL(5,2);
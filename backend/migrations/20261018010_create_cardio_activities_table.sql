@@ -0,0 +1,24 @@
+-- Cardio and conditioning activities of a session, alongside the strength sets
+
+CREATE TABLE IF NOT EXISTS cardio_activities (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES workout_sessions(id) ON DELETE CASCADE,
+    activity_type TEXT NOT NULL CHECK (activity_type IN (
+        'run', 'walk', 'cycle', 'row', 'swim', 'elliptical', 'ski_erg', 'stair_climb', 'jump_rope', 'other'
+    )),
+    duration_s INTEGER NOT NULL CHECK (duration_s > 0),
+    distance_m DECIMAL(10,2) CHECK (distance_m >= 0),
+    avg_power_w DECIMAL(6,1),
+    avg_heart_rate INTEGER,
+    max_heart_rate INTEGER,
+    avg_heart_rate_zone SMALLINT CHECK (avg_heart_rate_zone BETWEEN 1 AND 5),
+    heart_rate_zone_seconds INTEGER[],
+    energy_method TEXT NOT NULL CHECK (energy_method IN ('met', 'power')),
+    met_value DECIMAL(4,1),
+    energy_kcal DECIMAL(8,2) NOT NULL,
+    notes TEXT,
+    performed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_cardio_activities_session ON cardio_activities(session_id, performed_at);
@@ -0,0 +1,18 @@
+-- Per-user gym equipment: the bars available and the plate inventory, for the plate calculator
+
+CREATE TABLE IF NOT EXISTS equipment_profiles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- [{"name": "Women's bar", "weight": 15}], weights in the profile's unit
+    bars JSONB NOT NULL,
+    -- [{"weight": 1.25, "pairs": 2}]; a null pairs count means unlimited
+    plates JSONB NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, name)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_equipment_profiles_default
+    ON equipment_profiles(user_id) WHERE is_default;
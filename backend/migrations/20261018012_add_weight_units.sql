@@ -0,0 +1,19 @@
+-- Per-user weight unit. Weights stay canonical in kg; the value and unit as entered are
+-- kept next to them so pound entries read back without rounding drift.
+
+CREATE TABLE IF NOT EXISTS training_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    weight_unit TEXT NOT NULL DEFAULT 'kg' CHECK (weight_unit IN ('kg', 'lb')),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+ALTER TABLE workout_sets
+    ADD COLUMN IF NOT EXISTS weight_input DECIMAL(8,3),
+    ADD COLUMN IF NOT EXISTS weight_unit TEXT CHECK (weight_unit IN ('kg', 'lb'));
+
+ALTER TABLE body_measurements
+    ADD COLUMN IF NOT EXISTS body_weight_input DECIMAL(8,3),
+    ADD COLUMN IF NOT EXISTS body_weight_unit TEXT CHECK (body_weight_unit IN ('kg', 'lb'));
+
+ALTER TABLE equipment_profiles
+    ADD COLUMN IF NOT EXISTS weight_unit TEXT NOT NULL DEFAULT 'kg' CHECK (weight_unit IN ('kg', 'lb'));
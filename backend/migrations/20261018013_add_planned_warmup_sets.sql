@@ -0,0 +1,6 @@
+-- Generated warm-up sets are planned ahead of the working sets with their plate loadout,
+-- stored as {"unit": "lb", "plates": [{"weight": 45, "weightKg": 20.41, "count": 1}]}
+
+ALTER TABLE planned_sets
+    ADD COLUMN IF NOT EXISTS is_warmup BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS plate_loadout JSONB;
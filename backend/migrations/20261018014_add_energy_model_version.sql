@@ -0,0 +1,22 @@
+-- Energy model version each set's energy was computed with, and an audit of recomputations.
+-- Sets logged so far were computed with version 1.
+
+ALTER TABLE workout_sets
+    ADD COLUMN IF NOT EXISTS energy_model_version INTEGER NOT NULL DEFAULT 1,
+    ADD COLUMN IF NOT EXISTS energy_recomputed_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS energy_recomputations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    range_from TIMESTAMPTZ,
+    range_to TIMESTAMPTZ,
+    energy_model_version INTEGER NOT NULL,
+    sets_recomputed INTEGER NOT NULL,
+    sets_changed INTEGER NOT NULL,
+    sessions_changed INTEGER NOT NULL,
+    previous_energy_kcal DECIMAL(12,2) NOT NULL,
+    energy_kcal DECIMAL(12,2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_energy_recomputations_user ON energy_recomputations(user_id, created_at DESC);
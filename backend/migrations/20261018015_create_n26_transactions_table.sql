@@ -0,0 +1,22 @@
+-- Imported N26 transactions kept per user so the ledger can be exported later
+
+CREATE TABLE IF NOT EXISTS n26_transactions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Date exactly as exported by N26; booked_on is its day for range filters
+    source_date TEXT NOT NULL,
+    booked_on DATE,
+    -- Floating point so exports reproduce the imported amounts bit for bit
+    amount DOUBLE PRECISION NOT NULL,
+    currency TEXT NOT NULL,
+    original_amount DOUBLE PRECISION,
+    original_currency TEXT,
+    category TEXT NOT NULL,
+    comment TEXT NOT NULL,
+    -- Identifies an entry across re-imports of overlapping exports
+    import_key TEXT NOT NULL,
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, import_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_n26_transactions_user_date ON n26_transactions(user_id, booked_on);
@@ -0,0 +1,290 @@
+use crate::api::n26_analyzer::{strict_import_rejected, with_import_report, ImportOptions};
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::n26_analyzer::budgets::{compare_budgets, month_start, parse_month, Budget};
+use crate::tools::n26_analyzer::{
+    parse_n26_json_detailed, parse_transaction_date, N26Data, KNOWN_DATA_KEYS,
+};
+use axum::extract::{Extension, Json, Path, Query};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetRequest {
+    /// N26 data key the transactions come from, e.g. `cardTransactions`
+    pub category: String,
+    pub monthly_amount: f64,
+    pub carry_over: Option<bool>,
+    /// `YYYY-MM` or `YYYY-MM-DD`; stored as the first day of that month. Required on
+    /// create; an update without it keeps the current month.
+    pub starts_on: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareBudgetsRequest {
+    pub data: N26Data,
+    /// `YYYY-MM`; defaults to the month of the latest transaction
+    pub month: Option<String>,
+    /// Day the forecast is made on; defaults to the latest transaction date
+    pub as_of: Option<String>,
+}
+
+/// Largest amount `monthly_amount DECIMAL(12,2)` holds
+const MAX_MONTHLY_AMOUNT: f64 = 9_999_999_999.99;
+
+fn parse_starts_on(raw: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+        .ok()
+        .or_else(|| parse_month(raw))
+        .map(month_start)
+}
+
+fn validate(req: &BudgetRequest, require_starts_on: bool) -> Result<Option<NaiveDate>, String> {
+    if !KNOWN_DATA_KEYS.contains(&req.category.trim()) {
+        return Err(format!("category must be one of {}", KNOWN_DATA_KEYS.join(", ")));
+    }
+    if !req.monthly_amount.is_finite() || req.monthly_amount < 0.0 {
+        return Err("monthlyAmount must be zero or positive".to_string());
+    }
+    if req.monthly_amount > MAX_MONTHLY_AMOUNT {
+        return Err(format!("monthlyAmount must be at most {MAX_MONTHLY_AMOUNT}"));
+    }
+    match req.starts_on.as_deref() {
+        None if require_starts_on => Err("startsOn is required".to_string()),
+        None => Ok(None),
+        Some(raw) => parse_starts_on(raw)
+            .map(Some)
+            .ok_or_else(|| "startsOn must be YYYY-MM or YYYY-MM-DD".to_string()),
+    }
+}
+
+fn budget_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    json!({
+        "id": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
+        "category": row.try_get::<String, _>("category").unwrap_or_default(),
+        "monthlyAmount": row.try_get::<sqlx::types::BigDecimal, _>("monthly_amount").ok().map(|d| d.to_string()),
+        "carryOver": row.try_get::<bool, _>("carry_over").unwrap_or(false),
+        "startsOn": row.try_get::<NaiveDate, _>("starts_on").ok().map(|d| d.to_string()),
+    })
+}
+
+pub async fn list_budgets(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> impl IntoResponse {
+    match sqlx::query(
+        "SELECT id, category, monthly_amount, carry_over, starts_on
+         FROM budgets WHERE user_id = $1 ORDER BY category",
+    )
+    .bind(user.id)
+    .fetch_all(&*pool)
+    .await
+    {
+        Ok(rows) => {
+            let budgets: Vec<serde_json::Value> = rows.iter().map(budget_json).collect();
+            (StatusCode::OK, Json(json!({"budgets": budgets}))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("list_budgets failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn create_budget(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Json(req): Json<BudgetRequest>,
+) -> impl IntoResponse {
+    let starts_on = match validate(&req, true) {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+    match sqlx::query(
+        "INSERT INTO budgets (user_id, category, monthly_amount, carry_over, starts_on)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id",
+    )
+    .bind(user.id)
+    .bind(req.category.trim())
+    .bind(req.monthly_amount)
+    .bind(req.carry_over.unwrap_or(false))
+    .bind(starts_on)
+    .fetch_one(&*pool)
+    .await
+    {
+        Ok(row) => {
+            let id: Uuid = row.try_get("id").unwrap_or_default();
+            (StatusCode::CREATED, Json(json!({"id": id.to_string()}))).into_response()
+        }
+        Err(sqlx::Error::Database(db)) if db.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "budget for this category already exists"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("create_budget failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn update_budget(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+    Json(req): Json<BudgetRequest>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    let starts_on = match validate(&req, false) {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+    match sqlx::query(
+        "UPDATE budgets SET category = $1, monthly_amount = $2, carry_over = COALESCE($3, carry_over),
+                starts_on = COALESCE($4, starts_on), updated_at = now()
+         WHERE id = $5 AND user_id = $6",
+    )
+    .bind(req.category.trim())
+    .bind(req.monthly_amount)
+    .bind(req.carry_over)
+    .bind(starts_on)
+    .bind(uuid)
+    .bind(user.id)
+    .execute(&*pool)
+    .await
+    {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))).into_response()
+            } else {
+                (StatusCode::OK, Json(json!({"ok": true}))).into_response()
+            }
+        }
+        Err(sqlx::Error::Database(db)) if db.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "budget for this category already exists"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("update_budget failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn delete_budget(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    match sqlx::query("DELETE FROM budgets WHERE id = $1 AND user_id = $2")
+        .bind(uuid)
+        .bind(user.id)
+        .execute(&*pool)
+        .await
+    {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))).into_response()
+            } else {
+                (StatusCode::OK, Json(json!({"ok": true}))).into_response()
+            }
+        }
+        Err(e) => {
+            tracing::error!("delete_budget failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Compare the analyzer's transactions against the user's stored budgets
+pub async fn compare(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Query(import): Query<ImportOptions>,
+    Json(req): Json<CompareBudgetsRequest>,
+) -> impl IntoResponse {
+    let (transactions, import_report) = match parse_n26_json_detailed(req.data, import.strict) {
+        Ok(parsed) => parsed,
+        Err(report) => return strict_import_rejected(report),
+    };
+    let latest = transactions.iter().filter_map(|tx| parse_transaction_date(&tx.date)).max();
+
+    let month = match req.month.as_deref() {
+        Some(raw) => match parse_month(raw) {
+            Some(m) => m,
+            None => {
+                return (StatusCode::BAD_REQUEST, Json(json!({"error": "month must be YYYY-MM"})))
+                    .into_response()
+            }
+        },
+        None => {
+            match latest {
+                Some(d) => month_start(d),
+                None => return (
+                    StatusCode::BAD_REQUEST,
+                    Json(
+                        json!({"error": "month is required when there are no dated transactions"}),
+                    ),
+                )
+                    .into_response(),
+            }
+        }
+    };
+    let as_of = match req.as_of.as_deref() {
+        Some(raw) => match parse_transaction_date(raw) {
+            Some(d) => d,
+            None => {
+                return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid asOf date"})))
+                    .into_response()
+            }
+        },
+        None => latest.unwrap_or(month),
+    };
+
+    let budgets = match sqlx::query(
+        "SELECT category, monthly_amount::float8 AS monthly_amount, carry_over, starts_on
+         FROM budgets WHERE user_id = $1 ORDER BY category",
+    )
+    .bind(user.id)
+    .fetch_all(&*pool)
+    .await
+    {
+        Ok(rows) => rows
+            .iter()
+            .map(|row| Budget {
+                category: row.try_get("category").unwrap_or_default(),
+                monthly_amount: row.try_get("monthly_amount").unwrap_or(0.0),
+                carry_over: row.try_get("carry_over").unwrap_or(false),
+                starts_on: row.try_get("starts_on").unwrap_or(month),
+            })
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            tracing::error!("compare budgets failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+
+    let report = compare_budgets(&budgets, &transactions, month, as_of);
+    (StatusCode::OK, Json(with_import_report(report, &import_report))).into_response()
+}
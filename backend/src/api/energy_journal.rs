@@ -0,0 +1,383 @@
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::energy_journal::{summarize_journal, BmrFormula, ExpenditureParams, JournalDay};
+use crate::tools::metabolism::{ActivityLevel, Sex};
+use crate::tools::tdee::{estimate_tdee, Smoothing, TdeeOptions};
+use axum::extract::{Extension, Json, Path, Query};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Window used when `from` is not given
+const DEFAULT_WINDOW_DAYS: i64 = 28;
+
+/// History loaded for TDEE estimation when `from` is not given
+const DEFAULT_TDEE_HISTORY_DAYS: i64 = 90;
+
+/// `intake_kcal` is DECIMAL(7,1); no day's intake comes near this
+const MAX_INTAKE_KCAL: f64 = 50_000.0;
+/// `body_weight_kg` is DECIMAL(6,2)
+const MAX_BODY_WEIGHT_KG: f64 = 1_000.0;
+/// `body_fat_percentage` is DECIMAL(4,1) below 100, so 99.96 would round up to 100.0
+const MAX_BODY_FAT_PERCENTAGE: f64 = 99.9;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntryRequest {
+    pub date: NaiveDate,
+    pub intake_kcal: Option<f64>,
+    pub body_weight_kg: Option<f64>,
+    pub body_fat_percentage: Option<f64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JournalRange {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SummaryParams {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub sex: Sex,
+    pub age_years: f64,
+    /// Falls back to the latest body measurement
+    pub height_cm: Option<f64>,
+    pub activity_level: Option<ActivityLevel>,
+    /// Defaults to Katch-McArdle when a body fat percentage was logged, else Mifflin-St Jeor
+    pub formula: Option<BmrFormula>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TdeeParams {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub smoothing: Option<Smoothing>,
+    pub window_days: Option<usize>,
+    pub target_weekly_change_kg: Option<f64>,
+}
+
+impl JournalRange {
+    fn resolve(&self) -> (NaiveDate, NaiveDate) {
+        let to = self.to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+        let from = self.from.unwrap_or(to - chrono::Duration::days(DEFAULT_WINDOW_DAYS - 1));
+        (from, to)
+    }
+}
+
+fn validate(req: &JournalEntryRequest) -> Result<(), String> {
+    if req.intake_kcal.is_none()
+        && req.body_weight_kg.is_none()
+        && req.body_fat_percentage.is_none()
+    {
+        return Err("log at least one of intakeKcal, bodyWeightKg or bodyFatPercentage".to_string());
+    }
+    if req.intake_kcal.is_some_and(|v| !(0.0..=MAX_INTAKE_KCAL).contains(&v)) {
+        return Err(format!("intakeKcal must be between 0 and {MAX_INTAKE_KCAL}"));
+    }
+    if req.body_weight_kg.is_some_and(|v| !(v > 0.0 && v <= MAX_BODY_WEIGHT_KG)) {
+        return Err(format!("bodyWeightKg must be positive and at most {MAX_BODY_WEIGHT_KG}"));
+    }
+    if req.body_fat_percentage.is_some_and(|v| !(v > 0.0 && v <= MAX_BODY_FAT_PERCENTAGE)) {
+        return Err(format!(
+            "bodyFatPercentage must be above 0 and at most {MAX_BODY_FAT_PERCENTAGE}"
+        ));
+    }
+    Ok(())
+}
+
+/// Log intake and/or weight for a day; fields left out keep their previously logged value
+pub async fn upsert_entry(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Json(req): Json<JournalEntryRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate(&req) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response();
+    }
+    match sqlx::query(
+        "INSERT INTO energy_journal (user_id, entry_date, intake_kcal, body_weight_kg, body_fat_percentage, notes)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (user_id, entry_date) DO UPDATE SET
+            intake_kcal = COALESCE(EXCLUDED.intake_kcal, energy_journal.intake_kcal),
+            body_weight_kg = COALESCE(EXCLUDED.body_weight_kg, energy_journal.body_weight_kg),
+            body_fat_percentage = COALESCE(EXCLUDED.body_fat_percentage, energy_journal.body_fat_percentage),
+            notes = COALESCE(EXCLUDED.notes, energy_journal.notes),
+            updated_at = now()
+         RETURNING id, (xmax = 0) AS inserted",
+    )
+    .bind(user.id)
+    .bind(req.date)
+    .bind(req.intake_kcal)
+    .bind(req.body_weight_kg)
+    .bind(req.body_fat_percentage)
+    .bind(&req.notes)
+    .fetch_one(&*pool)
+    .await
+    {
+        Ok(row) => {
+            let id: Uuid = row.try_get("id").unwrap_or_default();
+            let status = if row.try_get::<bool, _>("inserted").unwrap_or(true) {
+                StatusCode::CREATED
+            } else {
+                StatusCode::OK
+            };
+            (status, Json(json!({"id": id.to_string()}))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("upsert_entry failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn list_entries(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Query(range): Query<JournalRange>,
+) -> impl IntoResponse {
+    let (from, to) = range.resolve();
+    match sqlx::query(
+        "SELECT id, entry_date, intake_kcal, body_weight_kg, body_fat_percentage, notes
+         FROM energy_journal WHERE user_id = $1 AND entry_date BETWEEN $2 AND $3
+         ORDER BY entry_date DESC",
+    )
+    .bind(user.id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&*pool)
+    .await
+    {
+        Ok(rows) => {
+            let entries: Vec<serde_json::Value> = rows.iter().map(|row| {
+                json!({
+                    "id": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
+                    "date": row.try_get::<NaiveDate, _>("entry_date").ok().map(|d| d.to_string()),
+                    "intakeKcal": row.try_get::<Option<sqlx::types::BigDecimal>, _>("intake_kcal").ok().flatten().map(|d| d.to_string()),
+                    "bodyWeightKg": row.try_get::<Option<sqlx::types::BigDecimal>, _>("body_weight_kg").ok().flatten().map(|d| d.to_string()),
+                    "bodyFatPercentage": row.try_get::<Option<sqlx::types::BigDecimal>, _>("body_fat_percentage").ok().flatten().map(|d| d.to_string()),
+                    "notes": row.try_get::<Option<String>, _>("notes").ok().flatten(),
+                })
+            }).collect();
+            (StatusCode::OK, Json(json!({"entries": entries}))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("list_entries failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn delete_entry(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    match sqlx::query("DELETE FROM energy_journal WHERE id = $1 AND user_id = $2")
+        .bind(uuid)
+        .bind(user.id)
+        .execute(&*pool)
+        .await
+    {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))).into_response()
+            } else {
+                (StatusCode::OK, Json(json!({"ok": true}))).into_response()
+            }
+        }
+        Err(e) => {
+            tracing::error!("delete_entry failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Load journal days with training energy from completed workout sessions. With
+/// `training_only_days`, days that have training but no journal entry are included too.
+async fn load_days(
+    pool: &PgPool,
+    user_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+    training_only_days: bool,
+) -> Result<Vec<JournalDay>, sqlx::Error> {
+    let training: HashMap<NaiveDate, f64> = sqlx::query(
+        "SELECT started_at::date AS day, COALESCE(SUM(total_energy_kcal), 0)::float8 AS kcal
+         FROM workout_sessions
+         WHERE user_id = $1 AND status = 'completed' AND started_at::date BETWEEN $2 AND $3
+         GROUP BY started_at::date",
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .filter_map(|row| Some((row.try_get("day").ok()?, row.try_get("kcal").ok()?)))
+    .collect();
+
+    let rows = sqlx::query(
+        "SELECT entry_date, intake_kcal::float8 AS intake_kcal, body_weight_kg::float8 AS body_weight_kg,
+                body_fat_percentage::float8 AS body_fat_percentage
+         FROM energy_journal WHERE user_id = $1 AND entry_date BETWEEN $2 AND $3
+         ORDER BY entry_date",
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+
+    let mut days: Vec<JournalDay> = rows
+        .iter()
+        .filter_map(|row| {
+            let date: NaiveDate = row.try_get("entry_date").ok()?;
+            Some(JournalDay {
+                date,
+                intake_kcal: row.try_get("intake_kcal").ok().flatten(),
+                body_weight_kg: row.try_get("body_weight_kg").ok().flatten(),
+                body_fat_percentage: row.try_get("body_fat_percentage").ok().flatten(),
+                training_kcal: training.get(&date).copied().unwrap_or(0.0),
+            })
+        })
+        .collect();
+    if training_only_days {
+        for (date, kcal) in &training {
+            if !days.iter().any(|d| d.date == *date) {
+                days.push(JournalDay {
+                    date: *date,
+                    intake_kcal: None,
+                    body_weight_kg: None,
+                    body_fat_percentage: None,
+                    training_kcal: *kcal,
+                });
+            }
+        }
+        days.sort_by_key(|d| d.date);
+    }
+    Ok(days)
+}
+
+/// Cumulative deficit, observed weight change and the resulting fat/muscle split over a window
+pub async fn summary(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Query(params): Query<SummaryParams>,
+) -> impl IntoResponse {
+    let (from, to) = JournalRange { from: params.from, to: params.to }.resolve();
+    if from > to {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "from must not be after to"})))
+            .into_response();
+    }
+
+    let latest_measurement = match sqlx::query(
+        "SELECT body_weight_kg::float8 AS body_weight_kg, height_cm::float8 AS height_cm
+         FROM body_measurements WHERE user_id = $1 ORDER BY measured_at DESC LIMIT 1",
+    )
+    .bind(user.id)
+    .fetch_optional(&*pool)
+    .await
+    {
+        Ok(row) => row,
+        Err(e) => {
+            tracing::error!("journal summary failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    let measured = |col: &str| {
+        latest_measurement
+            .as_ref()
+            .and_then(|row| row.try_get::<Option<f64>, _>(col).ok().flatten())
+    };
+    let Some(height_cm) = params.height_cm.or_else(|| measured("height_cm")) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "height_cm is required when no body measurement has a height"})),
+        )
+            .into_response();
+    };
+
+    let days = match load_days(&pool, user.id, from, to, true).await {
+        Ok(days) => days,
+        Err(e) => {
+            tracing::error!("journal summary failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+
+    let formula =
+        params.formula.unwrap_or(if days.iter().any(|d| d.body_fat_percentage.is_some()) {
+            BmrFormula::KatchMcardle
+        } else {
+            BmrFormula::MifflinStJeor
+        });
+    let expenditure = ExpenditureParams {
+        sex: params.sex,
+        age_years: params.age_years,
+        height_cm,
+        activity_level: params.activity_level.unwrap_or(ActivityLevel::Sedentary),
+        formula,
+        fallback_weight_kg: measured("body_weight_kg"),
+    };
+
+    match summarize_journal(&days, &expenditure) {
+        Ok(summary) => (
+            StatusCode::OK,
+            Json(json!({
+                "from": from.to_string(),
+                "to": to.to_string(),
+                "formula": formula,
+                "summary": summary,
+            })),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    }
+}
+
+/// Rolling maintenance estimate from logged intake and the smoothed weight trend
+pub async fn tdee(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Query(params): Query<TdeeParams>,
+) -> impl IntoResponse {
+    let to = params.to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let from = params.from.unwrap_or(to - chrono::Duration::days(DEFAULT_TDEE_HISTORY_DAYS - 1));
+    let defaults = TdeeOptions::default();
+    let options = TdeeOptions {
+        smoothing: params.smoothing.unwrap_or(defaults.smoothing),
+        window_days: params.window_days.unwrap_or(defaults.window_days),
+        target_weekly_change_kg: params.target_weekly_change_kg,
+        ..defaults
+    };
+
+    let days = match load_days(&pool, user.id, from, to, false).await {
+        Ok(days) => days,
+        Err(e) => {
+            tracing::error!("journal tdee failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+
+    match estimate_tdee(&days, &options) {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    }
+}
@@ -1,24 +1,41 @@
-use crate::tools::fat_loss::{calculate_fat_loss_percentage, FatLossRequest};
-use axum::{extract::Json, http::StatusCode, response::IntoResponse};
+use crate::tools::fat_loss::{estimate_fat_loss, FatLossRequest};
+use crate::tools::weight_simulator::{simulate_weight, SimulationRequest};
+use axum::{
+    extract::Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
 
 /// Handler for fat loss calculation endpoint
+///
+/// With a `window_days` the response carries a plausible range even when the point estimate
+/// leaves 0..100%, so only unusable inputs are rejected.
 pub async fn calculate_fat_loss(Json(request): Json<FatLossRequest>) -> impl IntoResponse {
-    let response = calculate_fat_loss_percentage(request.kcal_deficit, request.weight_loss_kg);
+    let response = estimate_fat_loss(&request);
 
-    if response.is_valid {
+    if response.is_valid || response.range.is_some() {
         (StatusCode::OK, Json(response))
     } else {
         (StatusCode::BAD_REQUEST, Json(response))
     }
 }
 
+/// Handler for the dynamic weight-change simulation endpoint
+pub async fn simulate(Json(request): Json<SimulationRequest>) -> Response {
+    match simulate_weight(&request) {
+        Ok(result) => (StatusCode::OK, Json(result)).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[tokio::test]
     async fn test_calculate_fat_loss_valid() {
-        let request = FatLossRequest { kcal_deficit: 3500.0, weight_loss_kg: 0.5 };
+        let request =
+            FatLossRequest { kcal_deficit: 3500.0, weight_loss_kg: 0.5, ..Default::default() };
 
         let response = calculate_fat_loss(Json(request)).await;
         assert_eq!(response.into_response().status(), StatusCode::OK);
@@ -26,9 +43,53 @@ mod tests {
 
     #[tokio::test]
     async fn test_calculate_fat_loss_invalid() {
-        let request = FatLossRequest { kcal_deficit: 0.0, weight_loss_kg: 0.5 };
+        let request =
+            FatLossRequest { kcal_deficit: 0.0, weight_loss_kg: 0.5, ..Default::default() };
 
         let response = calculate_fat_loss(Json(request)).await;
         assert_eq!(response.into_response().status(), StatusCode::BAD_REQUEST);
     }
+
+    #[tokio::test]
+    async fn test_calculate_fat_loss_short_window_returns_range() {
+        let request = FatLossRequest {
+            kcal_deficit: 10000.0,
+            weight_loss_kg: 0.5,
+            window_days: Some(7.0),
+            ..Default::default()
+        };
+
+        let response = calculate_fat_loss(Json(request)).await;
+        assert_eq!(response.into_response().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_calculate_fat_loss_weight_gain_returns_range() {
+        let request = FatLossRequest {
+            kcal_deficit: 3500.0,
+            weight_loss_kg: -0.4,
+            window_days: Some(7.0),
+            ..Default::default()
+        };
+
+        let response = calculate_fat_loss(Json(request)).await;
+        assert_eq!(response.into_response().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_invalid_schedule() {
+        let request: SimulationRequest = serde_json::from_value(serde_json::json!({
+            "weight_kg": 80.0,
+            "body_fat_percentage": 20.0,
+            "height_cm": 180.0,
+            "age_years": 30.0,
+            "sex": "male",
+            "activity_level": "light",
+            "intake_schedule": []
+        }))
+        .unwrap();
+
+        let response = simulate(Json(request)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 }
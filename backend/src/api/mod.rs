@@ -1,8 +1,11 @@
 pub mod auth;
 pub mod bloodlevel;
+pub mod budgets;
 pub mod dice;
 pub mod dice_history;
+pub mod energy_journal;
 pub mod fat_loss;
 pub mod n26_analyzer;
+pub mod n26_ledger;
 pub mod oidc;
 pub mod training;
@@ -1,26 +1,194 @@
-use crate::tools::n26_analyzer::{analyze_transactions, parse_n26_json, N26Data};
+use crate::tools::n26_analyzer::export::{export_transactions, AccountMapping, ExportFormat};
+use crate::tools::n26_analyzer::{
+    analyze_transactions, detect_recurring_payments, normalize_to_currency,
+    parse_n26_json_detailed, FxRateTable, ImportReport, N26Data, RecurringOptions,
+    DEFAULT_CURRENCY,
+};
 use axum::{
-    extract::Json,
-    http::StatusCode,
+    extract::{Json, Query},
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
 };
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// FX table loaded from `FX_RATES_PATH`, cached after the first successful load
+static CONFIGURED_FX_TABLE: OnceLock<FxRateTable> = OnceLock::new();
+
+fn configured_fx_table() -> Result<&'static FxRateTable, String> {
+    if let Some(table) = CONFIGURED_FX_TABLE.get() {
+        return Ok(table);
+    }
+    let path = std::env::var("FX_RATES_PATH").map_err(|_| {
+        "no FX rate table configured: set FX_RATES_PATH or send fx_rates_csv".to_string()
+    })?;
+    let table = FxRateTable::load_ecb_csv(path)?;
+    Ok(CONFIGURED_FX_TABLE.get_or_init(|| table))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportOptions {
+    /// Reject the whole import when any entry or data key would be dropped
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// Response for a strict import that would have dropped entries
+pub fn strict_import_rejected(report: ImportReport) -> Response {
+    let error_response = serde_json::json!({
+        "error": "import rejected in strict mode",
+        "import_report": report
+    });
+    (StatusCode::BAD_REQUEST, Json(error_response)).into_response()
+}
+
+/// Add the import report to a JSON response body
+pub fn with_import_report(body: impl serde::Serialize, report: &ImportReport) -> serde_json::Value {
+    let mut body = serde_json::json!(body);
+    body["import_report"] = serde_json::json!(report);
+    body
+}
+
+/// The import report as a header value; non-ASCII text is escaped so the JSON stays valid
+fn import_report_header(report: &ImportReport) -> Option<HeaderValue> {
+    let json = serde_json::to_string(report).ok()?;
+    let ascii: String = json
+        .encode_utf16()
+        .map(|unit| match char::from_u32(u32::from(unit)) {
+            Some(c) if c.is_ascii() && !c.is_ascii_control() => c.to_string(),
+            _ => format!("\\u{unit:04x}"),
+        })
+        .collect();
+    HeaderValue::from_str(&ascii).ok()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportRequest {
+    pub data: N26Data,
+    #[serde(default)]
+    pub accounts: AccountMapping,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CurrencyReportRequest {
+    pub data: N26Data,
+    pub reporting_currency: Option<String>,
+    /// ECB-style rate table sent inline instead of the server-side `FX_RATES_PATH` file
+    pub fx_rates_csv: Option<String>,
+}
 
 /// Handler for N26 data analysis endpoint
-pub async fn analyze_n26_data(Json(n26_data): Json<N26Data>) -> Response {
-    match parse_n26_json(n26_data) {
-        Ok(transactions) => {
-            let analysis = analyze_transactions(transactions);
+pub async fn analyze_n26_data(
+    Query(options): Query<ImportOptions>,
+    Json(n26_data): Json<N26Data>,
+) -> Response {
+    match parse_n26_json_detailed(n26_data, options.strict) {
+        Ok((transactions, report)) => {
+            let mut analysis = analyze_transactions(transactions);
+            analysis.import_report = Some(report);
             (StatusCode::OK, Json(analysis)).into_response()
         }
-        Err(e) => {
-            let error_response = serde_json::json!({
-                "error": e
-            });
-            (StatusCode::BAD_REQUEST, Json(error_response)).into_response()
+        Err(report) => strict_import_rejected(report),
+    }
+}
+
+/// Handler for recurring payment / subscription detection
+pub async fn detect_subscriptions(
+    Query(options): Query<RecurringOptions>,
+    Query(import): Query<ImportOptions>,
+    Json(n26_data): Json<N26Data>,
+) -> Response {
+    match parse_n26_json_detailed(n26_data, import.strict) {
+        Ok((transactions, import_report)) => {
+            let report = detect_recurring_payments(&transactions, &options);
+            (StatusCode::OK, Json(with_import_report(report, &import_report))).into_response()
+        }
+        Err(report) => strict_import_rejected(report),
+    }
+}
+
+/// Handler that normalises totals into a reporting currency and reports FX fees
+pub async fn currency_report(
+    Query(import): Query<ImportOptions>,
+    Json(request): Json<CurrencyReportRequest>,
+) -> Response {
+    let inline_table;
+    let table = match request.fx_rates_csv.as_deref() {
+        Some(csv) => match FxRateTable::from_ecb_csv(csv) {
+            Ok(t) => {
+                inline_table = t;
+                &inline_table
+            }
+            Err(e) => {
+                return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e})))
+                    .into_response()
+            }
+        },
+        None => match configured_fx_table() {
+            Ok(t) => t,
+            Err(e) => {
+                return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({"error": e})))
+                    .into_response()
+            }
+        },
+    };
+
+    let reporting_currency = request.reporting_currency.as_deref().unwrap_or(DEFAULT_CURRENCY);
+    match parse_n26_json_detailed(request.data, import.strict) {
+        Ok((transactions, import_report)) => {
+            let report = normalize_to_currency(&transactions, table, reporting_currency);
+            (StatusCode::OK, Json(with_import_report(report, &import_report))).into_response()
         }
+        Err(report) => strict_import_rejected(report),
     }
 }
 
+/// Handler that exports transactions as CSV or a ledger-cli / hledger journal. The import
+/// report is returned as JSON in the `x-import-report` header.
+pub async fn export(
+    Query(query): Query<ExportQuery>,
+    Query(import): Query<ImportOptions>,
+    Json(request): Json<ExportRequest>,
+) -> Response {
+    let (transactions, import_report) = match parse_n26_json_detailed(request.data, import.strict) {
+        Ok(parsed) => parsed,
+        Err(report) => return strict_import_rejected(report),
+    };
+    match export_transactions(&transactions, query.format, &request.accounts) {
+        Ok(body) => export_file(query.format, body, Some(&import_report)),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+}
+
+/// An export as a file download, with the import report in `x-import-report` when given
+pub fn export_file(
+    format: ExportFormat,
+    body: String,
+    import_report: Option<&ImportReport>,
+) -> Response {
+    let mut response = (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"n26-export.{}\"", format.file_extension()),
+            ),
+        ],
+        body,
+    )
+        .into_response();
+    if let Some(value) = import_report.and_then(import_report_header) {
+        response.headers_mut().insert("x-import-report", value);
+    }
+    response
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,7 +202,135 @@ mod tests {
             data: HashMap::new(),
         };
 
-        let _response = analyze_n26_data(Json(data)).await;
+        let _response = analyze_n26_data(Query(ImportOptions::default()), Json(data)).await;
         // Response should be OK with empty data
     }
+
+    #[tokio::test]
+    async fn test_analyze_strict_rejects_unknown_keys() {
+        let data = N26Data {
+            id: "test".to_string(),
+            created: "2024-01-01".to_string(),
+            data: HashMap::from([("spaces".to_string(), Some(vec![]))]),
+        };
+
+        let response = analyze_n26_data(Query(ImportOptions { strict: true }), Json(data)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_detect_subscriptions_empty() {
+        let data = N26Data {
+            id: "test".to_string(),
+            created: "2024-01-01".to_string(),
+            data: HashMap::new(),
+        };
+
+        let response = detect_subscriptions(
+            Query(RecurringOptions::default()),
+            Query(ImportOptions::default()),
+            Json(data),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_currency_report_with_inline_rates() {
+        let request = CurrencyReportRequest {
+            data: N26Data {
+                id: "test".to_string(),
+                created: "2024-01-01".to_string(),
+                data: HashMap::new(),
+            },
+            reporting_currency: Some("USD".to_string()),
+            fx_rates_csv: Some("Date,USD,\n2024-01-05,1.0942,\n".to_string()),
+        };
+
+        let response = currency_report(Query(ImportOptions::default()), Json(request)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_currency_report_invalid_rates() {
+        let request = CurrencyReportRequest {
+            data: N26Data {
+                id: "test".to_string(),
+                created: "2024-01-01".to_string(),
+                data: HashMap::new(),
+            },
+            reporting_currency: None,
+            fx_rates_csv: Some(String::new()),
+        };
+
+        let response = currency_report(Query(ImportOptions::default()), Json(request)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_export_csv_headers() {
+        let request = ExportRequest {
+            data: N26Data {
+                id: "test".to_string(),
+                created: "2024-01-01".to_string(),
+                data: HashMap::new(),
+            },
+            accounts: AccountMapping::default(),
+        };
+
+        let response = export(
+            Query(ExportQuery { format: ExportFormat::Csv }),
+            Query(ImportOptions::default()),
+            Json(request),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv; charset=utf-8");
+        assert!(response.headers().contains_key("x-import-report"));
+    }
+
+    #[tokio::test]
+    async fn test_strict_mode_applies_to_every_endpoint() {
+        let bad_rows = || N26Data {
+            id: "test".to_string(),
+            created: "2024-01-01".to_string(),
+            data: HashMap::from([(
+                "cash26Data".to_string(),
+                Some(vec![serde_json::json!({"amount": "ten"})]),
+            )]),
+        };
+        let strict = || Query(ImportOptions { strict: true });
+
+        let response =
+            detect_subscriptions(Query(RecurringOptions::default()), strict(), Json(bad_rows()))
+                .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let request = CurrencyReportRequest {
+            data: bad_rows(),
+            reporting_currency: None,
+            fx_rates_csv: Some("Date,USD,\n2024-01-05,1.0942,\n".to_string()),
+        };
+        assert_eq!(
+            currency_report(strict(), Json(request)).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let request = ExportRequest { data: bad_rows(), accounts: AccountMapping::default() };
+        let response =
+            export(Query(ExportQuery { format: ExportFormat::Csv }), strict(), Json(request)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Lenient mode reports the rejected row instead
+        let response = detect_subscriptions(
+            Query(RecurringOptions::default()),
+            Query(ImportOptions::default()),
+            Json(bad_rows()),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["import_report"]["rejected"].as_array().unwrap().len(), 1);
+    }
 }
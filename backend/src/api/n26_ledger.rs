@@ -0,0 +1,184 @@
+use crate::api::n26_analyzer::{export_file, strict_import_rejected, ExportQuery, ImportOptions};
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::n26_analyzer::export::{export_transactions, AccountMapping};
+use crate::tools::n26_analyzer::{
+    parse_n26_json_detailed, parse_transaction_date, N26Data, Transaction,
+};
+use axum::extract::{Extension, Json, Query};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StoredExportRequest {
+    #[serde(default)]
+    pub accounts: AccountMapping,
+    /// First booking day, `YYYY-MM-DD`
+    pub from: Option<String>,
+    /// Last booking day, inclusive
+    pub to: Option<String>,
+}
+
+/// Key that identifies a transaction across re-imports. Identical entries (two equal
+/// coffees on one day) are told apart by how often they occurred before in the export.
+fn import_keys(transactions: &[Transaction]) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    transactions
+        .iter()
+        .map(|tx| {
+            let base =
+                format!("{}|{}|{}|{}|{}", tx.date, tx.category, tx.amount, tx.currency, tx.comment);
+            let occurrence = seen.entry(base.clone()).or_insert(0);
+            *occurrence += 1;
+            format!("{base}#{occurrence}")
+        })
+        .collect()
+}
+
+/// Store the transactions of an N26 export in the user's ledger; entries already stored by
+/// an earlier import of the same or an overlapping export are skipped
+pub async fn import_ledger(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Query(import): Query<ImportOptions>,
+    Json(n26_data): Json<N26Data>,
+) -> Response {
+    let (transactions, import_report) = match parse_n26_json_detailed(n26_data, import.strict) {
+        Ok(parsed) => parsed,
+        Err(report) => return strict_import_rejected(report),
+    };
+    let keys = import_keys(&transactions);
+
+    let result: Result<u64, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        let mut stored = 0;
+        for (transaction, key) in transactions.iter().zip(&keys) {
+            stored += sqlx::query(
+                "INSERT INTO n26_transactions (user_id, source_date, booked_on, amount, currency,
+                    original_amount, original_currency, category, comment, import_key)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                 ON CONFLICT (user_id, import_key) DO NOTHING",
+            )
+            .bind(user.id)
+            .bind(&transaction.date)
+            .bind(parse_transaction_date(&transaction.date))
+            .bind(transaction.amount)
+            .bind(&transaction.currency)
+            .bind(transaction.original_amount)
+            .bind(&transaction.original_currency)
+            .bind(&transaction.category)
+            .bind(&transaction.comment)
+            .bind(key)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(stored)
+    }
+    .await;
+
+    match result {
+        Ok(stored) => (
+            StatusCode::OK,
+            Json(json!({
+                "stored": stored,
+                "already_stored": transactions.len() as u64 - stored,
+                "import_report": import_report,
+            })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("import_ledger failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Export the user's stored ledger as CSV or a ledger-cli / hledger journal
+pub async fn export_ledger(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Query(query): Query<ExportQuery>,
+    Json(request): Json<StoredExportRequest>,
+) -> Response {
+    let parse_day = |field: &str, raw: Option<&str>| match raw {
+        None => Ok(None),
+        Some(raw) => NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| format!("{field} must be YYYY-MM-DD")),
+    };
+    let (from, to) = match (
+        parse_day("from", request.from.as_deref()),
+        parse_day("to", request.to.as_deref()),
+    ) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response()
+        }
+    };
+
+    let rows = match sqlx::query(
+        "SELECT source_date, amount, currency, original_amount, original_currency, category, comment
+         FROM n26_transactions
+         WHERE user_id = $1
+           AND ($2::date IS NULL OR booked_on >= $2)
+           AND ($3::date IS NULL OR booked_on <= $3)
+         ORDER BY booked_on NULLS LAST, imported_at, import_key",
+    )
+    .bind(user.id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&*pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("export_ledger failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    let transactions: Vec<Transaction> = rows
+        .iter()
+        .map(|row| Transaction {
+            amount: row.try_get("amount").unwrap_or(0.0),
+            date: row.try_get("source_date").unwrap_or_default(),
+            category: row.try_get("category").unwrap_or_default(),
+            comment: row.try_get("comment").unwrap_or_default(),
+            currency: row.try_get("currency").unwrap_or_default(),
+            original_amount: row.try_get("original_amount").unwrap_or(None),
+            original_currency: row.try_get("original_currency").unwrap_or(None),
+        })
+        .collect();
+
+    match export_transactions(&transactions, query.format, &request.accounts) {
+        Ok(body) => export_file(query.format, body, None),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    }
+}
+
+/// Remove every stored transaction of the user
+pub async fn delete_ledger(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> Response {
+    match sqlx::query("DELETE FROM n26_transactions WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&*pool)
+        .await
+    {
+        Ok(result) => {
+            (StatusCode::OK, Json(json!({"deleted": result.rows_affected()}))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("delete_ledger failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use super::{recalculate_session_totals, session_body_weight};
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training::cardio::{
+    estimate_cardio_energy, heart_rate_zone, pace_s_per_km, speed_kmh, CardioActivity, CardioInput,
+};
+use axum::extract::{Extension, Json, Path};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogCardioRequest {
+    pub activity: CardioActivity,
+    pub duration_s: i32,
+    pub distance_m: Option<f64>,
+    pub avg_power_w: Option<f64>,
+    pub avg_heart_rate: Option<i32>,
+    pub max_heart_rate: Option<i32>,
+    /// The athlete's maximum heart rate, for the zone of the average
+    pub athlete_max_heart_rate: Option<i32>,
+    /// Seconds in heart-rate zones 1-5
+    pub zone_seconds: Option<Vec<u32>>,
+    /// Overrides the MET from the tables
+    pub met: Option<f64>,
+    pub notes: Option<String>,
+}
+
+/// Cardio activities of a session with speed and pace, oldest first
+pub(super) async fn cardio_json(
+    pool: &PgPool,
+    session_id: Uuid,
+) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, activity_type, duration_s, distance_m::float8 as distance_m, avg_power_w::float8 as avg_power_w,
+                avg_heart_rate, max_heart_rate, avg_heart_rate_zone, heart_rate_zone_seconds,
+                energy_method, met_value::float8 as met_value, energy_kcal, notes, performed_at
+         FROM cardio_activities WHERE session_id = $1 ORDER BY performed_at",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let duration_s = f64::from(row.try_get::<i32, _>("duration_s").unwrap_or(0));
+            let distance_m = row.try_get::<Option<f64>, _>("distance_m").ok().flatten();
+            json!({
+                "id": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
+                "activity": row.try_get::<String, _>("activity_type").unwrap_or_default(),
+                "durationS": duration_s,
+                "distanceM": distance_m,
+                "speedKmh": distance_m.and_then(|d| speed_kmh(d, duration_s)),
+                "paceSPerKm": distance_m.and_then(|d| pace_s_per_km(d, duration_s)),
+                "avgPowerW": row.try_get::<Option<f64>, _>("avg_power_w").ok().flatten(),
+                "avgHeartRate": row.try_get::<Option<i32>, _>("avg_heart_rate").ok().flatten(),
+                "maxHeartRate": row.try_get::<Option<i32>, _>("max_heart_rate").ok().flatten(),
+                "avgHeartRateZone": row.try_get::<Option<i16>, _>("avg_heart_rate_zone").ok().flatten(),
+                "zoneSeconds": row.try_get::<Option<Vec<i32>>, _>("heart_rate_zone_seconds").ok().flatten(),
+                "energyMethod": row.try_get::<String, _>("energy_method").unwrap_or_default(),
+                "met": row.try_get::<Option<f64>, _>("met_value").ok().flatten(),
+                "energyKcal": row.try_get::<sqlx::types::BigDecimal, _>("energy_kcal").ok().map(|d| d.to_string()),
+                "notes": row.try_get::<Option<String>, _>("notes").ok().flatten(),
+                "performedAt": row.try_get::<chrono::DateTime<chrono::Utc>, _>("performed_at").ok().map(|d| d.to_rfc3339()),
+            })
+        })
+        .collect())
+}
+
+pub async fn log_cardio(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(session_id): Path<String>,
+    Json(req): Json<LogCardioRequest>,
+) -> impl IntoResponse {
+    let session_uuid = match Uuid::parse_str(&session_id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid session_id"})))
+                .into_response()
+        }
+    };
+    let input = CardioInput {
+        activity: req.activity,
+        duration_s: f64::from(req.duration_s),
+        distance_m: req.distance_m,
+        avg_power_w: req.avg_power_w,
+        met: req.met,
+        zone_seconds: req.zone_seconds.clone(),
+    };
+    if [req.avg_heart_rate, req.max_heart_rate, req.athlete_max_heart_rate]
+        .iter()
+        .flatten()
+        .any(|bpm| !(25..=250).contains(bpm))
+    {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "heart rates must be 25-250 bpm"})))
+            .into_response();
+    }
+
+    let in_progress = sqlx::query(
+        "SELECT 1 FROM workout_sessions WHERE id = $1 AND user_id = $2 AND status = 'in_progress'",
+    )
+    .bind(session_uuid)
+    .bind(user.id)
+    .fetch_optional(&*pool)
+    .await;
+    let body_weight_kg = match in_progress {
+        Ok(Some(_)) => session_body_weight(&pool, session_uuid, user.id).await,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "session not found or not in_progress"})),
+            )
+                .into_response()
+        }
+        Err(e) => Err(e),
+    };
+    let body_weight_kg = match body_weight_kg {
+        Ok(weight) => weight.unwrap_or(75.0),
+        Err(e) => {
+            tracing::error!("log_cardio session check failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    let energy = match estimate_cardio_energy(&input, body_weight_kg) {
+        Ok(e) => e,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+    let zone = req
+        .avg_heart_rate
+        .zip(req.athlete_max_heart_rate)
+        .and_then(|(avg, max)| heart_rate_zone(avg as u32, max as u32));
+    let zone_seconds: Option<Vec<i32>> = match req
+        .zone_seconds
+        .map(|z| z.into_iter().map(i32::try_from).collect())
+        .transpose()
+    {
+        Ok(zone_seconds) => zone_seconds,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "zone seconds out of range"})))
+                .into_response()
+        }
+    };
+
+    match sqlx::query(
+        "INSERT INTO cardio_activities (session_id, activity_type, duration_s, distance_m, avg_power_w,
+            avg_heart_rate, max_heart_rate, avg_heart_rate_zone, heart_rate_zone_seconds,
+            energy_method, met_value, energy_kcal, notes)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+         RETURNING id",
+    )
+    .bind(session_uuid)
+    .bind(json!(req.activity).as_str().unwrap_or_default())
+    .bind(req.duration_s)
+    .bind(req.distance_m)
+    .bind(req.avg_power_w)
+    .bind(req.avg_heart_rate)
+    .bind(req.max_heart_rate)
+    .bind(zone.map(i16::from))
+    .bind(zone_seconds)
+    .bind(json!(energy.method).as_str().unwrap_or_default())
+    .bind(energy.met)
+    .bind(energy.energy_kcal)
+    .bind(&req.notes)
+    .fetch_one(&*pool)
+    .await
+    {
+        Ok(row) => (
+            StatusCode::CREATED,
+            Json(json!({
+                "id": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
+                "energyKcal": energy.energy_kcal,
+                "energyMethod": energy.method,
+                "met": energy.met,
+                "speedKmh": req.distance_m.and_then(|d| speed_kmh(d, input.duration_s)),
+                "paceSPerKm": req.distance_m.and_then(|d| pace_s_per_km(d, input.duration_s)),
+                "avgHeartRateZone": zone,
+            })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("log_cardio failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn delete_cardio(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path((session_id, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let (Ok(session_uuid), Ok(uuid)) = (Uuid::parse_str(&session_id), Uuid::parse_str(&id)) else {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response();
+    };
+    // The session total includes the activity, so both change together
+    let result: Result<u64, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        let deleted = sqlx::query(
+            "DELETE FROM cardio_activities WHERE id = $1 AND session_id = $2
+               AND session_id IN (SELECT id FROM workout_sessions WHERE user_id = $3)",
+        )
+        .bind(uuid)
+        .bind(session_uuid)
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if deleted > 0 {
+            recalculate_session_totals(&mut *tx, session_uuid).await?;
+        }
+        tx.commit().await?;
+        Ok(deleted)
+    }
+    .await;
+
+    match result {
+        Ok(0) => {
+            (StatusCode::NOT_FOUND, Json(json!({"error": "activity not found"}))).into_response()
+        }
+        Ok(_) => (StatusCode::OK, Json(json!({"ok": true}))).into_response(),
+        Err(e) => {
+            tracing::error!("delete_cardio failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
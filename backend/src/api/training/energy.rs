@@ -1,8 +1,25 @@
-use crate::tools::training::{self, SetEnergyParams};
-use axum::extract::Json;
+use super::{
+    default_measurements, energy_params, measurements_from_row, parse_bound,
+    recalculate_session_totals, store_muscle_energy, tempo_from_row,
+};
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::metabolism::Sex;
+use crate::tools::training::cardio::{
+    activity_met, estimate_cardio_energy, CardioActivity, CardioInput,
+};
+use crate::tools::training::wearable::{heart_rate_energy_kcal, HeartRateSample};
+use crate::tools::training::{self, BodyMeasurements, SetEnergy, SetEnergyParams};
+use axum::extract::{Extension, Json};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
 
 pub async fn calculate_energy(Json(params): Json<SetEnergyParams>) -> impl IntoResponse {
     let energy = training::compute_set_energy(&params);
@@ -17,3 +34,456 @@ pub async fn calculate_energy(Json(params): Json<SetEnergyParams>) -> impl IntoR
         })),
     )
 }
+
+/// Which sets to recompute: a user's sessions started in `[from, to)`, all when unbounded
+#[derive(Debug, Clone)]
+pub struct RecomputeScope {
+    pub user_id: Uuid,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Report the deltas without storing anything
+    pub dry_run: bool,
+    /// Use the latest measurement for every session instead of the one in effect when it started
+    pub use_latest_measurement: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionEnergyDelta {
+    pub session_id: Uuid,
+    pub name: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub sets_changed: u32,
+    pub cardio_changed: u32,
+    /// Session total: sets and cardio
+    pub previous_energy_kcal: f64,
+    pub energy_kcal: f64,
+    pub delta_kcal: f64,
+    pub previous_cardio_energy_kcal: f64,
+    pub cardio_energy_kcal: f64,
+    /// Estimate from an imported recording; None without one, or when it was imported
+    /// before its sex and age were kept
+    pub previous_heart_rate_energy_kcal: Option<f64>,
+    pub heart_rate_energy_kcal: Option<f64>,
+}
+
+impl SessionEnergyDelta {
+    fn heart_rate_changed(&self) -> bool {
+        self.previous_heart_rate_energy_kcal
+            .zip(self.heart_rate_energy_kcal)
+            .is_some_and(|(previous, kcal)| kcal_changed(previous, kcal))
+    }
+}
+
+/// Outcome of a recomputation; `sessions` lists only the sessions whose energy changed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnergyRecomputeReport {
+    pub energy_model_version: i32,
+    pub dry_run: bool,
+    pub sets_recomputed: u32,
+    pub sets_changed: u32,
+    pub cardio_recomputed: u32,
+    pub cardio_changed: u32,
+    pub heart_rate_changed: u32,
+    pub sessions_changed: u32,
+    /// Sets and cardio of the sessions in scope
+    pub previous_energy_kcal: f64,
+    pub energy_kcal: f64,
+    pub delta_kcal: f64,
+    pub sessions: Vec<SessionEnergyDelta>,
+}
+
+/// Stored energy is DECIMAL(8,2)
+fn round_kcal(kcal: f64) -> f64 {
+    (kcal * 100.0).round() / 100.0
+}
+
+fn kcal_changed(previous: f64, kcal: f64) -> bool {
+    (kcal - previous).abs() >= 0.005
+}
+
+/// Energy of a stored cardio activity at another body weight. The stored MET is kept
+/// when it is not the table's, since then the user gave it. None when the row no
+/// longer validates.
+fn recompute_cardio(row: &PgRow, body_weight_kg: f64) -> Option<f64> {
+    let f = |col: &str| row.try_get::<Option<f64>, _>(col).ok().flatten();
+    let activity: CardioActivity =
+        serde_json::from_value(json!(row.try_get::<String, _>("activity_type").ok()?)).ok()?;
+    let zone_seconds = row
+        .try_get::<Option<Vec<i32>>, _>("heart_rate_zone_seconds")
+        .ok()
+        .flatten()
+        .map(|z| z.into_iter().map(u32::try_from).collect::<Result<Vec<_>, _>>())
+        .transpose()
+        .ok()?;
+    let mut input = CardioInput {
+        activity,
+        duration_s: f64::from(row.try_get::<i32, _>("duration_s").ok()?),
+        distance_m: f("distance_m"),
+        avg_power_w: f("avg_power_w"),
+        met: None,
+        zone_seconds,
+    };
+    // met_value is stored with one decimal
+    let table_met = (activity_met(&input) * 10.0).round() / 10.0;
+    input.met = f("met_value").filter(|met| (met - table_met).abs() >= 0.05);
+    estimate_cardio_energy(&input, body_weight_kg).ok().map(|e| round_kcal(e.energy_kcal))
+}
+
+/// Recompute the energy of every set, cardio activity and heart-rate recording in scope
+/// with the current models and the measurements in effect for its session: the session's
+/// snapshot, else the latest measurement taken before it started, else the latest one,
+/// else the defaults. Changed sets get new energy, muscle energy and model version,
+/// changed sessions new totals, and a non-dry run is recorded in `energy_recomputations`,
+/// all in one transaction.
+pub async fn recompute_user_energy(
+    pool: &PgPool,
+    scope: &RecomputeScope,
+) -> Result<EnergyRecomputeReport, sqlx::Error> {
+    // Rows are read locked in the transaction that writes them back, so a set logged or
+    // edited meanwhile waits instead of being overwritten with stale energy
+    let mut tx = pool.begin().await?;
+    let measurement_rows = sqlx::query(
+        "SELECT id, measured_at, body_weight_kg, height_cm, upper_arm_length_cm, lower_arm_length_cm, upper_leg_length_cm, lower_leg_length_cm, torso_length_cm, arm_length_cm, leg_length_cm, shoulder_width_cm
+         FROM body_measurements WHERE user_id = $1 ORDER BY measured_at",
+    )
+    .bind(scope.user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let measurements: Vec<(Uuid, DateTime<Utc>, BodyMeasurements)> = measurement_rows
+        .iter()
+        .map(|r| (r.get("id"), r.get("measured_at"), measurements_from_row(r)))
+        .collect();
+    let measurements_for = |snapshot: Option<Uuid>, started_at: DateTime<Utc>| {
+        let latest = measurements.last();
+        let chosen = if scope.use_latest_measurement {
+            latest
+        } else {
+            snapshot
+                .and_then(|id| measurements.iter().find(|(mid, _, _)| *mid == id))
+                .or_else(|| measurements.iter().rev().find(|(_, at, _)| *at <= started_at))
+                .or(latest)
+        };
+        chosen.map_or_else(default_measurements, |(_, _, m)| m.clone())
+    };
+
+    // Every query below shares the session scope as $1-$3
+    let session_rows = sqlx::query(
+        "SELECT ws.id, ws.name, ws.started_at, ws.measurement_id, ws.heart_rate_sex,
+                ws.heart_rate_age_years::float8 AS heart_rate_age_years,
+                ws.heart_rate_energy_kcal::float8 AS heart_rate_energy_kcal
+         FROM workout_sessions ws
+         WHERE ws.user_id = $1
+           AND ($2::timestamptz IS NULL OR ws.started_at >= $2)
+           AND ($3::timestamptz IS NULL OR ws.started_at < $3)
+         ORDER BY ws.started_at
+         FOR UPDATE OF ws",
+    )
+    .bind(scope.user_id)
+    .bind(scope.from)
+    .bind(scope.to)
+    .fetch_all(&mut *tx)
+    .await?;
+    let rows = sqlx::query(
+        "SELECT wse.id, wse.session_id, wse.exercise_id, wse.reps, wse.energy_model_version,
+                wse.weight_kg::float8 AS set_weight_kg,
+                wse.energy_kcal::float8 AS stored_energy_kcal,
+                wse.tempo_eccentric_s::float8 AS tempo_eccentric_s,
+                wse.tempo_pause_bottom_s::float8 AS tempo_pause_bottom_s,
+                wse.tempo_concentric_s::float8 AS tempo_concentric_s,
+                wse.tempo_pause_top_s::float8 AS tempo_pause_top_s,
+                e.movement_pattern, e.primary_segments_moved, e.rom_degrees, e.is_bodyweight,
+                e.is_unilateral, e.body_mass_fraction_moved
+         FROM workout_sets wse
+         JOIN workout_sessions ws ON ws.id = wse.session_id
+         JOIN exercises e ON e.id = wse.exercise_id
+         WHERE ws.user_id = $1
+           AND ($2::timestamptz IS NULL OR ws.started_at >= $2)
+           AND ($3::timestamptz IS NULL OR ws.started_at < $3)
+         ORDER BY ws.started_at, wse.performed_at
+         FOR UPDATE OF wse",
+    )
+    .bind(scope.user_id)
+    .bind(scope.from)
+    .bind(scope.to)
+    .fetch_all(&mut *tx)
+    .await?;
+    let cardio_rows = sqlx::query(
+        "SELECT ca.id, ca.session_id, ca.activity_type, ca.duration_s,
+                ca.distance_m::float8 AS distance_m, ca.avg_power_w::float8 AS avg_power_w,
+                ca.heart_rate_zone_seconds, ca.met_value::float8 AS met_value,
+                ca.energy_kcal::float8 AS stored_energy_kcal
+         FROM cardio_activities ca
+         JOIN workout_sessions ws ON ws.id = ca.session_id
+         WHERE ws.user_id = $1
+           AND ($2::timestamptz IS NULL OR ws.started_at >= $2)
+           AND ($3::timestamptz IS NULL OR ws.started_at < $3)
+         ORDER BY ws.started_at, ca.performed_at
+         FOR UPDATE OF ca",
+    )
+    .bind(scope.user_id)
+    .bind(scope.from)
+    .bind(scope.to)
+    .fetch_all(&mut *tx)
+    .await?;
+    let sample_rows = sqlx::query(
+        "SELECT hr.session_id, hr.recorded_at, hr.bpm
+         FROM session_heart_rate_samples hr
+         JOIN workout_sessions ws ON ws.id = hr.session_id
+         WHERE ws.user_id = $1
+           AND ($2::timestamptz IS NULL OR ws.started_at >= $2)
+           AND ($3::timestamptz IS NULL OR ws.started_at < $3)
+           AND ws.heart_rate_sex IS NOT NULL AND ws.heart_rate_age_years IS NOT NULL
+         ORDER BY hr.session_id, hr.recorded_at",
+    )
+    .bind(scope.user_id)
+    .bind(scope.from)
+    .bind(scope.to)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut samples: HashMap<Uuid, Vec<HeartRateSample>> = HashMap::new();
+    for row in &sample_rows {
+        samples.entry(row.get("session_id")).or_default().push(HeartRateSample {
+            time: row.get("recorded_at"),
+            bpm: row.get::<i32, _>("bpm").max(0) as u32,
+        });
+    }
+
+    let mut report = EnergyRecomputeReport {
+        energy_model_version: training::ENERGY_MODEL_VERSION,
+        dry_run: scope.dry_run,
+        sets_recomputed: 0,
+        sets_changed: 0,
+        cardio_recomputed: 0,
+        cardio_changed: 0,
+        heart_rate_changed: 0,
+        sessions_changed: 0,
+        previous_energy_kcal: 0.0,
+        energy_kcal: 0.0,
+        delta_kcal: 0.0,
+        sessions: Vec::new(),
+    };
+    let mut sessions: Vec<SessionEnergyDelta> = Vec::with_capacity(session_rows.len());
+    let mut session_measurements: Vec<BodyMeasurements> = Vec::with_capacity(session_rows.len());
+    let mut index: HashMap<Uuid, usize> = HashMap::new();
+    // (session, kcal) of recordings to restore
+    let mut heart_rate_updates: Vec<(Uuid, f64)> = Vec::new();
+    for row in &session_rows {
+        let session_id: Uuid = row.get("id");
+        let started_at: DateTime<Utc> = row.get("started_at");
+        let measurements = measurements_for(row.get("measurement_id"), started_at);
+        let previous_heart_rate: Option<f64> = row.get("heart_rate_energy_kcal");
+        let sex: Option<Sex> = row
+            .get::<Option<String>, _>("heart_rate_sex")
+            .and_then(|s| serde_json::from_value(json!(s)).ok());
+        let age: Option<f64> = row.get("heart_rate_age_years");
+        let heart_rate = match (sex, age, samples.get(&session_id), previous_heart_rate) {
+            (Some(sex), Some(age), Some(samples), Some(_)) => Some(round_kcal(
+                heart_rate_energy_kcal(samples, sex, age, measurements.body_weight_kg),
+            )),
+            _ => previous_heart_rate,
+        };
+        if let (Some(previous), Some(kcal)) = (previous_heart_rate, heart_rate) {
+            if kcal_changed(previous, kcal) {
+                heart_rate_updates.push((session_id, kcal));
+            }
+        }
+        index.insert(session_id, sessions.len());
+        sessions.push(SessionEnergyDelta {
+            session_id,
+            name: row.get("name"),
+            started_at,
+            sets_changed: 0,
+            cardio_changed: 0,
+            previous_energy_kcal: 0.0,
+            energy_kcal: 0.0,
+            delta_kcal: 0.0,
+            previous_cardio_energy_kcal: 0.0,
+            cardio_energy_kcal: 0.0,
+            previous_heart_rate_energy_kcal: previous_heart_rate,
+            heart_rate_energy_kcal: heart_rate,
+        });
+        session_measurements.push(measurements);
+    }
+
+    // (set, exercise, energy); sets whose energy is unchanged only get the new version
+    let mut updates: Vec<(Uuid, Uuid, SetEnergy)> = Vec::new();
+    for row in &rows {
+        let f = |col: &str| row.try_get::<Option<f64>, _>(col).ok().flatten();
+        let Some(&i) = index.get(&row.get("session_id")) else { continue };
+        let params = energy_params(
+            row,
+            f("set_weight_kg").unwrap_or(0.0),
+            row.get("reps"),
+            tempo_from_row(row),
+            session_measurements[i].clone(),
+        );
+        let energy = training::compute_set_energy(&params);
+        let previous = f("stored_energy_kcal").unwrap_or(0.0);
+        let kcal = round_kcal(energy.total_kcal);
+        let changed = kcal_changed(previous, kcal);
+
+        let session = &mut sessions[i];
+        session.previous_energy_kcal += previous;
+        session.energy_kcal += kcal;
+        session.sets_changed += u32::from(changed);
+        report.sets_recomputed += 1;
+        report.sets_changed += u32::from(changed);
+        let version: i32 = row.get("energy_model_version");
+        if changed || version != training::ENERGY_MODEL_VERSION {
+            updates.push((row.get("id"), row.get("exercise_id"), energy));
+        }
+    }
+
+    // (activity, kcal)
+    let mut cardio_updates: Vec<(Uuid, f64)> = Vec::new();
+    for row in &cardio_rows {
+        let Some(&i) = index.get(&row.get("session_id")) else { continue };
+        let previous: f64 = row.try_get("stored_energy_kcal").unwrap_or(0.0);
+        let kcal =
+            recompute_cardio(row, session_measurements[i].body_weight_kg).unwrap_or(previous);
+        let changed = kcal_changed(previous, kcal);
+
+        let session = &mut sessions[i];
+        session.previous_energy_kcal += previous;
+        session.energy_kcal += kcal;
+        session.previous_cardio_energy_kcal += previous;
+        session.cardio_energy_kcal += kcal;
+        session.cardio_changed += u32::from(changed);
+        report.cardio_recomputed += 1;
+        report.cardio_changed += u32::from(changed);
+        if changed {
+            cardio_updates.push((row.get("id"), kcal));
+        }
+    }
+
+    for session in &mut sessions {
+        session.previous_energy_kcal = round_kcal(session.previous_energy_kcal);
+        session.energy_kcal = round_kcal(session.energy_kcal);
+        session.delta_kcal = round_kcal(session.energy_kcal - session.previous_energy_kcal);
+        session.previous_cardio_energy_kcal = round_kcal(session.previous_cardio_energy_kcal);
+        session.cardio_energy_kcal = round_kcal(session.cardio_energy_kcal);
+        report.previous_energy_kcal += session.previous_energy_kcal;
+        report.energy_kcal += session.energy_kcal;
+    }
+    report.heart_rate_changed = heart_rate_updates.len() as u32;
+    report.sessions = sessions
+        .into_iter()
+        .filter(|s| s.sets_changed > 0 || s.cardio_changed > 0 || s.heart_rate_changed())
+        .collect();
+    report.sessions_changed = report.sessions.len() as u32;
+    report.previous_energy_kcal = round_kcal(report.previous_energy_kcal);
+    report.energy_kcal = round_kcal(report.energy_kcal);
+    report.delta_kcal = round_kcal(report.energy_kcal - report.previous_energy_kcal);
+    if scope.dry_run {
+        return Ok(report);
+    }
+
+    for (set_id, exercise_id, energy) in &updates {
+        sqlx::query(
+            "UPDATE workout_sets SET energy_kcal = $2, energy_potential_kcal = $3,
+                energy_kinetic_kcal = $4, energy_isometric_kcal = $5,
+                energy_model_version = $6, energy_recomputed_at = now()
+             WHERE id = $1",
+        )
+        .bind(set_id)
+        .bind(energy.total_kcal)
+        .bind(energy.potential_kcal)
+        .bind(energy.kinetic_kcal)
+        .bind(energy.isometric_kcal)
+        .bind(training::ENERGY_MODEL_VERSION)
+        .execute(&mut *tx)
+        .await?;
+        store_muscle_energy(&mut tx, *set_id, *exercise_id, energy.total_kcal).await?;
+    }
+    for (id, kcal) in &cardio_updates {
+        sqlx::query("UPDATE cardio_activities SET energy_kcal = $2 WHERE id = $1")
+            .bind(id)
+            .bind(kcal)
+            .execute(&mut *tx)
+            .await?;
+    }
+    for (session_id, kcal) in &heart_rate_updates {
+        sqlx::query(
+            "UPDATE workout_sessions SET heart_rate_energy_kcal = $2, updated_at = now() WHERE id = $1",
+        )
+        .bind(session_id)
+        .bind(kcal)
+        .execute(&mut *tx)
+        .await?;
+    }
+    for session in &report.sessions {
+        recalculate_session_totals(&mut *tx, session.session_id).await?;
+    }
+    sqlx::query(
+        "INSERT INTO energy_recomputations (user_id, range_from, range_to, energy_model_version,
+            sets_recomputed, sets_changed, sessions_changed, previous_energy_kcal, energy_kcal)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+    )
+    .bind(scope.user_id)
+    .bind(scope.from)
+    .bind(scope.to)
+    .bind(report.energy_model_version)
+    .bind(report.sets_recomputed as i32)
+    .bind(report.sets_changed as i32)
+    .bind(report.sessions_changed as i32)
+    .bind(report.previous_energy_kcal)
+    .bind(report.energy_kcal)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(report)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecomputeEnergyRequest {
+    /// RFC 3339 timestamp or `YYYY-MM-DD`, by session start
+    pub from: Option<String>,
+    /// Inclusive; a bare date covers the whole day
+    pub to: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub use_latest_measurement: bool,
+}
+
+/// Recompute stored set and session energy after a measurement fix or an energy model
+/// change, reporting the deltas per session
+pub async fn recompute_energy(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Json(req): Json<RecomputeEnergyRequest>,
+) -> impl IntoResponse {
+    let bounds = (
+        req.from.as_deref().map(|v| parse_bound("from", v, false)).transpose(),
+        req.to.as_deref().map(|v| parse_bound("to", v, true)).transpose(),
+    );
+    let (from, to) = match bounds {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response()
+        }
+    };
+    if let (Some(from), Some(to)) = (from, to) {
+        if from >= to {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "from must be before to"})))
+                .into_response();
+        }
+    }
+    let scope = RecomputeScope {
+        user_id: user.id,
+        from,
+        to,
+        dry_run: req.dry_run,
+        use_latest_measurement: req.use_latest_measurement,
+    };
+    match recompute_user_energy(&pool, &scope).await {
+        Ok(report) => (StatusCode::OK, Json(json!(report))).into_response(),
+        Err(e) => {
+            tracing::error!("recompute_energy failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
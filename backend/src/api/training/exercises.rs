@@ -1,80 +1,169 @@
 use super::*;
 use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training::validation::{
+    validate_exercise, ExerciseDefinition, ExerciseMuscleInput,
+};
 use axum::extract::{Extension, Json, Path, Query};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use serde_json::json;
-use sqlx::{PgPool, Row};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row, Transaction};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Text search vector; must match the expression of `idx_exercises_search`
+const SEARCH_VECTOR_SQL: &str =
+    "to_tsvector('english', e.name || ' ' || coalesce(e.description, ''))";
+
+/// Facet whose own filter is left out so its counts show the alternatives
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Facet {
+    Equipment,
+    Pattern,
+}
+
+/// Append the filters as bound conditions on `exercises e`
+fn push_exercise_filters(
+    qb: &mut QueryBuilder<'_, Postgres>,
+    user_id: Uuid,
+    params: &ExerciseFilterParams,
+    skip: Option<Facet>,
+) {
+    qb.push(" WHERE (e.is_system_default = TRUE OR e.user_id = ").push_bind(user_id).push(")");
+    if let Some(equipment) = params.equipment.clone().filter(|_| skip != Some(Facet::Equipment)) {
+        qb.push(" AND e.equipment = ").push_bind(equipment);
+    }
+    if let Some(pattern) = params.pattern.clone().filter(|_| skip != Some(Facet::Pattern)) {
+        qb.push(" AND e.movement_pattern = ").push_bind(pattern);
+    }
+    if let Some(difficulty) = params.difficulty.clone() {
+        qb.push(" AND e.difficulty = ").push_bind(difficulty);
+    }
+    if params.muscle.is_some() || params.involvement.is_some() {
+        qb.push(
+            " AND EXISTS (SELECT 1 FROM exercise_muscles em JOIN muscle_groups mg ON mg.id = em.muscle_group_id
+               WHERE em.exercise_id = e.id",
+        );
+        if let Some(muscle) = params.muscle.clone() {
+            qb.push(" AND lower(mg.name) = lower(").push_bind(muscle).push(")");
+        }
+        if let Some(involvement) = params.involvement.clone() {
+            qb.push(" AND em.involvement = ").push_bind(involvement);
+        }
+        qb.push(")");
+    }
+    if let Some(search) = params.search.clone() {
+        qb.push(format!(" AND ({SEARCH_VECTOR_SQL} @@ websearch_to_tsquery('english', "))
+            .push_bind(search.clone())
+            .push(") OR strpos(lower(e.name), lower(")
+            .push_bind(search)
+            .push(")) > 0)");
+    }
+}
+
+/// Counts per value of `column` under the filters, except the facet's own
+async fn facet_counts(
+    pool: &PgPool,
+    user_id: Uuid,
+    params: &ExerciseFilterParams,
+    facet: Facet,
+) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let column = match facet {
+        Facet::Equipment => "e.equipment",
+        Facet::Pattern => "e.movement_pattern",
+    };
+    let mut qb =
+        QueryBuilder::new(format!("SELECT {column} as value, COUNT(*) as count FROM exercises e"));
+    push_exercise_filters(&mut qb, user_id, params, Some(facet));
+    qb.push(" GROUP BY value ORDER BY value");
+    let rows = qb.build().fetch_all(pool).await?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            json!({
+                "value": row.try_get::<String, _>("value").unwrap_or_default(),
+                "count": row.try_get::<i64, _>("count").unwrap_or(0),
+            })
+        })
+        .collect())
+}
+
 pub async fn list_exercises(
     AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
     Query(params): Query<ExerciseFilterParams>,
+    Query(page): Query<PaginationParams>,
 ) -> impl IntoResponse {
-    // Build dynamic query with filters
-    let mut query = String::from(
+    if let Some(involvement) = &params.involvement {
+        if !matches!(involvement.as_str(), "primary" | "secondary" | "stabilizer") {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "involvement must be primary, secondary or stabilizer"})),
+            )
+                .into_response();
+        }
+    }
+    // Without paging parameters the whole catalogue is returned, as before paging existed
+    let paged = page.limit.is_some() || page.offset.is_some();
+    let limit = paged.then(|| page.limit.unwrap_or(100).clamp(1, 200));
+    let offset = page.offset.unwrap_or(0).max(0);
+
+    let mut qb = QueryBuilder::new(
         "SELECT e.id, e.name, e.description, e.movement_pattern, e.equipment, e.difficulty,
                 e.is_bodyweight, e.is_unilateral, e.primary_segments_moved, e.rom_degrees,
-                e.body_mass_fraction_moved, e.is_system_default, e.metadata
-         FROM exercises e WHERE (e.is_system_default = TRUE OR e.user_id = $1)",
+                e.body_mass_fraction_moved, e.is_system_default, e.metadata,
+                COUNT(*) OVER () as total
+         FROM exercises e",
     );
-    let mut param_idx = 2u32;
-
-    if let Some(ref equipment) = params.equipment {
-        query.push_str(&format!(" AND e.equipment = ${param_idx}"));
-        param_idx += 1;
-        let _ = equipment;
+    push_exercise_filters(&mut qb, user.id, &params, None);
+    qb.push(" ORDER BY ");
+    if let Some(search) = params.search.clone() {
+        // Name matches first, then the best text matches
+        qb.push("(strpos(lower(e.name), lower(")
+            .push_bind(search.clone())
+            .push(format!(
+                ")) > 0) DESC, ts_rank({SEARCH_VECTOR_SQL}, websearch_to_tsquery('english', "
+            ))
+            .push_bind(search)
+            .push(")) DESC, ");
     }
-    if let Some(ref pattern) = params.pattern {
-        query.push_str(&format!(" AND e.movement_pattern = ${param_idx}"));
-        param_idx += 1;
-        let _ = pattern;
-    }
-    if let Some(ref difficulty) = params.difficulty {
-        query.push_str(&format!(" AND e.difficulty = ${param_idx}"));
-        param_idx += 1;
-        let _ = difficulty;
-    }
-    if let Some(ref search) = params.search {
-        query.push_str(&format!(" AND lower(e.name) LIKE '%' || lower(${param_idx}) || '%'"));
-        let _ = (param_idx, search);
+    qb.push("e.is_system_default DESC, e.name");
+    if let Some(limit) = limit {
+        qb.push(" LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
     }
 
-    query.push_str(" ORDER BY e.is_system_default DESC, e.name");
+    let result: Result<_, sqlx::Error> = async {
+        let rows = qb.build().fetch_all(&*pool).await?;
+        let equipment = facet_counts(&pool, user.id, &params, Facet::Equipment).await?;
+        let patterns = facet_counts(&pool, user.id, &params, Facet::Pattern).await?;
+        Ok((rows, equipment, patterns))
+    }
+    .await;
 
-    // Use simpler approach: fetch all and filter in Rust for dynamic params
-    // (sqlx doesn't support dynamic bind count easily)
-    match sqlx::query(
-        "SELECT e.id, e.name, e.description, e.movement_pattern, e.equipment, e.difficulty,
-                e.is_bodyweight, e.is_unilateral, e.primary_segments_moved, e.rom_degrees,
-                e.body_mass_fraction_moved, e.is_system_default, e.metadata
-         FROM exercises e WHERE (e.is_system_default = TRUE OR e.user_id = $1)
-         ORDER BY e.is_system_default DESC, e.name",
-    )
-    .bind(user.id)
-    .fetch_all(&*pool)
-    .await
-    {
-        Ok(rows) => {
-            let exercises: Vec<serde_json::Value> = rows.iter().filter(|row| {
-                // Apply filters in Rust
-                let matches_equipment = params.equipment.as_ref().is_none_or(|eq| {
-                    row.try_get::<String, _>("equipment").ok().as_deref() == Some(eq.as_str())
-                });
-                let matches_pattern = params.pattern.as_ref().is_none_or(|p| {
-                    row.try_get::<String, _>("movement_pattern").ok().as_deref() == Some(p.as_str())
-                });
-                let matches_difficulty = params.difficulty.as_ref().is_none_or(|d| {
-                    row.try_get::<String, _>("difficulty").ok().as_deref() == Some(d.as_str())
-                });
-                let matches_search = params.search.as_ref().is_none_or(|s| {
-                    row.try_get::<String, _>("name").ok()
-                        .is_some_and(|n| n.to_lowercase().contains(&s.to_lowercase()))
-                });
-                matches_equipment && matches_pattern && matches_difficulty && matches_search
-            }).map(|row| {
+    match result {
+        Ok((rows, equipment, patterns)) => {
+            // Past the last page there is no row to carry the window count
+            let total = match rows.first() {
+                Some(row) => row.try_get::<i64, _>("total").unwrap_or(0),
+                None if offset > 0 => {
+                    let mut count = QueryBuilder::new("SELECT COUNT(*) FROM exercises e");
+                    push_exercise_filters(&mut count, user.id, &params, None);
+                    match count.build_query_scalar::<i64>().fetch_one(&*pool).await {
+                        Ok(n) => n,
+                        Err(e) => {
+                            tracing::error!("list_exercises count failed: {e}");
+                            return (
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                Json(json!({"error": "internal"})),
+                            )
+                                .into_response();
+                        }
+                    }
+                }
+                None => 0,
+            };
+            let exercises: Vec<serde_json::Value> = rows.iter().map(|row| {
                 json!({
                     "id": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
                     "name": row.try_get::<String, _>("name").unwrap_or_default(),
@@ -91,7 +180,17 @@ pub async fn list_exercises(
                     "metadata": row.try_get::<serde_json::Value, _>("metadata").unwrap_or(json!({})),
                 })
             }).collect();
-            (StatusCode::OK, Json(json!({"exercises": exercises}))).into_response()
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "exercises": exercises,
+                    "total": total,
+                    "limit": limit,
+                    "offset": offset,
+                    "facets": {"equipment": equipment, "movementPattern": patterns},
+                })),
+            )
+                .into_response()
         }
         Err(e) => {
             tracing::error!("list_exercises failed: {e}");
@@ -101,6 +200,7 @@ pub async fn list_exercises(
 }
 
 pub async fn get_exercise(
+    AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
@@ -114,9 +214,10 @@ pub async fn get_exercise(
     let exercise = sqlx::query(
         "SELECT id, name, description, movement_pattern, equipment, difficulty, is_bodyweight, is_unilateral,
                 primary_segments_moved, rom_degrees, body_mass_fraction_moved, is_system_default, metadata
-         FROM exercises WHERE id = $1"
+         FROM exercises WHERE id = $1 AND (is_system_default = TRUE OR user_id = $2)"
     )
     .bind(uuid)
+    .bind(user.id)
     .fetch_optional(&*pool)
     .await;
 
@@ -166,3 +267,270 @@ pub async fn get_exercise(
         }
     }
 }
+
+/// Replace the muscle mappings of an exercise, resolving names against `muscle_groups`.
+///
+/// The outer error is a database failure, the inner one an unknown muscle name.
+async fn replace_muscles(
+    tx: &mut Transaction<'_, Postgres>,
+    exercise_id: Uuid,
+    muscles: &[ExerciseMuscleInput],
+) -> Result<Result<(), String>, sqlx::Error> {
+    let names: Vec<String> = muscles.iter().map(|m| m.name.to_lowercase()).collect();
+    let ids: HashMap<String, Uuid> = sqlx::query(
+        "SELECT id, lower(name) AS name FROM muscle_groups WHERE lower(name) = ANY($1)",
+    )
+    .bind(&names)
+    .fetch_all(&mut **tx)
+    .await?
+    .iter()
+    .filter_map(|row| Some((row.try_get("name").ok()?, row.try_get("id").ok()?)))
+    .collect();
+    if let Some(unknown) = names.iter().find(|n| !ids.contains_key(*n)) {
+        return Ok(Err(format!("unknown muscle: {unknown}")));
+    }
+
+    sqlx::query("DELETE FROM exercise_muscles WHERE exercise_id = $1")
+        .bind(exercise_id)
+        .execute(&mut **tx)
+        .await?;
+    for (muscle, name) in muscles.iter().zip(&names) {
+        sqlx::query(
+            "INSERT INTO exercise_muscles (exercise_id, muscle_group_id, involvement, activation_fraction)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(exercise_id)
+        .bind(ids[name])
+        .bind(&muscle.involvement)
+        .bind(muscle.activation_fraction)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(Ok(()))
+}
+
+/// Insert or update (when `id` is given) a user-owned exercise with its muscles.
+///
+/// Returns `None` when `id` does not name an exercise owned by the user.
+async fn save_exercise(
+    pool: &PgPool,
+    user_id: Uuid,
+    id: Option<Uuid>,
+    def: &ExerciseDefinition,
+) -> Result<Option<Result<Uuid, String>>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let query = match id {
+        None => sqlx::query(
+            "INSERT INTO exercises (user_id, name, description, movement_pattern, equipment, difficulty,
+                                    is_bodyweight, is_unilateral, primary_segments_moved, rom_degrees,
+                                    body_mass_fraction_moved, is_system_default)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, FALSE)
+             RETURNING id",
+        ),
+        Some(_) => sqlx::query(
+            "UPDATE exercises SET name = $2, description = $3, movement_pattern = $4, equipment = $5,
+                    difficulty = $6, is_bodyweight = $7, is_unilateral = $8, primary_segments_moved = $9,
+                    rom_degrees = $10, body_mass_fraction_moved = $11, updated_at = now()
+             WHERE id = $12 AND user_id = $1 AND is_system_default = FALSE
+             RETURNING id",
+        ),
+    };
+    let query = query
+        .bind(user_id)
+        .bind(def.name.trim())
+        .bind(&def.description)
+        .bind(&def.movement_pattern)
+        .bind(&def.equipment)
+        .bind(&def.difficulty)
+        .bind(def.is_bodyweight)
+        .bind(def.is_unilateral)
+        .bind(&def.primary_segments_moved)
+        .bind(def.rom_degrees)
+        .bind(def.body_mass_fraction_moved);
+    let query = match id {
+        Some(id) => query.bind(id),
+        None => query,
+    };
+    let row = query.fetch_optional(&mut *tx).await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let exercise_id: Uuid = row.try_get("id")?;
+
+    if let Err(e) = replace_muscles(&mut tx, exercise_id, &def.muscles).await? {
+        return Ok(Some(Err(e)));
+    }
+    tx.commit().await?;
+    Ok(Some(Ok(exercise_id)))
+}
+
+pub async fn create_exercise(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Json(def): Json<ExerciseDefinition>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_exercise(&def) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response();
+    }
+    match save_exercise(&pool, user.id, None, &def).await {
+        Ok(Some(Ok(id))) => {
+            (StatusCode::CREATED, Json(json!({"id": id.to_string()}))).into_response()
+        }
+        Ok(Some(Err(e))) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))).into_response(),
+        Err(e) => {
+            tracing::error!("create_exercise failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Replace a user-owned exercise; system exercises have to be cloned first
+pub async fn update_exercise(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+    Json(def): Json<ExerciseDefinition>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    if let Err(e) = validate_exercise(&def) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response();
+    }
+    match save_exercise(&pool, user.id, Some(uuid), &def).await {
+        Ok(Some(Ok(id))) => (StatusCode::OK, Json(json!({"id": id.to_string()}))).into_response(),
+        Ok(Some(Err(e))) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))).into_response(),
+        Err(e) => {
+            tracing::error!("update_exercise failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Copy a system (or own) exercise and its muscle mappings into a new user-owned exercise
+pub async fn clone_exercise(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    let result: Result<Option<Uuid>, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        let row = sqlx::query(
+            "INSERT INTO exercises (user_id, name, description, movement_pattern, equipment, difficulty,
+                                    is_bodyweight, is_unilateral, primary_segments_moved, rom_degrees,
+                                    body_mass_fraction_moved, is_system_default, metadata)
+             SELECT $2, name || ' (copy)', description, movement_pattern, equipment, difficulty,
+                    is_bodyweight, is_unilateral, primary_segments_moved, rom_degrees,
+                    body_mass_fraction_moved, FALSE, jsonb_build_object('clonedFrom', id)
+             FROM exercises WHERE id = $1 AND (is_system_default = TRUE OR user_id = $2)
+             RETURNING id",
+        )
+        .bind(uuid)
+        .bind(user.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let new_id: Uuid = row.try_get("id")?;
+        sqlx::query(
+            "INSERT INTO exercise_muscles (exercise_id, muscle_group_id, involvement, activation_fraction)
+             SELECT $2, muscle_group_id, involvement, activation_fraction
+             FROM exercise_muscles WHERE exercise_id = $1",
+        )
+        .bind(uuid)
+        .bind(new_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(new_id))
+    }
+    .await;
+
+    match result {
+        Ok(Some(id)) => (StatusCode::CREATED, Json(json!({"id": id.to_string()}))).into_response(),
+        Ok(None) => {
+            (StatusCode::NOT_FOUND, Json(json!({"error": "exercise not found"}))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("clone_exercise failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Delete a user-owned exercise that no logged set or training plan refers to
+pub async fn delete_exercise(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    // Sets, plan entries and planned sets cascade with their exercise, so history, plans and
+    // live sessions would silently lose it
+    match sqlx::query(
+        "DELETE FROM exercises e
+         WHERE e.id = $1 AND e.user_id = $2 AND e.is_system_default = FALSE
+           AND NOT EXISTS (SELECT 1 FROM workout_sets ws WHERE ws.exercise_id = e.id)
+           AND NOT EXISTS (SELECT 1 FROM training_plan_exercises tpe WHERE tpe.exercise_id = e.id)
+           AND NOT EXISTS (SELECT 1 FROM planned_sets ps WHERE ps.exercise_id = e.id)",
+    )
+    .bind(uuid)
+    .bind(user.id)
+    .execute(&*pool)
+    .await
+    {
+        Ok(result) if result.rows_affected() > 0 => {
+            (StatusCode::OK, Json(json!({"ok": true}))).into_response()
+        }
+        Ok(_) => {
+            let in_use = sqlx::query(
+                "SELECT 1 FROM exercises e
+                 WHERE e.id = $1 AND e.user_id = $2
+                   AND (EXISTS (SELECT 1 FROM workout_sets ws WHERE ws.exercise_id = e.id)
+                        OR EXISTS (SELECT 1 FROM training_plan_exercises tpe
+                                   WHERE tpe.exercise_id = e.id)
+                        OR EXISTS (SELECT 1 FROM planned_sets ps WHERE ps.exercise_id = e.id))",
+            )
+            .bind(uuid)
+            .bind(user.id)
+            .fetch_optional(&*pool)
+            .await;
+            match in_use {
+                Ok(Some(_)) => (
+                    StatusCode::CONFLICT,
+                    Json(json!({"error": "exercise is used by logged sets or training plans"})),
+                )
+                    .into_response(),
+                Ok(None) => {
+                    (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))).into_response()
+                }
+                Err(e) => {
+                    tracing::error!("delete_exercise failed: {e}");
+                    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                        .into_response()
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!("delete_exercise failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
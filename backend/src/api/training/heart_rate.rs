@@ -0,0 +1,222 @@
+use super::{session_body_weight, tempo_from_row};
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::metabolism::Sex;
+use crate::tools::training::wearable::{
+    heart_rate_energy_kcal, parse_heart_rate, set_window, summarize_window, HeartRateSample,
+    WearableFormat,
+};
+use axum::body::Bytes;
+use axum::extract::{Extension, Json, Path, Query};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct HeartRateImportParams {
+    /// Detected from the file contents when omitted
+    pub format: Option<WearableFormat>,
+    pub sex: Sex,
+    pub age_years: f64,
+}
+
+/// Heart-rate kcal next to the mechanical estimate, and heart rate per set
+async fn heart_rate_report(
+    pool: &PgPool,
+    session_id: Uuid,
+) -> Result<Option<serde_json::Value>, sqlx::Error> {
+    let session = sqlx::query(
+        "SELECT ws.heart_rate_source, ws.heart_rate_energy_kcal::float8 as heart_rate_energy,
+                (SELECT SUM(energy_kcal)::float8 FROM workout_sets WHERE session_id = ws.id) as mechanical_energy
+         FROM workout_sessions ws WHERE ws.id = $1 AND ws.heart_rate_source IS NOT NULL",
+    )
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some(session) = session else {
+        return Ok(None);
+    };
+
+    let samples: Vec<HeartRateSample> = sqlx::query(
+        "SELECT recorded_at, bpm FROM session_heart_rate_samples
+         WHERE session_id = $1 ORDER BY recorded_at",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| HeartRateSample {
+        time: row.try_get("recorded_at").unwrap_or_default(),
+        bpm: row.try_get::<i32, _>("bpm").unwrap_or(0).max(0) as u32,
+    })
+    .collect();
+
+    let set_rows = sqlx::query(
+        "SELECT wse.id, wse.exercise_id, e.name as exercise_name, wse.set_number, wse.reps, wse.performed_at,
+                wse.tempo_eccentric_s::float8 as tempo_eccentric_s, wse.tempo_pause_bottom_s::float8 as tempo_pause_bottom_s,
+                wse.tempo_concentric_s::float8 as tempo_concentric_s, wse.tempo_pause_top_s::float8 as tempo_pause_top_s
+         FROM workout_sets wse JOIN exercises e ON e.id = wse.exercise_id
+         WHERE wse.session_id = $1 ORDER BY wse.performed_at",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?;
+    let sets: Vec<serde_json::Value> = set_rows
+        .iter()
+        .map(|row| {
+            let tempo = tempo_from_row(row);
+            let reps = row.try_get::<i32, _>("reps").unwrap_or(0).max(0) as u32;
+            let window = set_window(row.try_get("performed_at").unwrap_or_default(), reps, &tempo);
+            let summary = summarize_window(&samples, &window);
+            json!({
+                "setId": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
+                "exerciseId": row.try_get::<Uuid, _>("exercise_id").unwrap_or_default().to_string(),
+                "exerciseName": row.try_get::<String, _>("exercise_name").unwrap_or_default(),
+                "setNumber": row.try_get::<i32, _>("set_number").unwrap_or(0),
+                "windowStart": window.start.to_rfc3339(),
+                "windowEnd": window.end.to_rfc3339(),
+                "avgHeartRate": summary.map(|s| s.avg_bpm),
+                "maxHeartRate": summary.map(|s| s.max_bpm),
+            })
+        })
+        .collect();
+
+    Ok(Some(json!({
+        "source": session.try_get::<String, _>("heart_rate_source").unwrap_or_default(),
+        "sampleCount": samples.len(),
+        "startedAt": samples.first().map(|s| s.time.to_rfc3339()),
+        "endedAt": samples.last().map(|s| s.time.to_rfc3339()),
+        "avgHeartRate": (!samples.is_empty()).then(|| {
+            f64::from(samples.iter().map(|s| s.bpm).sum::<u32>()) / samples.len() as f64
+        }),
+        "maxHeartRate": samples.iter().map(|s| s.bpm).max(),
+        "heartRateEnergyKcal": session.try_get::<Option<f64>, _>("heart_rate_energy").ok().flatten(),
+        "mechanicalEnergyKcal": session.try_get::<Option<f64>, _>("mechanical_energy").ok().flatten().unwrap_or(0.0),
+        "sets": sets,
+    })))
+}
+
+/// Attach a FIT, TCX or GPX recording (raw file as the request body) to a session,
+/// replacing any earlier one
+pub async fn import_heart_rate(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+    Query(params): Query<HeartRateImportParams>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    if !(10.0..=100.0).contains(&params.age_years) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "age_years must be 10-100"})))
+            .into_response();
+    }
+    let Some(format) = params.format.or_else(|| WearableFormat::detect(&body)) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "unrecognised file; expected FIT, TCX or GPX"})),
+        )
+            .into_response();
+    };
+    let samples = match parse_heart_rate(format, &body) {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+
+    let body_weight_kg = match session_body_weight(&pool, uuid, user.id).await {
+        Ok(Some(weight)) => weight,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({"error": "session not found"})))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("import_heart_rate session lookup failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    let energy = heart_rate_energy_kcal(&samples, params.sex, params.age_years, body_weight_kg);
+
+    let result: Result<Option<serde_json::Value>, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM session_heart_rate_samples WHERE session_id = $1")
+            .bind(uuid)
+            .execute(&mut *tx)
+            .await?;
+        let times: Vec<_> = samples.iter().map(|s| s.time).collect();
+        let bpms: Vec<i32> = samples.iter().map(|s| s.bpm as i32).collect();
+        sqlx::query(
+            "INSERT INTO session_heart_rate_samples (session_id, recorded_at, bpm)
+             SELECT $1, * FROM UNNEST($2::timestamptz[], $3::int4[])",
+        )
+        .bind(uuid)
+        .bind(&times)
+        .bind(&bpms)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "UPDATE workout_sessions SET heart_rate_source = $1, heart_rate_energy_kcal = $2,
+                heart_rate_sex = $3, heart_rate_age_years = $4, updated_at = now()
+             WHERE id = $5",
+        )
+        .bind(json!(format).as_str().unwrap_or_default())
+        .bind(energy)
+        .bind(json!(params.sex).as_str().unwrap_or_default())
+        .bind(params.age_years)
+        .bind(uuid)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        heart_rate_report(&pool, uuid).await
+    }
+    .await;
+
+    match result {
+        Ok(report) => (StatusCode::CREATED, Json(report.unwrap_or_default())).into_response(),
+        Err(e) => {
+            tracing::error!("import_heart_rate failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn session_heart_rate(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    let owned = sqlx::query("SELECT 1 FROM workout_sessions WHERE id = $1 AND user_id = $2")
+        .bind(uuid)
+        .bind(user.id)
+        .fetch_optional(&*pool)
+        .await;
+    let report = match owned {
+        Ok(Some(_)) => heart_rate_report(&pool, uuid).await,
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    };
+    match report {
+        Ok(Some(report)) => (StatusCode::OK, Json(report)).into_response(),
+        Ok(None) => {
+            (StatusCode::NOT_FOUND, Json(json!({"error": "no heart-rate data for session"})))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("session_heart_rate failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
@@ -1,5 +1,10 @@
 use super::*;
 use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::metabolism::Sex;
+use crate::tools::training::body_composition::{
+    estimate_body_composition, BodyComposition, Circumferences, Skinfolds,
+};
+use crate::tools::training::{display_weight, WeightUnit};
 use axum::extract::{Extension, Json, Path, Query};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
@@ -9,10 +14,20 @@ use sqlx::{PgPool, Row};
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Largest values the measurement columns hold: DECIMAL(6,2) kg, DECIMAL(5,1) cm and
+/// DECIMAL(4,1) mm
+const MAX_BODY_WEIGHT_KG: f64 = 9999.99;
+const MAX_TAPE_CM: f64 = 9999.9;
+const MAX_SKINFOLD_MM: f64 = 999.9;
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateMeasurementRequest {
-    pub body_weight_kg: f64,
+    /// Exactly one of `body_weight_kg` and `body_weight` is required
+    pub body_weight_kg: Option<f64>,
+    /// Body weight in `weight_unit` (the user's preferred unit by default)
+    pub body_weight: Option<f64>,
+    pub weight_unit: Option<WeightUnit>,
     pub height_cm: Option<f64>,
     pub leg_length_cm: Option<f64>,
     pub upper_leg_length_cm: Option<f64>,
@@ -22,18 +37,103 @@ pub struct CreateMeasurementRequest {
     pub lower_arm_length_cm: Option<f64>,
     pub torso_length_cm: Option<f64>,
     pub shoulder_width_cm: Option<f64>,
+    pub neck_cm: Option<f64>,
+    pub waist_cm: Option<f64>,
+    pub hip_cm: Option<f64>,
+    pub skinfold_chest_mm: Option<f64>,
+    pub skinfold_abdominal_mm: Option<f64>,
+    pub skinfold_thigh_mm: Option<f64>,
+    pub skinfold_triceps_mm: Option<f64>,
+    pub skinfold_suprailiac_mm: Option<f64>,
+    pub skinfold_subscapular_mm: Option<f64>,
+    pub skinfold_midaxillary_mm: Option<f64>,
     pub measured_at: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct BodyCompositionParams {
+    pub sex: Sex,
+    /// Needed for the Jackson-Pollock skinfold methods
+    pub age_years: Option<f64>,
+}
+
 pub async fn create_measurement(
     AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
     Json(req): Json<CreateMeasurementRequest>,
 ) -> impl IntoResponse {
-    if req.body_weight_kg <= 0.0 {
+    let field = match (req.body_weight_kg, req.body_weight) {
+        (None, None) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "bodyWeightKg or bodyWeight is required"})),
+            )
+                .into_response()
+        }
+        (Some(_), Some(_)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "send either bodyWeightKg or bodyWeight, not both"})),
+            )
+                .into_response()
+        }
+        (Some(_), None) => "bodyWeightKg",
+        (None, Some(_)) => "bodyWeight",
+    };
+    let entered =
+        match resolve_entered_weight(&pool, user.id, req.body_weight, req.weight_unit).await {
+            Ok(entered) => entered,
+            Err(e) => {
+                tracing::error!("create_measurement unit lookup failed: {e}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                    .into_response();
+            }
+        };
+    let body_weight_kg =
+        entered.map_or(req.body_weight_kg.unwrap_or_default(), |e| e.unit.to_kg(e.value));
+    if !(body_weight_kg.is_finite() && body_weight_kg > 0.0) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("{field} must be positive")})),
+        )
+            .into_response();
+    }
+    // Also keeps `body_weight_input` (DECIMAL(8,3)) in range, as no unit is lighter than kg
+    if body_weight_kg > MAX_BODY_WEIGHT_KG {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("{field} must be at most {MAX_BODY_WEIGHT_KG} kg")})),
+        )
+            .into_response();
+    }
+    let lengths = [
+        ("heightCm", req.height_cm, MAX_TAPE_CM),
+        ("legLengthCm", req.leg_length_cm, MAX_TAPE_CM),
+        ("upperLegLengthCm", req.upper_leg_length_cm, MAX_TAPE_CM),
+        ("lowerLegLengthCm", req.lower_leg_length_cm, MAX_TAPE_CM),
+        ("armLengthCm", req.arm_length_cm, MAX_TAPE_CM),
+        ("upperArmLengthCm", req.upper_arm_length_cm, MAX_TAPE_CM),
+        ("lowerArmLengthCm", req.lower_arm_length_cm, MAX_TAPE_CM),
+        ("torsoLengthCm", req.torso_length_cm, MAX_TAPE_CM),
+        ("shoulderWidthCm", req.shoulder_width_cm, MAX_TAPE_CM),
+        ("neckCm", req.neck_cm, MAX_TAPE_CM),
+        ("waistCm", req.waist_cm, MAX_TAPE_CM),
+        ("hipCm", req.hip_cm, MAX_TAPE_CM),
+        ("skinfoldChestMm", req.skinfold_chest_mm, MAX_SKINFOLD_MM),
+        ("skinfoldAbdominalMm", req.skinfold_abdominal_mm, MAX_SKINFOLD_MM),
+        ("skinfoldThighMm", req.skinfold_thigh_mm, MAX_SKINFOLD_MM),
+        ("skinfoldTricepsMm", req.skinfold_triceps_mm, MAX_SKINFOLD_MM),
+        ("skinfoldSuprailiacMm", req.skinfold_suprailiac_mm, MAX_SKINFOLD_MM),
+        ("skinfoldSubscapularMm", req.skinfold_subscapular_mm, MAX_SKINFOLD_MM),
+        ("skinfoldMidaxillaryMm", req.skinfold_midaxillary_mm, MAX_SKINFOLD_MM),
+    ];
+    if let Some((field, _, max)) = lengths
+        .iter()
+        .find(|(_, value, max)| value.is_some_and(|v| !(v.is_finite() && v > 0.0 && v <= *max)))
+    {
         return (
             StatusCode::BAD_REQUEST,
-            Json(json!({"error": "body_weight_kg must be positive"})),
+            Json(json!({"error": format!("{field} must be positive and at most {max}")})),
         )
             .into_response();
     }
@@ -46,13 +146,13 @@ pub async fn create_measurement(
         .unwrap_or_else(chrono::Utc::now);
 
     match sqlx::query(
-        "INSERT INTO body_measurements (user_id, measured_at, body_weight_kg, height_cm, leg_length_cm, upper_leg_length_cm, lower_leg_length_cm, arm_length_cm, upper_arm_length_cm, lower_arm_length_cm, torso_length_cm, shoulder_width_cm)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        "INSERT INTO body_measurements (user_id, measured_at, body_weight_kg, height_cm, leg_length_cm, upper_leg_length_cm, lower_leg_length_cm, arm_length_cm, upper_arm_length_cm, lower_arm_length_cm, torso_length_cm, shoulder_width_cm, neck_cm, waist_cm, hip_cm, skinfold_chest_mm, skinfold_abdominal_mm, skinfold_thigh_mm, skinfold_triceps_mm, skinfold_suprailiac_mm, skinfold_subscapular_mm, skinfold_midaxillary_mm, body_weight_input, body_weight_unit)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24)
          RETURNING id"
     )
     .bind(user.id)
     .bind(measured_at)
-    .bind(req.body_weight_kg)
+    .bind(body_weight_kg)
     .bind(req.height_cm)
     .bind(req.leg_length_cm)
     .bind(req.upper_leg_length_cm)
@@ -62,6 +162,18 @@ pub async fn create_measurement(
     .bind(req.lower_arm_length_cm)
     .bind(req.torso_length_cm)
     .bind(req.shoulder_width_cm)
+    .bind(req.neck_cm)
+    .bind(req.waist_cm)
+    .bind(req.hip_cm)
+    .bind(req.skinfold_chest_mm)
+    .bind(req.skinfold_abdominal_mm)
+    .bind(req.skinfold_thigh_mm)
+    .bind(req.skinfold_triceps_mm)
+    .bind(req.skinfold_suprailiac_mm)
+    .bind(req.skinfold_subscapular_mm)
+    .bind(req.skinfold_midaxillary_mm)
+    .bind(entered.map(|e| e.value))
+    .bind(entered.map(|e| unit_sql(e.unit)))
     .fetch_one(&*pool)
     .await
     {
@@ -76,6 +188,15 @@ pub async fn create_measurement(
     }
 }
 
+/// Body weight of a measurement row in the given unit, as entered when the units match
+fn body_weight(row: &sqlx::postgres::PgRow, unit: WeightUnit) -> f64 {
+    let entered = entered_weight(
+        row.try_get("body_weight_input").ok().flatten(),
+        row.try_get("body_weight_unit").ok().flatten(),
+    );
+    display_weight(row.try_get("body_weight_value").unwrap_or(0.0), entered, unit)
+}
+
 pub async fn list_measurements(
     AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
@@ -83,9 +204,17 @@ pub async fn list_measurements(
 ) -> impl IntoResponse {
     let limit = params.limit.unwrap_or(50).min(200);
     let offset = params.offset.unwrap_or(0);
+    let unit = match preferred_weight_unit(&pool, user.id).await {
+        Ok(unit) => unit,
+        Err(e) => {
+            tracing::error!("list_measurements unit lookup failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
 
     match sqlx::query(
-        "SELECT id, measured_at, body_weight_kg, height_cm, leg_length_cm, upper_leg_length_cm, lower_leg_length_cm, arm_length_cm, upper_arm_length_cm, lower_arm_length_cm, torso_length_cm, shoulder_width_cm, created_at
+        "SELECT id, measured_at, body_weight_kg, body_weight_kg::float8 AS body_weight_value, body_weight_input::float8 AS body_weight_input, body_weight_unit, height_cm, leg_length_cm, upper_leg_length_cm, lower_leg_length_cm, arm_length_cm, upper_arm_length_cm, lower_arm_length_cm, torso_length_cm, shoulder_width_cm, neck_cm, waist_cm, hip_cm, skinfold_chest_mm, skinfold_abdominal_mm, skinfold_thigh_mm, skinfold_triceps_mm, skinfold_suprailiac_mm, skinfold_subscapular_mm, skinfold_midaxillary_mm, created_at
          FROM body_measurements WHERE user_id = $1 ORDER BY measured_at DESC LIMIT $2 OFFSET $3"
     )
     .bind(user.id)
@@ -100,6 +229,8 @@ pub async fn list_measurements(
                     "id": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
                     "measuredAt": row.try_get::<chrono::DateTime<chrono::Utc>, _>("measured_at").ok().map(|d| d.to_rfc3339()),
                     "bodyWeightKg": row.try_get::<sqlx::types::BigDecimal, _>("body_weight_kg").ok().map(|d| d.to_string()),
+                    "bodyWeight": body_weight(row, unit),
+                    "weightUnit": unit,
                     "heightCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("height_cm").ok().flatten().map(|d| d.to_string()),
                     "legLengthCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("leg_length_cm").ok().flatten().map(|d| d.to_string()),
                     "upperLegLengthCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("upper_leg_length_cm").ok().flatten().map(|d| d.to_string()),
@@ -109,6 +240,16 @@ pub async fn list_measurements(
                     "lowerArmLengthCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("lower_arm_length_cm").ok().flatten().map(|d| d.to_string()),
                     "torsoLengthCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("torso_length_cm").ok().flatten().map(|d| d.to_string()),
                     "shoulderWidthCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("shoulder_width_cm").ok().flatten().map(|d| d.to_string()),
+                    "neckCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("neck_cm").ok().flatten().map(|d| d.to_string()),
+                    "waistCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("waist_cm").ok().flatten().map(|d| d.to_string()),
+                    "hipCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("hip_cm").ok().flatten().map(|d| d.to_string()),
+                    "skinfoldChestMm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("skinfold_chest_mm").ok().flatten().map(|d| d.to_string()),
+                    "skinfoldAbdominalMm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("skinfold_abdominal_mm").ok().flatten().map(|d| d.to_string()),
+                    "skinfoldThighMm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("skinfold_thigh_mm").ok().flatten().map(|d| d.to_string()),
+                    "skinfoldTricepsMm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("skinfold_triceps_mm").ok().flatten().map(|d| d.to_string()),
+                    "skinfoldSuprailiacMm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("skinfold_suprailiac_mm").ok().flatten().map(|d| d.to_string()),
+                    "skinfoldSubscapularMm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("skinfold_subscapular_mm").ok().flatten().map(|d| d.to_string()),
+                    "skinfoldMidaxillaryMm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("skinfold_midaxillary_mm").ok().flatten().map(|d| d.to_string()),
                 })
             }).collect();
             (StatusCode::OK, Json(json!({"measurements": measurements}))).into_response()
@@ -124,8 +265,16 @@ pub async fn latest_measurement(
     AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
 ) -> impl IntoResponse {
+    let unit = match preferred_weight_unit(&pool, user.id).await {
+        Ok(unit) => unit,
+        Err(e) => {
+            tracing::error!("latest_measurement unit lookup failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
     match sqlx::query(
-        "SELECT id, measured_at, body_weight_kg, height_cm, leg_length_cm, upper_leg_length_cm, lower_leg_length_cm, arm_length_cm, upper_arm_length_cm, lower_arm_length_cm, torso_length_cm, shoulder_width_cm
+        "SELECT id, measured_at, body_weight_kg, body_weight_kg::float8 AS body_weight_value, body_weight_input::float8 AS body_weight_input, body_weight_unit, height_cm, leg_length_cm, upper_leg_length_cm, lower_leg_length_cm, arm_length_cm, upper_arm_length_cm, lower_arm_length_cm, torso_length_cm, shoulder_width_cm
          FROM body_measurements WHERE user_id = $1 ORDER BY measured_at DESC LIMIT 1"
     )
     .bind(user.id)
@@ -136,6 +285,8 @@ pub async fn latest_measurement(
             (StatusCode::OK, Json(json!({
                 "id": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
                 "bodyWeightKg": row.try_get::<sqlx::types::BigDecimal, _>("body_weight_kg").ok().map(|d| d.to_string()),
+                "bodyWeight": body_weight(&row, unit),
+                "weightUnit": unit,
                 "heightCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("height_cm").ok().flatten().map(|d| d.to_string()),
                 "upperArmLengthCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("upper_arm_length_cm").ok().flatten().map(|d| d.to_string()),
                 "lowerArmLengthCm": row.try_get::<Option<sqlx::types::BigDecimal>, _>("lower_arm_length_cm").ok().flatten().map(|d| d.to_string()),
@@ -155,6 +306,104 @@ pub async fn latest_measurement(
     }
 }
 
+/// A measurement together with the body composition estimates its inputs allow
+pub(super) struct MeasuredComposition {
+    pub id: Uuid,
+    pub measured_at: chrono::DateTime<chrono::Utc>,
+    pub body_weight_kg: f64,
+    pub height_cm: f64,
+    pub estimates: Vec<BodyComposition>,
+}
+
+/// Body composition for every measurement with enough inputs, oldest first.
+///
+/// Measurements without a height use the most recent earlier one.
+pub(super) async fn load_body_composition(
+    pool: &PgPool,
+    user_id: Uuid,
+    params: &BodyCompositionParams,
+) -> Result<Vec<MeasuredComposition>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, measured_at, body_weight_kg::float8 AS body_weight_kg, height_cm::float8 AS height_cm,
+                neck_cm::float8 AS neck_cm, waist_cm::float8 AS waist_cm, hip_cm::float8 AS hip_cm,
+                skinfold_chest_mm::float8 AS chest_mm, skinfold_abdominal_mm::float8 AS abdominal_mm,
+                skinfold_thigh_mm::float8 AS thigh_mm, skinfold_triceps_mm::float8 AS triceps_mm,
+                skinfold_suprailiac_mm::float8 AS suprailiac_mm, skinfold_subscapular_mm::float8 AS subscapular_mm,
+                skinfold_midaxillary_mm::float8 AS midaxillary_mm
+         FROM body_measurements WHERE user_id = $1 ORDER BY measured_at ASC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut height_cm: Option<f64> = None;
+    let mut history = Vec::new();
+    for row in &rows {
+        let get = |col: &str| row.try_get::<Option<f64>, _>(col).ok().flatten();
+        height_cm = get("height_cm").or(height_cm);
+        let (Some(height), Some(weight), Ok(measured_at)) =
+            (height_cm, get("body_weight_kg"), row.try_get("measured_at"))
+        else {
+            continue;
+        };
+        let tape = Circumferences {
+            neck_cm: get("neck_cm"),
+            waist_cm: get("waist_cm"),
+            hip_cm: get("hip_cm"),
+        };
+        let folds = Skinfolds {
+            chest_mm: get("chest_mm"),
+            abdominal_mm: get("abdominal_mm"),
+            thigh_mm: get("thigh_mm"),
+            triceps_mm: get("triceps_mm"),
+            suprailiac_mm: get("suprailiac_mm"),
+            subscapular_mm: get("subscapular_mm"),
+            midaxillary_mm: get("midaxillary_mm"),
+        };
+        let estimates =
+            estimate_body_composition(params.sex, params.age_years, weight, height, &tape, &folds);
+        if estimates.is_empty() {
+            continue;
+        }
+        history.push(MeasuredComposition {
+            id: row.try_get("id").unwrap_or_default(),
+            measured_at,
+            body_weight_kg: weight,
+            height_cm: height,
+            estimates,
+        });
+    }
+    Ok(history)
+}
+
+pub async fn body_composition_history(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Query(params): Query<BodyCompositionParams>,
+) -> impl IntoResponse {
+    match load_body_composition(&pool, user.id, &params).await {
+        Ok(history) => {
+            let history: Vec<serde_json::Value> = history
+                .into_iter()
+                .map(|m| {
+                    json!({
+                        "id": m.id.to_string(),
+                        "measuredAt": m.measured_at.to_rfc3339(),
+                        "bodyWeightKg": m.body_weight_kg,
+                        "heightCm": m.height_cm,
+                        "estimates": m.estimates,
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(json!({"history": history}))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("body_composition_history failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
 pub async fn delete_measurement(
     AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
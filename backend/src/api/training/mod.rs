@@ -1,21 +1,35 @@
+pub mod cardio;
 pub mod energy;
 pub mod exercises;
+pub mod heart_rate;
 pub mod measurements;
 pub mod muscles;
+pub mod nutrition;
 pub mod plans;
 pub mod plates;
+pub mod preferences;
+pub mod programs;
+pub mod records;
 pub mod sessions;
 pub mod sets;
 pub mod shared;
 pub mod stats;
+pub mod warmup;
 
+pub use cardio::*;
 pub use energy::*;
 pub use exercises::*;
+pub use heart_rate::*;
 pub use measurements::*;
 pub use muscles::*;
+pub use nutrition::*;
 pub use plans::*;
 pub use plates::*;
+pub use preferences::*;
+pub use programs::*;
+pub use records::*;
 pub use sessions::*;
 pub use sets::*;
 pub use shared::*;
 pub use stats::*;
+pub use warmup::*;
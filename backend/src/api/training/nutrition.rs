@@ -0,0 +1,105 @@
+use super::*;
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::metabolism::{ActivityLevel, Sex};
+use crate::tools::training::nutrition::{plan_macros, Goal, NutritionInputs, TrainingWeek};
+use axum::extract::{Extension, Json, Query};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct NutritionPlanParams {
+    pub goal: Goal,
+    pub sex: Sex,
+    pub age_years: Option<f64>,
+    /// Any day of the Monday-Sunday week whose training is used; defaults to today
+    pub week_of: Option<NaiveDate>,
+    pub activity_level: Option<ActivityLevel>,
+}
+
+/// Macro targets from the latest lean-mass estimate and the week's completed training
+pub async fn nutrition_plan(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Query(params): Query<NutritionPlanParams>,
+) -> impl IntoResponse {
+    let day = params.week_of.unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let week_start = day - chrono::Duration::days(i64::from(day.weekday().num_days_from_monday()));
+    let week_end = week_start + chrono::Duration::days(6);
+
+    let composition_params = BodyCompositionParams { sex: params.sex, age_years: params.age_years };
+    let history = match load_body_composition(&pool, user.id, &composition_params).await {
+        Ok(history) => history,
+        Err(e) => {
+            tracing::error!("nutrition_plan failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    // Estimates are ordered Navy, JP3, JP7; the last one uses the most sites
+    let Some((measurement, composition)) = history
+        .iter()
+        .rev()
+        .find(|m| m.measured_at.date_naive() <= week_end)
+        .and_then(|m| m.estimates.last().map(|c| (m, c)))
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "log a measurement with tape or skinfold values first"})),
+        )
+            .into_response();
+    };
+
+    let week = match sqlx::query(
+        "SELECT COUNT(DISTINCT started_at::date)::int8 AS training_days,
+                COALESCE(SUM(total_energy_kcal), 0)::float8 AS energy_kcal,
+                COALESCE(SUM(total_volume_kg), 0)::float8 AS volume_kg
+         FROM workout_sessions
+         WHERE user_id = $1 AND status = 'completed' AND started_at::date BETWEEN $2 AND $3",
+    )
+    .bind(user.id)
+    .bind(week_start)
+    .bind(week_end)
+    .fetch_one(&*pool)
+    .await
+    {
+        Ok(row) => TrainingWeek {
+            training_days: u32::try_from(row.try_get::<i64, _>("training_days").unwrap_or(0))
+                .unwrap_or(0),
+            energy_kcal: row.try_get("energy_kcal").unwrap_or(0.0),
+            volume_kg: row.try_get("volume_kg").unwrap_or(0.0),
+        },
+        Err(e) => {
+            tracing::error!("nutrition_plan failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+
+    let inputs = NutritionInputs {
+        lean_mass_kg: composition.lean_mass_kg,
+        body_weight_kg: measurement.body_weight_kg,
+        goal: params.goal,
+        activity_level: params.activity_level.unwrap_or(ActivityLevel::Sedentary),
+        week: week.clone(),
+    };
+    match plan_macros(&inputs) {
+        Ok(plan) => (
+            StatusCode::OK,
+            Json(json!({
+                "weekStart": week_start,
+                "weekEnd": week_end,
+                "measurementId": measurement.id.to_string(),
+                "bodyComposition": composition,
+                "trainingWeek": week,
+                "plan": plan,
+            })),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    }
+}
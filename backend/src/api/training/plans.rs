@@ -1,10 +1,17 @@
+use super::*;
 use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training::progression::{
+    next_target, BaseTarget, NextTarget, PerformedSet, ProgressionConfig, ProgressionScheme,
+    TargetSet,
+};
+use crate::tools::training::{display_weight, WeightUnit};
 use axum::extract::{Extension, Json, Path};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use serde::Deserialize;
 use serde_json::json;
 use sqlx::{PgPool, Row};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -97,7 +104,9 @@ pub async fn get_plan(
 
     let exercises = sqlx::query(
         "SELECT tpe.id, tpe.exercise_id, e.name as exercise_name, tpe.sort_order, tpe.target_sets,
-                tpe.target_reps, tpe.target_weight_kg, tpe.target_rpe, tpe.rest_seconds, tpe.superset_group, tpe.notes
+                tpe.target_reps, tpe.target_weight_kg, tpe.target_weight_kg::float8 AS target_weight_kg_value,
+                tpe.target_rpe, tpe.rest_seconds, tpe.superset_group, tpe.notes,
+                tpe.progression_scheme, tpe.progression_config
          FROM training_plan_exercises tpe
          JOIN exercises e ON e.id = tpe.exercise_id
          WHERE tpe.plan_id = $1 ORDER BY tpe.sort_order"
@@ -105,9 +114,10 @@ pub async fn get_plan(
     .bind(uuid)
     .fetch_all(&*pool)
     .await;
+    let unit = preferred_weight_unit(&pool, user.id).await;
 
-    match (plan, exercises) {
-        (Ok(Some(row)), Ok(ex_rows)) => {
+    match (plan, exercises, unit) {
+        (Ok(Some(row)), Ok(ex_rows), Ok(unit)) => {
             let exercises: Vec<serde_json::Value> = ex_rows.iter().map(|er| {
                 json!({
                     "id": er.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
@@ -117,10 +127,13 @@ pub async fn get_plan(
                     "targetSets": er.try_get::<i32, _>("target_sets").unwrap_or(3),
                     "targetReps": er.try_get::<i32, _>("target_reps").unwrap_or(10),
                     "targetWeightKg": er.try_get::<Option<sqlx::types::BigDecimal>, _>("target_weight_kg").ok().flatten().map(|d| d.to_string()),
+                    "targetWeight": er.try_get::<Option<f64>, _>("target_weight_kg_value").ok().flatten().map(|kg| display_weight(kg, None, unit)),
                     "targetRpe": er.try_get::<Option<sqlx::types::BigDecimal>, _>("target_rpe").ok().flatten().map(|d| d.to_string()),
                     "restSeconds": er.try_get::<Option<i32>, _>("rest_seconds").ok().flatten(),
                     "supersetGroup": er.try_get::<Option<i32>, _>("superset_group").ok().flatten(),
                     "notes": er.try_get::<Option<String>, _>("notes").ok().flatten(),
+                    "progressionScheme": er.try_get::<String, _>("progression_scheme").unwrap_or_default(),
+                    "progressionConfig": er.try_get::<serde_json::Value, _>("progression_config").unwrap_or_default(),
                 })
             }).collect();
 
@@ -132,15 +145,16 @@ pub async fn get_plan(
                     "description": row.try_get::<Option<String>, _>("description").ok().flatten(),
                     "planType": row.try_get::<String, _>("plan_type").unwrap_or_default(),
                     "isActive": row.try_get::<bool, _>("is_active").unwrap_or(true),
+                    "weightUnit": unit,
                     "exercises": exercises,
                 })),
             )
                 .into_response()
         }
-        (Ok(None), _) => {
+        (Ok(None), _, _) => {
             (StatusCode::NOT_FOUND, Json(json!({"error": "plan not found"}))).into_response()
         }
-        (Err(e), _) | (_, Err(e)) => {
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
             tracing::error!("get_plan failed: {e}");
             (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
         }
@@ -228,6 +242,17 @@ pub struct AddPlanExerciseRequest {
     pub rest_seconds: Option<i32>,
     pub superset_group: Option<i32>,
     pub notes: Option<String>,
+    pub progression_scheme: Option<ProgressionScheme>,
+    pub progression_config: Option<ProgressionConfig>,
+}
+
+fn base_target(req: &AddPlanExerciseRequest) -> BaseTarget {
+    BaseTarget {
+        sets: req.target_sets.unwrap_or(3).max(1) as u32,
+        reps: req.target_reps.unwrap_or(10).max(0) as u32,
+        weight_kg: req.target_weight_kg,
+        rpe: req.target_rpe,
+    }
 }
 
 pub async fn add_plan_exercise(
@@ -261,9 +286,15 @@ pub async fn add_plan_exercise(
         return (StatusCode::NOT_FOUND, Json(json!({"error": "plan not found"}))).into_response();
     }
 
+    let scheme = req.progression_scheme.unwrap_or_default();
+    let config = req.progression_config.clone().unwrap_or_default();
+    if let Err(e) = next_target(scheme, &config, &base_target(&req), &[]) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response();
+    }
+
     match sqlx::query(
-        "INSERT INTO training_plan_exercises (plan_id, exercise_id, sort_order, target_sets, target_reps, target_weight_kg, target_rpe, rest_seconds, superset_group, notes)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id"
+        "INSERT INTO training_plan_exercises (plan_id, exercise_id, sort_order, target_sets, target_reps, target_weight_kg, target_rpe, rest_seconds, superset_group, notes, progression_scheme, progression_config)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING id"
     )
     .bind(plan_uuid)
     .bind(exercise_uuid)
@@ -275,6 +306,8 @@ pub async fn add_plan_exercise(
     .bind(req.rest_seconds)
     .bind(req.superset_group)
     .bind(&req.notes)
+    .bind(json!(scheme).as_str())
+    .bind(json!(config))
     .fetch_one(&*pool)
     .await
     {
@@ -337,3 +370,194 @@ pub async fn delete_plan_exercise(
         }
     }
 }
+
+/// A plan exercise with its next-session prescription
+pub(super) struct PlanExerciseTarget {
+    pub plan_exercise_id: Uuid,
+    pub exercise_id: Uuid,
+    pub exercise_name: String,
+    pub sort_order: i32,
+    pub superset_group: Option<i32>,
+    pub rest_seconds: Option<i32>,
+    pub scheme: ProgressionScheme,
+    pub base: BaseTarget,
+    pub sessions_logged: usize,
+    /// Load step of the exercise's progression settings
+    pub rounding_kg: f64,
+    pub next: Result<NextTarget, String>,
+}
+
+impl PlanExerciseTarget {
+    /// Sets to prescribe; a scheme with invalid settings falls back to the fixed targets
+    pub fn target_sets(&self) -> Vec<TargetSet> {
+        match &self.next {
+            Ok(next) => next.sets.clone(),
+            Err(_) => next_target(
+                ProgressionScheme::Fixed,
+                &ProgressionConfig::default(),
+                &self.base,
+                &[],
+            )
+            .map(|n| n.sets)
+            .unwrap_or_default(),
+        }
+    }
+}
+
+/// Target sets with each weight also in `unit`
+pub(super) fn target_sets_json(sets: &[TargetSet], unit: WeightUnit) -> Vec<serde_json::Value> {
+    sets.iter()
+        .map(|set| {
+            let mut value = json!(set);
+            value["weight"] = json!(set.weight_kg.map(|kg| display_weight(kg, None, unit)));
+            value
+        })
+        .collect()
+}
+
+/// Next-session targets of a plan's exercises, from the plan's completed sessions.
+///
+/// Empty when the plan does not exist or belongs to someone else.
+pub(super) async fn load_plan_targets(
+    pool: &PgPool,
+    user_id: Uuid,
+    plan_id: Uuid,
+) -> Result<Vec<PlanExerciseTarget>, sqlx::Error> {
+    let exercises = sqlx::query(
+        "SELECT tpe.id, tpe.exercise_id, e.name AS exercise_name, tpe.sort_order, tpe.target_sets,
+                tpe.target_reps, tpe.target_weight_kg::float8 AS target_weight_kg,
+                tpe.target_rpe::float8 AS target_rpe, tpe.rest_seconds, tpe.superset_group,
+                tpe.progression_scheme, tpe.progression_config
+         FROM training_plan_exercises tpe
+         JOIN training_plans p ON p.id = tpe.plan_id
+         JOIN exercises e ON e.id = tpe.exercise_id
+         WHERE tpe.plan_id = $1 AND p.user_id = $2 ORDER BY tpe.sort_order",
+    )
+    .bind(plan_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    let sets = sqlx::query(
+        "SELECT ws.session_id, ws.exercise_id, ws.weight_kg::float8 AS weight_kg, ws.reps,
+                ws.rpe::float8 AS rpe
+         FROM workout_sets ws JOIN workout_sessions s ON s.id = ws.session_id
+         WHERE s.plan_id = $1 AND s.user_id = $2 AND s.status = 'completed' AND ws.is_warmup = FALSE
+         ORDER BY s.started_at, ws.set_number",
+    )
+    .bind(plan_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    // Sessions per exercise, oldest first; rows of one session are adjacent
+    let mut history: HashMap<Uuid, Vec<(Uuid, Vec<PerformedSet>)>> = HashMap::new();
+    for row in &sets {
+        let session_id: Uuid = row.try_get("session_id").unwrap_or_default();
+        let sessions = history.entry(row.try_get("exercise_id").unwrap_or_default()).or_default();
+        let set = PerformedSet {
+            weight_kg: row.try_get("weight_kg").unwrap_or(0.0),
+            reps: row.try_get::<i32, _>("reps").unwrap_or(0).max(0) as u32,
+            rpe: row.try_get("rpe").ok().flatten(),
+        };
+        match sessions.last_mut() {
+            Some((id, session)) if *id == session_id => session.push(set),
+            _ => sessions.push((session_id, vec![set])),
+        }
+    }
+
+    Ok(exercises
+        .iter()
+        .map(|row| {
+            let exercise_id: Uuid = row.try_get("exercise_id").unwrap_or_default();
+            let scheme: ProgressionScheme = row
+                .try_get::<String, _>("progression_scheme")
+                .ok()
+                .and_then(|s| serde_json::from_value(json!(s)).ok())
+                .unwrap_or_default();
+            let config: ProgressionConfig = row
+                .try_get::<serde_json::Value, _>("progression_config")
+                .ok()
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default();
+            let base = BaseTarget {
+                sets: row.try_get::<i32, _>("target_sets").unwrap_or(3).max(1) as u32,
+                reps: row.try_get::<i32, _>("target_reps").unwrap_or(10).max(0) as u32,
+                weight_kg: row.try_get("target_weight_kg").ok().flatten(),
+                rpe: row.try_get("target_rpe").ok().flatten(),
+            };
+            // An exercise listed twice in a plan shares its history
+            let sessions: Vec<Vec<PerformedSet>> = history
+                .get(&exercise_id)
+                .map(|s| s.iter().map(|(_, sets)| sets.clone()).collect())
+                .unwrap_or_default();
+            PlanExerciseTarget {
+                plan_exercise_id: row.try_get("id").unwrap_or_default(),
+                exercise_id,
+                exercise_name: row.try_get("exercise_name").unwrap_or_default(),
+                sort_order: row.try_get("sort_order").unwrap_or(0),
+                superset_group: row.try_get("superset_group").ok().flatten(),
+                rest_seconds: row.try_get("rest_seconds").ok().flatten(),
+                scheme,
+                sessions_logged: sessions.len(),
+                rounding_kg: config.rounding_kg,
+                next: next_target(scheme, &config, &base, &sessions),
+                base,
+            }
+        })
+        .collect())
+}
+
+/// Next-session prescription for every exercise of a plan, from the plan's completed sessions
+pub async fn next_plan_targets(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+
+    let result: Result<_, sqlx::Error> = async {
+        Ok((
+            load_plan_targets(&pool, user.id, uuid).await?,
+            preferred_weight_unit(&pool, user.id).await?,
+        ))
+    }
+    .await;
+    match result {
+        Ok((exercises, unit)) => {
+            let targets: Vec<serde_json::Value> = exercises
+                .iter()
+                .map(|t| {
+                    let next = match &t.next {
+                        Ok(next) => {
+                            let mut body = json!(next);
+                            body["sets"] = json!(target_sets_json(&next.sets, unit));
+                            body
+                        }
+                        Err(e) => json!({"scheme": t.scheme, "error": e}),
+                    };
+                    json!({
+                        "planExerciseId": t.plan_exercise_id.to_string(),
+                        "exerciseId": t.exercise_id.to_string(),
+                        "exerciseName": t.exercise_name,
+                        "sessionsLogged": t.sessions_logged,
+                        "next": next,
+                    })
+                })
+                .collect();
+            (
+                StatusCode::OK,
+                Json(json!({"planId": uuid.to_string(), "weightUnit": unit, "exercises": targets})),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("next_plan_targets failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
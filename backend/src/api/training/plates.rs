@@ -1,16 +1,303 @@
-use crate::tools::training::{self};
-use axum::extract::Json;
+use super::parse_weight_unit;
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training::{
+    self, validate_profile, Bar, EquipmentProfile, PlateInventory, WeightUnit,
+};
+use axum::extract::{Extension, Json, Path};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlateCalcRequest {
-    pub target_weight_kg: f64,
+    /// `targetWeightKg` is accepted from clients predating units
+    #[serde(alias = "targetWeightKg")]
+    pub target_weight: f64,
+    /// Defaults to a 20 kg (45 lb) Olympic bar
+    #[serde(alias = "barWeightKg")]
+    pub bar_weight: Option<f64>,
+    /// Defaults to unlimited standard plates of the unit
+    pub plates: Option<Vec<PlateInventory>>,
+    /// Unit of all weights in the request and response; kg by default
+    #[serde(default)]
+    pub unit: WeightUnit,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EquipmentProfileRequest {
+    pub name: String,
+    #[serde(default)]
+    pub unit: WeightUnit,
+    pub bars: Vec<Bar>,
+    pub plates: Vec<PlateInventory>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfilePlateCalcRequest {
+    /// In the profile's unit
+    #[serde(alias = "targetWeightKg")]
+    pub target_weight: f64,
+    /// Bar name from the profile; the first bar when omitted
+    pub bar: Option<String>,
 }
 
 pub async fn calculate_plates(Json(req): Json<PlateCalcRequest>) -> impl IntoResponse {
-    let result = training::calculate_plates(req.target_weight_kg);
-    (StatusCode::OK, Json(result))
+    let standard = EquipmentProfile::standard(req.unit);
+    let bar_weight = req.bar_weight.unwrap_or(standard.bars[0].weight);
+    if !(0.0..=50.0).contains(&req.unit.to_kg(bar_weight)) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "bar weight must be 0-50 kg"})))
+            .into_response();
+    }
+    let plates = req.plates.unwrap_or(standard.plates);
+    match training::solve_plates(req.target_weight, bar_weight, &plates, req.unit) {
+        Ok(result) => (StatusCode::OK, Json(json!(result))).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    }
+}
+
+fn profile_json(row: &sqlx::postgres::PgRow) -> serde_json::Value {
+    json!({
+        "id": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
+        "name": row.try_get::<String, _>("name").unwrap_or_default(),
+        "unit": parse_weight_unit(row.try_get("weight_unit").ok()).unwrap_or_default(),
+        "bars": row.try_get::<serde_json::Value, _>("bars").unwrap_or_default(),
+        "plates": row.try_get::<serde_json::Value, _>("plates").unwrap_or_default(),
+        "isDefault": row.try_get::<bool, _>("is_default").unwrap_or(false),
+    })
+}
+
+/// Validates the request and stores it as a new profile or over an existing one
+async fn save_profile(
+    pool: &PgPool,
+    user_id: Uuid,
+    id: Option<Uuid>,
+    req: EquipmentProfileRequest,
+) -> axum::response::Response {
+    let profile =
+        EquipmentProfile { name: req.name, unit: req.unit, bars: req.bars, plates: req.plates };
+    if let Err(e) = validate_profile(&profile) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response();
+    }
+
+    let result: Result<Option<sqlx::postgres::PgRow>, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        if req.is_default {
+            sqlx::query("UPDATE equipment_profiles SET is_default = false WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let row = match id {
+            Some(id) => {
+                sqlx::query(
+                    "UPDATE equipment_profiles
+                     SET name = $1, bars = $2, plates = $3, is_default = $4, weight_unit = $5,
+                         updated_at = now()
+                     WHERE id = $6 AND user_id = $7
+                     RETURNING id, name, weight_unit, bars, plates, is_default",
+                )
+                .bind(&profile.name)
+                .bind(json!(profile.bars))
+                .bind(json!(profile.plates))
+                .bind(req.is_default)
+                .bind(json!(profile.unit).as_str().unwrap_or_default())
+                .bind(id)
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await?
+            }
+            None => Some(
+                sqlx::query(
+                    "INSERT INTO equipment_profiles (user_id, name, bars, plates, is_default, weight_unit)
+                     VALUES ($1, $2, $3, $4, $5, $6)
+                     RETURNING id, name, weight_unit, bars, plates, is_default",
+                )
+                .bind(user_id)
+                .bind(&profile.name)
+                .bind(json!(profile.bars))
+                .bind(json!(profile.plates))
+                .bind(req.is_default)
+                .bind(json!(profile.unit).as_str().unwrap_or_default())
+                .fetch_one(&mut *tx)
+                .await?,
+            ),
+        };
+        tx.commit().await?;
+        Ok(row)
+    }
+    .await;
+
+    let status = if id.is_some() { StatusCode::OK } else { StatusCode::CREATED };
+    match result {
+        Ok(Some(row)) => (status, Json(profile_json(&row))).into_response(),
+        Ok(None) => {
+            (StatusCode::NOT_FOUND, Json(json!({"error": "profile not found"}))).into_response()
+        }
+        Err(sqlx::Error::Database(db)) if db.is_unique_violation() => (
+            StatusCode::CONFLICT,
+            Json(json!({"error": "a profile with this name already exists"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("save_profile failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub(super) async fn load_equipment_profile(
+    pool: &PgPool,
+    user_id: Uuid,
+    id: Uuid,
+) -> Result<Option<EquipmentProfile>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT name, weight_unit, bars, plates FROM equipment_profiles WHERE id = $1 AND user_id = $2",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|row| EquipmentProfile {
+        name: row.try_get("name").unwrap_or_default(),
+        unit: parse_weight_unit(row.try_get("weight_unit").ok()).unwrap_or_default(),
+        bars: row
+            .try_get::<serde_json::Value, _>("bars")
+            .ok()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default(),
+        plates: row
+            .try_get::<serde_json::Value, _>("plates")
+            .ok()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default(),
+    }))
+}
+
+/// Bar of a profile by name, or its first bar
+pub(super) fn profile_bar<'a>(
+    profile: &'a EquipmentProfile,
+    name: Option<&str>,
+) -> Option<&'a Bar> {
+    match name {
+        Some(name) => profile.bars.iter().find(|b| b.name.eq_ignore_ascii_case(name)),
+        None => profile.bars.first(),
+    }
+}
+
+pub async fn list_equipment_profiles(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> impl IntoResponse {
+    match sqlx::query(
+        "SELECT id, name, weight_unit, bars, plates, is_default FROM equipment_profiles
+         WHERE user_id = $1 ORDER BY is_default DESC, name",
+    )
+    .bind(user.id)
+    .fetch_all(&*pool)
+    .await
+    {
+        Ok(rows) => {
+            let profiles: Vec<serde_json::Value> = rows.iter().map(profile_json).collect();
+            (StatusCode::OK, Json(json!({"profiles": profiles}))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("list_equipment_profiles failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn create_equipment_profile(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Json(req): Json<EquipmentProfileRequest>,
+) -> impl IntoResponse {
+    save_profile(&pool, user.id, None, req).await
+}
+
+pub async fn update_equipment_profile(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+    Json(req): Json<EquipmentProfileRequest>,
+) -> impl IntoResponse {
+    match Uuid::parse_str(&id) {
+        Ok(uuid) => save_profile(&pool, user.id, Some(uuid), req).await,
+        Err(_) => (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response(),
+    }
+}
+
+pub async fn delete_equipment_profile(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    match sqlx::query("DELETE FROM equipment_profiles WHERE id = $1 AND user_id = $2")
+        .bind(uuid)
+        .bind(user.id)
+        .execute(&*pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            (StatusCode::NOT_FOUND, Json(json!({"error": "profile not found"}))).into_response()
+        }
+        Ok(_) => (StatusCode::OK, Json(json!({"ok": true}))).into_response(),
+        Err(e) => {
+            tracing::error!("delete_equipment_profile failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Plate calculation with the bars and plate inventory of a saved profile
+pub async fn calculate_profile_plates(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+    Json(req): Json<ProfilePlateCalcRequest>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    let profile = match load_equipment_profile(&pool, user.id, uuid).await {
+        Ok(Some(profile)) => profile,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({"error": "profile not found"})))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("calculate_profile_plates failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    let Some(bar) = profile_bar(&profile, req.bar.as_deref()) else {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "unknown bar"}))).into_response();
+    };
+    match training::solve_plates(req.target_weight, bar.weight, &profile.plates, profile.unit) {
+        Ok(result) => {
+            let mut body = json!(result);
+            body["bar"] = json!(bar.name);
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    }
 }
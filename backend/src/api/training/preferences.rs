@@ -0,0 +1,107 @@
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training::{EnteredWeight, WeightUnit};
+use axum::extract::{Extension, Json};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePreferencesRequest {
+    pub weight_unit: WeightUnit,
+}
+
+/// The user's weight unit; kg until they choose otherwise
+pub(super) async fn preferred_weight_unit(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<WeightUnit, sqlx::Error> {
+    let unit: Option<String> =
+        sqlx::query_scalar("SELECT weight_unit FROM training_preferences WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(parse_weight_unit(unit).unwrap_or_default())
+}
+
+/// SQL for the weight of the `workout_sets` row `alias` in the unit bound as `$unit`, with
+/// that unit's kg bound as `$kg_per_unit`: the typed value when the units match, else kg
+/// converted and rounded as `display_weight` does
+pub(super) fn weight_in_unit_sql(alias: &str, unit: usize, kg_per_unit: usize) -> String {
+    format!(
+        "CASE WHEN {alias}.weight_unit = ${unit} AND {alias}.weight_input IS NOT NULL
+              THEN {alias}.weight_input::float8
+              ELSE ROUND({alias}.weight_kg / ${kg_per_unit}::numeric, 2)::float8 END"
+    )
+}
+
+/// Unit as stored in unit columns
+pub(super) fn unit_sql(unit: WeightUnit) -> String {
+    json!(unit).as_str().unwrap_or_default().to_string()
+}
+
+/// Stored unit column to unit; NULL for rows from before units were recorded
+pub(super) fn parse_weight_unit(unit: Option<String>) -> Option<WeightUnit> {
+    unit.and_then(|u| serde_json::from_value(json!(u)).ok())
+}
+
+/// Stored input value and unit columns to the weight as entered
+pub(super) fn entered_weight(value: Option<f64>, unit: Option<String>) -> Option<EnteredWeight> {
+    Some(EnteredWeight { value: value?, unit: parse_weight_unit(unit)? })
+}
+
+/// A weight from a request, in the user's preferred unit unless one is given
+pub(super) async fn resolve_entered_weight(
+    pool: &PgPool,
+    user_id: Uuid,
+    value: Option<f64>,
+    unit: Option<WeightUnit>,
+) -> Result<Option<EnteredWeight>, sqlx::Error> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let unit = match unit {
+        Some(unit) => unit,
+        None => preferred_weight_unit(pool, user_id).await?,
+    };
+    Ok(Some(EnteredWeight { value, unit }))
+}
+
+pub async fn get_preferences(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> impl IntoResponse {
+    match preferred_weight_unit(&pool, user.id).await {
+        Ok(unit) => (StatusCode::OK, Json(json!({"weightUnit": unit}))).into_response(),
+        Err(e) => {
+            tracing::error!("get_preferences failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn update_preferences(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Json(req): Json<UpdatePreferencesRequest>,
+) -> impl IntoResponse {
+    match sqlx::query(
+        "INSERT INTO training_preferences (user_id, weight_unit) VALUES ($1, $2)
+         ON CONFLICT (user_id) DO UPDATE SET weight_unit = EXCLUDED.weight_unit, updated_at = now()",
+    )
+    .bind(user.id)
+    .bind(unit_sql(req.weight_unit))
+    .execute(&*pool)
+    .await
+    {
+        Ok(_) => (StatusCode::OK, Json(json!({"weightUnit": req.weight_unit}))).into_response(),
+        Err(e) => {
+            tracing::error!("update_preferences failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
@@ -0,0 +1,456 @@
+use super::*;
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training::program::{
+    adherence, apply_week, full_schedule, scheduled_on, validate_program, CompletedWorkout,
+    ProgramDefinition, ScheduleDay,
+};
+use axum::extract::{Extension, Json, Path, Query};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateProgramRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub definition: ProgramDefinition,
+}
+
+/// Partial update; the schedule and ramps are fixed once a program is created
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProgramRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Inactive programs keep their history but no longer schedule workouts
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TodayParams {
+    /// Defaults to today
+    pub date: Option<NaiveDate>,
+}
+
+pub(super) struct StoredProgram {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_active: bool,
+    pub definition: ProgramDefinition,
+}
+
+/// The user's programs with their schedules, optionally only one of them
+pub(super) async fn load_programs(
+    pool: &PgPool,
+    user_id: Uuid,
+    program_id: Option<Uuid>,
+) -> Result<Vec<StoredProgram>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, name, description, is_active, start_date, weeks, deload_week,
+                volume_ramp_percentage::float8 AS volume_ramp_percentage,
+                intensity_ramp_percentage::float8 AS intensity_ramp_percentage,
+                deload_volume_percentage::float8 AS deload_volume_percentage,
+                deload_intensity_percentage::float8 AS deload_intensity_percentage
+         FROM training_programs
+         WHERE user_id = $1 AND ($2::uuid IS NULL OR id = $2)
+         ORDER BY start_date DESC, name",
+    )
+    .bind(user_id)
+    .bind(program_id)
+    .fetch_all(pool)
+    .await?;
+    let schedule_rows = sqlx::query(
+        "SELECT ps.program_id, ps.day_of_week, ps.plan_id
+         FROM program_schedule ps JOIN training_programs p ON p.id = ps.program_id
+         WHERE p.user_id = $1 AND ($2::uuid IS NULL OR p.id = $2)
+         ORDER BY ps.day_of_week, ps.rotation_index",
+    )
+    .bind(user_id)
+    .bind(program_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let id: Uuid = row.try_get("id").unwrap_or_default();
+            let mut schedule: Vec<ScheduleDay> = Vec::new();
+            for s in
+                schedule_rows.iter().filter(|s| s.try_get::<Uuid, _>("program_id").ok() == Some(id))
+            {
+                let day_of_week = s.try_get::<i32, _>("day_of_week").unwrap_or(1) as u32;
+                let plan_id: Uuid = s.try_get("plan_id").unwrap_or_default();
+                match schedule.last_mut() {
+                    Some(day) if day.day_of_week == day_of_week => day.plan_ids.push(plan_id),
+                    _ => schedule.push(ScheduleDay { day_of_week, plan_ids: vec![plan_id] }),
+                }
+            }
+            StoredProgram {
+                id,
+                name: row.try_get("name").unwrap_or_default(),
+                description: row.try_get("description").ok().flatten(),
+                is_active: row.try_get("is_active").unwrap_or(true),
+                definition: ProgramDefinition {
+                    start_date: row.try_get("start_date").unwrap_or_default(),
+                    weeks: row.try_get::<i32, _>("weeks").unwrap_or(1) as u32,
+                    deload_week: row
+                        .try_get::<Option<i32>, _>("deload_week")
+                        .ok()
+                        .flatten()
+                        .map(|w| w as u32),
+                    volume_ramp_percentage: row.try_get("volume_ramp_percentage").unwrap_or(0.0),
+                    intensity_ramp_percentage: row
+                        .try_get("intensity_ramp_percentage")
+                        .unwrap_or(0.0),
+                    deload_volume_percentage: row
+                        .try_get("deload_volume_percentage")
+                        .unwrap_or(50.0),
+                    deload_intensity_percentage: row
+                        .try_get("deload_intensity_percentage")
+                        .unwrap_or(90.0),
+                    schedule,
+                },
+            }
+        })
+        .collect())
+}
+
+fn program_json(program: &StoredProgram) -> serde_json::Value {
+    let def = &program.definition;
+    json!({
+        "id": program.id.to_string(),
+        "name": program.name,
+        "description": program.description,
+        "isActive": program.is_active,
+        "startDate": def.start_date,
+        "weeks": def.weeks,
+        "deloadWeek": def.deload_week,
+        "volumeRampPercentage": def.volume_ramp_percentage,
+        "intensityRampPercentage": def.intensity_ramp_percentage,
+        "deloadVolumePercentage": def.deload_volume_percentage,
+        "deloadIntensityPercentage": def.deload_intensity_percentage,
+        "schedule": def.schedule,
+    })
+}
+
+pub async fn create_program(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Json(req): Json<CreateProgramRequest>,
+) -> impl IntoResponse {
+    let def = &req.definition;
+    if let Err(e) = validate_program(def) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response();
+    }
+    let mut plan_ids: Vec<Uuid> = def.schedule.iter().flat_map(|d| d.plan_ids.clone()).collect();
+    plan_ids.sort();
+    plan_ids.dedup();
+    match sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM training_plans WHERE user_id = $1 AND id = ANY($2)",
+    )
+    .bind(user.id)
+    .bind(&plan_ids)
+    .fetch_one(&*pool)
+    .await
+    {
+        Ok(count) if count as usize == plan_ids.len() => {}
+        Ok(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "unknown plan in schedule"})))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("create_program failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    }
+
+    let result: Result<Uuid, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        let id: Uuid = sqlx::query_scalar(
+            "INSERT INTO training_programs (user_id, name, description, start_date, weeks, deload_week,
+                volume_ramp_percentage, intensity_ramp_percentage, deload_volume_percentage,
+                deload_intensity_percentage)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id",
+        )
+        .bind(user.id)
+        .bind(&req.name)
+        .bind(&req.description)
+        .bind(def.start_date)
+        .bind(def.weeks as i32)
+        .bind(def.deload_week.map(|w| w as i32))
+        .bind(def.volume_ramp_percentage)
+        .bind(def.intensity_ramp_percentage)
+        .bind(def.deload_volume_percentage)
+        .bind(def.deload_intensity_percentage)
+        .fetch_one(&mut *tx)
+        .await?;
+        for day in &def.schedule {
+            for (rotation_index, plan_id) in day.plan_ids.iter().enumerate() {
+                sqlx::query(
+                    "INSERT INTO program_schedule (program_id, day_of_week, rotation_index, plan_id)
+                     VALUES ($1, $2, $3, $4)",
+                )
+                .bind(id)
+                .bind(day.day_of_week as i32)
+                .bind(rotation_index as i32)
+                .bind(plan_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(id)
+    }
+    .await;
+
+    match result {
+        Ok(id) => (StatusCode::CREATED, Json(json!({"id": id.to_string()}))).into_response(),
+        Err(sqlx::Error::Database(db)) if db.is_unique_violation() => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "each weekday may appear only once in the schedule"})),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("create_program failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn list_programs(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+) -> impl IntoResponse {
+    match load_programs(&pool, user.id, None).await {
+        Ok(programs) => {
+            let programs: Vec<serde_json::Value> = programs.iter().map(program_json).collect();
+            (StatusCode::OK, Json(json!({"programs": programs}))).into_response()
+        }
+        Err(e) => {
+            tracing::error!("list_programs failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Program with its full schedule and adherence up to today
+pub async fn get_program(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    let program = match load_programs(&pool, user.id, Some(uuid)).await {
+        Ok(mut programs) => match programs.pop() {
+            Some(program) => program,
+            None => {
+                return (StatusCode::NOT_FOUND, Json(json!({"error": "program not found"})))
+                    .into_response()
+            }
+        },
+        Err(e) => {
+            tracing::error!("get_program failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+
+    let def = &program.definition;
+    let end = def.start_date + chrono::Duration::days(i64::from(def.weeks) * 7 - 1);
+    let completed = match sqlx::query(
+        "SELECT started_at::date AS day, plan_id FROM workout_sessions
+         WHERE user_id = $1 AND status = 'completed' AND started_at::date BETWEEN $2 AND $3",
+    )
+    .bind(user.id)
+    .bind(def.start_date)
+    .bind(end)
+    .fetch_all(&*pool)
+    .await
+    {
+        Ok(rows) => rows
+            .iter()
+            .map(|r| CompletedWorkout {
+                date: r.try_get("day").unwrap_or_default(),
+                plan_id: r.try_get("plan_id").ok().flatten(),
+            })
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            tracing::error!("get_program failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+
+    let today = chrono::Utc::now().date_naive();
+    let mut body = program_json(&program);
+    body["calendar"] = json!(full_schedule(def));
+    body["adherence"] = json!(adherence(def, &completed, today));
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+pub async fn update_program(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateProgramRequest>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    if req.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "name must not be empty"})))
+            .into_response();
+    }
+    match sqlx::query(
+        "UPDATE training_programs SET name = COALESCE($1, name), description = COALESCE($2, description),
+                is_active = COALESCE($3, is_active), updated_at = now()
+         WHERE id = $4 AND user_id = $5",
+    )
+    .bind(&req.name)
+    .bind(&req.description)
+    .bind(req.is_active)
+    .bind(uuid)
+    .bind(user.id)
+    .execute(&*pool)
+    .await
+    {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))).into_response()
+            } else {
+                (StatusCode::OK, Json(json!({"ok": true}))).into_response()
+            }
+        }
+        Err(e) => {
+            tracing::error!("update_program failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+pub async fn delete_program(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    match sqlx::query("DELETE FROM training_programs WHERE id = $1 AND user_id = $2")
+        .bind(uuid)
+        .bind(user.id)
+        .execute(&*pool)
+        .await
+    {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                (StatusCode::NOT_FOUND, Json(json!({"error": "not found"}))).into_response()
+            } else {
+                (StatusCode::OK, Json(json!({"ok": true}))).into_response()
+            }
+        }
+        Err(e) => {
+            tracing::error!("delete_program failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Plans the active programs schedule for a day, with the week's ramped targets
+pub async fn training_today(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Query(params): Query<TodayParams>,
+) -> impl IntoResponse {
+    let date = params.date.unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let result: Result<_, sqlx::Error> = async {
+        Ok((
+            load_programs(&pool, user.id, None).await?,
+            preferred_weight_unit(&pool, user.id).await?,
+        ))
+    }
+    .await;
+    let (programs, unit) = match result {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            tracing::error!("training_today failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+
+    let mut workouts = Vec::new();
+    for program in programs.iter().filter(|p| p.is_active) {
+        for workout in scheduled_on(&program.definition, date) {
+            let plan = sqlx::query(
+                "SELECT p.name,
+                        (SELECT s.id FROM workout_sessions s
+                         WHERE s.plan_id = p.id AND s.user_id = $2 AND s.status <> 'cancelled'
+                           AND s.started_at::date = $3
+                         ORDER BY s.started_at DESC LIMIT 1) AS session_id
+                 FROM training_plans p WHERE p.id = $1",
+            )
+            .bind(workout.plan_id)
+            .bind(user.id)
+            .bind(date)
+            .fetch_optional(&*pool)
+            .await;
+            let targets = load_plan_targets(&pool, user.id, workout.plan_id).await;
+            let (plan, targets) = match (plan, targets) {
+                (Ok(Some(plan)), Ok(targets)) => (plan, targets),
+                (Ok(None), _) => continue,
+                (Err(e), _) | (_, Err(e)) => {
+                    tracing::error!("training_today failed: {e}");
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                        .into_response();
+                }
+            };
+            let exercises: Vec<serde_json::Value> = targets
+                .iter()
+                .map(|t| {
+                    json!({
+                        "planExerciseId": t.plan_exercise_id.to_string(),
+                        "exerciseId": t.exercise_id.to_string(),
+                        "exerciseName": t.exercise_name,
+                        "sets": target_sets_json(
+                            &apply_week(&t.target_sets(), &workout.modifiers, t.rounding_kg),
+                            unit,
+                        ),
+                    })
+                })
+                .collect();
+            workouts.push(json!({
+                "programId": program.id.to_string(),
+                "programName": program.name,
+                "planId": workout.plan_id.to_string(),
+                "planName": plan.try_get::<String, _>("name").unwrap_or_default(),
+                "week": workout.modifiers.week,
+                "modifiers": workout.modifiers,
+                "sessionId": plan.try_get::<Option<Uuid>, _>("session_id").ok().flatten().map(|u| u.to_string()),
+                "exercises": exercises,
+            }));
+        }
+    }
+    (StatusCode::OK, Json(json!({"date": date, "weightUnit": unit, "workouts": workouts})))
+        .into_response()
+}
@@ -0,0 +1,125 @@
+use super::*;
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training::records::{e1rm_trend, personal_records, LoggedSet};
+use crate::tools::training::OneRmFormula;
+use axum::extract::{Extension, Json, Path, Query};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct RecordParams {
+    pub formula: Option<OneRmFormula>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// Working (non warm-up) sets of an exercise across the user's sessions that were not cancelled
+pub(super) async fn load_working_sets(
+    pool: &PgPool,
+    user_id: Uuid,
+    exercise_id: Uuid,
+) -> Result<Vec<LoggedSet>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT ws.session_id, ws.performed_at, ws.weight_kg::float8 AS weight_kg, ws.reps,
+                ws.weight_input::float8 AS weight_input, ws.weight_unit
+         FROM workout_sets ws JOIN workout_sessions s ON s.id = ws.session_id
+         WHERE s.user_id = $1 AND ws.exercise_id = $2 AND ws.is_warmup = FALSE
+           AND s.status <> 'cancelled'
+         ORDER BY ws.performed_at",
+    )
+    .bind(user_id)
+    .bind(exercise_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(LoggedSet {
+                session_id: row.try_get("session_id").ok()?,
+                performed_at: row.try_get("performed_at").ok()?,
+                weight_kg: row.try_get("weight_kg").ok()?,
+                entered: entered_weight(
+                    row.try_get("weight_input").ok().flatten(),
+                    row.try_get("weight_unit").ok().flatten(),
+                ),
+                reps: u32::try_from(row.try_get::<i32, _>("reps").ok()?).ok()?,
+            })
+        })
+        .collect())
+}
+
+pub async fn exercise_records(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+    Query(params): Query<RecordParams>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    let result: Result<_, sqlx::Error> = async {
+        Ok((
+            load_working_sets(&pool, user.id, uuid).await?,
+            preferred_weight_unit(&pool, user.id).await?,
+        ))
+    }
+    .await;
+    match result {
+        Ok((sets, unit)) => {
+            let records = personal_records(&sets, params.formula.unwrap_or_default(), unit);
+            (StatusCode::OK, Json(json!({"exerciseId": uuid.to_string(), "records": records})))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("exercise_records failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+/// Best estimated 1RM per session between `from` and `to`
+pub async fn exercise_e1rm_trend(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+    Query(params): Query<RecordParams>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    let result: Result<_, sqlx::Error> = async {
+        Ok((
+            load_working_sets(&pool, user.id, uuid).await?,
+            preferred_weight_unit(&pool, user.id).await?,
+        ))
+    }
+    .await;
+    match result {
+        Ok((sets, unit)) => {
+            let formula = params.formula.unwrap_or_default();
+            let trend: Vec<_> = e1rm_trend(&sets, formula, unit)
+                .into_iter()
+                .filter(|p| params.from.is_none_or(|from| p.date >= from))
+                .filter(|p| params.to.is_none_or(|to| p.date <= to))
+                .collect();
+            (StatusCode::OK, Json(json!({"formula": formula, "weightUnit": unit, "trend": trend})))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("exercise_e1rm_trend failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
@@ -1,11 +1,16 @@
 use super::*;
 use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training::program::{apply_week, program_week, week_modifiers};
+use crate::tools::training::summary::{
+    summarize_session, PlannedSet, PlannedSetStatus, SessionSet,
+};
+use crate::tools::training::{display_weight, WeightUnit};
 use axum::extract::{Extension, Json, Path, Query};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use serde::Deserialize;
 use serde_json::json;
-use sqlx::{PgPool, Row};
+use sqlx::{PgExecutor, PgPool, Row};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -14,6 +19,8 @@ use uuid::Uuid;
 pub struct StartSessionRequest {
     pub name: String,
     pub plan_id: Option<String>,
+    /// Program whose current week ramps the plan's targets
+    pub program_id: Option<String>,
 }
 
 pub async fn start_session(
@@ -21,7 +28,76 @@ pub async fn start_session(
     Extension(pool): Extension<Arc<PgPool>>,
     Json(req): Json<StartSessionRequest>,
 ) -> impl IntoResponse {
-    let plan_uuid = req.plan_id.as_deref().and_then(|s| Uuid::parse_str(s).ok());
+    let plan_uuid = match req.plan_id.as_deref().map(Uuid::parse_str) {
+        None => None,
+        Some(Ok(u)) => Some(u),
+        Some(Err(_)) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid plan_id"})))
+                .into_response()
+        }
+    };
+    let program_uuid = match req.program_id.as_deref().map(Uuid::parse_str) {
+        None => None,
+        Some(Ok(u)) => Some(u),
+        Some(Err(_)) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid program_id"})))
+                .into_response()
+        }
+    };
+
+    // Prescription from the plan, computed before the new session exists
+    let targets = match plan_uuid {
+        Some(plan_id) => {
+            match sqlx::query("SELECT 1 FROM training_plans WHERE id = $1 AND user_id = $2")
+                .bind(plan_id)
+                .bind(user.id)
+                .fetch_optional(&*pool)
+                .await
+            {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return (StatusCode::NOT_FOUND, Json(json!({"error": "plan not found"})))
+                        .into_response()
+                }
+                Err(e) => {
+                    tracing::error!("start_session failed: {e}");
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                        .into_response();
+                }
+            }
+            match load_plan_targets(&pool, user.id, plan_id).await {
+                Ok(targets) => targets,
+                Err(e) => {
+                    tracing::error!("start_session failed: {e}");
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                        .into_response();
+                }
+            }
+        }
+        None => Vec::new(),
+    };
+
+    let modifiers = match program_uuid {
+        Some(program_id) => match load_programs(&pool, user.id, Some(program_id)).await {
+            Ok(programs) => {
+                let Some(program) = programs.first() else {
+                    return (StatusCode::NOT_FOUND, Json(json!({"error": "program not found"})))
+                        .into_response();
+                };
+                // A paused program no longer ramps the plan's targets
+                let def = &program.definition;
+                program_week(def, chrono::Utc::now().date_naive())
+                    .filter(|_| program.is_active)
+                    .map(|w| week_modifiers(def, w))
+            }
+            Err(e) => {
+                tracing::error!("start_session failed: {e}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                    .into_response();
+            }
+        },
+        None => None,
+    };
 
     // Snapshot latest measurement
     let measurement_id: Option<Uuid> = sqlx::query(
@@ -34,24 +110,63 @@ pub async fn start_session(
     .flatten()
     .and_then(|row| row.try_get("id").ok());
 
-    match sqlx::query(
-        "INSERT INTO workout_sessions (user_id, plan_id, measurement_id, name) VALUES ($1, $2, $3, $4) RETURNING id, started_at"
-    )
-    .bind(user.id)
-    .bind(plan_uuid)
-    .bind(measurement_id)
-    .bind(&req.name)
-    .fetch_one(&*pool)
-    .await
-    {
-        Ok(row) => {
-            let id: Uuid = row.try_get("id").unwrap_or_default();
-            let started_at: chrono::DateTime<chrono::Utc> = row.try_get("started_at").unwrap_or_else(|_| chrono::Utc::now());
-            (StatusCode::CREATED, Json(json!({
-                "id": id.to_string(),
-                "startedAt": started_at.to_rfc3339(),
-                "measurementId": measurement_id.map(|m| m.to_string()),
-            }))).into_response()
+    let result: Result<(Uuid, chrono::DateTime<chrono::Utc>), sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        let row = sqlx::query(
+            "INSERT INTO workout_sessions (user_id, plan_id, measurement_id, name) VALUES ($1, $2, $3, $4) RETURNING id, started_at"
+        )
+        .bind(user.id)
+        .bind(plan_uuid)
+        .bind(measurement_id)
+        .bind(&req.name)
+        .fetch_one(&mut *tx)
+        .await?;
+        let id: Uuid = row.try_get("id")?;
+        for target in &targets {
+            let sets = match &modifiers {
+                Some(m) => apply_week(&target.target_sets(), m, target.rounding_kg),
+                None => target.target_sets(),
+            };
+            for set in sets {
+                sqlx::query(
+                    "INSERT INTO planned_sets (session_id, plan_exercise_id, exercise_id, sort_order, set_number,
+                        superset_group, target_weight_kg, target_reps, target_rpe, rest_seconds, is_amrap)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+                )
+                .bind(id)
+                .bind(target.plan_exercise_id)
+                .bind(target.exercise_id)
+                .bind(target.sort_order)
+                .bind(set.set_number as i32)
+                .bind(target.superset_group)
+                .bind(set.weight_kg)
+                .bind(set.reps as i32)
+                .bind(set.rpe)
+                .bind(target.rest_seconds)
+                .bind(set.is_amrap)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok((id, row.try_get("started_at").unwrap_or_else(|_| chrono::Utc::now())))
+    }
+    .await;
+
+    match result {
+        Ok((id, started_at)) => {
+            let unit = preferred_weight_unit(&pool, user.id).await.unwrap_or_default();
+            let planned_sets = planned_sets_json(&pool, id, unit).await.unwrap_or_default();
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "id": id.to_string(),
+                    "startedAt": started_at.to_rfc3339(),
+                    "measurementId": measurement_id.map(|m| m.to_string()),
+                    "plannedSets": planned_sets,
+                })),
+            )
+                .into_response()
         }
         Err(e) => {
             tracing::error!("start_session failed: {e}");
@@ -60,33 +175,91 @@ pub async fn start_session(
     }
 }
 
+/// Planned sets of a session in plan order, supersets included, with targets also in `unit`
+async fn planned_sets_json(
+    pool: &PgPool,
+    session_id: Uuid,
+    unit: WeightUnit,
+) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT ps.id, ps.plan_exercise_id, ps.exercise_id, e.name AS exercise_name, ps.sort_order,
+                ps.set_number, ps.superset_group, ps.target_weight_kg,
+                ps.target_weight_kg::float8 AS target_weight_kg_value, ps.target_reps, ps.target_rpe,
+                ps.rest_seconds, ps.is_amrap, ps.status, ps.workout_set_id, ps.is_warmup, ps.plate_loadout
+         FROM planned_sets ps JOIN exercises e ON e.id = ps.exercise_id
+         WHERE ps.session_id = $1 ORDER BY ps.sort_order, ps.is_warmup DESC, ps.set_number",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(|r| {
+        json!({
+            "id": r.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
+            "planExerciseId": r.try_get::<Option<Uuid>, _>("plan_exercise_id").ok().flatten().map(|u| u.to_string()),
+            "exerciseId": r.try_get::<Uuid, _>("exercise_id").unwrap_or_default().to_string(),
+            "exerciseName": r.try_get::<String, _>("exercise_name").unwrap_or_default(),
+            "sortOrder": r.try_get::<i32, _>("sort_order").unwrap_or(0),
+            "setNumber": r.try_get::<i32, _>("set_number").unwrap_or(0),
+            "supersetGroup": r.try_get::<Option<i32>, _>("superset_group").ok().flatten(),
+            "targetWeightKg": r.try_get::<Option<sqlx::types::BigDecimal>, _>("target_weight_kg").ok().flatten().map(|d| d.to_string()),
+            "targetWeight": r.try_get::<Option<f64>, _>("target_weight_kg_value").ok().flatten().map(|kg| display_weight(kg, None, unit)),
+            "weightUnit": unit,
+            "targetReps": r.try_get::<i32, _>("target_reps").unwrap_or(0),
+            "targetRpe": r.try_get::<Option<sqlx::types::BigDecimal>, _>("target_rpe").ok().flatten().map(|d| d.to_string()),
+            "restSeconds": r.try_get::<Option<i32>, _>("rest_seconds").ok().flatten(),
+            "isAmrap": r.try_get::<bool, _>("is_amrap").unwrap_or(false),
+            "isWarmup": r.try_get::<bool, _>("is_warmup").unwrap_or(false),
+            "plateLoadout": r.try_get::<Option<serde_json::Value>, _>("plate_loadout").ok().flatten(),
+            "status": r.try_get::<String, _>("status").unwrap_or_default(),
+            "workoutSetId": r.try_get::<Option<Uuid>, _>("workout_set_id").ok().flatten().map(|u| u.to_string()),
+        })
+    }).collect())
+}
+
 pub async fn list_sessions(
     AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
     Query(params): Query<StatsFilterParams>,
 ) -> impl IntoResponse {
-    match sqlx::query(
-        "SELECT id, name, plan_id, started_at, completed_at, status, total_energy_kcal, total_volume_kg, notes
-         FROM workout_sessions WHERE user_id = $1 ORDER BY started_at DESC LIMIT 100"
-    )
+    let filter = match params.resolve() {
+        Ok(f) => f,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+    let unit = match preferred_weight_unit(&pool, user.id).await {
+        Ok(unit) => unit,
+        Err(e) => {
+            tracing::error!("list_sessions failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    match sqlx::query(&format!(
+        "SELECT s.id, s.name, s.plan_id, s.started_at, s.completed_at, s.status, s.total_energy_kcal,
+                s.total_volume_kg, s.notes,
+                (SELECT COALESCE(SUM({} * wse.reps), 0) FROM workout_sets wse
+                 WHERE wse.session_id = s.id) AS total_volume
+         FROM workout_sessions s
+         WHERE s.user_id = $1
+           AND ($4::timestamptz IS NULL OR s.started_at >= $4)
+           AND ($5::timestamptz IS NULL OR s.started_at < $5)
+           AND ($6::uuid IS NULL OR s.plan_id = $6)
+           AND ($7::uuid IS NULL OR EXISTS (
+                SELECT 1 FROM workout_sets f WHERE f.session_id = s.id AND f.exercise_id = $7))
+         ORDER BY s.started_at DESC LIMIT 100",
+        weight_in_unit_sql("wse", 2, 3)
+    ))
     .bind(user.id)
+    .bind(unit_sql(unit))
+    .bind(unit.to_kg(1.0))
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(filter.plan_id)
+    .bind(filter.exercise_id)
     .fetch_all(&*pool)
     .await
     {
         Ok(rows) => {
-            let sessions: Vec<serde_json::Value> = rows.iter().filter(|row| {
-                // Apply date filters
-                let started: Option<chrono::DateTime<chrono::Utc>> = row.try_get("started_at").ok();
-                let matches_from = params.from.as_ref().is_none_or(|f| {
-                    chrono::DateTime::parse_from_rfc3339(f).ok()
-                        .is_none_or(|from| started.is_some_and(|s| s >= from))
-                });
-                let matches_to = params.to.as_ref().is_none_or(|t| {
-                    chrono::DateTime::parse_from_rfc3339(t).ok()
-                        .is_none_or(|to| started.is_some_and(|s| s <= to))
-                });
-                matches_from && matches_to
-            }).map(|row| {
+            let sessions: Vec<serde_json::Value> = rows.iter().map(|row| {
                 json!({
                     "id": row.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
                     "name": row.try_get::<String, _>("name").unwrap_or_default(),
@@ -96,10 +269,11 @@ pub async fn list_sessions(
                     "status": row.try_get::<String, _>("status").unwrap_or_default(),
                     "totalEnergyKcal": row.try_get::<Option<sqlx::types::BigDecimal>, _>("total_energy_kcal").ok().flatten().map(|d| d.to_string()),
                     "totalVolumeKg": row.try_get::<Option<sqlx::types::BigDecimal>, _>("total_volume_kg").ok().flatten().map(|d| d.to_string()),
+                    "totalVolume": row.try_get::<f64, _>("total_volume").unwrap_or(0.0),
                     "notes": row.try_get::<Option<String>, _>("notes").ok().flatten(),
                 })
             }).collect();
-            (StatusCode::OK, Json(json!({"sessions": sessions}))).into_response()
+            (StatusCode::OK, Json(json!({"sessions": sessions, "weightUnit": unit}))).into_response()
         }
         Err(e) => {
             tracing::error!("list_sessions failed: {e}");
@@ -134,7 +308,8 @@ pub async fn get_session(
                 ws.tempo_eccentric_s, ws.tempo_pause_bottom_s, ws.tempo_concentric_s, ws.tempo_pause_top_s,
                 ws.is_warmup, ws.is_dropset, ws.is_failure, ws.rest_after_seconds,
                 ws.energy_kcal, ws.energy_potential_kcal, ws.energy_kinetic_kcal, ws.energy_isometric_kcal,
-                ws.notes, ws.performed_at
+                ws.notes, ws.performed_at, ws.weight_kg::float8 AS weight_kg_value,
+                ws.weight_input::float8 AS weight_input, ws.weight_unit
          FROM workout_sets ws JOIN exercises e ON e.id = ws.exercise_id
          WHERE ws.session_id = $1 ORDER BY ws.performed_at, ws.set_number"
     )
@@ -142,15 +317,32 @@ pub async fn get_session(
     .fetch_all(&*pool)
     .await;
 
-    match (session, sets) {
-        (Ok(Some(row)), Ok(set_rows)) => {
+    let unit = preferred_weight_unit(&pool, user.id).await;
+    let planned_sets = match unit {
+        Ok(unit) => planned_sets_json(&pool, uuid, unit).await,
+        Err(_) => Ok(Vec::new()),
+    };
+    let cardio = cardio_json(&pool, uuid).await;
+
+    match (session, sets, planned_sets, cardio, unit) {
+        (Ok(Some(row)), Ok(set_rows), Ok(planned_sets), Ok(cardio), Ok(unit)) => {
+            // Summed as shown, so sets typed in the user's unit add up exactly
+            let mut total_volume = 0.0;
             let sets_json: Vec<serde_json::Value> = set_rows.iter().map(|sr| {
+                let entered = entered_weight(
+                    sr.try_get("weight_input").ok().flatten(),
+                    sr.try_get("weight_unit").ok().flatten(),
+                );
+                let weight = display_weight(sr.try_get("weight_kg_value").unwrap_or(0.0), entered, unit);
+                total_volume += weight * f64::from(sr.try_get::<i32, _>("reps").unwrap_or(0));
                 json!({
                     "id": sr.try_get::<Uuid, _>("id").unwrap_or_default().to_string(),
                     "exerciseId": sr.try_get::<Uuid, _>("exercise_id").unwrap_or_default().to_string(),
                     "exerciseName": sr.try_get::<String, _>("exercise_name").unwrap_or_default(),
                     "setNumber": sr.try_get::<i32, _>("set_number").unwrap_or(0),
                     "weightKg": sr.try_get::<sqlx::types::BigDecimal, _>("weight_kg").ok().map(|d| d.to_string()),
+                    "weight": weight,
+                    "weightUnit": unit,
                     "reps": sr.try_get::<i32, _>("reps").unwrap_or(0),
                     "rpe": sr.try_get::<Option<sqlx::types::BigDecimal>, _>("rpe").ok().flatten().map(|d| d.to_string()),
                     "tempoEccentricS": sr.try_get::<Option<sqlx::types::BigDecimal>, _>("tempo_eccentric_s").ok().flatten().map(|d| d.to_string()),
@@ -179,20 +371,113 @@ pub async fn get_session(
                 "status": row.try_get::<String, _>("status").unwrap_or_default(),
                 "totalEnergyKcal": row.try_get::<Option<sqlx::types::BigDecimal>, _>("total_energy_kcal").ok().flatten().map(|d| d.to_string()),
                 "totalVolumeKg": row.try_get::<Option<sqlx::types::BigDecimal>, _>("total_volume_kg").ok().flatten().map(|d| d.to_string()),
+                "totalVolume": total_volume,
+                "weightUnit": unit,
                 "notes": row.try_get::<Option<String>, _>("notes").ok().flatten(),
                 "sets": sets_json,
+                "plannedSets": planned_sets,
+                "cardio": cardio,
             }))).into_response()
         }
-        (Ok(None), _) => {
+        (Ok(None), _, _, _, _) => {
             (StatusCode::NOT_FOUND, Json(json!({"error": "session not found"}))).into_response()
         }
-        (Err(e), _) | (_, Err(e)) => {
+        (Err(e), _, _, _, _)
+        | (_, Err(e), _, _, _)
+        | (_, _, Err(e), _, _)
+        | (_, _, _, Err(e), _)
+        | (_, _, _, _, Err(e)) => {
             tracing::error!("get_session failed: {e}");
             (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
         }
     }
 }
 
+/// Planned vs. performed sets per exercise
+pub async fn session_summary(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+
+    let owns = sqlx::query("SELECT 1 FROM workout_sessions WHERE id = $1 AND user_id = $2")
+        .bind(uuid)
+        .bind(user.id)
+        .fetch_optional(&*pool)
+        .await;
+    match owns {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({"error": "session not found"})))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("session_summary failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    }
+
+    let planned = sqlx::query(
+        "SELECT ps.exercise_id, e.name AS exercise_name, ps.target_weight_kg::float8 AS target_weight_kg,
+                ps.target_reps, ps.status
+         FROM planned_sets ps JOIN exercises e ON e.id = ps.exercise_id
+         WHERE ps.session_id = $1 AND ps.is_warmup = FALSE ORDER BY ps.sort_order, ps.set_number",
+    )
+    .bind(uuid)
+    .fetch_all(&*pool)
+    .await;
+    let performed = sqlx::query(
+        "SELECT ws.exercise_id, e.name AS exercise_name, ws.weight_kg::float8 AS weight_kg, ws.reps,
+                EXISTS (SELECT 1 FROM planned_sets ps WHERE ps.workout_set_id = ws.id) AS is_planned
+         FROM workout_sets ws JOIN exercises e ON e.id = ws.exercise_id
+         WHERE ws.session_id = $1 AND ws.is_warmup = FALSE ORDER BY ws.performed_at, ws.set_number",
+    )
+    .bind(uuid)
+    .fetch_all(&*pool)
+    .await;
+
+    match (planned, performed) {
+        (Ok(planned), Ok(performed)) => {
+            let planned: Vec<PlannedSet> = planned
+                .iter()
+                .map(|r| PlannedSet {
+                    exercise_id: r.try_get("exercise_id").unwrap_or_default(),
+                    exercise_name: r.try_get("exercise_name").unwrap_or_default(),
+                    target_weight_kg: r.try_get("target_weight_kg").ok().flatten(),
+                    target_reps: r.try_get::<i32, _>("target_reps").unwrap_or(0).max(0) as u32,
+                    status: r
+                        .try_get::<String, _>("status")
+                        .ok()
+                        .and_then(|s| serde_json::from_value(json!(s)).ok())
+                        .unwrap_or(PlannedSetStatus::Pending),
+                })
+                .collect();
+            let performed: Vec<SessionSet> = performed
+                .iter()
+                .map(|r| SessionSet {
+                    exercise_id: r.try_get("exercise_id").unwrap_or_default(),
+                    exercise_name: r.try_get("exercise_name").unwrap_or_default(),
+                    weight_kg: r.try_get("weight_kg").unwrap_or(0.0),
+                    reps: r.try_get::<i32, _>("reps").unwrap_or(0).max(0) as u32,
+                    is_planned: r.try_get("is_planned").unwrap_or(false),
+                })
+                .collect();
+            (StatusCode::OK, Json(json!(summarize_session(&planned, &performed)))).into_response()
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::error!("session_summary failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateSessionRequest {
@@ -241,7 +526,7 @@ pub async fn update_session(
 
             // If completing, recalculate totals
             if req.status.as_deref() == Some("completed") {
-                let _ = recalculate_session_totals(&pool, uuid).await;
+                let _ = recalculate_session_totals(&*pool, uuid).await;
             }
 
             (StatusCode::OK, Json(json!({"ok": true}))).into_response()
@@ -253,16 +538,44 @@ pub async fn update_session(
     }
 }
 
-async fn recalculate_session_totals(pool: &PgPool, session_id: Uuid) -> Result<(), sqlx::Error> {
+/// Refresh a session's stored energy and volume totals; takes a transaction when the totals
+/// must change together with the sets or activities they sum
+pub(super) async fn recalculate_session_totals<'e>(
+    executor: impl PgExecutor<'e>,
+    session_id: Uuid,
+) -> Result<(), sqlx::Error> {
     sqlx::query(
         "UPDATE workout_sessions SET
-            total_energy_kcal = (SELECT COALESCE(SUM(energy_kcal), 0) FROM workout_sets WHERE session_id = $1),
+            total_energy_kcal = (SELECT COALESCE(SUM(energy_kcal), 0) FROM workout_sets WHERE session_id = $1)
+                + (SELECT COALESCE(SUM(energy_kcal), 0) FROM cardio_activities WHERE session_id = $1),
             total_volume_kg = (SELECT COALESCE(SUM(weight_kg * reps), 0) FROM workout_sets WHERE session_id = $1),
             updated_at = now()
          WHERE id = $1"
     )
     .bind(session_id)
-    .execute(pool)
+    .execute(executor)
     .await?;
     Ok(())
 }
+
+/// Body weight of the session's measurement snapshot, else the user's latest, else 75 kg.
+/// None when the session doesn't belong to the user.
+pub(super) async fn session_body_weight(
+    pool: &PgPool,
+    session_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<f64>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT COALESCE(
+                (SELECT bm.body_weight_kg FROM body_measurements bm WHERE bm.id = ws.measurement_id),
+                (SELECT bm.body_weight_kg FROM body_measurements bm WHERE bm.user_id = ws.user_id
+                 ORDER BY bm.measured_at DESC LIMIT 1)
+            )::float8 as body_weight_kg
+         FROM workout_sessions ws WHERE ws.id = $1 AND ws.user_id = $2",
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| r.try_get::<Option<f64>, _>("body_weight_kg").ok().flatten().unwrap_or(75.0)))
+}
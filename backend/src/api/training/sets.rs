@@ -1,11 +1,18 @@
+use super::{load_working_sets, resolve_entered_weight, unit_sql};
 use crate::middleware::session_middleware::AuthenticatedUser;
-use crate::tools::training::{self, BodyMeasurements, SetEnergy, SetEnergyParams, Tempo};
+use crate::tools::training::records::{new_records, LoggedSet};
+use crate::tools::training::summary::PlannedSetStatus;
+use crate::tools::training::{
+    self, BodyMeasurements, MuscleMapping, OneRmFormula, SetEnergy, SetEnergyParams, Tempo,
+    WeightUnit,
+};
 use axum::extract::{Extension, Json, Path};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use serde::Deserialize;
 use serde_json::json;
-use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, PgPool, Row};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -14,7 +21,11 @@ use uuid::Uuid;
 pub struct LogSetRequest {
     pub exercise_id: String,
     pub set_number: i32,
-    pub weight_kg: f64,
+    /// Load in kg; exactly one of `weight_kg` and `weight` is required
+    pub weight_kg: Option<f64>,
+    /// Load in `weight_unit` (the user's preferred unit by default)
+    pub weight: Option<f64>,
+    pub weight_unit: Option<WeightUnit>,
     pub reps: i32,
     pub rpe: Option<f64>,
     pub tempo_eccentric_s: Option<f64>,
@@ -26,6 +37,10 @@ pub struct LogSetRequest {
     pub is_failure: Option<bool>,
     pub rest_after_seconds: Option<i32>,
     pub notes: Option<String>,
+    /// Formula for the e1RM record check (Epley by default)
+    pub one_rm_formula: Option<OneRmFormula>,
+    /// Planned set this set checks off
+    pub planned_set_id: Option<String>,
 }
 
 pub async fn log_set(
@@ -74,49 +89,200 @@ pub async fn log_set(
         }
     };
 
+    match (req.weight_kg, req.weight) {
+        (None, None) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "weightKg or weight is required"})),
+            )
+                .into_response()
+        }
+        (Some(_), Some(_)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "send either weightKg or weight, not both"})),
+            )
+                .into_response()
+        }
+        _ => {}
+    }
+    // Kept as entered so it reads back exactly; the kg value drives every calculation
+    let entered = match resolve_entered_weight(&pool, user.id, req.weight, req.weight_unit).await {
+        Ok(entered) => entered,
+        Err(e) => {
+            tracing::error!("log_set unit lookup failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    let weight_kg = entered.map_or(req.weight_kg.unwrap_or_default(), |e| e.unit.to_kg(e.value));
+    if weight_kg < 0.0 {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "weight must not be negative"})))
+            .into_response();
+    }
+
+    let planned_set_uuid = match req.planned_set_id.as_deref().map(Uuid::parse_str) {
+        None => None,
+        Some(Ok(u)) => Some(u),
+        Some(Err(_)) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid planned_set_id"})))
+                .into_response()
+        }
+    };
+    let mut planned_warmup = false;
+    if let Some(planned_id) = planned_set_uuid {
+        match sqlx::query(
+            "SELECT is_warmup, exercise_id, status FROM planned_sets WHERE id = $1 AND session_id = $2",
+        )
+        .bind(planned_id)
+        .bind(session_uuid)
+        .fetch_optional(&*pool)
+        .await
+        {
+            Ok(Some(row)) => {
+                if row.try_get::<Uuid, _>("exercise_id").ok() != Some(exercise_uuid) {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(json!({"error": "planned set is for a different exercise"})),
+                    )
+                        .into_response();
+                }
+                if row.try_get::<String, _>("status").ok().as_deref() != Some("pending") {
+                    return (
+                        StatusCode::CONFLICT,
+                        Json(json!({"error": "planned set is not pending"})),
+                    )
+                        .into_response();
+                }
+                planned_warmup = row.try_get("is_warmup").unwrap_or(false);
+            }
+            Ok(None) => {
+                return (StatusCode::NOT_FOUND, Json(json!({"error": "planned set not found"})))
+                    .into_response()
+            }
+            Err(e) => {
+                tracing::error!("log_set planned set check failed: {e}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                    .into_response();
+            }
+        }
+    }
+
     // Compute energy for this set
-    let energy = compute_set_energy_for_log(&pool, exercise_uuid, measurement_id, &req).await;
+    let energy =
+        compute_set_energy_for_log(&pool, exercise_uuid, measurement_id, weight_kg, &req).await;
 
-    match sqlx::query(
-        "INSERT INTO workout_sets (session_id, exercise_id, set_number, weight_kg, reps, rpe,
-            tempo_eccentric_s, tempo_pause_bottom_s, tempo_concentric_s, tempo_pause_top_s,
-            is_warmup, is_dropset, is_failure, rest_after_seconds,
-            energy_kcal, energy_potential_kcal, energy_kinetic_kcal, energy_isometric_kcal, notes)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
-         RETURNING id"
-    )
-    .bind(session_uuid)
-    .bind(exercise_uuid)
-    .bind(req.set_number)
-    .bind(req.weight_kg)
-    .bind(req.reps)
-    .bind(req.rpe)
-    .bind(req.tempo_eccentric_s.unwrap_or(2.0))
-    .bind(req.tempo_pause_bottom_s.unwrap_or(0.0))
-    .bind(req.tempo_concentric_s.unwrap_or(1.0))
-    .bind(req.tempo_pause_top_s.unwrap_or(0.0))
-    .bind(req.is_warmup.unwrap_or(false))
-    .bind(req.is_dropset.unwrap_or(false))
-    .bind(req.is_failure.unwrap_or(false))
-    .bind(req.rest_after_seconds)
-    .bind(energy.total_kcal)
-    .bind(energy.potential_kcal)
-    .bind(energy.kinetic_kcal)
-    .bind(energy.isometric_kcal)
-    .bind(&req.notes)
-    .fetch_one(&*pool)
-    .await
-    {
-        Ok(row) => {
+    // Earlier working sets, loaded before the insert so the new set is compared against them
+    // Sets checking off a planned warm-up are warm-ups unless the request says otherwise
+    let is_warmup = req.is_warmup.unwrap_or(planned_warmup);
+    let previous_sets = if is_warmup {
+        Vec::new()
+    } else {
+        match load_working_sets(&pool, user.id, exercise_uuid).await {
+            Ok(sets) => sets,
+            Err(e) => {
+                tracing::error!("log_set record lookup failed: {e}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                    .into_response();
+            }
+        }
+    };
+
+    // The set, its muscle energy and the planned set it checks off are stored together or not
+    // at all
+    let result: Result<Option<PgRow>, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        let row = sqlx::query(
+            "INSERT INTO workout_sets (session_id, exercise_id, set_number, weight_kg, reps, rpe,
+                tempo_eccentric_s, tempo_pause_bottom_s, tempo_concentric_s, tempo_pause_top_s,
+                is_warmup, is_dropset, is_failure, rest_after_seconds,
+                energy_kcal, energy_potential_kcal, energy_kinetic_kcal, energy_isometric_kcal, notes,
+                weight_input, weight_unit, energy_model_version)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22)
+             RETURNING id, performed_at"
+        )
+        .bind(session_uuid)
+        .bind(exercise_uuid)
+        .bind(req.set_number)
+        .bind(weight_kg)
+        .bind(req.reps)
+        .bind(req.rpe)
+        .bind(req.tempo_eccentric_s.unwrap_or(2.0))
+        .bind(req.tempo_pause_bottom_s.unwrap_or(0.0))
+        .bind(req.tempo_concentric_s.unwrap_or(1.0))
+        .bind(req.tempo_pause_top_s.unwrap_or(0.0))
+        .bind(is_warmup)
+        .bind(req.is_dropset.unwrap_or(false))
+        .bind(req.is_failure.unwrap_or(false))
+        .bind(req.rest_after_seconds)
+        .bind(energy.total_kcal)
+        .bind(energy.potential_kcal)
+        .bind(energy.kinetic_kcal)
+        .bind(energy.isometric_kcal)
+        .bind(&req.notes)
+        .bind(entered.map(|e| e.value))
+        .bind(entered.map(|e| unit_sql(e.unit)))
+        .bind(training::ENERGY_MODEL_VERSION)
+        .fetch_one(&mut *tx)
+        .await?;
+        let id: Uuid = row.try_get("id")?;
+        store_muscle_energy(&mut tx, id, exercise_uuid, energy.total_kcal).await?;
+        if let Some(planned_id) = planned_set_uuid {
+            let completed = sqlx::query(
+                "UPDATE planned_sets SET status = 'completed', workout_set_id = $1, updated_at = now()
+                 WHERE id = $2 AND status = 'pending'",
+            )
+            .bind(id)
+            .bind(planned_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            // Checked off by a concurrent request since the lookup above
+            if completed == 0 {
+                return Ok(None);
+            }
+        }
+        tx.commit().await?;
+        Ok(Some(row))
+    }
+    .await;
+
+    match result {
+        Ok(Some(row)) => {
             let id: Uuid = row.try_get("id").unwrap_or_default();
-            (StatusCode::CREATED, Json(json!({
-                "id": id.to_string(),
-                "energyKcal": energy.total_kcal,
-                "energyPotentialKcal": energy.potential_kcal,
-                "energyKineticKcal": energy.kinetic_kcal,
-                "energyIsometricKcal": energy.isometric_kcal,
-            }))).into_response()
+            let records = if is_warmup {
+                Vec::new()
+            } else {
+                let set = LoggedSet {
+                    session_id: session_uuid,
+                    performed_at: row
+                        .try_get("performed_at")
+                        .unwrap_or_else(|_| chrono::Utc::now()),
+                    weight_kg,
+                    entered,
+                    reps: req.reps.max(0) as u32,
+                };
+                new_records(&previous_sets, &set, req.one_rm_formula.unwrap_or_default())
+            };
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "id": id.to_string(),
+                    "weightKg": weight_kg,
+                    "weight": entered.map_or(weight_kg, |e| e.value),
+                    "weightUnit": entered.map_or(WeightUnit::Kg, |e| e.unit),
+                    "energyKcal": energy.total_kcal,
+                    "energyPotentialKcal": energy.potential_kcal,
+                    "energyKineticKcal": energy.kinetic_kcal,
+                    "energyIsometricKcal": energy.isometric_kcal,
+                    "isRecord": !records.is_empty(),
+                    "newRecords": records,
+                })),
+            )
+                .into_response()
         }
+        Ok(None) => (StatusCode::CONFLICT, Json(json!({"error": "planned set is not pending"})))
+            .into_response(),
         Err(e) => {
             tracing::error!("log_set failed: {e}");
             (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
@@ -124,11 +290,156 @@ pub async fn log_set(
     }
 }
 
+/// Split a set's energy over the exercise's muscles and store the shares. Runs on the
+/// caller's connection so the shares are written in the same transaction as the set.
+pub(super) async fn store_muscle_energy(
+    conn: &mut PgConnection,
+    set_id: Uuid,
+    exercise_id: Uuid,
+    energy_kcal: f64,
+) -> Result<(), sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT mg.id, mg.name, em.involvement, em.activation_fraction::float8 as activation_fraction
+         FROM exercise_muscles em
+         JOIN muscle_groups mg ON mg.id = em.muscle_group_id
+         WHERE em.exercise_id = $1",
+    )
+    .bind(exercise_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mappings: Vec<(Uuid, MuscleMapping)> = rows
+        .iter()
+        .map(|row| {
+            (
+                row.try_get("id").unwrap_or_default(),
+                MuscleMapping {
+                    muscle_name: row.try_get("name").unwrap_or_default(),
+                    involvement: row.try_get("involvement").unwrap_or_default(),
+                    activation_fraction: row.try_get("activation_fraction").unwrap_or(1.0),
+                },
+            )
+        })
+        .collect();
+    let plain: Vec<MuscleMapping> = mappings.iter().map(|(_, m)| m.clone()).collect();
+    let attributed = training::attribute_muscle_energy(energy_kcal, &plain);
+
+    let mut muscle_ids = Vec::new();
+    let mut involvements = Vec::new();
+    let mut shares = Vec::new();
+    let mut energies = Vec::new();
+    for ((muscle_id, mapping), muscle) in mappings.iter().zip(&attributed) {
+        muscle_ids.push(*muscle_id);
+        involvements.push(mapping.involvement.clone());
+        shares.push(muscle.share_fraction);
+        energies.push(muscle.energy_kcal);
+    }
+
+    sqlx::query("DELETE FROM workout_set_muscle_energy WHERE workout_set_id = $1")
+        .bind(set_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        "INSERT INTO workout_set_muscle_energy
+            (workout_set_id, muscle_group_id, involvement, share_fraction, energy_kcal)
+         SELECT $1, * FROM UNNEST($2::uuid[], $3::text[], $4::float8[], $5::float8[])",
+    )
+    .bind(set_id)
+    .bind(&muscle_ids)
+    .bind(&involvements)
+    .bind(&shares)
+    .bind(&energies)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Body measurements from a `body_measurements` row
+pub(super) fn measurements_from_row(row: &PgRow) -> BodyMeasurements {
+    let bd = |col: &str| -> Option<f64> {
+        row.try_get::<Option<sqlx::types::BigDecimal>, _>(col)
+            .ok()
+            .flatten()
+            .and_then(|d| d.to_string().parse::<f64>().ok())
+    };
+    BodyMeasurements {
+        body_weight_kg: bd("body_weight_kg").unwrap_or(75.0),
+        height_cm: bd("height_cm"),
+        upper_arm_length_cm: bd("upper_arm_length_cm"),
+        lower_arm_length_cm: bd("lower_arm_length_cm"),
+        upper_leg_length_cm: bd("upper_leg_length_cm"),
+        lower_leg_length_cm: bd("lower_leg_length_cm"),
+        torso_length_cm: bd("torso_length_cm"),
+        arm_length_cm: bd("arm_length_cm"),
+        leg_length_cm: bd("leg_length_cm"),
+        shoulder_width_cm: bd("shoulder_width_cm"),
+    }
+}
+
+/// Tempo from the float8 `tempo_*` columns of a set row, standard tempo where unset
+pub(super) fn tempo_from_row(row: &PgRow) -> Tempo {
+    let standard = Tempo::standard();
+    let f = |col: &str| row.try_get::<Option<f64>, _>(col).ok().flatten();
+    Tempo {
+        eccentric_s: f("tempo_eccentric_s").unwrap_or(standard.eccentric_s),
+        pause_bottom_s: f("tempo_pause_bottom_s").unwrap_or(standard.pause_bottom_s),
+        concentric_s: f("tempo_concentric_s").unwrap_or(standard.concentric_s),
+        pause_top_s: f("tempo_pause_top_s").unwrap_or(standard.pause_top_s),
+    }
+}
+
+/// Measurements assumed when the user has none
+pub(super) fn default_measurements() -> BodyMeasurements {
+    BodyMeasurements {
+        body_weight_kg: 75.0,
+        height_cm: Some(175.0),
+        upper_arm_length_cm: None,
+        lower_arm_length_cm: None,
+        upper_leg_length_cm: None,
+        lower_leg_length_cm: None,
+        torso_length_cm: None,
+        arm_length_cm: None,
+        leg_length_cm: None,
+        shoulder_width_cm: None,
+    }
+}
+
+/// Energy model inputs for a set, from a row with the exercise's movement columns
+pub(super) fn energy_params(
+    exercise: &PgRow,
+    weight_kg: f64,
+    reps: i32,
+    tempo: Tempo,
+    measurements: BodyMeasurements,
+) -> SetEnergyParams {
+    let bd_ex = |col: &str| -> Option<f64> {
+        exercise
+            .try_get::<Option<sqlx::types::BigDecimal>, _>(col)
+            .ok()
+            .flatten()
+            .and_then(|d| d.to_string().parse::<f64>().ok())
+    };
+    SetEnergyParams {
+        weight_kg,
+        reps: reps.max(0) as u32,
+        movement_pattern: exercise.try_get::<String, _>("movement_pattern").unwrap_or_default(),
+        primary_segments_moved: exercise
+            .try_get::<Vec<String>, _>("primary_segments_moved")
+            .unwrap_or_default(),
+        rom_degrees: bd_ex("rom_degrees").unwrap_or(90.0),
+        is_bodyweight: exercise.try_get::<bool, _>("is_bodyweight").unwrap_or(false),
+        is_unilateral: exercise.try_get::<bool, _>("is_unilateral").unwrap_or(false),
+        body_mass_fraction_moved: bd_ex("body_mass_fraction_moved").unwrap_or(0.0),
+        measurements,
+        tempo,
+    }
+}
+
 /// Helper: compute energy for a set being logged, loading exercise + measurement data from DB.
 async fn compute_set_energy_for_log(
     pool: &PgPool,
     exercise_id: Uuid,
     measurement_id: Option<Uuid>,
+    weight_kg: f64,
     req: &LogSetRequest,
 ) -> SetEnergy {
     // Load exercise data
@@ -167,67 +478,15 @@ async fn compute_set_energy_for_log(
         };
     };
 
-    let measurements = if let Some(mr) = measurement_row {
-        let bd = |col: &str| -> Option<f64> {
-            mr.try_get::<Option<sqlx::types::BigDecimal>, _>(col)
-                .ok()
-                .flatten()
-                .and_then(|d| d.to_string().parse::<f64>().ok())
-        };
-        BodyMeasurements {
-            body_weight_kg: bd("body_weight_kg").unwrap_or(75.0),
-            height_cm: bd("height_cm"),
-            upper_arm_length_cm: bd("upper_arm_length_cm"),
-            lower_arm_length_cm: bd("lower_arm_length_cm"),
-            upper_leg_length_cm: bd("upper_leg_length_cm"),
-            lower_leg_length_cm: bd("lower_leg_length_cm"),
-            torso_length_cm: bd("torso_length_cm"),
-            arm_length_cm: bd("arm_length_cm"),
-            leg_length_cm: bd("leg_length_cm"),
-            shoulder_width_cm: bd("shoulder_width_cm"),
-        }
-    } else {
-        // Default measurements
-        BodyMeasurements {
-            body_weight_kg: 75.0,
-            height_cm: Some(175.0),
-            upper_arm_length_cm: None,
-            lower_arm_length_cm: None,
-            upper_leg_length_cm: None,
-            lower_leg_length_cm: None,
-            torso_length_cm: None,
-            arm_length_cm: None,
-            leg_length_cm: None,
-            shoulder_width_cm: None,
-        }
-    };
-
-    let bd_ex = |col: &str| -> Option<f64> {
-        ex.try_get::<Option<sqlx::types::BigDecimal>, _>(col)
-            .ok()
-            .flatten()
-            .and_then(|d| d.to_string().parse::<f64>().ok())
-    };
-
-    let params = SetEnergyParams {
-        weight_kg: req.weight_kg,
-        reps: req.reps.max(0) as u32,
-        movement_pattern: ex.try_get::<String, _>("movement_pattern").unwrap_or_default(),
-        primary_segments_moved: ex
-            .try_get::<Vec<String>, _>("primary_segments_moved")
-            .unwrap_or_default(),
-        rom_degrees: bd_ex("rom_degrees").unwrap_or(90.0),
-        is_bodyweight: ex.try_get::<bool, _>("is_bodyweight").unwrap_or(false),
-        is_unilateral: ex.try_get::<bool, _>("is_unilateral").unwrap_or(false),
-        body_mass_fraction_moved: bd_ex("body_mass_fraction_moved").unwrap_or(0.0),
-        measurements,
-        tempo: Tempo {
-            eccentric_s: req.tempo_eccentric_s.unwrap_or(2.0),
-            pause_bottom_s: req.tempo_pause_bottom_s.unwrap_or(0.0),
-            concentric_s: req.tempo_concentric_s.unwrap_or(1.0),
-            pause_top_s: req.tempo_pause_top_s.unwrap_or(0.0),
-        },
+    let measurements =
+        measurement_row.as_ref().map_or_else(default_measurements, measurements_from_row);
+    let tempo = Tempo {
+        eccentric_s: req.tempo_eccentric_s.unwrap_or(2.0),
+        pause_bottom_s: req.tempo_pause_bottom_s.unwrap_or(0.0),
+        concentric_s: req.tempo_concentric_s.unwrap_or(1.0),
+        pause_top_s: req.tempo_pause_top_s.unwrap_or(0.0),
     };
+    let params = energy_params(&ex, weight_kg, req.reps, tempo, measurements);
 
     training::compute_set_energy(&params)
 }
@@ -263,21 +522,127 @@ pub async fn delete_set(
             .into_response();
     }
 
-    match sqlx::query("DELETE FROM workout_sets WHERE id = $1 AND session_id = $2")
+    // A planned set checked off by this set becomes pending again
+    let result: Result<u64, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            "UPDATE planned_sets SET status = 'pending', workout_set_id = NULL, updated_at = now()
+             WHERE workout_set_id = $1 AND session_id = $2",
+        )
         .bind(set_uuid)
         .bind(session_uuid)
-        .execute(&*pool)
-        .await
+        .execute(&mut *tx)
+        .await?;
+        let deleted = sqlx::query("DELETE FROM workout_sets WHERE id = $1 AND session_id = $2")
+            .bind(set_uuid)
+            .bind(session_uuid)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted)
+    }
+    .await;
+
+    match result {
+        Ok(deleted) => {
+            if deleted == 0 {
+                (StatusCode::NOT_FOUND, Json(json!({"error": "set not found"}))).into_response()
+            } else {
+                (StatusCode::OK, Json(json!({"ok": true}))).into_response()
+            }
+        }
+        Err(e) => {
+            tracing::error!("delete_set failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePlannedSetRequest {
+    pub target_weight_kg: Option<f64>,
+    pub target_reps: Option<i32>,
+    pub target_rpe: Option<f64>,
+    pub rest_seconds: Option<i32>,
+    /// `pending` or `skipped`; sets are completed by logging them with `plannedSetId`
+    pub status: Option<PlannedSetStatus>,
+}
+
+/// Adjust or skip a planned set during the workout
+pub async fn update_planned_set(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path((session_id, id)): Path<(String, String)>,
+    Json(req): Json<UpdatePlannedSetRequest>,
+) -> impl IntoResponse {
+    let session_uuid = match Uuid::parse_str(&session_id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid session_id"})))
+                .into_response()
+        }
+    };
+    let uuid = match Uuid::parse_str(&id) {
+        Ok(u) => u,
+        Err(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response()
+        }
+    };
+    if req.status == Some(PlannedSetStatus::Completed) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "log a set with plannedSetId to complete a planned set"})),
+        )
+            .into_response();
+    }
+    // Same bounds as the warm-up generator that creates planned sets
+    if req.target_weight_kg.is_some_and(|w| !(0.0..=training::MAX_TARGET_KG).contains(&w)) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("targetWeightKg must be 0-{} kg", training::MAX_TARGET_KG)})),
+        )
+            .into_response();
+    }
+    if req.target_reps.is_some_and(|r| r <= 0) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "targetReps must be positive"})))
+            .into_response();
+    }
+
+    match sqlx::query(
+        "UPDATE planned_sets SET
+            target_weight_kg = COALESCE($1, target_weight_kg),
+            target_reps = COALESCE($2, target_reps),
+            target_rpe = COALESCE($3, target_rpe),
+            rest_seconds = COALESCE($4, rest_seconds),
+            status = COALESCE($5, status),
+            workout_set_id = CASE WHEN $5 IS NULL THEN workout_set_id ELSE NULL END,
+            updated_at = now()
+         WHERE id = $6 AND session_id = $7
+           AND session_id IN (SELECT id FROM workout_sessions WHERE user_id = $8)",
+    )
+    .bind(req.target_weight_kg)
+    .bind(req.target_reps)
+    .bind(req.target_rpe)
+    .bind(req.rest_seconds)
+    .bind(req.status.map(|s| json!(s).as_str().unwrap_or_default().to_string()))
+    .bind(uuid)
+    .bind(session_uuid)
+    .bind(user.id)
+    .execute(&*pool)
+    .await
     {
         Ok(result) => {
             if result.rows_affected() == 0 {
-                (StatusCode::NOT_FOUND, Json(json!({"error": "set not found"}))).into_response()
+                (StatusCode::NOT_FOUND, Json(json!({"error": "planned set not found"})))
+                    .into_response()
             } else {
                 (StatusCode::OK, Json(json!({"ok": true}))).into_response()
             }
         }
         Err(e) => {
-            tracing::error!("delete_set failed: {e}");
+            tracing::error!("update_planned_set failed: {e}");
             (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
         }
     }
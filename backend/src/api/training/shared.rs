@@ -1,4 +1,6 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::Deserialize;
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
 pub struct PaginationParams {
@@ -6,21 +8,97 @@ pub struct PaginationParams {
     pub offset: Option<i64>,
 }
 
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsGroupBy {
+    #[default]
+    Day,
+    /// ISO week, starting on Monday
+    Week,
+    Month,
+}
+
+impl StatsGroupBy {
+    /// Field name for `date_trunc`
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 pub struct StatsFilterParams {
+    /// RFC 3339 timestamp or `YYYY-MM-DD`
     pub from: Option<String>,
+    /// RFC 3339 timestamp or `YYYY-MM-DD`; both are inclusive
     pub to: Option<String>,
     pub plan_id: Option<String>,
     pub exercise_id: Option<String>,
+    pub group_by: Option<StatsGroupBy>,
+    #[serde(default)]
+    pub exclude_warmups: bool,
+}
+
+/// Validated stats filters; `to` is an exclusive bound
+#[derive(Debug, Clone, Default)]
+pub struct StatsFilter {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub plan_id: Option<Uuid>,
+    pub exercise_id: Option<Uuid>,
+    pub group_by: StatsGroupBy,
+    pub exclude_warmups: bool,
+}
+
+/// RFC 3339 timestamp or `YYYY-MM-DD`; `end_of_day` turns it into an exclusive upper bound
+pub fn parse_bound(field: &str, value: &str, end_of_day: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let date = if end_of_day { date.succ_opt().unwrap_or(date) } else { date };
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|ts| {
+            let ts = ts.with_timezone(&Utc);
+            if end_of_day {
+                ts + chrono::Duration::microseconds(1)
+            } else {
+                ts
+            }
+        })
+        .map_err(|_| format!("{field} must be an RFC 3339 timestamp or YYYY-MM-DD"))
+}
+
+impl StatsFilterParams {
+    pub fn resolve(&self) -> Result<StatsFilter, String> {
+        let uuid = |field: &str, value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|v| Uuid::parse_str(v).map_err(|_| format!("invalid {field}")))
+                .transpose()
+        };
+        Ok(StatsFilter {
+            from: self.from.as_deref().map(|v| parse_bound("from", v, false)).transpose()?,
+            to: self.to.as_deref().map(|v| parse_bound("to", v, true)).transpose()?,
+            plan_id: uuid("plan_id", &self.plan_id)?,
+            exercise_id: uuid("exercise_id", &self.exercise_id)?,
+            group_by: self.group_by.unwrap_or_default(),
+            exclude_warmups: self.exclude_warmups,
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 pub struct ExerciseFilterParams {
     pub equipment: Option<String>,
+    /// Muscle group name, e.g. `chest`
     pub muscle: Option<String>,
+    /// `primary`, `secondary` or `stabilizer`; narrows `muscle` when both are given
+    pub involvement: Option<String>,
     pub pattern: Option<String>,
     pub difficulty: Option<String>,
+    /// Full-text query over name and description, or a substring of the name
     pub search: Option<String>,
 }
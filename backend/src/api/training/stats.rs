@@ -1,28 +1,77 @@
 use super::*;
 use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training;
+use crate::tools::training::WeightUnit;
 use axum::extract::{Extension, Json, Query};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use serde_json::json;
-use sqlx::{PgPool, Row};
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query as SqlQuery;
+use sqlx::{PgPool, Postgres, Row};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
+/// Conditions on `workout_sessions ws` and `workout_sets wse` bound by `bind_filter` as $2-$6
+const FILTER_SQL: &str = "ws.user_id = $1 AND ws.status = 'completed'
+           AND ($2::timestamptz IS NULL OR ws.started_at >= $2)
+           AND ($3::timestamptz IS NULL OR ws.started_at < $3)
+           AND ($4::uuid IS NULL OR ws.plan_id = $4)
+           AND ($5::uuid IS NULL OR wse.exercise_id = $5)
+           AND (NOT $6 OR wse.is_warmup = FALSE)";
+
+fn bind_filter<'q>(
+    query: SqlQuery<'q, Postgres, PgArguments>,
+    user_id: uuid::Uuid,
+    filter: &StatsFilter,
+) -> SqlQuery<'q, Postgres, PgArguments> {
+    query
+        .bind(user_id)
+        .bind(filter.from)
+        .bind(filter.to)
+        .bind(filter.plan_id)
+        .bind(filter.exercise_id)
+        .bind(filter.exclude_warmups)
+}
+
+/// Binds the weight unit and its kg as $8 and $9 for `weight_in_unit_sql(.., 8, 9)`
+fn bind_unit<'q>(
+    query: SqlQuery<'q, Postgres, PgArguments>,
+    unit: WeightUnit,
+) -> SqlQuery<'q, Postgres, PgArguments> {
+    query.bind(unit_sql(unit)).bind(unit.to_kg(1.0))
+}
+
+fn bad_filter(e: String) -> axum::response::Response {
+    (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response()
+}
+
 pub async fn stats_energy(
     AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
-    Query(_params): Query<StatsFilterParams>,
+    Query(params): Query<StatsFilterParams>,
 ) -> impl IntoResponse {
-    match sqlx::query(
-        "SELECT ws.started_at::date as day, SUM(wse.energy_kcal) as total_energy
+    let filter = match params.resolve() {
+        Ok(f) => f,
+        Err(e) => return bad_filter(e),
+    };
+    // Cardio has no exercise, so an exercise filter leaves it out.
+    // Without a lower bound only the latest 365 periods are returned
+    let sql = format!(
+        "SELECT date_trunc($7, ws.started_at)::date as day, SUM(wse.energy_kcal) as total_energy
          FROM workout_sessions ws
-         JOIN workout_sets wse ON wse.session_id = ws.id
-         WHERE ws.user_id = $1 AND ws.status = 'completed'
-         GROUP BY ws.started_at::date
-         ORDER BY day DESC LIMIT 365",
-    )
-    .bind(user.id)
-    .fetch_all(&*pool)
-    .await
+         JOIN (SELECT session_id, exercise_id, is_warmup, energy_kcal FROM workout_sets
+               UNION ALL
+               SELECT session_id, NULL, FALSE, energy_kcal FROM cardio_activities) wse
+           ON wse.session_id = ws.id
+         WHERE {FILTER_SQL}
+         GROUP BY day
+         ORDER BY day DESC LIMIT CASE WHEN $2::timestamptz IS NULL THEN 365 END"
+    );
+    match bind_filter(sqlx::query(&sql), user.id, &filter)
+        .bind(filter.group_by.as_sql())
+        .fetch_all(&*pool)
+        .await
     {
         Ok(rows) => {
             let data: Vec<serde_json::Value> = rows.iter().map(|row| {
@@ -31,7 +80,8 @@ pub async fn stats_energy(
                     "totalEnergyKcal": row.try_get::<Option<sqlx::types::BigDecimal>, _>("total_energy").ok().flatten().map(|d| d.to_string()),
                 })
             }).collect();
-            (StatusCode::OK, Json(json!({"data": data}))).into_response()
+            (StatusCode::OK, Json(json!({"groupBy": filter.group_by.as_sql(), "data": data})))
+                .into_response()
         }
         Err(e) => {
             tracing::error!("stats_energy failed: {e}");
@@ -43,28 +93,47 @@ pub async fn stats_energy(
 pub async fn stats_volume(
     AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
-    Query(_params): Query<StatsFilterParams>,
+    Query(params): Query<StatsFilterParams>,
 ) -> impl IntoResponse {
-    match sqlx::query(
-        "SELECT ws.started_at::date as day, SUM(wse.weight_kg * wse.reps) as total_volume
+    let filter = match params.resolve() {
+        Ok(f) => f,
+        Err(e) => return bad_filter(e),
+    };
+    let unit = match preferred_weight_unit(&pool, user.id).await {
+        Ok(unit) => unit,
+        Err(e) => {
+            tracing::error!("stats_volume failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    let weight = weight_in_unit_sql("wse", 8, 9);
+    let sql = format!(
+        "SELECT date_trunc($7, ws.started_at)::date as day, SUM(wse.weight_kg * wse.reps) as total_volume,
+                SUM({weight} * wse.reps) as total_volume_in_unit
          FROM workout_sessions ws
          JOIN workout_sets wse ON wse.session_id = ws.id
-         WHERE ws.user_id = $1 AND ws.status = 'completed'
-         GROUP BY ws.started_at::date
-         ORDER BY day DESC LIMIT 365",
-    )
-    .bind(user.id)
-    .fetch_all(&*pool)
-    .await
-    {
+         WHERE {FILTER_SQL}
+         GROUP BY day
+         ORDER BY day DESC LIMIT CASE WHEN $2::timestamptz IS NULL THEN 365 END"
+    );
+    let query = bind_filter(sqlx::query(&sql), user.id, &filter).bind(filter.group_by.as_sql());
+    match bind_unit(query, unit).fetch_all(&*pool).await {
         Ok(rows) => {
             let data: Vec<serde_json::Value> = rows.iter().map(|row| {
                 json!({
                     "date": row.try_get::<chrono::NaiveDate, _>("day").ok().map(|d| d.to_string()),
                     "totalVolumeKg": row.try_get::<Option<sqlx::types::BigDecimal>, _>("total_volume").ok().flatten().map(|d| d.to_string()),
+                    "totalVolume": row.try_get::<Option<f64>, _>("total_volume_in_unit").ok().flatten(),
                 })
             }).collect();
-            (StatusCode::OK, Json(json!({"data": data}))).into_response()
+            (
+                StatusCode::OK,
+                Json(
+                    json!({"groupBy": filter.group_by.as_sql(), "weightUnit": unit, "data": data}),
+                ),
+            )
+                .into_response()
         }
         Err(e) => {
             tracing::error!("stats_volume failed: {e}");
@@ -73,49 +142,137 @@ pub async fn stats_volume(
     }
 }
 
+/// Credited sets and volume of one muscle in one period
+#[derive(Default)]
+struct MuscleWork {
+    direct_sets: i64,
+    sets: f64,
+    volume_kg: f64,
+    /// Volume in the user's weight unit
+    volume: f64,
+}
+
+/// Energy per muscle from the stored involvement-weighted split, plus set counts
+/// and volume per muscle and period (weekly unless `group_by` is given)
 pub async fn stats_muscle_energy(
     AuthenticatedUser(user): AuthenticatedUser,
     Extension(pool): Extension<Arc<PgPool>>,
-    Query(_params): Query<StatsFilterParams>,
+    Query(params): Query<StatsFilterParams>,
 ) -> impl IntoResponse {
-    // Get all completed session sets with their exercise muscle mappings, summing energy directly in SQL
-    match sqlx::query(
+    let period = params.group_by.unwrap_or(StatsGroupBy::Week);
+    let filter = match params.resolve() {
+        Ok(f) => f,
+        Err(e) => return bad_filter(e),
+    };
+    let energy_sql = format!(
         "SELECT mg.name as muscle_name, mg.display_name, mg.relative_size, mg.body_map_position, mg.svg_region_id,
-                SUM(wse.energy_kcal) as total_energy
+                SUM(wsme.energy_kcal)::float8 as total_energy
          FROM workout_sessions ws
          JOIN workout_sets wse ON wse.session_id = ws.id
-         JOIN exercise_muscles em ON em.exercise_id = wse.exercise_id
-         JOIN muscle_groups mg ON mg.id = em.muscle_group_id
-         WHERE ws.user_id = $1 AND ws.status = 'completed'
+         JOIN workout_set_muscle_energy wsme ON wsme.workout_set_id = wse.id
+         JOIN muscle_groups mg ON mg.id = wsme.muscle_group_id
+         WHERE {FILTER_SQL}
          GROUP BY mg.name, mg.display_name, mg.relative_size, mg.body_map_position, mg.svg_region_id
          ORDER BY mg.name"
-    )
-    .bind(user.id)
-    .fetch_all(&*pool)
-    .await
-    {
-        Ok(rows) => {
-            let data: Vec<serde_json::Value> = rows.iter().map(|row| {
-                let energy: f64 = row.try_get::<Option<sqlx::types::BigDecimal>, _>("total_energy")
-                    .ok().flatten()
-                    .and_then(|d| d.to_string().parse::<f64>().ok())
-                    .unwrap_or(0.0);
-
-                json!({
-                    "muscleName": row.try_get::<String, _>("muscle_name").unwrap_or_default(),
-                    "displayName": row.try_get::<String, _>("display_name").unwrap_or_default(),
-                    "relativeSize": row.try_get::<sqlx::types::BigDecimal, _>("relative_size").ok().map(|d| d.to_string()),
-                    "bodyMapPosition": row.try_get::<String, _>("body_map_position").unwrap_or_default(),
-                    "svgRegionId": row.try_get::<String, _>("svg_region_id").unwrap_or_default(),
-                    "energyKcal": energy,
-                })
-            }).collect();
-
-            (StatusCode::OK, Json(json!({"muscles": data}))).into_response()
-        }
+    );
+    // Sets count for the muscles an exercise currently maps, stabilizers excluded
+    let weight = weight_in_unit_sql("wse", 8, 9);
+    let work_sql = format!(
+        "SELECT date_trunc($7, ws.started_at)::date as period, mg.name as muscle_name, em.involvement,
+                COUNT(*) as sets, SUM(wse.weight_kg * wse.reps)::float8 as volume,
+                SUM({weight} * wse.reps) as volume_in_unit
+         FROM workout_sessions ws
+         JOIN workout_sets wse ON wse.session_id = ws.id
+         JOIN exercise_muscles em ON em.exercise_id = wse.exercise_id
+         JOIN muscle_groups mg ON mg.id = em.muscle_group_id
+         WHERE {FILTER_SQL} AND em.involvement <> 'stabilizer'
+         GROUP BY period, mg.name, em.involvement"
+    );
+    let result: Result<_, sqlx::Error> = async {
+        let unit = preferred_weight_unit(&pool, user.id).await?;
+        let energy =
+            bind_filter(sqlx::query(&energy_sql), user.id, &filter).fetch_all(&*pool).await?;
+        let work = bind_filter(sqlx::query(&work_sql), user.id, &filter).bind(period.as_sql());
+        let work = bind_unit(work, unit).fetch_all(&*pool).await?;
+        Ok((unit, energy, work))
+    }
+    .await;
+    let (unit, energy_rows, work_rows) = match result {
+        Ok(rows) => rows,
         Err(e) => {
             tracing::error!("stats_muscle_energy failed: {e}");
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+
+    let mut periods: BTreeMap<chrono::NaiveDate, BTreeMap<String, MuscleWork>> = BTreeMap::new();
+    let mut totals: BTreeMap<String, MuscleWork> = BTreeMap::new();
+    for row in &work_rows {
+        let Ok(date) = row.try_get::<chrono::NaiveDate, _>("period") else { continue };
+        let muscle: String = row.try_get("muscle_name").unwrap_or_default();
+        let involvement: String = row.try_get("involvement").unwrap_or_default();
+        let sets: i64 = row.try_get("sets").unwrap_or(0);
+        let volume: f64 = row.try_get::<Option<f64>, _>("volume").ok().flatten().unwrap_or(0.0);
+        let volume_in_unit: f64 =
+            row.try_get::<Option<f64>, _>("volume_in_unit").ok().flatten().unwrap_or(0.0);
+        let credit = training::muscle_set_credit(&involvement);
+        let direct = if involvement == "primary" { sets } else { 0 };
+        for work in [
+            periods.entry(date).or_default().entry(muscle.clone()).or_default(),
+            totals.entry(muscle).or_default(),
+        ] {
+            work.direct_sets += direct;
+            work.sets += credit * sets as f64;
+            work.volume_kg += credit * volume;
+            work.volume += credit * volume_in_unit;
         }
     }
+
+    let muscles: Vec<serde_json::Value> = energy_rows.iter().map(|row| {
+        let name = row.try_get::<String, _>("muscle_name").unwrap_or_default();
+        let work = totals.get(&name);
+        json!({
+            "muscleName": name,
+            "displayName": row.try_get::<String, _>("display_name").unwrap_or_default(),
+            "relativeSize": row.try_get::<sqlx::types::BigDecimal, _>("relative_size").ok().map(|d| d.to_string()),
+            "bodyMapPosition": row.try_get::<String, _>("body_map_position").unwrap_or_default(),
+            "svgRegionId": row.try_get::<String, _>("svg_region_id").unwrap_or_default(),
+            "energyKcal": row.try_get::<Option<f64>, _>("total_energy").ok().flatten().unwrap_or(0.0),
+            "directSets": work.map_or(0, |w| w.direct_sets),
+            "sets": work.map_or(0.0, |w| w.sets),
+            "volumeKg": work.map_or(0.0, |w| w.volume_kg),
+            "volume": work.map_or(0.0, |w| w.volume),
+        })
+    }).collect();
+    let periods: Vec<serde_json::Value> = periods
+        .iter()
+        .rev()
+        .map(|(date, muscles)| {
+            let muscles: Vec<serde_json::Value> = muscles
+                .iter()
+                .map(|(name, w)| {
+                    json!({
+                        "muscleName": name,
+                        "directSets": w.direct_sets,
+                        "sets": w.sets,
+                        "volumeKg": w.volume_kg,
+                        "volume": w.volume,
+                    })
+                })
+                .collect();
+            json!({"date": date.to_string(), "muscles": muscles})
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "muscles": muscles,
+            "groupBy": period.as_sql(),
+            "weightUnit": unit,
+            "periods": periods,
+        })),
+    )
+        .into_response()
 }
@@ -0,0 +1,178 @@
+use super::{load_equipment_profile, preferred_weight_unit, profile_bar};
+use crate::middleware::session_middleware::AuthenticatedUser;
+use crate::tools::training::warmup::generate_warmup;
+use crate::tools::training::{EquipmentProfile, WeightUnit};
+use axum::extract::{Extension, Json, Path};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarmupRequest {
+    pub exercise_id: String,
+    /// In the unit of the equipment
+    pub working_weight: f64,
+    /// Working sets of 8 reps or more get no 80% single
+    pub working_reps: u32,
+    /// Equipment profile for the bar and plates; standard plates otherwise
+    pub profile_id: Option<String>,
+    /// Bar name from the profile; the first bar when omitted
+    pub bar: Option<String>,
+    /// Unit without a profile; the user's preferred unit by default
+    pub unit: Option<WeightUnit>,
+}
+
+/// Generate warm-up sets up to a working set and plan them ahead of the exercise's
+/// working sets, replacing warm-ups generated earlier that are still pending.
+/// The ramp is the empty bar, then 40%, 60% and 80% of the working weight; the 80%
+/// single is left out when the working set is 8 reps or more.
+/// Logging a set against one of them marks it as a warm-up.
+pub async fn generate_warmup_sets(
+    AuthenticatedUser(user): AuthenticatedUser,
+    Extension(pool): Extension<Arc<PgPool>>,
+    Path(session_id): Path<String>,
+    Json(req): Json<WarmupRequest>,
+) -> impl IntoResponse {
+    let (Ok(session_uuid), Ok(exercise_uuid)) =
+        (Uuid::parse_str(&session_id), Uuid::parse_str(&req.exercise_id))
+    else {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid id"}))).into_response();
+    };
+    let profile_uuid = match req.profile_id.as_deref().map(Uuid::parse_str) {
+        None => None,
+        Some(Ok(u)) => Some(u),
+        Some(Err(_)) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid profile_id"})))
+                .into_response()
+        }
+    };
+
+    let checks = sqlx::query(
+        "SELECT EXISTS (SELECT 1 FROM workout_sessions WHERE id = $1 AND user_id = $2 AND status = 'in_progress') AS session_found,
+                EXISTS (SELECT 1 FROM exercises WHERE id = $3 AND (is_system_default = TRUE OR user_id = $2)) AS exercise_found",
+    )
+    .bind(session_uuid)
+    .bind(user.id)
+    .bind(exercise_uuid)
+    .fetch_one(&*pool)
+    .await;
+    match checks {
+        Ok(row) if !row.try_get::<bool, _>("session_found").unwrap_or(false) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "session not found or not in_progress"})),
+            )
+                .into_response()
+        }
+        Ok(row) if !row.try_get::<bool, _>("exercise_found").unwrap_or(false) => {
+            return (StatusCode::NOT_FOUND, Json(json!({"error": "exercise not found"})))
+                .into_response()
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::error!("generate_warmup_sets checks failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    }
+
+    let profile = match profile_uuid {
+        Some(id) => load_equipment_profile(&pool, user.id, id).await,
+        None => match req.unit {
+            Some(unit) => Ok(Some(EquipmentProfile::standard(unit))),
+            None => preferred_weight_unit(&pool, user.id)
+                .await
+                .map(|unit| Some(EquipmentProfile::standard(unit))),
+        },
+    };
+    let profile = match profile {
+        Ok(Some(profile)) => profile,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({"error": "profile not found"})))
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("generate_warmup_sets equipment lookup failed: {e}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"})))
+                .into_response();
+        }
+    };
+    let Some(bar) = profile_bar(&profile, req.bar.as_deref()) else {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "unknown bar"}))).into_response();
+    };
+    let plan = match generate_warmup(
+        req.working_weight,
+        req.working_reps,
+        bar.weight,
+        &profile.plates,
+        profile.unit,
+    ) {
+        Ok(plan) => plan,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))).into_response(),
+    };
+
+    let result: Result<Vec<Uuid>, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            "DELETE FROM planned_sets
+             WHERE session_id = $1 AND exercise_id = $2 AND is_warmup AND status = 'pending'",
+        )
+        .bind(session_uuid)
+        .bind(exercise_uuid)
+        .execute(&mut *tx)
+        .await?;
+        // Ahead of the exercise's working sets, or after everything planned so far
+        let sort_order: i32 = sqlx::query_scalar(
+            "SELECT COALESCE(
+                 (SELECT MIN(sort_order) FROM planned_sets WHERE session_id = $1 AND exercise_id = $2),
+                 (SELECT MAX(sort_order) + 1 FROM planned_sets WHERE session_id = $1),
+                 0)",
+        )
+        .bind(session_uuid)
+        .bind(exercise_uuid)
+        .fetch_one(&mut *tx)
+        .await?;
+        let mut ids = Vec::with_capacity(plan.sets.len());
+        for set in &plan.sets {
+            let id: Uuid = sqlx::query_scalar(
+                "INSERT INTO planned_sets (session_id, exercise_id, sort_order, set_number,
+                    target_weight_kg, target_reps, is_warmup, plate_loadout)
+                 VALUES ($1, $2, $3, $4, $5, $6, TRUE, $7) RETURNING id",
+            )
+            .bind(session_uuid)
+            .bind(exercise_uuid)
+            .bind(sort_order)
+            .bind(set.set_number as i32)
+            .bind(profile.unit.to_kg(set.loadout.achievable_weight))
+            .bind(set.reps as i32)
+            .bind(json!({"unit": profile.unit, "plates": set.loadout.plates_per_side}))
+            .fetch_one(&mut *tx)
+            .await?;
+            ids.push(id);
+        }
+        tx.commit().await?;
+        Ok(ids)
+    }
+    .await;
+
+    match result {
+        Ok(ids) => {
+            let mut body = json!(plan);
+            for (set, id) in body["sets"].as_array_mut().into_iter().flatten().zip(ids) {
+                set["plannedSetId"] = json!(id.to_string());
+            }
+            body["exerciseId"] = json!(exercise_uuid.to_string());
+            body["bar"] = json!(bar.name);
+            (StatusCode::CREATED, Json(body)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("generate_warmup_sets failed: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "internal"}))).into_response()
+        }
+    }
+}
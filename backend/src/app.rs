@@ -30,7 +30,43 @@ pub fn build_app(
         .route("/healthz", get(health_check))
         .route("/api/health", get(health_check))
         .route("/api/tools/fat-loss", post(crate::api::fat_loss::calculate_fat_loss))
+        .route("/api/tools/fat-loss/simulate", post(crate::api::fat_loss::simulate))
+        // Energy-balance journal
+        .route(
+            "/api/tools/energy-journal",
+            get(crate::api::energy_journal::list_entries)
+                .post(crate::api::energy_journal::upsert_entry),
+        )
+        .route("/api/tools/energy-journal/summary", get(crate::api::energy_journal::summary))
+        .route("/api/tools/energy-journal/tdee", get(crate::api::energy_journal::tdee))
+        .route("/api/tools/energy-journal/{id}", delete(crate::api::energy_journal::delete_entry))
         .route("/api/tools/n26-analyzer", post(crate::api::n26_analyzer::analyze_n26_data))
+        .route(
+            "/api/tools/n26-analyzer/subscriptions",
+            post(crate::api::n26_analyzer::detect_subscriptions),
+        )
+        .route(
+            "/api/tools/n26-analyzer/currencies",
+            post(crate::api::n26_analyzer::currency_report),
+        )
+        .route("/api/tools/n26-analyzer/export", post(crate::api::n26_analyzer::export))
+        // Stored N26 ledger
+        .route(
+            "/api/tools/n26-analyzer/ledger",
+            post(crate::api::n26_ledger::import_ledger)
+                .delete(crate::api::n26_ledger::delete_ledger),
+        )
+        .route("/api/tools/n26-analyzer/ledger/export", post(crate::api::n26_ledger::export_ledger))
+        // Budgets
+        .route(
+            "/api/tools/budgets",
+            get(crate::api::budgets::list_budgets).post(crate::api::budgets::create_budget),
+        )
+        .route("/api/tools/budgets/compare", post(crate::api::budgets::compare))
+        .route(
+            "/api/tools/budgets/{id}",
+            put(crate::api::budgets::update_budget).delete(crate::api::budgets::delete_budget),
+        )
         .route("/api/tools/bloodlevel/calculate", post(crate::api::bloodlevel::calculate_tolerance))
         .route("/api/tools/bloodlevel/substances", get(crate::api::bloodlevel::get_substances))
         .route("/api/tools/dice/roll", post(crate::api::dice::roll))
@@ -54,6 +90,10 @@ pub fn build_app(
             "/api/tools/training/measurements/latest",
             get(crate::api::training::latest_measurement),
         )
+        .route(
+            "/api/tools/training/measurements/body-composition",
+            get(crate::api::training::body_composition_history),
+        )
         .route(
             "/api/tools/training/measurements/{id}",
             delete(crate::api::training::delete_measurement),
@@ -61,8 +101,28 @@ pub fn build_app(
         // Muscle groups
         .route("/api/tools/training/muscles", get(crate::api::training::list_muscles))
         // Exercises
-        .route("/api/tools/training/exercises", get(crate::api::training::list_exercises))
-        .route("/api/tools/training/exercises/{id}", get(crate::api::training::get_exercise))
+        .route(
+            "/api/tools/training/exercises",
+            get(crate::api::training::list_exercises).post(crate::api::training::create_exercise),
+        )
+        .route(
+            "/api/tools/training/exercises/{id}",
+            get(crate::api::training::get_exercise)
+                .put(crate::api::training::update_exercise)
+                .delete(crate::api::training::delete_exercise),
+        )
+        .route(
+            "/api/tools/training/exercises/{id}/clone",
+            post(crate::api::training::clone_exercise),
+        )
+        .route(
+            "/api/tools/training/exercises/{id}/records",
+            get(crate::api::training::exercise_records),
+        )
+        .route(
+            "/api/tools/training/exercises/{id}/e1rm-trend",
+            get(crate::api::training::exercise_e1rm_trend),
+        )
         // Training plans
         .route(
             "/api/tools/training/plans",
@@ -74,6 +134,7 @@ pub fn build_app(
                 .put(crate::api::training::update_plan)
                 .delete(crate::api::training::delete_plan),
         )
+        .route("/api/tools/training/plans/{id}/next", get(crate::api::training::next_plan_targets))
         .route(
             "/api/tools/training/plans/{plan_id}/exercises",
             post(crate::api::training::add_plan_exercise),
@@ -82,6 +143,18 @@ pub fn build_app(
             "/api/tools/training/plans/{plan_id}/exercises/{id}",
             delete(crate::api::training::delete_plan_exercise),
         )
+        // Training programs
+        .route(
+            "/api/tools/training/programs",
+            get(crate::api::training::list_programs).post(crate::api::training::create_program),
+        )
+        .route(
+            "/api/tools/training/programs/{id}",
+            get(crate::api::training::get_program)
+                .patch(crate::api::training::update_program)
+                .delete(crate::api::training::delete_program),
+        )
+        .route("/api/tools/training/today", get(crate::api::training::training_today))
         // Workout sessions
         .route(
             "/api/tools/training/sessions",
@@ -91,14 +164,41 @@ pub fn build_app(
             "/api/tools/training/sessions/{id}",
             get(crate::api::training::get_session).put(crate::api::training::update_session),
         )
+        .route(
+            "/api/tools/training/sessions/{id}/summary",
+            get(crate::api::training::session_summary),
+        )
+        // Wearable recordings are larger than the default body limit
+        .route(
+            "/api/tools/training/sessions/{id}/heart-rate",
+            get(crate::api::training::session_heart_rate)
+                .post(crate::api::training::import_heart_rate)
+                .layer(DefaultBodyLimit::max(16 * 1024 * 1024)),
+        )
         .route(
             "/api/tools/training/sessions/{session_id}/sets",
             post(crate::api::training::log_set),
         )
+        .route(
+            "/api/tools/training/sessions/{session_id}/warmup",
+            post(crate::api::training::generate_warmup_sets),
+        )
         .route(
             "/api/tools/training/sessions/{session_id}/sets/{id}",
             delete(crate::api::training::delete_set),
         )
+        .route(
+            "/api/tools/training/sessions/{session_id}/cardio",
+            post(crate::api::training::log_cardio),
+        )
+        .route(
+            "/api/tools/training/sessions/{session_id}/cardio/{id}",
+            delete(crate::api::training::delete_cardio),
+        )
+        .route(
+            "/api/tools/training/sessions/{session_id}/planned-sets/{id}",
+            put(crate::api::training::update_planned_set),
+        )
         // Stats
         .route("/api/tools/training/stats/energy", get(crate::api::training::stats_energy))
         .route("/api/tools/training/stats/volume", get(crate::api::training::stats_volume))
@@ -106,9 +206,31 @@ pub fn build_app(
             "/api/tools/training/stats/muscle-energy",
             get(crate::api::training::stats_muscle_energy),
         )
+        .route(
+            "/api/tools/training/preferences",
+            get(crate::api::training::get_preferences)
+                .put(crate::api::training::update_preferences),
+        )
+        // Nutrition
+        .route("/api/tools/training/nutrition-plan", get(crate::api::training::nutrition_plan))
         // Utilities
         .route("/api/tools/training/calculate-energy", post(crate::api::training::calculate_energy))
+        .route("/api/tools/training/recompute-energy", post(crate::api::training::recompute_energy))
         .route("/api/tools/training/calculate-plates", post(crate::api::training::calculate_plates))
+        .route(
+            "/api/tools/training/equipment-profiles",
+            get(crate::api::training::list_equipment_profiles)
+                .post(crate::api::training::create_equipment_profile),
+        )
+        .route(
+            "/api/tools/training/equipment-profiles/{id}",
+            put(crate::api::training::update_equipment_profile)
+                .delete(crate::api::training::delete_equipment_profile),
+        )
+        .route(
+            "/api/tools/training/equipment-profiles/{id}/calculate-plates",
+            post(crate::api::training::calculate_profile_plates),
+        )
         // Limit request body to 1 MB to prevent abuse
         .layer(DefaultBodyLimit::max(1024 * 1024))
         .layer(tower_http::cors::CorsLayer::new())
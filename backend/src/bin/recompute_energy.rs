@@ -0,0 +1,65 @@
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use tools_backend::api::training::{parse_bound, recompute_user_energy, RecomputeScope};
+use uuid::Uuid;
+
+const USAGE: &str = "usage: recompute_energy (--user <email> | --all) [--from <date>] [--to <date>] [--dry-run] [--latest-measurement]";
+
+/// Recompute stored set and session energy, e.g. after tuning the energy model constants.
+/// Prints one JSON report per user.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut emails: Vec<String> = Vec::new();
+    let mut all = false;
+    let (mut from, mut to) = (None, None);
+    let (mut dry_run, mut use_latest_measurement) = (false, false);
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--user" => emails.push(args.next().ok_or_else(|| anyhow::anyhow!(USAGE))?),
+            "--all" => all = true,
+            "--from" => {
+                let value = args.next().ok_or_else(|| anyhow::anyhow!(USAGE))?;
+                from = Some(parse_bound("from", &value, false).map_err(anyhow::Error::msg)?);
+            }
+            "--to" => {
+                let value = args.next().ok_or_else(|| anyhow::anyhow!(USAGE))?;
+                to = Some(parse_bound("to", &value, true).map_err(anyhow::Error::msg)?);
+            }
+            "--dry-run" => dry_run = true,
+            "--latest-measurement" => use_latest_measurement = true,
+            _ => anyhow::bail!(USAGE),
+        }
+    }
+    // Exactly one of --all and --user
+    if all != emails.is_empty() {
+        anyhow::bail!(USAGE);
+    }
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let pool = PgPoolOptions::new().max_connections(5).connect(&database_url).await?;
+
+    let users: Vec<(Uuid, String)> = if all {
+        sqlx::query_as("SELECT id, email FROM users ORDER BY created_at").fetch_all(&pool).await?
+    } else {
+        let lowered: Vec<String> = emails.iter().map(|e| e.to_lowercase()).collect();
+        let found: Vec<(Uuid, String)> =
+            sqlx::query_as("SELECT id, email FROM users WHERE lower(email) = ANY($1)")
+                .bind(&lowered)
+                .fetch_all(&pool)
+                .await?;
+        for email in &lowered {
+            if !found.iter().any(|(_, e)| e.to_lowercase() == *email) {
+                anyhow::bail!("no user with email {email}");
+            }
+        }
+        found
+    };
+
+    for (user_id, email) in users {
+        let scope = RecomputeScope { user_id, from, to, dry_run, use_latest_measurement };
+        let report = recompute_user_energy(&pool, &scope).await?;
+        println!("{}", serde_json::json!({ "user": email, "report": report }));
+    }
+    Ok(())
+}
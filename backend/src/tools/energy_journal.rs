@@ -0,0 +1,228 @@
+use crate::tools::fat_loss::{calculate_fat_loss_percentage, FatLossResponse};
+use crate::tools::metabolism::{katch_mcardle_bmr, mifflin_st_jeor_bmr, ActivityLevel, Sex};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BmrFormula {
+    MifflinStJeor,
+    /// Needs a logged body fat percentage
+    KatchMcardle,
+}
+
+/// One journal day; training energy comes from completed workout sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalDay {
+    pub date: NaiveDate,
+    pub intake_kcal: Option<f64>,
+    pub body_weight_kg: Option<f64>,
+    pub body_fat_percentage: Option<f64>,
+    pub training_kcal: f64,
+}
+
+/// Person-level inputs of the expenditure estimate
+#[derive(Debug, Clone)]
+pub struct ExpenditureParams {
+    pub sex: Sex,
+    pub age_years: f64,
+    pub height_cm: f64,
+    /// Non-exercise activity; logged training is added on top
+    pub activity_level: ActivityLevel,
+    pub formula: BmrFormula,
+    /// Used when no weight has been logged yet
+    pub fallback_weight_kg: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyBalance {
+    pub date: NaiveDate,
+    pub intake_kcal: f64,
+    pub bmr_kcal: f64,
+    pub training_kcal: f64,
+    pub expenditure_kcal: f64,
+    /// Expenditure minus intake (positive while in a deficit)
+    pub deficit_kcal: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalSummary {
+    pub days_with_intake: usize,
+    pub total_intake_kcal: f64,
+    pub total_expenditure_kcal: f64,
+    pub cumulative_deficit_kcal: f64,
+    /// Training energy of every day in the window
+    pub total_training_kcal: f64,
+    /// Training on days without logged intake; their deficit is unknown, so it is not part of
+    /// `cumulative_deficit_kcal`
+    pub training_kcal_without_intake: f64,
+    pub start_weight_kg: Option<f64>,
+    pub end_weight_kg: Option<f64>,
+    /// First minus last logged weight (positive when weight was lost)
+    pub weight_loss_kg: Option<f64>,
+    /// Fat/muscle split of the observed loss, when deficit and loss are both positive
+    pub fat_loss: Option<FatLossResponse>,
+    pub daily: Vec<DailyBalance>,
+}
+
+fn bmr(params: &ExpenditureParams, weight_kg: f64, body_fat: Option<f64>) -> Result<f64, String> {
+    match params.formula {
+        BmrFormula::MifflinStJeor => {
+            Ok(mifflin_st_jeor_bmr(weight_kg, params.height_cm, params.age_years, params.sex))
+        }
+        BmrFormula::KatchMcardle => body_fat
+            .map(|bf| katch_mcardle_bmr(weight_kg, bf))
+            .ok_or_else(|| "Katch-McArdle needs a logged body fat percentage".to_string()),
+    }
+}
+
+/// Derive daily expenditure, the cumulative deficit and the observed weight change.
+///
+/// Days are expected in date order. Weight and body fat are carried forward to days without a
+/// measurement (and backwards to the days before the first one). Only days with logged intake
+/// count towards the deficit; training on the other days is reported separately.
+pub fn summarize_journal(
+    days: &[JournalDay],
+    params: &ExpenditureParams,
+) -> Result<JournalSummary, String> {
+    let first_weight = days.iter().find_map(|d| d.body_weight_kg);
+    let first_body_fat = days.iter().find_map(|d| d.body_fat_percentage);
+    let mut weight = first_weight.or(params.fallback_weight_kg);
+    let mut body_fat = first_body_fat;
+
+    let mut daily = Vec::new();
+    for day in days {
+        weight = day.body_weight_kg.or(weight);
+        body_fat = day.body_fat_percentage.or(body_fat);
+        let Some(intake) = day.intake_kcal else {
+            continue;
+        };
+        let current_weight =
+            weight.ok_or_else(|| "log a body weight to estimate expenditure".to_string())?;
+        let bmr_kcal = bmr(params, current_weight, body_fat)?;
+        let expenditure = bmr_kcal.mul_add(params.activity_level.pal(), day.training_kcal);
+        daily.push(DailyBalance {
+            date: day.date,
+            intake_kcal: intake,
+            bmr_kcal,
+            training_kcal: day.training_kcal,
+            expenditure_kcal: expenditure,
+            deficit_kcal: expenditure - intake,
+        });
+    }
+
+    let total_intake_kcal: f64 = daily.iter().map(|d| d.intake_kcal).sum();
+    let total_expenditure_kcal: f64 = daily.iter().map(|d| d.expenditure_kcal).sum();
+    let cumulative_deficit_kcal = total_expenditure_kcal - total_intake_kcal;
+    let total_training_kcal: f64 = days.iter().map(|d| d.training_kcal).sum();
+    let training_kcal_without_intake: f64 =
+        days.iter().filter(|d| d.intake_kcal.is_none()).map(|d| d.training_kcal).sum();
+    let end_weight_kg = days.iter().rev().find_map(|d| d.body_weight_kg);
+    let weight_loss_kg = first_weight.zip(end_weight_kg).map(|(start, end)| start - end);
+    let fat_loss = weight_loss_kg
+        .filter(|loss| *loss > 0.0 && cumulative_deficit_kcal > 0.0)
+        .map(|loss| calculate_fat_loss_percentage(cumulative_deficit_kcal, loss));
+
+    Ok(JournalSummary {
+        days_with_intake: daily.len(),
+        total_intake_kcal,
+        total_expenditure_kcal,
+        cumulative_deficit_kcal,
+        total_training_kcal,
+        training_kcal_without_intake,
+        start_weight_kg: first_weight,
+        end_weight_kg,
+        weight_loss_kg,
+        fat_loss,
+        daily,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(formula: BmrFormula) -> ExpenditureParams {
+        ExpenditureParams {
+            sex: Sex::Male,
+            age_years: 30.0,
+            height_cm: 180.0,
+            activity_level: ActivityLevel::Sedentary,
+            formula,
+            fallback_weight_kg: None,
+        }
+    }
+
+    fn day(d: u32, intake: Option<f64>, weight: Option<f64>, training: f64) -> JournalDay {
+        JournalDay {
+            date: NaiveDate::from_ymd_opt(2024, 3, d).unwrap(),
+            intake_kcal: intake,
+            body_weight_kg: weight,
+            body_fat_percentage: None,
+            training_kcal: training,
+        }
+    }
+
+    #[test]
+    fn test_expenditure_and_deficit() {
+        let days = vec![day(1, Some(1500.0), Some(80.0), 0.0), day(2, Some(1800.0), None, 120.0)];
+        let summary = summarize_journal(&days, &params(BmrFormula::MifflinStJeor)).unwrap();
+
+        // Mifflin 1780 * 1.2 = 2136
+        assert!((summary.daily[0].expenditure_kcal - 2136.0).abs() < 1e-9);
+        assert!((summary.daily[1].expenditure_kcal - 2256.0).abs() < 1e-9);
+        assert!((summary.cumulative_deficit_kcal - (636.0 + 456.0)).abs() < 1e-9);
+        assert_eq!(summary.weight_loss_kg, Some(0.0));
+        assert!(summary.fat_loss.is_none());
+    }
+
+    #[test]
+    fn test_days_without_intake_are_skipped() {
+        let days = vec![day(1, None, Some(80.0), 300.0), day(2, Some(2000.0), None, 100.0)];
+        let summary = summarize_journal(&days, &params(BmrFormula::MifflinStJeor)).unwrap();
+        assert_eq!(summary.days_with_intake, 1);
+        assert_eq!(summary.daily[0].date, days[1].date);
+        // Their training is still reported
+        assert_eq!(summary.total_training_kcal, 400.0);
+        assert_eq!(summary.training_kcal_without_intake, 300.0);
+    }
+
+    #[test]
+    fn test_fat_loss_from_observed_change() {
+        let mut days: Vec<JournalDay> = (1..=28).map(|d| day(d, Some(1400.0), None, 0.0)).collect();
+        days[0].body_weight_kg = Some(82.0);
+        days[27].body_weight_kg = Some(80.0);
+        let summary = summarize_journal(&days, &params(BmrFormula::MifflinStJeor)).unwrap();
+
+        assert_eq!(summary.weight_loss_kg, Some(2.0));
+        let fat_loss = summary.fat_loss.expect("fat loss");
+        let expected =
+            calculate_fat_loss_percentage(summary.cumulative_deficit_kcal, 2.0).fat_loss_percentage;
+        assert_eq!(fat_loss.fat_loss_percentage, expected);
+    }
+
+    #[test]
+    fn test_katch_mcardle_needs_body_fat() {
+        let mut days = vec![day(1, Some(2000.0), Some(80.0), 0.0)];
+        assert!(summarize_journal(&days, &params(BmrFormula::KatchMcardle)).is_err());
+
+        days[0].body_fat_percentage = Some(20.0);
+        let summary = summarize_journal(&days, &params(BmrFormula::KatchMcardle)).unwrap();
+        assert!((summary.daily[0].bmr_kcal - 1752.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_missing_weight_uses_fallback() {
+        let days = vec![day(1, Some(2000.0), None, 0.0)];
+        assert!(summarize_journal(&days, &params(BmrFormula::MifflinStJeor)).is_err());
+
+        let mut p = params(BmrFormula::MifflinStJeor);
+        p.fallback_weight_kg = Some(80.0);
+        let summary = summarize_journal(&days, &p).unwrap();
+        assert_eq!(summary.weight_loss_kg, None);
+        assert!((summary.daily[0].bmr_kcal - 1780.0).abs() < 1e-9);
+    }
+}
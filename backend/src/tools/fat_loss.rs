@@ -4,10 +4,41 @@ use serde::{Deserialize, Serialize};
 const KCAL_PER_KG_FAT: f64 = 7000.0;
 const KCAL_PER_KG_MUSCLE: f64 = 1200.0;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Energy released per gram of glycogen
+const KCAL_PER_G_GLYCOGEN: f64 = 4.0;
+/// Water stored with each gram of glycogen (g)
+const WATER_PER_G_GLYCOGEN: f64 = 3.0;
+/// Day-to-day water swing assumed when nothing is known about it (kg)
+const UNKNOWN_WATER_SWING_KG: f64 = 1.0;
+/// Remaining swing once water or glycogen changes are supplied (kg)
+const RESIDUAL_WATER_SWING_KG: f64 = 0.5;
+/// Random daily error of a logged deficit (kcal), growing with the square root of the window
+const DAILY_DEFICIT_ERROR_KCAL: f64 = 300.0;
+/// Range width (percentage points) below which a result counts as meaningful
+const MEANINGFUL_RANGE_WIDTH: f64 = 30.0;
+/// Longest window searched for `min_window_days`
+const MAX_WINDOW_DAYS: u32 = 365;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct FatLossRequest {
     pub kcal_deficit: f64,
     pub weight_loss_kg: f64,
+    /// Days over which deficit and loss were measured; enables the uncertainty range
+    #[serde(default)]
+    pub window_days: Option<f64>,
+    /// Known water loss included in `weight_loss_kg` (negative when water was gained)
+    #[serde(default)]
+    pub water_change_kg: Option<f64>,
+    /// Glycogen depleted over the window in grams (negative when refilled)
+    #[serde(default)]
+    pub glycogen_change_g: Option<f64>,
+}
+
+/// Plausible fat share of the loss once water swings and logging error are accounted for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FatLossRange {
+    pub fat_low_percentage: f64,
+    pub fat_high_percentage: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,6 +46,13 @@ pub struct FatLossResponse {
     pub fat_loss_percentage: Option<f64>,
     pub muscle_loss_percentage: Option<f64>,
     pub is_valid: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<FatLossRange>,
+    /// Shortest window at the same daily rates that narrows the range below 30 points
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_window_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub explanation: Vec<String>,
 }
 
 /// Calculate the percentage of fat loss vs muscle loss based on calorie deficit and weight loss
@@ -31,11 +69,7 @@ pub fn calculate_fat_loss_percentage(kcal_deficit: f64, weight_loss_kg: f64) ->
         || !kcal_deficit.is_finite()
         || !weight_loss_kg.is_finite()
     {
-        return FatLossResponse {
-            fat_loss_percentage: None,
-            muscle_loss_percentage: None,
-            is_valid: false,
-        };
+        return FatLossResponse::invalid();
     }
 
     // Calculate fat loss percentage
@@ -45,11 +79,7 @@ pub fn calculate_fat_loss_percentage(kcal_deficit: f64, weight_loss_kg: f64) ->
 
     // Ensure percentage is between 0 and 1
     if !(0.0..=1.0).contains(&fat_percentage) {
-        return FatLossResponse {
-            fat_loss_percentage: None,
-            muscle_loss_percentage: None,
-            is_valid: false,
-        };
+        return FatLossResponse::invalid();
     }
 
     let muscle_percentage = 1.0 - fat_percentage;
@@ -58,7 +88,153 @@ pub fn calculate_fat_loss_percentage(kcal_deficit: f64, weight_loss_kg: f64) ->
         fat_loss_percentage: Some(fat_percentage * 100.0),
         muscle_loss_percentage: Some(muscle_percentage * 100.0),
         is_valid: true,
+        range: None,
+        min_window_days: None,
+        explanation: Vec::new(),
+    }
+}
+
+impl FatLossResponse {
+    fn invalid() -> Self {
+        Self {
+            fat_loss_percentage: None,
+            muscle_loss_percentage: None,
+            is_valid: false,
+            range: None,
+            min_window_days: None,
+            explanation: Vec::new(),
+        }
+    }
+}
+
+/// Unclamped fat share of the loss in percent; `None` unless the loss is positive
+fn fat_share(kcal_deficit: f64, weight_loss_kg: f64) -> Option<f64> {
+    if weight_loss_kg <= 0.0 || !weight_loss_kg.is_finite() {
+        return None;
+    }
+    let share = KCAL_PER_KG_MUSCLE.mul_add(-weight_loss_kg, kcal_deficit)
+        / (KCAL_PER_KG_FAT - KCAL_PER_KG_MUSCLE)
+        / weight_loss_kg
+        * 100.0;
+    share.is_finite().then_some(share)
+}
+
+/// Fat share bounds for a deficit and loss known to within the given errors.
+///
+/// `None` when the tissue loss is not positive, as there is nothing to split.
+fn fat_range(deficit: f64, loss: f64, deficit_error: f64, loss_error: f64) -> Option<FatLossRange> {
+    if loss <= 0.0 {
+        return None;
+    }
+    // The share grows with the deficit and shrinks with the loss
+    let low = fat_share(deficit - deficit_error, loss + loss_error)?;
+    let high = fat_share(deficit + deficit_error, loss - loss_error).unwrap_or(100.0);
+    Some(FatLossRange {
+        fat_low_percentage: low.clamp(0.0, 100.0),
+        fat_high_percentage: high.clamp(0.0, 100.0),
+    })
+}
+
+/// Fat/muscle split with water and glycogen correction and, given a window, a plausible range.
+///
+/// Without `window_days` this is [`calculate_fat_loss_percentage`] on the corrected inputs. With
+/// a window, an out-of-range point estimate is not an error: the range and explanation describe
+/// what the data can support and `min_window_days` how long to measure for a meaningful answer.
+/// When the scale shows no tissue loss at all the range is the whole 0-100%.
+#[must_use]
+pub fn estimate_fat_loss(request: &FatLossRequest) -> FatLossResponse {
+    let water = request.water_change_kg.unwrap_or(0.0);
+    let glycogen_g = request.glycogen_change_g.unwrap_or(0.0);
+    let glycogen_weight_kg = glycogen_g * (1.0 + WATER_PER_G_GLYCOGEN) / 1000.0;
+    let tissue_loss = request.weight_loss_kg - water - glycogen_weight_kg;
+    let tissue_deficit = KCAL_PER_G_GLYCOGEN.mul_add(-glycogen_g, request.kcal_deficit);
+
+    let mut response = calculate_fat_loss_percentage(tissue_deficit, tissue_loss);
+    let Some(window_days) = request.window_days else {
+        return response;
+    };
+    if !(window_days > 0.0
+        && request.kcal_deficit > 0.0
+        && request.kcal_deficit.is_finite()
+        && request.weight_loss_kg.is_finite()
+        && water.is_finite()
+        && glycogen_g.is_finite())
+    {
+        return FatLossResponse::invalid();
+    }
+
+    let mut explanation = Vec::new();
+    if water != 0.0 || glycogen_g != 0.0 {
+        explanation.push(format!(
+            "Removing {water:.2} kg water and {glycogen_weight_kg:.2} kg glycogen with bound water \
+             leaves {tissue_loss:.2} kg tissue loss and {tissue_deficit:.0} kcal deficit"
+        ));
+    }
+    let swing = if request.water_change_kg.is_some() || request.glycogen_change_g.is_some() {
+        RESIDUAL_WATER_SWING_KG
+    } else {
+        UNKNOWN_WATER_SWING_KG
+    };
+    let deficit_error = DAILY_DEFICIT_ERROR_KCAL * window_days.sqrt();
+    explanation.push(format!(
+        "Scale weight swings about ±{swing} kg with water and the deficit over {window_days} days \
+         is uncertain by about ±{deficit_error:.0} kcal"
+    ));
+
+    let range = fat_range(tissue_deficit, tissue_loss, deficit_error, swing);
+    if range.is_none() {
+        explanation.push(if request.weight_loss_kg <= 0.0 {
+            "The scale weight did not drop, so any fat lost is hidden by water gain and the \
+             split cannot be narrowed"
+                .to_string()
+        } else {
+            "Water and glycogen account for the whole weight change, leaving no tissue loss to \
+             split"
+                .to_string()
+        });
     }
+    let range =
+        range.unwrap_or(FatLossRange { fat_low_percentage: 0.0, fat_high_percentage: 100.0 });
+    explanation.push(format!(
+        "Plausible fat share: {:.0}% to {:.0}% of the loss",
+        range.fat_low_percentage, range.fat_high_percentage
+    ));
+    if !response.is_valid && tissue_loss > 0.0 {
+        explanation.push(
+            "The point estimate falls outside 0-100%, so water and glycogen shifts dominate this \
+             window"
+                .to_string(),
+        );
+    }
+
+    let daily_deficit = tissue_deficit / window_days;
+    // Without a visible loss, assume the deficit starts to show on the scale as fat would
+    let daily_loss =
+        if tissue_loss > 0.0 { tissue_loss / window_days } else { daily_deficit / KCAL_PER_KG_FAT };
+    let min_window_days = (1..=MAX_WINDOW_DAYS).find(|&days| {
+        let days = f64::from(days);
+        fat_range(
+            daily_deficit * days,
+            daily_loss * days,
+            DAILY_DEFICIT_ERROR_KCAL * days.sqrt(),
+            swing,
+        )
+        .is_some_and(|r| r.fat_high_percentage - r.fat_low_percentage <= MEANINGFUL_RANGE_WIDTH)
+    });
+    explanation.push(match min_window_days {
+        Some(days) => format!(
+            "At the current rate, measure over at least {days} days for a range narrower than \
+             {MEANINGFUL_RANGE_WIDTH} points"
+        ),
+        None => format!(
+            "At the current rate even {MAX_WINDOW_DAYS} days are not enough for a meaningful split"
+        ),
+    });
+
+    response.range = Some(range);
+    response.min_window_days = min_window_days;
+    response.explanation = explanation;
+    response
 }
 
 #[cfg(test)]
@@ -152,6 +328,122 @@ mod tests {
         assert!(!result.is_valid);
     }
 
+    #[test]
+    fn test_without_window_matches_point_estimate() {
+        let request =
+            FatLossRequest { kcal_deficit: 3500.0, weight_loss_kg: 0.5, ..Default::default() };
+        let response = estimate_fat_loss(&request);
+        let expected = calculate_fat_loss_percentage(3500.0, 0.5);
+        assert_eq!(response.fat_loss_percentage, expected.fat_loss_percentage);
+        assert!(response.range.is_none());
+        assert!(response.explanation.is_empty());
+    }
+
+    #[test]
+    fn test_short_window_returns_range_instead_of_rejecting() {
+        // 3 days, 1.5 kg "lost" on a 1500 kcal deficit: mostly water
+        let request = FatLossRequest {
+            kcal_deficit: 1500.0,
+            weight_loss_kg: 1.5,
+            window_days: Some(3.0),
+            ..Default::default()
+        };
+        let response = estimate_fat_loss(&request);
+        assert!(!response.is_valid);
+        let range = response.range.expect("range");
+        assert!(range.fat_low_percentage >= 0.0);
+        assert!(range.fat_high_percentage <= 100.0);
+        assert!(range.fat_low_percentage <= range.fat_high_percentage);
+        let min_days = response.min_window_days.expect("min window");
+        assert!(min_days > 3);
+        assert!(!response.explanation.is_empty());
+    }
+
+    #[test]
+    fn test_longer_window_narrows_range() {
+        // 0.5 kg/week on 500 kcal/day, measured over 2 vs 12 weeks
+        let range_width = |weeks: f64| {
+            let request = FatLossRequest {
+                kcal_deficit: 3500.0 * weeks,
+                weight_loss_kg: 0.5 * weeks,
+                window_days: Some(7.0 * weeks),
+                ..Default::default()
+            };
+            let r = estimate_fat_loss(&request).range.unwrap();
+            r.fat_high_percentage - r.fat_low_percentage
+        };
+        assert!(range_width(12.0) < range_width(2.0));
+    }
+
+    #[test]
+    fn test_glycogen_and_water_correction() {
+        // 400 g glycogen carries 1.6 kg with its water and 1600 kcal
+        let request = FatLossRequest {
+            kcal_deficit: 8600.0,
+            weight_loss_kg: 3.1,
+            window_days: Some(14.0),
+            water_change_kg: Some(0.5),
+            glycogen_change_g: Some(400.0),
+        };
+        let response = estimate_fat_loss(&request);
+        let expected = calculate_fat_loss_percentage(7000.0, 1.0);
+        assert!(response.is_valid);
+        assert!(
+            (response.fat_loss_percentage.unwrap() - expected.fat_loss_percentage.unwrap()).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_full_range_without_tissue_loss() {
+        // Water exceeds the scale loss by exactly the residual swing
+        let request = FatLossRequest {
+            kcal_deficit: 3000.0,
+            weight_loss_kg: 0.3,
+            window_days: Some(7.0),
+            water_change_kg: Some(0.8),
+            glycogen_change_g: None,
+        };
+        let response = estimate_fat_loss(&request);
+        let range = response.range.expect("range");
+        assert_eq!(range.fat_low_percentage, 0.0);
+        assert_eq!(range.fat_high_percentage, 100.0);
+        assert!(response.min_window_days.is_some_and(|days| days > 7));
+        assert!(response.explanation[2].contains("Water and glycogen"));
+        assert!(response.fat_loss_percentage.is_none_or(f64::is_finite));
+        assert!(fat_range(3000.0, 0.0, 100.0, 0.5).is_none());
+    }
+
+    #[test]
+    fn test_weight_gain_returns_full_range() {
+        let request = FatLossRequest {
+            kcal_deficit: 3500.0,
+            weight_loss_kg: -0.4,
+            window_days: Some(7.0),
+            ..Default::default()
+        };
+        let response = estimate_fat_loss(&request);
+        assert!(!response.is_valid);
+        let range = response.range.expect("range");
+        assert_eq!(range.fat_low_percentage, 0.0);
+        assert_eq!(range.fat_high_percentage, 100.0);
+        assert!(response.min_window_days.is_some_and(|days| days > 7));
+        assert!(response.explanation.iter().any(|e| e.contains("did not drop")));
+    }
+
+    #[test]
+    fn test_window_still_rejects_nonsense_inputs() {
+        let request = FatLossRequest {
+            kcal_deficit: -100.0,
+            weight_loss_kg: 0.5,
+            window_days: Some(7.0),
+            ..Default::default()
+        };
+        let response = estimate_fat_loss(&request);
+        assert!(!response.is_valid);
+        assert!(response.range.is_none());
+    }
+
     #[test]
     fn test_invalid_infinity_values() {
         let result = calculate_fat_loss_percentage(f64::INFINITY, 0.5);
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+/// Habitual activity, expressed as a multiple of resting metabolic rate
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityLevel {
+    Sedentary,
+    Light,
+    Moderate,
+    Active,
+    VeryActive,
+}
+
+impl ActivityLevel {
+    /// Physical activity level (total / resting expenditure)
+    #[must_use]
+    pub fn pal(self) -> f64 {
+        match self {
+            Self::Sedentary => 1.2,
+            Self::Light => 1.375,
+            Self::Moderate => 1.55,
+            Self::Active => 1.725,
+            Self::VeryActive => 1.9,
+        }
+    }
+}
+
+/// Resting metabolic rate in kcal/day (Mifflin-St Jeor, 1990)
+#[must_use]
+pub fn mifflin_st_jeor_bmr(weight_kg: f64, height_cm: f64, age_years: f64, sex: Sex) -> f64 {
+    let sex_offset = match sex {
+        Sex::Male => 5.0,
+        Sex::Female => -161.0,
+    };
+    10.0f64.mul_add(weight_kg, 6.25f64.mul_add(height_cm, (-5.0f64).mul_add(age_years, sex_offset)))
+}
+
+/// Resting metabolic rate in kcal/day from lean body mass (Katch-McArdle)
+#[must_use]
+pub fn katch_mcardle_bmr(weight_kg: f64, body_fat_percentage: f64) -> f64 {
+    let lean_mass_kg = weight_kg * (1.0 - body_fat_percentage / 100.0);
+    21.6f64.mul_add(lean_mass_kg, 370.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mifflin_st_jeor() {
+        // 10*80 + 6.25*180 - 5*30 + 5 = 1780
+        assert!((mifflin_st_jeor_bmr(80.0, 180.0, 30.0, Sex::Male) - 1780.0).abs() < 1e-9);
+        // 10*60 + 6.25*165 - 5*40 - 161 = 1270.25
+        assert!((mifflin_st_jeor_bmr(60.0, 165.0, 40.0, Sex::Female) - 1270.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_katch_mcardle() {
+        // lean mass 64 kg -> 370 + 21.6*64 = 1752.4
+        assert!((katch_mcardle_bmr(80.0, 20.0) - 1752.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_activity_levels_increase() {
+        let levels = [
+            ActivityLevel::Sedentary,
+            ActivityLevel::Light,
+            ActivityLevel::Moderate,
+            ActivityLevel::Active,
+            ActivityLevel::VeryActive,
+        ];
+        assert!(levels.windows(2).all(|w| w[0].pal() < w[1].pal()));
+    }
+}
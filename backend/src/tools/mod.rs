@@ -1,7 +1,11 @@
 pub mod auth;
 pub mod bloodlevel;
 pub mod dice;
+pub mod energy_journal;
 pub mod fat_loss;
+pub mod metabolism;
 pub mod n26_analyzer;
 pub mod session;
+pub mod tdee;
 pub mod training;
+pub mod weight_simulator;
@@ -0,0 +1,281 @@
+use super::{parse_transaction_date, Transaction};
+use chrono::{Datelike, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Monthly spending limit for one transaction category
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Budget {
+    pub category: String,
+    pub monthly_amount: f64,
+    pub carry_over: bool,
+    /// First month that counts towards carry-over
+    pub starts_on: NaiveDate,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetState {
+    OnTrack,
+    /// Spending is within budget so far but the run rate exceeds it by month end
+    AtRisk,
+    OverBudget,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    pub category: String,
+    pub monthly_amount: f64,
+    /// Unspent budget carried in from previous months
+    pub carried_over: f64,
+    /// Monthly amount plus carry-over
+    pub available: f64,
+    pub spent: f64,
+    pub remaining: f64,
+    pub percent_used: Option<f64>,
+    /// Spending projected to month end from the current run rate
+    pub forecast: f64,
+    pub state: BudgetState,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetReport {
+    /// First day of the compared month
+    pub month: NaiveDate,
+    pub as_of: NaiveDate,
+    pub days_elapsed: u32,
+    pub days_in_month: u32,
+    pub budgets: Vec<BudgetStatus>,
+    /// Spending in categories without a budget
+    pub unbudgeted: HashMap<String, f64>,
+    pub alerts: Vec<String>,
+}
+
+/// First day of the month containing `date`
+#[must_use]
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn days_in_month(month: NaiveDate) -> u32 {
+    let next = month.checked_add_months(Months::new(1)).unwrap_or(month);
+    (next - month).num_days() as u32
+}
+
+/// Parse a `YYYY-MM` month into its first day
+#[must_use]
+pub fn parse_month(raw: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", raw.trim()), "%Y-%m-%d").ok()
+}
+
+/// Outflows per (category, month); incoming money never counts as spending
+fn spending_by_month(transactions: &[Transaction]) -> HashMap<(String, NaiveDate), f64> {
+    let mut spending = HashMap::new();
+    for tx in transactions.iter().filter(|tx| tx.amount < 0.0) {
+        if let Some(date) = parse_transaction_date(&tx.date) {
+            *spending.entry((tx.category.clone(), month_start(date))).or_insert(0.0) -= tx.amount;
+        }
+    }
+    spending
+}
+
+/// Compare actual spending in `month` against each budget.
+///
+/// Carry-over accumulates unspent budget from `starts_on` (or the first month with any
+/// transactions, whichever is later) up to the month before `month`. Overspending never
+/// carries a negative balance forward.
+#[must_use]
+pub fn compare_budgets(
+    budgets: &[Budget],
+    transactions: &[Transaction],
+    month: NaiveDate,
+    as_of: NaiveDate,
+) -> BudgetReport {
+    let month = month_start(month);
+    let spending = spending_by_month(transactions);
+    let first_data_month = spending.keys().map(|(_, m)| *m).min();
+    let total_days = days_in_month(month);
+    let days_elapsed = if as_of < month {
+        0
+    } else if month_start(as_of) > month {
+        total_days
+    } else {
+        as_of.day()
+    };
+
+    let spent_in = |category: &str, m: NaiveDate| -> f64 {
+        spending.get(&(category.to_string(), m)).copied().unwrap_or(0.0)
+    };
+
+    let mut alerts = Vec::new();
+    let statuses: Vec<BudgetStatus> = budgets
+        .iter()
+        .map(|budget| {
+            let mut carried_over = 0.0;
+            if budget.carry_over {
+                let mut m = month_start(budget.starts_on).max(first_data_month.unwrap_or(month));
+                while m < month {
+                    let available = budget.monthly_amount + carried_over;
+                    carried_over = (available - spent_in(&budget.category, m)).max(0.0);
+                    m = m.checked_add_months(Months::new(1)).unwrap_or(month);
+                }
+            }
+
+            let available = budget.monthly_amount + carried_over;
+            let spent = spent_in(&budget.category, month);
+            let forecast = if days_elapsed == 0 {
+                0.0
+            } else {
+                spent / f64::from(days_elapsed) * f64::from(total_days)
+            };
+            let state = if spent > available {
+                alerts.push(format!(
+                    "{}: {:.2} spent, {:.2} over budget",
+                    budget.category,
+                    spent,
+                    spent - available
+                ));
+                BudgetState::OverBudget
+            } else if forecast > available {
+                alerts.push(format!(
+                    "{}: on track to spend {:.2} of {:.2} by month end",
+                    budget.category, forecast, available
+                ));
+                BudgetState::AtRisk
+            } else {
+                BudgetState::OnTrack
+            };
+
+            BudgetStatus {
+                category: budget.category.clone(),
+                monthly_amount: budget.monthly_amount,
+                carried_over,
+                available,
+                spent,
+                remaining: available - spent,
+                percent_used: (available > 0.0).then(|| spent / available * 100.0),
+                forecast,
+                state,
+            }
+        })
+        .collect();
+
+    let unbudgeted = spending
+        .iter()
+        .filter(|((category, m), _)| {
+            *m == month && !budgets.iter().any(|b| &b.category == category)
+        })
+        .map(|((category, _), spent)| (category.clone(), *spent))
+        .collect();
+
+    BudgetReport {
+        month,
+        as_of,
+        days_elapsed,
+        days_in_month: total_days,
+        budgets: statuses,
+        unbudgeted,
+        alerts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(amount: f64, date: &str, category: &str) -> Transaction {
+        Transaction {
+            amount,
+            date: date.to_string(),
+            category: category.to_string(),
+            comment: "test".to_string(),
+            currency: "EUR".to_string(),
+            original_amount: None,
+            original_currency: None,
+        }
+    }
+
+    fn budget(category: &str, amount: f64, carry_over: bool) -> Budget {
+        Budget {
+            category: category.to_string(),
+            monthly_amount: amount,
+            carry_over,
+            starts_on: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_month() {
+        assert_eq!(parse_month("2024-02"), Some(date(2024, 2, 1)));
+        assert_eq!(parse_month("2024-13"), None);
+    }
+
+    #[test]
+    fn test_spending_and_forecast() {
+        let txs = vec![
+            tx(-100.0, "2024-04-05", "cardTransactions"),
+            tx(-50.0, "2024-04-10", "cardTransactions"),
+            tx(2000.0, "2024-04-01", "cardTransactions"),
+        ];
+        let report = compare_budgets(
+            &[budget("cardTransactions", 400.0, false)],
+            &txs,
+            date(2024, 4, 1),
+            date(2024, 4, 10),
+        );
+
+        let status = &report.budgets[0];
+        assert_eq!(report.days_in_month, 30);
+        assert_eq!(status.spent, 150.0);
+        assert_eq!(status.remaining, 250.0);
+        // 150 in 10 days -> 450 over 30 days
+        assert!((status.forecast - 450.0).abs() < 1e-9);
+        assert_eq!(status.state, BudgetState::AtRisk);
+        assert_eq!(report.alerts.len(), 1);
+    }
+
+    #[test]
+    fn test_over_budget() {
+        let txs = vec![tx(-120.0, "2024-04-02", "cash26Data")];
+        let report = compare_budgets(
+            &[budget("cash26Data", 100.0, false)],
+            &txs,
+            date(2024, 4, 1),
+            date(2024, 4, 30),
+        );
+        assert_eq!(report.budgets[0].state, BudgetState::OverBudget);
+        assert_eq!(report.budgets[0].remaining, -20.0);
+    }
+
+    #[test]
+    fn test_carry_over_accumulates_unspent_budget() {
+        let txs = vec![
+            tx(-60.0, "2024-01-10", "cash26Data"),
+            tx(-150.0, "2024-02-10", "cash26Data"),
+            tx(-10.0, "2024-03-10", "cash26Data"),
+        ];
+        let budgets = [budget("cash26Data", 100.0, true)];
+        let report = compare_budgets(&budgets, &txs, date(2024, 3, 1), date(2024, 3, 31));
+
+        // January leaves 40, February spends 150 of 140 -> nothing carried into March
+        assert_eq!(report.budgets[0].carried_over, 0.0);
+
+        let report = compare_budgets(&budgets, &txs, date(2024, 2, 1), date(2024, 2, 29));
+        assert_eq!(report.budgets[0].carried_over, 40.0);
+        assert_eq!(report.budgets[0].available, 140.0);
+    }
+
+    #[test]
+    fn test_unbudgeted_spending_listed() {
+        let txs = vec![tx(-30.0, "2024-04-02", "bankTransfers")];
+        let report = compare_budgets(&[], &txs, date(2024, 4, 1), date(2024, 4, 30));
+        assert_eq!(report.unbudgeted.get("bankTransfers"), Some(&30.0));
+    }
+}
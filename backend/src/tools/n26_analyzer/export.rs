@@ -0,0 +1,495 @@
+use super::{parse_transaction_date, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Column order of the CSV export
+pub const CSV_HEADER: &str =
+    "date,payee,amount,currency,original_amount,original_currency,category,account,comment";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    /// ledger-cli journal (`YYYY/MM/DD` dates, metadata comments)
+    Ledger,
+    /// hledger journal (`YYYY-MM-DD` dates, tag comments)
+    Hledger,
+}
+
+impl ExportFormat {
+    #[must_use]
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ledger | Self::Hledger => "text/plain; charset=utf-8",
+        }
+    }
+
+    #[must_use]
+    pub fn file_extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ledger => "ledger",
+            Self::Hledger => "journal",
+        }
+    }
+}
+
+/// Maps analyzer categories onto plain-text accounting account names
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountMapping {
+    /// The account the money moves in and out of
+    pub asset_account: String,
+    /// Account per transaction category
+    pub accounts: HashMap<String, String>,
+    /// Used for unmapped categories when money leaves the account
+    pub default_expense_account: String,
+    /// Used for unmapped categories when money comes in
+    pub default_income_account: String,
+}
+
+impl Default for AccountMapping {
+    fn default() -> Self {
+        Self {
+            asset_account: "Assets:N26".to_string(),
+            accounts: HashMap::from([
+                ("cardTransactions".to_string(), "Expenses:Card".to_string()),
+                ("bankTransfers".to_string(), "Expenses:Transfers".to_string()),
+                ("cash26Data".to_string(), "Assets:Cash".to_string()),
+            ]),
+            default_expense_account: "Expenses:Uncategorized".to_string(),
+            default_income_account: "Income:Uncategorized".to_string(),
+        }
+    }
+}
+
+impl AccountMapping {
+    /// Account the category side of a transaction is booked to
+    #[must_use]
+    pub fn account_for(&self, tx: &Transaction) -> &str {
+        match self.accounts.get(&tx.category) {
+            Some(account) => account,
+            None if tx.amount < 0.0 => &self.default_expense_account,
+            None => &self.default_income_account,
+        }
+    }
+
+    /// Journal syntax ends an account name at two spaces, a tab or a line break
+    pub fn validate(&self) -> Result<(), String> {
+        let names = std::iter::once(&self.asset_account)
+            .chain(self.accounts.values())
+            .chain([&self.default_expense_account, &self.default_income_account]);
+        for name in names {
+            if name.trim().is_empty()
+                || name.contains("  ")
+                || name.contains(['\t', '\n', '\r', ';'])
+            {
+                return Err(format!("invalid account name '{name}'"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Merchant of a card payment (comments are written as `merchant: original amount`), or the
+/// whole comment for other categories
+#[must_use]
+pub fn payee(tx: &Transaction) -> &str {
+    if tx.category == "cardTransactions" {
+        if let Some((merchant, amount)) = tx.comment.rsplit_once(": ") {
+            if amount.parse::<f64>().is_ok() {
+                return merchant;
+            }
+        }
+    }
+    &tx.comment
+}
+
+/// Shortest decimal form that parses back to exactly the same `f64`
+fn format_amount(amount: f64) -> String {
+    format!("{amount}")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) || value.trim() != value {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escape free text so it fits on a single journal line
+fn escape_journal_text(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+/// Escape a tag value; hledger ends a tag value at the first ',' and trims it, so commas and
+/// surrounding spaces are written as `\x2c` and `\x20`
+fn escape_tag_value(value: &str) -> String {
+    let escaped = escape_journal_text(value).replace(',', "\\x2c");
+    let body = escaped.trim_matches(' ');
+    if body.len() == escaped.len() {
+        return escaped;
+    }
+    let leading = escaped.len() - escaped.trim_start_matches(' ').len();
+    let trailing = escaped.len() - escaped.trim_end_matches(' ').len().max(leading);
+    format!("{}{body}{}", "\\x20".repeat(leading), "\\x20".repeat(trailing))
+}
+
+fn export_csv(transactions: &[Transaction], mapping: &AccountMapping) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for tx in transactions {
+        let row = [
+            csv_field(&tx.date),
+            csv_field(payee(tx)),
+            format_amount(tx.amount),
+            csv_field(&tx.currency),
+            tx.original_amount.map(format_amount).unwrap_or_default(),
+            csv_field(tx.original_currency.as_deref().unwrap_or_default()),
+            csv_field(&tx.category),
+            csv_field(mapping.account_for(tx)),
+            csv_field(&tx.comment),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn export_journal(
+    transactions: &[Transaction],
+    mapping: &AccountMapping,
+    format: ExportFormat,
+) -> Result<String, String> {
+    let date_format = if format == ExportFormat::Ledger { "%Y/%m/%d" } else { "%Y-%m-%d" };
+    let mut out = String::new();
+
+    for (index, tx) in transactions.iter().enumerate() {
+        let date = parse_transaction_date(&tx.date)
+            .ok_or_else(|| format!("transaction {index} has an unreadable date '{}'", tx.date))?;
+        let formatted_date = date.format(date_format).to_string();
+        // A ';' would start a comment in the description and a leading '*', '!' or '(' would
+        // read as a cleared/pending flag or a code, so these only survive in the comment tag
+        let escaped = escape_journal_text(payee(tx)).replace(';', ",");
+        let stripped =
+            escaped.trim_start_matches(|c: char| matches!(c, '*' | '!' | '(') || c.is_whitespace());
+        let description = if stripped.is_empty() {
+            tx.category.clone()
+        } else if stripped == escaped.trim_start() {
+            escaped
+        } else {
+            stripped.to_string()
+        };
+
+        // Writing to a String cannot fail
+        let _ = writeln!(out, "{formatted_date} {description}");
+        let _ = writeln!(out, "    ; category: {}", escape_tag_value(&tx.category));
+        if tx.date != date.format("%Y-%m-%d").to_string() {
+            let _ = writeln!(out, "    ; source-date: {}", escape_tag_value(&tx.date));
+        }
+        if description != tx.comment {
+            let _ = writeln!(out, "    ; comment: {}", escape_tag_value(&tx.comment));
+        }
+        if let (Some(amount), Some(currency)) = (tx.original_amount, &tx.original_currency) {
+            let original = format!("{} {currency}", format_amount(amount));
+            let _ = writeln!(out, "    ; original: {}", escape_tag_value(&original));
+        }
+
+        let category_posting = match (tx.original_amount, &tx.original_currency) {
+            (Some(amount), Some(currency)) if currency != &tx.currency => format!(
+                "{} {currency} @@ {} {}",
+                format_amount(-amount),
+                format_amount(tx.amount.abs()),
+                tx.currency
+            ),
+            _ => format!("{} {}", format_amount(-tx.amount), tx.currency),
+        };
+        let _ = writeln!(out, "    {}  {category_posting}", mapping.account_for(tx));
+        let _ = writeln!(
+            out,
+            "    {}  {} {}",
+            mapping.asset_account,
+            format_amount(tx.amount),
+            tx.currency
+        );
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+/// Render transactions as a CSV file or a double-entry journal
+pub fn export_transactions(
+    transactions: &[Transaction],
+    format: ExportFormat,
+    mapping: &AccountMapping,
+) -> Result<String, String> {
+    mapping.validate()?;
+    match format {
+        ExportFormat::Csv => Ok(export_csv(transactions, mapping)),
+        ExportFormat::Ledger | ExportFormat::Hledger => {
+            export_journal(transactions, mapping, format)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(amount: f64, date: &str, category: &str, comment: &str) -> Transaction {
+        Transaction {
+            amount,
+            date: date.to_string(),
+            category: category.to_string(),
+            comment: comment.to_string(),
+            currency: "EUR".to_string(),
+            original_amount: None,
+            original_currency: None,
+        }
+    }
+
+    fn sample() -> Vec<Transaction> {
+        let mut card = tx(-11.37, "2024-01-05", "cardTransactions", "Coffee, NYC: 12.1");
+        card.original_amount = Some(-12.1);
+        card.original_currency = Some("USD".to_string());
+        let mut local_card = tx(-4.2, "2024-01-06", "cardTransactions", "Bakery: 4.2");
+        local_card.original_amount = Some(-4.2);
+        local_card.original_currency = Some("EUR".to_string());
+        vec![
+            card,
+            local_card,
+            tx(2500.0, "2024-01-01T08:30:00Z", "bankTransfers", "Salary \"January\"; bonus"),
+            tx(-0.1, "1704067200000", "cash26Data", "multi\nline\\text"),
+            tx(-19.99, "2024-01-31", "spaces", "  padded  "),
+            tx(-3.0, "2024-02-01", "bankTransfers", "*Star Shop"),
+            tx(-3.0, "2024-02-02", "bankTransfers", "! (ref 7) Kiosk"),
+            tx(-3.0, "2024-02-03", "bankTransfers", " (*"),
+            tx(-3.0, "2024-02-04", "Food, Drinks", "Lunch, team; paid, split"),
+        ]
+    }
+
+    /// Minimal RFC 4180 reader for the round-trip tests
+    fn read_csv(input: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (quoted, c) {
+                (true, '"') if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                (true, '"') => quoted = false,
+                (true, c) => field.push(c),
+                (false, '"') => quoted = true,
+                (false, ',') => row.push(std::mem::take(&mut field)),
+                (false, '\n') => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                (false, c) => field.push(c),
+            }
+        }
+        rows
+    }
+
+    fn parse_csv_export(input: &str) -> Vec<Transaction> {
+        let rows = read_csv(input);
+        assert_eq!(rows[0].join(","), CSV_HEADER);
+        rows[1..]
+            .iter()
+            .map(|r| Transaction {
+                date: r[0].clone(),
+                amount: r[2].parse().unwrap(),
+                currency: r[3].clone(),
+                original_amount: (!r[4].is_empty()).then(|| r[4].parse().unwrap()),
+                original_currency: (!r[5].is_empty()).then(|| r[5].clone()),
+                category: r[6].clone(),
+                comment: r[8].clone(),
+            })
+            .collect()
+    }
+
+    fn unescape_journal_text(value: &str) -> String {
+        let mut out = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next() {
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('x') => {
+                        let code: String = chars.by_ref().take(2).collect();
+                        out.push(char::from(u8::from_str_radix(&code, 16).unwrap()));
+                    }
+                    Some(other) => out.push(other),
+                    None => out.push('\\'),
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    /// Splits a comment into tags the way hledger does: a tag is `name:` and its value runs to
+    /// the next ',' or the end of the line
+    fn hledger_tags(comment: &str) -> Vec<(String, String)> {
+        comment
+            .split(',')
+            .filter_map(|part| {
+                let (name, value) = part.split_once(':')?;
+                let name = name.split_whitespace().last()?;
+                Some((name.to_string(), value.trim().to_string()))
+            })
+            .collect()
+    }
+
+    /// Reads back the journals written by `export_journal`
+    fn parse_journal(input: &str, mapping: &AccountMapping) -> Vec<Transaction> {
+        let mut transactions = Vec::new();
+        for block in input.split("\n\n").filter(|b| !b.trim().is_empty()) {
+            let mut lines = block.lines();
+            let header = lines.next().unwrap();
+            let (date, description) = header.split_once(' ').unwrap();
+            let mut tx = tx(0.0, &date.replace('/', "-"), "", &unescape_journal_text(description));
+            for line in lines.map(str::trim) {
+                if let Some(comment) = line.strip_prefix("; ") {
+                    let tags = hledger_tags(comment);
+                    assert_eq!(tags.len(), 1, "comment line '{comment}' holds one tag");
+                    let (key, value) = &tags[0];
+                    let value = unescape_journal_text(value);
+                    match key.as_str() {
+                        "category" => tx.category = value,
+                        "source-date" => tx.date = value,
+                        "comment" => tx.comment = value,
+                        "original" => {
+                            let (amount, currency) = value.split_once(' ').unwrap();
+                            tx.original_amount = Some(amount.parse().unwrap());
+                            tx.original_currency = Some(currency.to_string());
+                        }
+                        _ => panic!("unexpected tag {key}"),
+                    }
+                } else if let Some(amount) = line.strip_prefix(&mapping.asset_account) {
+                    let (amount, currency) = amount.trim().split_once(' ').unwrap();
+                    tx.amount = amount.parse().unwrap();
+                    tx.currency = currency.to_string();
+                }
+            }
+            transactions.push(tx);
+        }
+        transactions
+    }
+
+    fn assert_same(left: &[Transaction], right: &[Transaction]) {
+        assert_eq!(left.len(), right.len());
+        for (l, r) in left.iter().zip(right) {
+            assert_eq!(l.date, r.date);
+            assert_eq!(l.amount.to_bits(), r.amount.to_bits());
+            assert_eq!(l.currency, r.currency);
+            assert_eq!(l.original_amount.map(f64::to_bits), r.original_amount.map(f64::to_bits));
+            assert_eq!(l.original_currency, r.original_currency);
+            assert_eq!(l.category, r.category);
+            assert_eq!(l.comment, r.comment);
+        }
+    }
+
+    #[test]
+    fn test_payee_strips_card_amount() {
+        let card = tx(-1.0, "2024-01-01", "cardTransactions", "Shop: Berlin: 1");
+        assert_eq!(payee(&card), "Shop: Berlin");
+        let transfer = tx(-1.0, "2024-01-01", "bankTransfers", "Rent: 1");
+        assert_eq!(payee(&transfer), "Rent: 1");
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let txs = sample();
+        let csv = export_transactions(&txs, ExportFormat::Csv, &AccountMapping::default()).unwrap();
+        assert!(csv.contains(
+            "2024-01-05,\"Coffee, NYC\",-11.37,EUR,-12.1,USD,cardTransactions,Expenses:Card,"
+        ));
+        assert_same(&parse_csv_export(&csv), &txs);
+    }
+
+    #[test]
+    fn test_journal_round_trip() {
+        let txs = sample();
+        let mapping = AccountMapping::default();
+        for format in [ExportFormat::Ledger, ExportFormat::Hledger] {
+            let journal = export_transactions(&txs, format, &mapping).unwrap();
+            assert_same(&parse_journal(&journal, &mapping), &txs);
+        }
+    }
+
+    #[test]
+    fn test_journal_postings_balance() {
+        let txs = sample();
+        let ledger =
+            export_transactions(&txs[..1], ExportFormat::Ledger, &AccountMapping::default())
+                .unwrap();
+        assert!(ledger.starts_with("2024/01/05 Coffee, NYC\n"));
+        assert!(ledger.contains("    Expenses:Card  12.1 USD @@ 11.37 EUR\n"));
+        assert!(ledger.contains("    Assets:N26  -11.37 EUR\n"));
+
+        let hledger =
+            export_transactions(&txs[2..3], ExportFormat::Hledger, &AccountMapping::default())
+                .unwrap();
+        assert!(hledger.starts_with("2024-01-01 Salary \"January\", bonus\n"));
+        assert!(hledger.contains("    Expenses:Transfers  -2500 EUR\n"));
+        assert!(hledger.contains("    Assets:N26  2500 EUR\n"));
+
+        // Payees never start with a status flag or a code
+        let flagged =
+            export_transactions(&txs[5..8], ExportFormat::Ledger, &AccountMapping::default())
+                .unwrap();
+        let headers: Vec<&str> = flagged.split("\n\n").filter_map(|b| b.lines().next()).collect();
+        assert_eq!(
+            headers,
+            vec!["2024/02/01 Star Shop", "2024/02/02 ref 7) Kiosk", "2024/02/03 bankTransfers"]
+        );
+    }
+
+    #[test]
+    fn test_journal_tag_values_keep_commas() {
+        let txs = sample();
+        let journal =
+            export_transactions(&txs[8..], ExportFormat::Hledger, &AccountMapping::default())
+                .unwrap();
+        assert!(journal.contains("    ; category: Food\\x2c Drinks\n"));
+        assert!(journal.contains("    ; comment: Lunch\\x2c team; paid\\x2c split\n"));
+        let tags: Vec<_> = journal
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("; "))
+            .flat_map(hledger_tags)
+            .collect();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(unescape_journal_text(&tags[1].1), "Lunch, team; paid, split");
+    }
+
+    #[test]
+    fn test_account_mapping() {
+        let mut mapping = AccountMapping::default();
+        mapping.accounts.insert("bankTransfers".to_string(), "Income:Salary".to_string());
+        assert_eq!(mapping.account_for(&sample()[2]), "Income:Salary");
+        assert_eq!(mapping.account_for(&sample()[4]), "Expenses:Uncategorized");
+
+        mapping.asset_account = "Assets:Bank  N26".to_string();
+        assert!(export_transactions(&[], ExportFormat::Ledger, &mapping).is_err());
+    }
+
+    #[test]
+    fn test_journal_rejects_unreadable_date() {
+        let txs = vec![tx(-1.0, "someday", "cash26Data", "x")];
+        assert!(
+            export_transactions(&txs, ExportFormat::Hledger, &AccountMapping::default()).is_err()
+        );
+        assert!(export_transactions(&txs, ExportFormat::Csv, &AccountMapping::default()).is_ok());
+    }
+}
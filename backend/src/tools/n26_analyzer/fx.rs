@@ -0,0 +1,268 @@
+use super::{parse_transaction_date, Transaction};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Base currency of the ECB euro foreign exchange reference rates
+const ECB_BASE_CURRENCY: &str = "EUR";
+
+/// How far back a rate may be reused for dates without a quote (weekends, bank holidays)
+const MAX_RATE_AGE_DAYS: i64 = 7;
+
+/// Daily FX reference rates, quoted as units of foreign currency per one unit of `base`.
+#[derive(Debug, Clone)]
+pub struct FxRateTable {
+    pub base: String,
+    rates: BTreeMap<NaiveDate, HashMap<String, f64>>,
+}
+
+impl FxRateTable {
+    /// Parse the ECB reference rate CSV (`eurofxref-hist.csv` or `eurofxref.csv`).
+    ///
+    /// The first column is the date, the header names the currencies and missing quotes are
+    /// written as `N/A`. Both the historical (ISO dates) and the daily file
+    /// (`5 January 2024`) date formats are accepted.
+    pub fn from_ecb_csv(csv: &str) -> Result<Self, String> {
+        let mut lines = csv.lines().map(str::trim).filter(|l| !l.is_empty());
+        let header = lines.next().ok_or_else(|| "FX rate CSV is empty".to_string())?;
+        let currencies: Vec<String> =
+            header.split(',').skip(1).map(|c| c.trim().to_ascii_uppercase()).collect();
+
+        let mut rates = BTreeMap::new();
+        for (line_no, line) in lines.enumerate() {
+            let mut cells = line.split(',').map(str::trim);
+            let raw_date = cells.next().unwrap_or_default();
+            let date = NaiveDate::parse_from_str(raw_date, "%Y-%m-%d")
+                .or_else(|_| NaiveDate::parse_from_str(raw_date, "%d %B %Y"))
+                .map_err(|_| format!("invalid date '{raw_date}' on line {}", line_no + 2))?;
+
+            let day: HashMap<String, f64> = currencies
+                .iter()
+                .zip(cells)
+                .filter(|(currency, _)| !currency.is_empty())
+                .filter_map(|(currency, cell)| {
+                    cell.parse::<f64>().ok().filter(|r| *r > 0.0).map(|r| (currency.clone(), r))
+                })
+                .collect();
+            rates.insert(date, day);
+        }
+
+        if rates.is_empty() {
+            return Err("FX rate CSV contains no rates".to_string());
+        }
+        Ok(Self { base: ECB_BASE_CURRENCY.to_string(), rates })
+    }
+
+    /// Load an ECB reference rate CSV from disk
+    pub fn load_ecb_csv(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let csv = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read FX rates from {}: {e}", path.display()))?;
+        Self::from_ecb_csv(&csv)
+    }
+
+    /// Units of `currency` per one unit of the base currency on (or shortly before) `date`
+    fn quote(&self, currency: &str, date: NaiveDate) -> Option<f64> {
+        if currency.eq_ignore_ascii_case(&self.base) {
+            return Some(1.0);
+        }
+        let oldest = date - chrono::Duration::days(MAX_RATE_AGE_DAYS);
+        self.rates
+            .range(oldest..=date)
+            .rev()
+            .find_map(|(_, day)| day.get(&currency.to_ascii_uppercase()).copied())
+    }
+
+    /// Units of `to` per one unit of `from` on `date`
+    #[must_use]
+    pub fn rate(&self, from: &str, to: &str, date: NaiveDate) -> Option<f64> {
+        if from.eq_ignore_ascii_case(to) {
+            return Some(1.0);
+        }
+        Some(self.quote(to, date)? / self.quote(from, date)?)
+    }
+
+    #[must_use]
+    pub fn convert(&self, amount: f64, from: &str, to: &str, date: NaiveDate) -> Option<f64> {
+        self.rate(from, to, date).map(|rate| amount * rate)
+    }
+}
+
+/// Markup paid on a foreign-currency card payment compared to the reference rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxFee {
+    pub date: String,
+    pub comment: String,
+    pub original_amount: f64,
+    pub original_currency: String,
+    pub settled_amount: f64,
+    pub settled_currency: String,
+    /// `original_amount` converted at the reference rate into the settled currency
+    pub reference_amount: f64,
+    /// Extra cost in the settled currency (negative when the settlement was cheaper)
+    pub fee: f64,
+    pub fee_percentage: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CurrencyReport {
+    pub reporting_currency: String,
+    pub overall_total: f64,
+    pub category_totals: HashMap<String, f64>,
+    /// Totals in the currency each payment was originally made in
+    pub original_currency_totals: HashMap<String, f64>,
+    pub fx_fees: Vec<FxFee>,
+    /// Sum of all FX fees in the reporting currency
+    pub total_fx_fees: f64,
+    /// Transactions left out of the totals because no rate or date was available
+    pub unconverted: usize,
+}
+
+/// Normalise settled amounts into `reporting_currency` and work out FX fees.
+#[must_use]
+pub fn normalize_to_currency(
+    transactions: &[Transaction],
+    table: &FxRateTable,
+    reporting_currency: &str,
+) -> CurrencyReport {
+    let reporting_currency = reporting_currency.trim().to_ascii_uppercase();
+    let mut report = CurrencyReport {
+        reporting_currency: reporting_currency.clone(),
+        overall_total: 0.0,
+        category_totals: HashMap::new(),
+        original_currency_totals: HashMap::new(),
+        fx_fees: Vec::new(),
+        total_fx_fees: 0.0,
+        unconverted: 0,
+    };
+
+    for tx in transactions {
+        let (original_amount, original_currency) =
+            match (tx.original_amount, tx.original_currency.as_deref()) {
+                (Some(amount), Some(currency)) => (amount, currency),
+                _ => (tx.amount, tx.currency.as_str()),
+            };
+        *report.original_currency_totals.entry(original_currency.to_string()).or_insert(0.0) +=
+            original_amount;
+
+        let Some(date) = parse_transaction_date(&tx.date) else {
+            report.unconverted += 1;
+            continue;
+        };
+        let Some(converted) = table.convert(tx.amount, &tx.currency, &reporting_currency, date)
+        else {
+            report.unconverted += 1;
+            continue;
+        };
+        report.overall_total += converted;
+        *report.category_totals.entry(tx.category.clone()).or_insert(0.0) += converted;
+
+        if original_currency.eq_ignore_ascii_case(&tx.currency) {
+            continue;
+        }
+        let Some(reference) = table.convert(original_amount, original_currency, &tx.currency, date)
+        else {
+            continue;
+        };
+        let fee = tx.amount.abs() - reference.abs();
+        if let Some(fee_reporting) = table.convert(fee, &tx.currency, &reporting_currency, date) {
+            report.total_fx_fees += fee_reporting;
+        }
+        report.fx_fees.push(FxFee {
+            date: tx.date.clone(),
+            comment: tx.comment.clone(),
+            original_amount,
+            original_currency: original_currency.to_string(),
+            settled_amount: tx.amount,
+            settled_currency: tx.currency.clone(),
+            reference_amount: reference,
+            fee,
+            fee_percentage: if reference != 0.0 { fee / reference.abs() * 100.0 } else { 0.0 },
+        });
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ECB_CSV: &str = "Date,USD,JPY,GBP,\n\
+        2024-01-05,1.0942,158.01,0.8600,\n\
+        2024-01-04,1.0953,157.80,N/A,\n";
+
+    fn tx(amount: f64, date: &str, currency: &str, original: Option<(f64, &str)>) -> Transaction {
+        Transaction {
+            amount,
+            date: date.to_string(),
+            category: "cardTransactions".to_string(),
+            comment: "test".to_string(),
+            currency: currency.to_string(),
+            original_amount: original.map(|(a, _)| a),
+            original_currency: original.map(|(_, c)| c.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_ecb_csv() {
+        let table = FxRateTable::from_ecb_csv(ECB_CSV).expect("parse");
+        assert_eq!(table.rates.len(), 2);
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        assert_eq!(table.rate("EUR", "USD", date), Some(1.0942));
+        assert!((table.rate("USD", "EUR", date).unwrap() - 1.0 / 1.0942).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_rate_falls_back_to_previous_quote() {
+        let table = FxRateTable::from_ecb_csv(ECB_CSV).expect("parse");
+        // Saturday: reuse Friday's quote
+        let saturday = NaiveDate::from_ymd_opt(2024, 1, 6).unwrap();
+        assert_eq!(table.rate("EUR", "JPY", saturday), Some(158.01));
+        // GBP is N/A on the 4th and there is no earlier quote
+        let thursday = NaiveDate::from_ymd_opt(2024, 1, 4).unwrap();
+        assert_eq!(table.rate("EUR", "GBP", thursday), None);
+        // Too far after the last quote
+        let later = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        assert_eq!(table.rate("EUR", "USD", later), None);
+    }
+
+    #[test]
+    fn test_invalid_csv_rejected() {
+        assert!(FxRateTable::from_ecb_csv("").is_err());
+        assert!(FxRateTable::from_ecb_csv("Date,USD\nnot-a-date,1.1\n").is_err());
+    }
+
+    #[test]
+    fn test_normalize_totals_and_fx_fee() {
+        let table = FxRateTable::from_ecb_csv(ECB_CSV).expect("parse");
+        let txs = vec![
+            tx(-11.5, "2024-01-05", "EUR", Some((-12.0, "USD"))),
+            tx(100.0, "2024-01-05", "EUR", None),
+        ];
+
+        let report = normalize_to_currency(&txs, &table, "usd");
+        assert_eq!(report.reporting_currency, "USD");
+        assert_eq!(report.unconverted, 0);
+        assert!((report.overall_total - 88.5 * 1.0942).abs() < 1e-9);
+        assert_eq!(report.original_currency_totals.get("USD"), Some(&-12.0));
+        assert_eq!(report.original_currency_totals.get("EUR"), Some(&100.0));
+
+        let fee = &report.fx_fees[0];
+        let reference = 12.0 / 1.0942;
+        assert!((fee.reference_amount.abs() - reference).abs() < 1e-9);
+        assert!((fee.fee - (11.5 - reference)).abs() < 1e-9);
+        assert!(fee.fee > 0.0);
+        assert!((report.total_fx_fees - fee.fee * 1.0942).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_missing_rate_counted_as_unconverted() {
+        let table = FxRateTable::from_ecb_csv(ECB_CSV).expect("parse");
+        let txs = vec![tx(-5.0, "2024-01-05", "CHF", None), tx(-5.0, "someday", "EUR", None)];
+        let report = normalize_to_currency(&txs, &table, "EUR");
+        assert_eq!(report.unconverted, 2);
+        assert_eq!(report.overall_total, 0.0);
+    }
+}
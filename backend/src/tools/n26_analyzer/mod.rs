@@ -0,0 +1,515 @@
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub mod budgets;
+pub mod export;
+pub mod fx;
+pub mod recurring;
+
+pub use fx::{normalize_to_currency, FxRateTable};
+pub use recurring::{detect_recurring_payments, RecurringOptions};
+
+/// Currency of N26 accounts when an entry does not state one
+pub const DEFAULT_CURRENCY: &str = "EUR";
+
+fn default_currency() -> String {
+    DEFAULT_CURRENCY.to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Transaction {
+    /// Settled amount in the account currency
+    pub amount: f64,
+    pub date: String,
+    pub category: String,
+    pub comment: String,
+    /// ISO 4217 code of the settled `amount`
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// Amount in the currency the payment was made in, when it differs from the settlement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_amount: Option<f64>,
+    /// ISO 4217 code of `original_amount`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_currency: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct N26Data {
+    pub id: String,
+    pub created: String,
+    pub data: HashMap<String, Option<Vec<serde_json::Value>>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnalysisResult {
+    pub transactions: Vec<Transaction>,
+    pub category_totals: HashMap<String, f64>,
+    pub overall_total: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import_report: Option<ImportReport>,
+}
+
+/// Parse the date of a transaction as exported by N26.
+///
+/// Accepts RFC 3339 timestamps, strings starting with `YYYY-MM-DD` and epoch milliseconds.
+#[must_use]
+pub fn parse_transaction_date(raw: &str) -> Option<NaiveDate> {
+    let raw = raw.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Some(dt.date_naive());
+    }
+    if let Some(date) =
+        raw.get(..10).and_then(|prefix| NaiveDate::parse_from_str(prefix, "%Y-%m-%d").ok())
+    {
+        return Some(date);
+    }
+    raw.parse::<i64>().ok().and_then(DateTime::from_timestamp_millis).map(|dt| dt.date_naive())
+}
+
+/// Read an ISO currency code from an entry, falling back to the account currency
+fn entry_currency(entry: &serde_json::Value, field: &str) -> String {
+    entry
+        .get(field)
+        .and_then(|v| v.as_str())
+        .map(|c| c.trim().to_ascii_uppercase())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(default_currency)
+}
+
+/// Top-level data keys the parser knows how to turn into transactions
+pub const KNOWN_DATA_KEYS: [&str; 3] = ["cash26Data", "bankTransfers", "cardTransactions"];
+
+/// An entry that could not be turned into a transaction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RejectedRow {
+    pub category: String,
+    /// Position of the entry within its category array
+    pub index: usize,
+    pub reason: String,
+}
+
+/// What happened to each entry of an N26 export during parsing
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub accepted: usize,
+    pub accepted_by_category: HashMap<String, usize>,
+    pub rejected: Vec<RejectedRow>,
+    /// Data keys that were present but are not parsed
+    pub unknown_keys: Vec<String>,
+}
+
+impl ImportReport {
+    /// True when every entry was imported and no data key was ignored
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.rejected.is_empty() && self.unknown_keys.is_empty()
+    }
+
+    fn accept(&mut self, category: &str) {
+        self.accepted += 1;
+        *self.accepted_by_category.entry(category.to_string()).or_insert(0) += 1;
+    }
+
+    fn reject(&mut self, category: &str, index: usize, reasons: &[String]) {
+        self.rejected.push(RejectedRow {
+            category: category.to_string(),
+            index,
+            reason: reasons.join("; "),
+        });
+    }
+}
+
+fn number_field(entry: &serde_json::Value, field: &str, errors: &mut Vec<String>) -> Option<f64> {
+    match entry.get(field) {
+        None | Some(serde_json::Value::Null) => {
+            errors.push(format!("missing field '{field}'"));
+            None
+        }
+        Some(v) => v.as_f64().or_else(|| {
+            errors.push(format!("field '{field}' is not a number"));
+            None
+        }),
+    }
+}
+
+fn string_field<'a>(
+    entry: &'a serde_json::Value,
+    field: &str,
+    errors: &mut Vec<String>,
+) -> Option<&'a str> {
+    match entry.get(field) {
+        None | Some(serde_json::Value::Null) => {
+            errors.push(format!("missing field '{field}'"));
+            None
+        }
+        Some(v) => v.as_str().or_else(|| {
+            errors.push(format!("field '{field}' is not a string"));
+            None
+        }),
+    }
+}
+
+/// Helper function to process a category of transactions from N26 data
+fn process_category(
+    data: &[serde_json::Value],
+    category: &str,
+    amount_field: &str,
+    date_field: &str,
+    comment_field: &str,
+    amount_multiplier: f64,
+    report: &mut ImportReport,
+) -> Vec<Transaction> {
+    let mut transactions = Vec::new();
+
+    for (index, entry) in data.iter().enumerate() {
+        if !entry.is_object() {
+            report.reject(category, index, &["entry is not an object".to_string()]);
+            continue;
+        }
+        let mut errors = Vec::new();
+        if let (Some(amount), Some(date), Some(comment)) = (
+            number_field(entry, amount_field, &mut errors),
+            string_field(entry, date_field, &mut errors),
+            string_field(entry, comment_field, &mut errors),
+        ) {
+            report.accept(category);
+            transactions.push(Transaction {
+                amount: amount * amount_multiplier,
+                date: date.to_string(),
+                category: category.to_string(),
+                comment: comment.to_string(),
+                currency: entry_currency(entry, "currency"),
+                original_amount: None,
+                original_currency: None,
+            });
+        } else {
+            report.reject(category, index, &errors);
+        }
+    }
+
+    transactions
+}
+
+/// Card payments are outflows, so the settled amount is negated
+fn process_card_transactions(
+    data: &[serde_json::Value],
+    report: &mut ImportReport,
+) -> Vec<Transaction> {
+    let category = "cardTransactions";
+    let mut transactions = Vec::new();
+
+    for (index, entry) in data.iter().enumerate() {
+        if !entry.is_object() {
+            report.reject(category, index, &["entry is not an object".to_string()]);
+            continue;
+        }
+        let mut errors = Vec::new();
+        let (Some(end_amount), Some(date), Some(merchant)) = (
+            number_field(entry, "end_amount", &mut errors),
+            string_field(entry, "transaction_date", &mut errors),
+            string_field(entry, "merchant_name", &mut errors),
+        ) else {
+            report.reject(category, index, &errors);
+            continue;
+        };
+
+        let settled_currency = entry_currency(entry, "end_currency");
+        // Without an original amount nothing is known about a foreign payment
+        let original =
+            entry.get("original_amount").and_then(serde_json::Value::as_f64).map(|amount| {
+                let currency = entry
+                    .get("original_currency")
+                    .and_then(|v| v.as_str())
+                    .map(|c| c.trim().to_ascii_uppercase())
+                    .filter(|c| !c.is_empty())
+                    .unwrap_or_else(|| settled_currency.clone());
+                (amount, currency)
+            });
+        let shown_amount = original.as_ref().map_or(end_amount, |(amount, _)| *amount);
+
+        report.accept(category);
+        transactions.push(Transaction {
+            amount: -end_amount,
+            date: date.to_string(),
+            category: category.to_string(),
+            comment: format!("{merchant}: {shown_amount}"),
+            currency: settled_currency,
+            original_amount: original.as_ref().map(|(amount, _)| -amount),
+            original_currency: original.map(|(_, currency)| currency),
+        });
+    }
+
+    transactions
+}
+
+/// Parse N26 JSON data and report every entry that was accepted or rejected
+fn parse_entries(n26_data: N26Data) -> (Vec<Transaction>, ImportReport) {
+    let mut transactions = Vec::new();
+    let mut report = ImportReport::default();
+
+    let mut unknown_keys: Vec<String> = n26_data
+        .data
+        .keys()
+        .filter(|key| !KNOWN_DATA_KEYS.contains(&key.as_str()))
+        .cloned()
+        .collect();
+    unknown_keys.sort();
+    report.unknown_keys = unknown_keys;
+
+    // Process cash26Data
+    if let Some(Some(cash_data)) = n26_data.data.get("cash26Data") {
+        transactions.extend(process_category(
+            cash_data,
+            "cash26Data",
+            "amount",
+            "transaction_date",
+            "transaction_type",
+            1.0,
+            &mut report,
+        ));
+    }
+
+    // Process bankTransfers
+    if let Some(Some(bank_data)) = n26_data.data.get("bankTransfers") {
+        transactions.extend(process_category(
+            bank_data,
+            "bankTransfers",
+            "amount",
+            "ts",
+            "reference_text",
+            1.0,
+            &mut report,
+        ));
+    }
+
+    // Process cardTransactions (special case: negative amounts)
+    if let Some(Some(card_data)) = n26_data.data.get("cardTransactions") {
+        transactions.extend(process_card_transactions(card_data, &mut report));
+    }
+
+    (transactions, report)
+}
+
+/// Parse N26 JSON data and report every entry that was accepted or rejected.
+///
+/// In strict mode any rejected entry or unknown data key fails the import and the report is
+/// returned as the error.
+pub fn parse_n26_json_detailed(
+    n26_data: N26Data,
+    strict: bool,
+) -> Result<(Vec<Transaction>, ImportReport), ImportReport> {
+    let (transactions, report) = parse_entries(n26_data);
+    if strict && !report.is_clean() {
+        return Err(report);
+    }
+    Ok((transactions, report))
+}
+
+/// Analyze transactions and calculate totals
+#[must_use]
+pub fn analyze_transactions(transactions: Vec<Transaction>) -> AnalysisResult {
+    let mut category_totals: HashMap<String, f64> = HashMap::new();
+    let mut overall_total = 0.0;
+
+    for transaction in &transactions {
+        *category_totals.entry(transaction.category.clone()).or_insert(0.0) += transaction.amount;
+        overall_total += transaction.amount;
+    }
+
+    AnalysisResult { transactions, category_totals, overall_total, import_report: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_empty_transactions() {
+        let result = analyze_transactions(vec![]);
+        assert_eq!(result.transactions.len(), 0);
+        assert_eq!(result.overall_total, 0.0);
+    }
+
+    #[test]
+    fn test_analyze_transactions_with_data() {
+        let transactions = vec![
+            Transaction {
+                amount: 100.0,
+                date: "2024-01-01".to_string(),
+                category: "income".to_string(),
+                comment: "test".to_string(),
+                currency: DEFAULT_CURRENCY.to_string(),
+                original_amount: None,
+                original_currency: None,
+            },
+            Transaction {
+                amount: -50.0,
+                date: "2024-01-02".to_string(),
+                category: "expense".to_string(),
+                comment: "test".to_string(),
+                currency: DEFAULT_CURRENCY.to_string(),
+                original_amount: None,
+                original_currency: None,
+            },
+        ];
+
+        let result = analyze_transactions(transactions);
+        assert_eq!(result.transactions.len(), 2);
+        assert_eq!(result.overall_total, 50.0);
+        assert_eq!(result.category_totals.get("income"), Some(&100.0));
+        assert_eq!(result.category_totals.get("expense"), Some(&-50.0));
+    }
+
+    #[test]
+    fn test_parse_transaction_date_formats() {
+        let expected = NaiveDate::from_ymd_opt(2024, 3, 5);
+        assert_eq!(parse_transaction_date("2024-03-05"), expected);
+        assert_eq!(parse_transaction_date("2024-03-05 14:22:01"), expected);
+        assert_eq!(parse_transaction_date("2024-03-05T14:22:01+01:00"), expected);
+        assert_eq!(parse_transaction_date("1709647200000"), expected);
+        assert_eq!(parse_transaction_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_parse_n26_json_basic() {
+        use serde_json::json;
+
+        let mut data_map = std::collections::HashMap::new();
+        data_map.insert(
+            "cash26Data".to_string(),
+            Some(vec![json!({"amount": 10.5, "transaction_date": "2024-01-01", "transaction_type": "cash"})]),
+        );
+
+        let n26 =
+            N26Data { id: "1".to_string(), created: "2024-01-01".to_string(), data: data_map };
+
+        let (res, _) = parse_n26_json_detailed(n26, false).expect("parse failed");
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].category, "cash26Data");
+        assert_eq!(res[0].amount, 10.5);
+        assert_eq!(res[0].currency, "EUR");
+    }
+
+    #[test]
+    fn test_parse_card_transaction_keeps_original_currency() {
+        use serde_json::json;
+
+        let mut data_map = std::collections::HashMap::new();
+        data_map.insert(
+            "cardTransactions".to_string(),
+            Some(vec![json!({
+                "end_amount": 11.5,
+                "end_currency": "EUR",
+                "original_amount": 12.0,
+                "original_currency": "usd",
+                "transaction_date": "2024-01-05",
+                "merchant_name": "Coffee NYC"
+            })]),
+        );
+
+        let n26 =
+            N26Data { id: "1".to_string(), created: "2024-01-05".to_string(), data: data_map };
+
+        let (res, _) = parse_n26_json_detailed(n26, false).expect("parse failed");
+        assert_eq!(res[0].amount, -11.5);
+        assert_eq!(res[0].currency, "EUR");
+        assert_eq!(res[0].original_amount, Some(-12.0));
+        assert_eq!(res[0].original_currency.as_deref(), Some("USD"));
+    }
+
+    #[test]
+    fn test_card_transaction_without_original_amount() {
+        use serde_json::json;
+
+        let n26 = n26_with(vec![(
+            "cardTransactions",
+            Some(vec![json!({
+                "end_amount": 4.2,
+                "end_currency": "EUR",
+                "original_currency": "USD",
+                "transaction_date": "2024-01-05",
+                "merchant_name": "Bakery"
+            })]),
+        )]);
+
+        let (res, _) = parse_n26_json_detailed(n26, false).expect("parse failed");
+        assert_eq!(res[0].amount, -4.2);
+        assert_eq!(res[0].original_amount, None);
+        assert_eq!(res[0].original_currency, None);
+        assert_eq!(res[0].comment, "Bakery: 4.2");
+    }
+
+    fn n26_with(data: Vec<(&str, Option<Vec<serde_json::Value>>)>) -> N26Data {
+        N26Data {
+            id: "1".to_string(),
+            created: "2024-01-01".to_string(),
+            data: data.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_import_report_lists_rejected_rows() {
+        use serde_json::json;
+
+        let n26 = n26_with(vec![
+            (
+                "cash26Data",
+                Some(vec![
+                    json!({"amount": 10.5, "transaction_date": "2024-01-01", "transaction_type": "cash"}),
+                    json!({"amount": "10.5", "transaction_date": "2024-01-02", "transaction_type": "cash"}),
+                    json!({"transaction_type": "cash"}),
+                ]),
+            ),
+            ("cardTransactions", Some(vec![json!("not an entry")])),
+            ("spaces", Some(vec![])),
+            ("bankTransfers", None),
+        ]);
+
+        let (transactions, report) = parse_n26_json_detailed(n26, false).expect("lenient parse");
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(report.accepted, 1);
+        assert_eq!(report.accepted_by_category.get("cash26Data"), Some(&1));
+        assert_eq!(report.unknown_keys, vec!["spaces".to_string()]);
+        assert_eq!(
+            report.rejected,
+            vec![
+                RejectedRow {
+                    category: "cash26Data".to_string(),
+                    index: 1,
+                    reason: "field 'amount' is not a number".to_string(),
+                },
+                RejectedRow {
+                    category: "cash26Data".to_string(),
+                    index: 2,
+                    reason: "missing field 'amount'; missing field 'transaction_date'".to_string(),
+                },
+                RejectedRow {
+                    category: "cardTransactions".to_string(),
+                    index: 0,
+                    reason: "entry is not an object".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strict_import_fails_on_dropped_data() {
+        use serde_json::json;
+
+        let clean = n26_with(vec![(
+            "bankTransfers",
+            Some(vec![json!({"amount": -20.0, "ts": "2024-01-01", "reference_text": "rent"})]),
+        )]);
+        assert!(parse_n26_json_detailed(clean, true).is_ok());
+
+        let unknown = n26_with(vec![("spaces", Some(vec![]))]);
+        let report = parse_n26_json_detailed(unknown, true).expect_err("strict parse");
+        assert_eq!(report.unknown_keys, vec!["spaces".to_string()]);
+
+        let bad_row = n26_with(vec![("bankTransfers", Some(vec![json!({"amount": -20.0})]))]);
+        let report = parse_n26_json_detailed(bad_row, true).expect_err("strict parse");
+        assert_eq!(report.rejected.len(), 1);
+    }
+}
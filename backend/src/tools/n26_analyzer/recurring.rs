@@ -0,0 +1,485 @@
+use super::{parse_transaction_date, Transaction};
+use chrono::{Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Legal-form and domain tokens that do not help to tell counterparties apart
+const IGNORED_COUNTERPARTY_TOKENS: [&str; 16] = [
+    "gmbh", "ag", "ab", "ltd", "inc", "llc", "sarl", "sa", "bv", "co", "kg", "se", "plc", "com",
+    "www", "eu",
+];
+
+/// Smallest price difference (in account currency) reported as a price change
+const PRICE_CHANGE_EPSILON: f64 = 0.005;
+
+/// Minimum share of intervals that must match the detected period
+const MIN_MATCHING_INTERVAL_SHARE: f64 = 0.75;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    const ALL: [Self; 3] = [Self::Weekly, Self::Monthly, Self::Yearly];
+
+    /// Average length of one period in days
+    #[must_use]
+    pub fn nominal_days(self) -> f64 {
+        match self {
+            Self::Weekly => 7.0,
+            Self::Monthly => 30.44,
+            Self::Yearly => 365.25,
+        }
+    }
+
+    #[must_use]
+    pub fn periods_per_year(self) -> f64 {
+        match self {
+            Self::Weekly => 365.25 / 7.0,
+            Self::Monthly => 12.0,
+            Self::Yearly => 1.0,
+        }
+    }
+
+    /// Advance a date by one period, keeping the day of month for monthly/yearly charges
+    #[must_use]
+    pub fn advance(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Weekly => date + chrono::Duration::days(7),
+            Self::Monthly => date.checked_add_months(Months::new(1)).unwrap_or(date),
+            Self::Yearly => date.checked_add_months(Months::new(12)).unwrap_or(date),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    Active,
+    Stopped,
+}
+
+/// Tuning knobs for recurring payment detection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecurringOptions {
+    /// Maximum relative change between two consecutive charges of one price period
+    /// (0.25 = 25%); a larger change starts a new period, reported as a price change when
+    /// the charges keep their cadence across it
+    pub amount_tolerance: f64,
+    /// Maximum relative deviation of an interval from the nominal period (0.15 = 15%)
+    pub interval_tolerance: f64,
+    /// Minimum number of charges for weekly and monthly subscriptions (yearly needs two)
+    pub min_occurrences: usize,
+    /// Reference date for the stopped check; defaults to the latest transaction date
+    pub as_of: Option<NaiveDate>,
+}
+
+impl Default for RecurringOptions {
+    fn default() -> Self {
+        Self { amount_tolerance: 0.25, interval_tolerance: 0.15, min_occurrences: 3, as_of: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceChange {
+    pub date: NaiveDate,
+    pub previous_amount: f64,
+    pub new_amount: f64,
+    pub change_percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringPayment {
+    /// Normalised counterparty used for grouping
+    pub counterparty: String,
+    /// Comment of the most recent charge as it appeared in the export
+    pub display_name: String,
+    pub category: String,
+    pub frequency: Frequency,
+    pub occurrences: usize,
+    pub first_date: NaiveDate,
+    pub last_date: NaiveDate,
+    /// Latest charge as a positive amount
+    pub current_amount: f64,
+    pub average_amount: f64,
+    pub next_expected_date: NaiveDate,
+    pub annualized_cost: f64,
+    pub price_changes: Vec<PriceChange>,
+    pub status: SubscriptionStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecurringReport {
+    pub as_of: Option<NaiveDate>,
+    pub subscriptions: Vec<RecurringPayment>,
+    /// Sum of the annualised cost of all subscriptions that are still active
+    pub active_annualized_cost: f64,
+}
+
+/// Normalise a transaction comment into a stable counterparty key.
+///
+/// Lowercases, splits on punctuation, and drops tokens containing digits (invoice numbers,
+/// amounts) as well as legal-form suffixes, so "NETFLIX.COM: 12.99" becomes "netflix".
+#[must_use]
+pub fn normalize_counterparty(raw: &str) -> String {
+    raw.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| {
+            !token.is_empty()
+                && !token.chars().any(|c| c.is_ascii_digit())
+                && !IGNORED_COUNTERPARTY_TOKENS.contains(token)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+/// Classify a sorted series of charge dates into a billing frequency, if any
+fn classify_frequency(dates: &[NaiveDate], interval_tolerance: f64) -> Option<Frequency> {
+    let intervals: Vec<f64> = dates.windows(2).map(|w| (w[1] - w[0]).num_days() as f64).collect();
+    let median_interval = median(&mut intervals.clone())?;
+
+    Frequency::ALL.into_iter().find(|freq| {
+        let nominal = freq.nominal_days();
+        let within = |days: f64| (days - nominal).abs() <= nominal * interval_tolerance;
+        if !within(median_interval) {
+            return false;
+        }
+        let matching = intervals.iter().filter(|&&days| within(days)).count();
+        matching as f64 / intervals.len() as f64 >= MIN_MATCHING_INTERVAL_SHARE
+    })
+}
+
+/// Detect a subscription in a run of one counterparty's charges (sorted by date)
+fn detect_group(
+    counterparty: &str,
+    charges: &[(NaiveDate, &Transaction)],
+    options: &RecurringOptions,
+    as_of: NaiveDate,
+) -> Option<RecurringPayment> {
+    if charges.len() < 2 {
+        return None;
+    }
+
+    let dates: Vec<NaiveDate> = charges.iter().map(|(d, _)| *d).collect();
+    let frequency = classify_frequency(&dates, options.interval_tolerance)?;
+
+    let required = if frequency == Frequency::Yearly { 2 } else { options.min_occurrences.max(2) };
+    if charges.len() < required {
+        return None;
+    }
+
+    let amounts: Vec<f64> = charges.iter().map(|(_, t)| t.amount.abs()).collect();
+    let mut price_changes = Vec::new();
+    for (i, pair) in amounts.windows(2).enumerate() {
+        let (previous, current) = (pair[0], pair[1]);
+        if previous <= 0.0 {
+            return None;
+        }
+        if (current - previous).abs() > PRICE_CHANGE_EPSILON {
+            price_changes.push(PriceChange {
+                date: dates[i + 1],
+                previous_amount: previous,
+                new_amount: current,
+                change_percentage: (current - previous) / previous * 100.0,
+            });
+        }
+    }
+
+    let (last_date, last_tx) = charges.last()?;
+    let current_amount = amounts.last().copied().unwrap_or(0.0);
+    let next_expected_date = frequency.advance(*last_date);
+    let grace_days = (frequency.nominal_days() * options.interval_tolerance).ceil() as i64;
+    let status = if as_of > next_expected_date + chrono::Duration::days(grace_days) {
+        SubscriptionStatus::Stopped
+    } else {
+        SubscriptionStatus::Active
+    };
+
+    Some(RecurringPayment {
+        counterparty: counterparty.to_string(),
+        display_name: last_tx.comment.clone(),
+        category: last_tx.category.clone(),
+        frequency,
+        occurrences: charges.len(),
+        first_date: dates[0],
+        last_date: *last_date,
+        current_amount,
+        average_amount: amounts.iter().sum::<f64>() / amounts.len() as f64,
+        next_expected_date,
+        annualized_cost: current_amount * frequency.periods_per_year(),
+        price_changes,
+        status,
+    })
+}
+
+/// Split charges into price periods wherever consecutive amounts differ by more than
+/// `amount_tolerance`
+fn split_price_periods<'a, 'b>(
+    charges: &'b [(NaiveDate, &'a Transaction)],
+    amount_tolerance: f64,
+) -> Vec<&'b [(NaiveDate, &'a Transaction)]> {
+    let mut periods = Vec::new();
+    let mut start = 0;
+    for i in 1..charges.len() {
+        let previous = charges[i - 1].1.amount.abs();
+        let current = charges[i].1.amount.abs();
+        if previous <= 0.0 || (current - previous).abs() / previous > amount_tolerance {
+            periods.push(&charges[start..i]);
+            start = i;
+        }
+    }
+    if start < charges.len() {
+        periods.push(&charges[start..]);
+    }
+    periods
+}
+
+/// Detect subscriptions in one counterparty group (charges sorted by date).
+///
+/// Adjacent price periods are joined unless both are single charges, so a price increase
+/// beyond `amount_tolerance` stays one subscription with a price change. When the joined
+/// charges lose their cadence, each price period is detected on its own instead.
+fn detect_subscriptions(
+    counterparty: &str,
+    charges: &[(NaiveDate, &Transaction)],
+    options: &RecurringOptions,
+    as_of: NaiveDate,
+) -> Vec<RecurringPayment> {
+    let periods = split_price_periods(charges, options.amount_tolerance);
+    let mut runs: Vec<Vec<&[(NaiveDate, &Transaction)]>> = Vec::new();
+    for (i, period) in periods.iter().enumerate() {
+        match runs.last_mut() {
+            Some(run) if periods[i - 1].len() > 1 || period.len() > 1 => run.push(period),
+            _ => runs.push(vec![period]),
+        }
+    }
+
+    let mut found = Vec::new();
+    for run in runs {
+        let joined: Vec<(NaiveDate, &Transaction)> = run.concat();
+        match detect_group(counterparty, &joined, options, as_of) {
+            Some(payment) => found.push(payment),
+            None if run.len() > 1 => found.extend(
+                run.iter().filter_map(|period| detect_group(counterparty, period, options, as_of)),
+            ),
+            None => {}
+        }
+    }
+    found
+}
+
+/// Group outgoing transactions by normalised counterparty and detect periodic charges.
+///
+/// Only charges (negative amounts) with a parseable date are considered. A group becomes a
+/// subscription when its intervals match a weekly, monthly or yearly period within
+/// `interval_tolerance`; amount jumps beyond `amount_tolerance` are reported as price
+/// changes or, when the cadence breaks with them, as separate subscriptions.
+#[must_use]
+pub fn detect_recurring_payments(
+    transactions: &[Transaction],
+    options: &RecurringOptions,
+) -> RecurringReport {
+    let mut groups: BTreeMap<String, Vec<(NaiveDate, &Transaction)>> = BTreeMap::new();
+    let mut latest: Option<NaiveDate> = None;
+
+    for transaction in transactions {
+        let Some(date) = parse_transaction_date(&transaction.date) else {
+            continue;
+        };
+        latest = latest.max(Some(date));
+        if transaction.amount >= 0.0 {
+            continue;
+        }
+        let key = normalize_counterparty(&transaction.comment);
+        if key.is_empty() {
+            continue;
+        }
+        groups.entry(key).or_default().push((date, transaction));
+    }
+
+    let as_of = options.as_of.or(latest);
+    let mut subscriptions: Vec<RecurringPayment> = match as_of {
+        Some(as_of) => groups
+            .iter_mut()
+            .flat_map(|(key, charges)| {
+                charges.sort_by_key(|(date, _)| *date);
+                detect_subscriptions(key, charges, options, as_of)
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    subscriptions.sort_by(|a, b| b.annualized_cost.total_cmp(&a.annualized_cost));
+    let active_annualized_cost = subscriptions
+        .iter()
+        .filter(|s| s.status == SubscriptionStatus::Active)
+        .map(|s| s.annualized_cost)
+        .sum();
+
+    RecurringReport { as_of, subscriptions, active_annualized_cost }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn charge(amount: f64, date: &str, comment: &str) -> Transaction {
+        Transaction {
+            amount,
+            date: date.to_string(),
+            category: "cardTransactions".to_string(),
+            comment: comment.to_string(),
+            currency: "EUR".to_string(),
+            original_amount: None,
+            original_currency: None,
+        }
+    }
+
+    #[test]
+    fn test_normalize_counterparty() {
+        assert_eq!(normalize_counterparty("NETFLIX.COM: 12.99"), "netflix");
+        assert_eq!(normalize_counterparty("Spotify AB"), "spotify");
+        assert_eq!(normalize_counterparty("Invoice 2024-001 Gym GmbH"), "invoice gym");
+        assert_eq!(normalize_counterparty("12345"), "");
+    }
+
+    #[test]
+    fn test_monthly_subscription_detected() {
+        let txs = vec![
+            charge(-12.99, "2024-01-15", "Netflix.com: 12.99"),
+            charge(-12.99, "2024-02-15", "Netflix.com: 12.99"),
+            charge(-12.99, "2024-03-15", "Netflix.com: 12.99"),
+            charge(-40.0, "2024-03-20", "Supermarket"),
+        ];
+        let report = detect_recurring_payments(&txs, &RecurringOptions::default());
+        assert_eq!(report.subscriptions.len(), 1);
+
+        let sub = &report.subscriptions[0];
+        assert_eq!(sub.counterparty, "netflix");
+        assert_eq!(sub.frequency, Frequency::Monthly);
+        assert_eq!(sub.occurrences, 3);
+        assert_eq!(sub.next_expected_date, NaiveDate::from_ymd_opt(2024, 4, 15).unwrap());
+        assert!((sub.annualized_cost - 12.99 * 12.0).abs() < 1e-9);
+        assert_eq!(sub.status, SubscriptionStatus::Active);
+        assert!(sub.price_changes.is_empty());
+    }
+
+    #[test]
+    fn test_price_change_reported() {
+        let txs = vec![
+            charge(-9.99, "2024-01-01", "Spotify AB"),
+            charge(-9.99, "2024-02-01", "Spotify AB"),
+            charge(-10.99, "2024-03-01", "Spotify AB"),
+            charge(-10.99, "2024-04-01", "Spotify AB"),
+        ];
+        let report = detect_recurring_payments(&txs, &RecurringOptions::default());
+        let sub = &report.subscriptions[0];
+        assert_eq!(sub.price_changes.len(), 1);
+        assert_eq!(sub.price_changes[0].date, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert!((sub.current_amount - 10.99).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_large_price_increase_kept() {
+        // +80% is beyond the amount tolerance but the monthly cadence continues
+        let txs = vec![
+            charge(-9.99, "2024-01-05", "Streaming Co"),
+            charge(-9.99, "2024-02-05", "Streaming Co"),
+            charge(-9.99, "2024-03-05", "Streaming Co"),
+            charge(-17.99, "2024-04-05", "Streaming Co"),
+        ];
+        let report = detect_recurring_payments(&txs, &RecurringOptions::default());
+        assert_eq!(report.subscriptions.len(), 1);
+        let sub = &report.subscriptions[0];
+        assert_eq!(sub.occurrences, 4);
+        assert!((sub.current_amount - 17.99).abs() < 1e-9);
+        assert_eq!(sub.price_changes.len(), 1);
+        assert!((sub.price_changes[0].change_percentage - 80.08).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_price_periods_split_when_cadence_breaks() {
+        // A monthly membership plus two irregular larger charges from the same counterparty
+        let txs = vec![
+            charge(-30.0, "2024-01-01", "City Gym"),
+            charge(-30.0, "2024-02-01", "City Gym"),
+            charge(-30.0, "2024-03-01", "City Gym"),
+            charge(-80.0, "2024-03-09", "City Gym"),
+            charge(-80.0, "2024-03-12", "City Gym"),
+        ];
+        let report = detect_recurring_payments(&txs, &RecurringOptions::default());
+        assert_eq!(report.subscriptions.len(), 1);
+        assert_eq!(report.subscriptions[0].occurrences, 3);
+        assert!((report.subscriptions[0].current_amount - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stopped_subscription_flagged() {
+        let txs = vec![
+            charge(-5.0, "2024-01-03", "Cloud Storage"),
+            charge(-5.0, "2024-02-03", "Cloud Storage"),
+            charge(-5.0, "2024-03-03", "Cloud Storage"),
+            charge(-30.0, "2024-07-01", "Bakery"),
+        ];
+        let report = detect_recurring_payments(&txs, &RecurringOptions::default());
+        assert_eq!(report.subscriptions[0].status, SubscriptionStatus::Stopped);
+        assert_eq!(report.active_annualized_cost, 0.0);
+    }
+
+    #[test]
+    fn test_weekly_and_yearly_detected() {
+        let txs = vec![
+            charge(-3.5, "2024-01-01", "Newspaper"),
+            charge(-3.5, "2024-01-08", "Newspaper"),
+            charge(-3.5, "2024-01-15", "Newspaper"),
+            charge(-3.5, "2024-01-22", "Newspaper"),
+            charge(-49.0, "2023-01-10", "Domain Registrar"),
+            charge(-49.0, "2024-01-10", "Domain Registrar"),
+        ];
+        let report = detect_recurring_payments(&txs, &RecurringOptions::default());
+        let find = |name: &str| report.subscriptions.iter().find(|s| s.counterparty == name);
+        assert_eq!(find("newspaper").unwrap().frequency, Frequency::Weekly);
+        assert_eq!(find("domain registrar").unwrap().frequency, Frequency::Yearly);
+    }
+
+    #[test]
+    fn test_irregular_payments_not_detected() {
+        let txs = vec![
+            charge(-20.0, "2024-01-01", "Online Shop"),
+            charge(-75.0, "2024-01-04", "Online Shop"),
+            charge(-12.0, "2024-02-20", "Online Shop"),
+            charge(-33.0, "2024-02-22", "Online Shop"),
+        ];
+        let report = detect_recurring_payments(&txs, &RecurringOptions::default());
+        assert!(report.subscriptions.is_empty());
+    }
+
+    #[test]
+    fn test_income_ignored() {
+        let txs = vec![
+            charge(2500.0, "2024-01-28", "Salary"),
+            charge(2500.0, "2024-02-28", "Salary"),
+            charge(2500.0, "2024-03-28", "Salary"),
+        ];
+        let report = detect_recurring_payments(&txs, &RecurringOptions::default());
+        assert!(report.subscriptions.is_empty());
+    }
+}
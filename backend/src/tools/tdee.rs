@@ -0,0 +1,260 @@
+use crate::tools::energy_journal::JournalDay;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Energy stored in one kg of mixed body weight change (kcal)
+pub const KCAL_PER_KG_BODY_WEIGHT: f64 = 7700.0;
+
+/// z-score of the two-sided 95% confidence interval
+const Z_95: f64 = 1.96;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Smoothing {
+    /// Exponentially weighted moving average
+    #[default]
+    Ewma,
+    /// Random-walk Kalman filter
+    Kalman,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TdeeOptions {
+    pub smoothing: Smoothing,
+    /// EWMA weight of each new measurement
+    pub alpha: f64,
+    /// Kalman process noise: expected day-to-day drift of true weight (kg²)
+    pub process_noise: f64,
+    /// Kalman measurement noise: scale-to-scale water and food fluctuation (kg²)
+    pub measurement_noise: f64,
+    /// Days of intake averaged into each estimate
+    pub window_days: usize,
+    /// Desired change in kg per week (negative to lose) for the calorie target
+    pub target_weekly_change_kg: Option<f64>,
+}
+
+impl Default for TdeeOptions {
+    fn default() -> Self {
+        Self {
+            smoothing: Smoothing::Ewma,
+            alpha: 0.1,
+            process_noise: 0.01,
+            measurement_noise: 0.5,
+            window_days: 14,
+            target_weekly_change_kg: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TdeeEstimate {
+    pub tdee_kcal: f64,
+    pub ci_low_kcal: f64,
+    pub ci_high_kcal: f64,
+    /// Days with logged intake the estimate is based on
+    pub days_used: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TdeePoint {
+    pub date: NaiveDate,
+    pub weight_kg: Option<f64>,
+    pub trend_weight_kg: f64,
+    pub intake_kcal: Option<f64>,
+    /// Rolling estimate ending on this day, once enough days are logged
+    pub estimate: Option<TdeeEstimate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalorieTarget {
+    pub weekly_change_kg: f64,
+    pub daily_kcal: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TdeeReport {
+    pub smoothing: Smoothing,
+    pub history: Vec<TdeePoint>,
+    pub current: Option<TdeeEstimate>,
+    pub target: Option<CalorieTarget>,
+}
+
+/// Daily trend weight over every calendar day from the first to the last logged weight
+fn trend_weights(weights: &[(NaiveDate, Option<f64>)], options: &TdeeOptions) -> Vec<f64> {
+    let mut trend = Vec::with_capacity(weights.len());
+    let mut state: Option<(f64, f64)> = None;
+    for (_, weight) in weights {
+        state = match (state, weight) {
+            (None, Some(w)) => Some((*w, options.measurement_noise)),
+            (None, None) => None,
+            (Some((x, p)), w) => Some(match options.smoothing {
+                Smoothing::Ewma => (w.map_or(x, |w| options.alpha.mul_add(w - x, x)), p),
+                Smoothing::Kalman => {
+                    let predicted = p + options.process_noise;
+                    match w {
+                        Some(w) => {
+                            let gain = predicted / (predicted + options.measurement_noise);
+                            (gain.mul_add(w - x, x), (1.0 - gain) * predicted)
+                        }
+                        None => (x, predicted),
+                    }
+                }
+            }),
+        };
+        trend.push(state.map_or(f64::NAN, |(x, _)| x));
+    }
+    trend
+}
+
+fn estimate(implied: &[f64]) -> Option<TdeeEstimate> {
+    let n = implied.len();
+    if n < 2 {
+        return None;
+    }
+    let mean = implied.iter().sum::<f64>() / n as f64;
+    let variance = implied.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+    let margin = Z_95 * (variance / n as f64).sqrt();
+    Some(TdeeEstimate {
+        tdee_kcal: mean,
+        ci_low_kcal: mean - margin,
+        ci_high_kcal: mean + margin,
+        days_used: n,
+    })
+}
+
+/// Estimate maintenance calories from logged intake and the smoothed weight trend.
+///
+/// Each day with logged intake implies `intake - 7700 * (trend change)` kcal of expenditure.
+/// The rolling estimate averages those over `window_days` and its confidence interval comes
+/// from the standard error of the daily values.
+pub fn estimate_tdee(days: &[JournalDay], options: &TdeeOptions) -> Result<TdeeReport, String> {
+    if !(options.alpha > 0.0 && options.alpha <= 1.0) {
+        return Err("alpha must be in (0, 1]".to_string());
+    }
+    if options.process_noise <= 0.0 || options.measurement_noise <= 0.0 {
+        return Err("Kalman noise parameters must be positive".to_string());
+    }
+    if options.window_days < 2 {
+        return Err("window_days must be at least 2".to_string());
+    }
+    let (Some(first), Some(last)) = (
+        days.iter().filter(|d| d.body_weight_kg.is_some()).map(|d| d.date).min(),
+        days.iter().map(|d| d.date).max(),
+    ) else {
+        return Err("log at least one body weight".to_string());
+    };
+
+    let by_date: HashMap<NaiveDate, &JournalDay> = days.iter().map(|d| (d.date, d)).collect();
+    let calendar: Vec<NaiveDate> = first.iter_days().take_while(|d| *d <= last).collect();
+    let weights: Vec<(NaiveDate, Option<f64>)> =
+        calendar.iter().map(|d| (*d, by_date.get(d).and_then(|day| day.body_weight_kg))).collect();
+    let trend = trend_weights(&weights, options);
+
+    let mut implied: Vec<f64> = Vec::new();
+    let mut history = Vec::with_capacity(calendar.len());
+    for (i, date) in calendar.iter().enumerate() {
+        let day = by_date.get(date);
+        let intake = day.and_then(|d| d.intake_kcal);
+        if let (Some(intake), Some(previous)) = (intake, i.checked_sub(1).map(|p| trend[p])) {
+            implied.push(KCAL_PER_KG_BODY_WEIGHT.mul_add(-(trend[i] - previous), intake));
+        }
+        let window = &implied[implied.len().saturating_sub(options.window_days)..];
+        history.push(TdeePoint {
+            date: *date,
+            weight_kg: day.and_then(|d| d.body_weight_kg),
+            trend_weight_kg: trend[i],
+            intake_kcal: intake,
+            estimate: (window.len() * 2 >= options.window_days).then(|| estimate(window)).flatten(),
+        });
+    }
+
+    let current = history.last().and_then(|p| p.estimate.clone());
+    let target =
+        options.target_weekly_change_kg.zip(current.as_ref()).map(|(change, est)| CalorieTarget {
+            weekly_change_kg: change,
+            daily_kcal: (change * KCAL_PER_KG_BODY_WEIGHT).mul_add(1.0 / 7.0, est.tdee_kcal),
+        });
+
+    Ok(TdeeReport { smoothing: options.smoothing, history, current, target })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days(n: u32, intake: f64, weight: impl Fn(u32) -> Option<f64>) -> Vec<JournalDay> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        (0..n)
+            .map(|i| JournalDay {
+                date: start + chrono::Duration::days(i64::from(i)),
+                intake_kcal: Some(intake),
+                body_weight_kg: weight(i),
+                body_fat_percentage: None,
+                training_kcal: 0.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stable_weight_means_intake_is_maintenance() {
+        let report =
+            estimate_tdee(&days(30, 2400.0, |_| Some(80.0)), &TdeeOptions::default()).unwrap();
+        let current = report.current.unwrap();
+        assert!((current.tdee_kcal - 2400.0).abs() < 1e-6);
+        assert!((current.ci_high_kcal - current.ci_low_kcal).abs() < 1e-6);
+        assert_eq!(current.days_used, 14);
+    }
+
+    #[test]
+    fn test_steady_loss_raises_estimate() {
+        // Losing 0.1 kg/day on 2000 kcal implies 2000 + 770 = 2770 kcal
+        for smoothing in [Smoothing::Ewma, Smoothing::Kalman] {
+            let options = TdeeOptions { smoothing, ..TdeeOptions::default() };
+            let report = estimate_tdee(
+                &days(120, 2000.0, |i| Some(f64::from(i).mul_add(-0.1, 90.0))),
+                &options,
+            )
+            .unwrap();
+            let tdee = report.current.unwrap().tdee_kcal;
+            assert!((tdee - 2770.0).abs() < 20.0, "{smoothing:?}: {tdee}");
+        }
+    }
+
+    #[test]
+    fn test_noise_is_smoothed_and_reflected_in_interval() {
+        let noisy = days(60, 2200.0, |i| Some(if i % 2 == 0 { 80.6 } else { 79.4 }));
+        let report = estimate_tdee(&noisy, &TdeeOptions::default()).unwrap();
+        let spread = report.history.iter().skip(30).map(|p| p.trend_weight_kg);
+        let (min, max) = spread.fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        assert!(max - min < 0.3, "trend should damp the 1.2 kg swings");
+
+        let current = report.current.unwrap();
+        assert!((current.tdee_kcal - 2200.0).abs() < 150.0);
+        assert!(current.ci_high_kcal - current.ci_low_kcal > 100.0);
+    }
+
+    #[test]
+    fn test_missing_weigh_ins_and_target() {
+        let sparse = days(28, 2500.0, |i| (i % 7 == 0).then_some(80.0));
+        let options = TdeeOptions { target_weekly_change_kg: Some(-0.5), ..TdeeOptions::default() };
+        let report = estimate_tdee(&sparse, &options).unwrap();
+        assert_eq!(report.history.len(), 28);
+        assert!(report.history[0].estimate.is_none());
+
+        let target = report.target.unwrap();
+        assert!((target.daily_kcal - (2500.0 - 550.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_requires_weight_and_valid_options() {
+        assert!(estimate_tdee(&days(10, 2000.0, |_| None), &TdeeOptions::default()).is_err());
+        let options = TdeeOptions { alpha: 0.0, ..TdeeOptions::default() };
+        assert!(estimate_tdee(&days(10, 2000.0, |_| Some(80.0)), &options).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use crate::tools::metabolism::Sex;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// BODY COMPOSITION
+// ============================================================================
+
+/// Height used to normalise FFMI (Kouri et al., 1995)
+const FFMI_REFERENCE_HEIGHT_M: f64 = 1.8;
+
+/// Physiological body fat range (%); equation results outside it are extrapolation artefacts
+const MIN_BODY_FAT_PERCENTAGE: f64 = 2.0;
+const MAX_BODY_FAT_PERCENTAGE: f64 = 60.0;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyFatMethod {
+    /// US Navy circumference method (Hodgdon & Beckett)
+    Navy,
+    /// Jackson-Pollock 3-site skinfolds with the Siri equation
+    JacksonPollock3,
+    /// Jackson-Pollock 7-site skinfolds with the Siri equation
+    JacksonPollock7,
+}
+
+/// Tape measurements in cm
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Circumferences {
+    pub neck_cm: Option<f64>,
+    pub waist_cm: Option<f64>,
+    pub hip_cm: Option<f64>,
+}
+
+/// Skinfold thicknesses in mm
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Skinfolds {
+    pub chest_mm: Option<f64>,
+    pub abdominal_mm: Option<f64>,
+    pub thigh_mm: Option<f64>,
+    pub triceps_mm: Option<f64>,
+    pub suprailiac_mm: Option<f64>,
+    pub subscapular_mm: Option<f64>,
+    pub midaxillary_mm: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BodyComposition {
+    pub method: BodyFatMethod,
+    pub body_fat_percentage: f64,
+    pub fat_mass_kg: f64,
+    pub lean_mass_kg: f64,
+    /// Fat-free mass index (kg/m²)
+    pub ffmi: f64,
+    /// FFMI adjusted to a height of 1.8 m
+    pub normalized_ffmi: f64,
+}
+
+/// Body fat percentage from body density (Siri, 1961), clamped to the physiological range
+#[must_use]
+pub fn siri_body_fat(density: f64) -> f64 {
+    (495.0 / density - 450.0).clamp(MIN_BODY_FAT_PERCENTAGE, MAX_BODY_FAT_PERCENTAGE)
+}
+
+fn positive(value: f64, what: &str) -> Result<f64, String> {
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err(format!("{what} must be positive"))
+    }
+}
+
+/// Body fat percentage from tape measurements (US Navy, metric form).
+///
+/// Men need neck and waist; women also need hip.
+pub fn navy_body_fat(sex: Sex, height_cm: f64, tape: &Circumferences) -> Result<f64, String> {
+    let (Some(neck), Some(waist)) = (tape.neck_cm, tape.waist_cm) else {
+        return Err("Navy method needs neck and waist".to_string());
+    };
+    let (neck, waist) = (positive(neck, "neck")?, positive(waist, "waist")?);
+    let height_cm = positive(height_cm, "height")?;
+    let girth = match sex {
+        Sex::Male => waist - neck,
+        Sex::Female => {
+            let hip = tape.hip_cm.ok_or_else(|| "Navy method needs hip for women".to_string())?;
+            waist + positive(hip, "hip")? - neck
+        }
+    };
+    if girth <= 0.0 {
+        return Err("waist must exceed neck".to_string());
+    }
+    let density_term = match sex {
+        Sex::Male => {
+            (-0.19077f64).mul_add(girth.log10(), 0.15456f64.mul_add(height_cm.log10(), 1.0324))
+        }
+        Sex::Female => {
+            (-0.35004f64).mul_add(girth.log10(), 0.22100f64.mul_add(height_cm.log10(), 1.29579))
+        }
+    };
+    Ok((495.0 / density_term - 450.0).clamp(MIN_BODY_FAT_PERCENTAGE, MAX_BODY_FAT_PERCENTAGE))
+}
+
+fn sum_sites(sites: &[Option<f64>], method: &str) -> Result<f64, String> {
+    sites.iter().try_fold(0.0, |sum, site| {
+        let mm = site.ok_or_else(|| format!("{method} needs every skinfold site"))?;
+        Ok(sum + positive(mm, "skinfold")?)
+    })
+}
+
+/// Quadratic Jackson-Pollock density: `c0 - c1*S + c2*S² - c_age*age`
+fn density([c0, c1, c2, c_age]: [f64; 4], sum_mm: f64, age_years: f64) -> f64 {
+    c2.mul_add(sum_mm * sum_mm, (-c1).mul_add(sum_mm, (-c_age).mul_add(age_years, c0)))
+}
+
+/// Body density from the 3-site Jackson-Pollock equations.
+///
+/// Men: chest, abdominal, thigh. Women: triceps, suprailiac, thigh.
+pub fn jackson_pollock_3_density(
+    sex: Sex,
+    age_years: f64,
+    folds: &Skinfolds,
+) -> Result<f64, String> {
+    let (sum, coefficients) = match sex {
+        Sex::Male => (
+            sum_sites(&[folds.chest_mm, folds.abdominal_mm, folds.thigh_mm], "Jackson-Pollock 3")?,
+            [1.10938, 0.000_826_7, 0.000_001_6, 0.000_257_4],
+        ),
+        Sex::Female => (
+            sum_sites(
+                &[folds.triceps_mm, folds.suprailiac_mm, folds.thigh_mm],
+                "Jackson-Pollock 3",
+            )?,
+            [1.099_492_1, 0.000_992_9, 0.000_002_3, 0.000_139_2],
+        ),
+    };
+    Ok(density(coefficients, sum, age_years))
+}
+
+/// Body density from the 7-site Jackson-Pollock equations
+pub fn jackson_pollock_7_density(
+    sex: Sex,
+    age_years: f64,
+    folds: &Skinfolds,
+) -> Result<f64, String> {
+    let sum = sum_sites(
+        &[
+            folds.chest_mm,
+            folds.midaxillary_mm,
+            folds.triceps_mm,
+            folds.subscapular_mm,
+            folds.abdominal_mm,
+            folds.suprailiac_mm,
+            folds.thigh_mm,
+        ],
+        "Jackson-Pollock 7",
+    )?;
+    let coefficients = match sex {
+        Sex::Male => [1.112, 0.000_434_99, 0.000_000_55, 0.000_288_26],
+        Sex::Female => [1.097, 0.000_469_71, 0.000_000_56, 0.000_128_28],
+    };
+    Ok(density(coefficients, sum, age_years))
+}
+
+/// Split body weight into fat and lean mass and derive FFMI
+#[must_use]
+pub fn body_composition(
+    method: BodyFatMethod,
+    body_fat_percentage: f64,
+    weight_kg: f64,
+    height_cm: f64,
+) -> BodyComposition {
+    let fat_mass_kg = weight_kg * body_fat_percentage / 100.0;
+    let lean_mass_kg = weight_kg - fat_mass_kg;
+    let height_m = height_cm / 100.0;
+    let ffmi = lean_mass_kg / (height_m * height_m);
+    BodyComposition {
+        method,
+        body_fat_percentage,
+        fat_mass_kg,
+        lean_mass_kg,
+        ffmi,
+        normalized_ffmi: 6.1f64.mul_add(FFMI_REFERENCE_HEIGHT_M - height_m, ffmi),
+    }
+}
+
+/// Every estimate the available measurements allow, in Navy, JP3, JP7 order.
+///
+/// `age_years` is only needed for the skinfold methods; methods missing an input are skipped.
+#[must_use]
+pub fn estimate_body_composition(
+    sex: Sex,
+    age_years: Option<f64>,
+    weight_kg: f64,
+    height_cm: f64,
+    tape: &Circumferences,
+    folds: &Skinfolds,
+) -> Vec<BodyComposition> {
+    let mut estimates = Vec::new();
+    if let Ok(bf) = navy_body_fat(sex, height_cm, tape) {
+        estimates.push(body_composition(BodyFatMethod::Navy, bf, weight_kg, height_cm));
+    }
+    if let Some(age) = age_years {
+        if let Ok(density) = jackson_pollock_3_density(sex, age, folds) {
+            let bf = siri_body_fat(density);
+            estimates.push(body_composition(
+                BodyFatMethod::JacksonPollock3,
+                bf,
+                weight_kg,
+                height_cm,
+            ));
+        }
+        if let Ok(density) = jackson_pollock_7_density(sex, age, folds) {
+            let bf = siri_body_fat(density);
+            estimates.push(body_composition(
+                BodyFatMethod::JacksonPollock7,
+                bf,
+                weight_kg,
+                height_cm,
+            ));
+        }
+    }
+    estimates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_navy_reference_values() {
+        let male = Circumferences { neck_cm: Some(38.0), waist_cm: Some(85.0), hip_cm: None };
+        let bf = navy_body_fat(Sex::Male, 180.0, &male).unwrap();
+        assert!((bf - 16.11).abs() < 0.01, "male navy: {bf}");
+
+        let female =
+            Circumferences { neck_cm: Some(32.0), waist_cm: Some(70.0), hip_cm: Some(95.0) };
+        let bf = navy_body_fat(Sex::Female, 165.0, &female).unwrap();
+        assert!((bf - 24.86).abs() < 0.01, "female navy: {bf}");
+
+        assert!(navy_body_fat(Sex::Female, 165.0, &male).is_err());
+    }
+
+    #[test]
+    fn test_rejects_non_positive_inputs_and_clamps() {
+        let zero_neck = Circumferences { neck_cm: Some(0.0), waist_cm: Some(85.0), hip_cm: None };
+        assert!(navy_body_fat(Sex::Male, 180.0, &zero_neck).is_err());
+        let negative_hip =
+            Circumferences { neck_cm: Some(32.0), waist_cm: Some(70.0), hip_cm: Some(-95.0) };
+        assert!(navy_body_fat(Sex::Female, 165.0, &negative_hip).is_err());
+
+        // A waist barely above the neck extrapolates below any real body fat
+        let narrow = Circumferences { neck_cm: Some(38.0), waist_cm: Some(39.0), hip_cm: None };
+        let bf = navy_body_fat(Sex::Male, 180.0, &narrow).unwrap();
+        assert!((bf - MIN_BODY_FAT_PERCENTAGE).abs() < f64::EPSILON);
+
+        let folds = Skinfolds {
+            chest_mm: Some(15.0),
+            abdominal_mm: Some(0.0),
+            thigh_mm: Some(20.0),
+            ..Skinfolds::default()
+        };
+        assert!(jackson_pollock_3_density(Sex::Male, 30.0, &folds).is_err());
+        assert!((siri_body_fat(0.9) - MAX_BODY_FAT_PERCENTAGE).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_jackson_pollock_3_male() {
+        // Sum 60 mm at 30 years: D = 1.10938 - 0.049602 + 0.00576 - 0.007722 = 1.057816
+        let folds = Skinfolds {
+            chest_mm: Some(15.0),
+            abdominal_mm: Some(25.0),
+            thigh_mm: Some(20.0),
+            ..Skinfolds::default()
+        };
+        let density = jackson_pollock_3_density(Sex::Male, 30.0, &folds).unwrap();
+        assert!((density - 1.057_816).abs() < 1e-6);
+        assert!((siri_body_fat(density) - 17.95).abs() < 0.01);
+        assert!(jackson_pollock_3_density(Sex::Female, 30.0, &folds).is_err());
+    }
+
+    #[test]
+    fn test_jackson_pollock_7_needs_all_sites() {
+        let mut folds = Skinfolds {
+            chest_mm: Some(10.0),
+            midaxillary_mm: Some(12.0),
+            triceps_mm: Some(10.0),
+            subscapular_mm: Some(14.0),
+            abdominal_mm: Some(20.0),
+            suprailiac_mm: Some(12.0),
+            thigh_mm: Some(16.0),
+        };
+        // Sum 94 mm at 30 years: D = 1.112 - 0.04088906 + 0.0048598 - 0.0086478 = 1.06732294
+        let density = jackson_pollock_7_density(Sex::Male, 30.0, &folds).unwrap();
+        assert!((density - 1.067_322_94).abs() < 1e-7);
+
+        folds.midaxillary_mm = None;
+        assert!(jackson_pollock_7_density(Sex::Male, 30.0, &folds).is_err());
+    }
+
+    #[test]
+    fn test_composition_and_ffmi() {
+        let c = body_composition(BodyFatMethod::Navy, 20.0, 80.0, 180.0);
+        assert!((c.fat_mass_kg - 16.0).abs() < 1e-9);
+        assert!((c.lean_mass_kg - 64.0).abs() < 1e-9);
+        assert!((c.ffmi - 64.0 / 3.24).abs() < 1e-9);
+        assert!((c.normalized_ffmi - c.ffmi).abs() < 1e-9);
+
+        let short = body_composition(BodyFatMethod::Navy, 20.0, 80.0, 170.0);
+        assert!(short.normalized_ffmi > short.ffmi);
+    }
+
+    #[test]
+    fn test_estimate_skips_methods_without_inputs() {
+        let tape = Circumferences { neck_cm: Some(38.0), waist_cm: Some(85.0), hip_cm: None };
+        let folds = Skinfolds {
+            chest_mm: Some(15.0),
+            abdominal_mm: Some(25.0),
+            thigh_mm: Some(20.0),
+            ..Skinfolds::default()
+        };
+        let all = estimate_body_composition(Sex::Male, Some(30.0), 80.0, 180.0, &tape, &folds);
+        let methods: Vec<_> = all.iter().map(|c| c.method).collect();
+        assert_eq!(methods, vec![BodyFatMethod::Navy, BodyFatMethod::JacksonPollock3]);
+
+        let no_age = estimate_body_composition(Sex::Male, None, 80.0, 180.0, &tape, &folds);
+        assert_eq!(no_age.len(), 1);
+    }
+}
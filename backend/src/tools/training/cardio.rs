@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// CARDIO AND CONDITIONING
+// ============================================================================
+
+const J_PER_KCAL: f64 = 4184.0;
+/// Longest single activity that can be logged
+const MAX_DURATION_S: f64 = 24.0 * 3600.0;
+/// Longest single distance that can be logged (1000 km)
+const MAX_DISTANCE_M: f64 = 1_000_000.0;
+/// Heart-rate zones as shares of maximum heart rate; zone 1 starts at 50 %
+const ZONE_FLOORS: [f64; 5] = [0.5, 0.6, 0.7, 0.8, 0.9];
+
+/// MET by speed in km/h, from the Compendium of Physical Activities (Ainsworth et al., 2011)
+const RUN_METS: &[(f64, f64)] = &[
+    (6.4, 6.0),
+    (8.0, 8.3),
+    (9.7, 9.8),
+    (11.3, 11.0),
+    (12.9, 11.8),
+    (14.5, 12.8),
+    (16.1, 14.5),
+    (17.7, 16.0),
+    (19.3, 19.0),
+    (22.5, 23.0),
+];
+const WALK_METS: &[(f64, f64)] =
+    &[(3.2, 2.8), (4.0, 3.0), (4.8, 3.5), (5.6, 4.3), (6.4, 5.0), (7.2, 7.0)];
+const CYCLE_METS: &[(f64, f64)] =
+    &[(16.0, 6.8), (20.5, 8.0), (24.0, 10.0), (28.0, 12.0), (32.0, 15.8)];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CardioActivity {
+    Run,
+    Walk,
+    Cycle,
+    Row,
+    Swim,
+    Elliptical,
+    SkiErg,
+    StairClimb,
+    JumpRope,
+    Other,
+}
+
+impl CardioActivity {
+    /// MET at a moderate effort when no speed is known
+    #[must_use]
+    pub fn default_met(self) -> f64 {
+        match self {
+            Self::Run => 9.8,
+            Self::Walk => 3.5,
+            Self::Cycle => 7.5,
+            Self::Row => 7.0,
+            Self::Swim => 7.0,
+            Self::Elliptical => 5.0,
+            Self::SkiErg => 7.0,
+            Self::StairClimb => 9.0,
+            Self::JumpRope => 11.8,
+            Self::Other => 5.0,
+        }
+    }
+
+    /// Gross efficiency of turning metabolic energy into measured power
+    #[must_use]
+    pub fn efficiency(self) -> f64 {
+        match self {
+            Self::Cycle => 0.24,
+            Self::Row | Self::SkiErg => 0.20,
+            _ => 0.22,
+        }
+    }
+
+    fn met_by_speed(self) -> Option<&'static [(f64, f64)]> {
+        match self {
+            Self::Run => Some(RUN_METS),
+            Self::Walk => Some(WALK_METS),
+            Self::Cycle => Some(CYCLE_METS),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardioInput {
+    pub activity: CardioActivity,
+    pub duration_s: f64,
+    pub distance_m: Option<f64>,
+    pub avg_power_w: Option<f64>,
+    /// Overrides the MET from the tables
+    pub met: Option<f64>,
+    /// Seconds spent in heart-rate zones 1-5
+    pub zone_seconds: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CardioEnergyMethod {
+    /// Measured power divided by gross efficiency
+    Power,
+    /// Metabolic equivalent × body weight × hours
+    Met,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardioEnergy {
+    pub energy_kcal: f64,
+    pub method: CardioEnergyMethod,
+    /// MET used; None for power-based estimates
+    pub met: Option<f64>,
+}
+
+pub fn validate_cardio(input: &CardioInput) -> Result<(), String> {
+    if !(input.duration_s.is_finite() && input.duration_s > 0.0) {
+        return Err("duration must be positive".to_string());
+    }
+    if input.duration_s > MAX_DURATION_S {
+        return Err("duration must be at most 24 hours".to_string());
+    }
+    if input.distance_m.is_some_and(|d| !(0.0..=MAX_DISTANCE_M).contains(&d)) {
+        return Err("distance must be 0-1000 km".to_string());
+    }
+    if input.avg_power_w.is_some_and(|p| !(0.0..=2500.0).contains(&p)) {
+        return Err("average power must be 0-2500 W".to_string());
+    }
+    if input.met.is_some_and(|m| !(1.0..=25.0).contains(&m)) {
+        return Err("MET must be 1-25".to_string());
+    }
+    if let Some(zones) = &input.zone_seconds {
+        if zones.len() != ZONE_FLOORS.len() {
+            return Err("zone seconds need one entry per zone 1-5".to_string());
+        }
+        // A second of slack per zone for rounding in device exports
+        let total: f64 = zones.iter().map(|&s| f64::from(s)).sum();
+        if total > input.duration_s + zones.len() as f64 {
+            return Err("time in zones exceeds the duration".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Average speed in km/h
+#[must_use]
+pub fn speed_kmh(distance_m: f64, duration_s: f64) -> Option<f64> {
+    (distance_m > 0.0 && duration_s > 0.0).then(|| distance_m / duration_s * 3.6)
+}
+
+/// Pace in seconds per km
+#[must_use]
+pub fn pace_s_per_km(distance_m: f64, duration_s: f64) -> Option<f64> {
+    (distance_m > 0.0 && duration_s > 0.0).then(|| duration_s / distance_m * 1000.0)
+}
+
+/// Linear interpolation between table points, flat beyond either end
+fn interpolate(points: &[(f64, f64)], x: f64) -> f64 {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if x <= first.0 {
+        return first.1;
+    }
+    if x >= last.0 {
+        return last.1;
+    }
+    points.windows(2).find(|pair| x <= pair[1].0).map_or(last.1, |pair| {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+    })
+}
+
+/// MET of the activity: the override, the speed table, or the moderate default
+#[must_use]
+pub fn activity_met(input: &CardioInput) -> f64 {
+    if let Some(met) = input.met {
+        return met;
+    }
+    let speed = input.distance_m.and_then(|d| speed_kmh(d, input.duration_s));
+    match (input.activity.met_by_speed(), speed) {
+        (Some(table), Some(speed)) => interpolate(table, speed),
+        _ => input.activity.default_met(),
+    }
+}
+
+/// Energy of a cardio activity. Measured power wins over MET estimates.
+pub fn estimate_cardio_energy(
+    input: &CardioInput,
+    body_weight_kg: f64,
+) -> Result<CardioEnergy, String> {
+    validate_cardio(input)?;
+    if let Some(power) = input.avg_power_w.filter(|&p| p > 0.0) {
+        return Ok(CardioEnergy {
+            energy_kcal: power * input.duration_s / input.activity.efficiency() / J_PER_KCAL,
+            method: CardioEnergyMethod::Power,
+            met: None,
+        });
+    }
+    let met = activity_met(input);
+    Ok(CardioEnergy {
+        energy_kcal: met * body_weight_kg * input.duration_s / 3600.0,
+        method: CardioEnergyMethod::Met,
+        met: Some(met),
+    })
+}
+
+/// Zone 1-5 of a heart rate by share of maximum heart rate; None below zone 1
+#[must_use]
+pub fn heart_rate_zone(bpm: u32, max_heart_rate: u32) -> Option<u8> {
+    if max_heart_rate == 0 {
+        return None;
+    }
+    let share = f64::from(bpm) / f64::from(max_heart_rate);
+    ZONE_FLOORS.iter().rposition(|&floor| share >= floor).map(|i| i as u8 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(activity: CardioActivity, duration_s: f64, distance_m: Option<f64>) -> CardioInput {
+        CardioInput {
+            activity,
+            duration_s,
+            distance_m,
+            avg_power_w: None,
+            met: None,
+            zone_seconds: None,
+        }
+    }
+
+    #[test]
+    fn test_met_from_speed() {
+        // 10 km in 50 min is 12 km/h, between 11.3 (11.0) and 12.9 km/h (11.8)
+        let run = input(CardioActivity::Run, 3000.0, Some(10_000.0));
+        let met = activity_met(&run);
+        assert!((met - (11.0 + 0.8 * 0.7 / 1.6)).abs() < 1e-9, "{met}");
+        let energy = estimate_cardio_energy(&run, 70.0).unwrap();
+        assert_eq!(energy.method, CardioEnergyMethod::Met);
+        assert!((energy.energy_kcal - met * 70.0 * 50.0 / 60.0).abs() < 1e-9);
+        assert_eq!(pace_s_per_km(10_000.0, 3000.0), Some(300.0));
+
+        // No distance or no table: moderate default; very fast: flat at the table end
+        assert_eq!(activity_met(&input(CardioActivity::Row, 600.0, Some(2000.0))), 7.0);
+        assert_eq!(activity_met(&input(CardioActivity::Run, 600.0, Some(5000.0))), 23.0);
+    }
+
+    #[test]
+    fn test_power_beats_met() {
+        // 200 W for an hour at 24 % efficiency: 720 kJ of work, 3000 kJ metabolic
+        let mut ride = input(CardioActivity::Cycle, 3600.0, Some(30_000.0));
+        ride.avg_power_w = Some(200.0);
+        let energy = estimate_cardio_energy(&ride, 80.0).unwrap();
+        assert_eq!(energy.method, CardioEnergyMethod::Power);
+        assert!((energy.energy_kcal - 3_000_000.0 / 4184.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cardio_validation() {
+        assert!(validate_cardio(&input(CardioActivity::Walk, 0.0, None)).is_err());
+        assert!(validate_cardio(&input(CardioActivity::Walk, 86_401.0, None)).is_err());
+        assert!(validate_cardio(&input(CardioActivity::Cycle, 3600.0, Some(-1.0))).is_err());
+        assert!(validate_cardio(&input(CardioActivity::Cycle, 3600.0, Some(1e8))).is_err());
+        assert!(validate_cardio(&input(CardioActivity::Cycle, 3600.0, Some(f64::NAN))).is_err());
+        let mut zones = input(CardioActivity::Run, 600.0, None);
+        zones.zone_seconds = Some(vec![0, 100, 300, 200, 0]);
+        assert!(validate_cardio(&zones).is_ok());
+        zones.zone_seconds = Some(vec![0, 100, 300, 300, 0]);
+        assert!(validate_cardio(&zones).is_err());
+        zones.zone_seconds = Some(vec![600]);
+        assert!(validate_cardio(&zones).is_err());
+    }
+
+    #[test]
+    fn test_heart_rate_zones() {
+        assert_eq!(heart_rate_zone(80, 200), None);
+        assert_eq!(heart_rate_zone(100, 200), Some(1));
+        assert_eq!(heart_rate_zone(150, 200), Some(3));
+        assert_eq!(heart_rate_zone(199, 200), Some(5));
+        assert_eq!(heart_rate_zone(150, 0), None);
+    }
+}
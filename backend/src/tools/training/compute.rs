@@ -310,17 +310,40 @@ pub fn attribute_muscle_energy(
     energies
 }
 
+/// Sets a muscle is credited with per set of an exercise, by involvement.
+/// Primary movers count fully, secondary movers half, stabilizers not at all.
+#[must_use]
+pub fn muscle_set_credit(involvement: &str) -> f64 {
+    match involvement {
+        "primary" => 1.0,
+        "secondary" => 0.5,
+        _ => 0.0,
+    }
+}
+
 /// Estimate 1-rep max from a set using the Epley formula.
 /// Returns None if reps is 0 or 1 (direct 1RM) or weight is 0.
 #[must_use]
 pub fn estimate_1rm(weight_kg: f64, reps: u32) -> Option<f64> {
+    estimate_1rm_with(weight_kg, reps, OneRmFormula::Epley)
+}
+
+/// Estimate 1-rep max with the chosen formula.
+/// Brzycki is undefined from 37 reps on and returns None there.
+#[must_use]
+pub fn estimate_1rm_with(weight_kg: f64, reps: u32, formula: OneRmFormula) -> Option<f64> {
     if weight_kg <= 0.0 || reps == 0 {
         return None;
     }
     if reps == 1 {
         return Some(weight_kg);
     }
-    Some(weight_kg * (1.0 + reps as f64 / 30.0))
+    let reps = reps as f64;
+    match formula {
+        OneRmFormula::Epley => Some(weight_kg * (1.0 + reps / 30.0)),
+        OneRmFormula::Brzycki => (reps < 37.0).then(|| weight_kg * 36.0 / (37.0 - reps)),
+        OneRmFormula::Lombardi => Some(weight_kg * reps.powf(0.10)),
+    }
 }
 
 /// Compute total volume for a collection of sets: sum(weight_kg * reps)
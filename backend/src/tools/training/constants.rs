@@ -3,6 +3,10 @@
 // CONSTANTS
 // ============================================================================
 
+/// Version of the set energy model stored with each set. Bump it whenever a constant
+/// or formula below changes so stored energy can be found and recomputed.
+pub const ENERGY_MODEL_VERSION: i32 = 1;
+
 /// Gravitational acceleration (m/s²)
 pub const GRAVITY: f64 = 9.81;
 
@@ -45,3 +49,33 @@ impl SegmentMassFractions {
     pub const COM_UPPER_LEG: f64 = 0.433;
     pub const COM_LOWER_LEG: f64 = 0.606;
 }
+
+// ============================================================================
+// EXERCISE ENUMS (mirror the CHECK constraints of `exercises` / `exercise_muscles`)
+// ============================================================================
+
+pub const MOVEMENT_PATTERNS: [&str; 13] = [
+    "horizontal_push",
+    "vertical_push",
+    "horizontal_pull",
+    "vertical_pull",
+    "squat",
+    "hinge",
+    "lunge",
+    "isolation_upper",
+    "isolation_lower",
+    "core",
+    "carry",
+    "plyometric",
+    "bodyweight_compound",
+];
+
+pub const EQUIPMENT_TYPES: [&str; 8] =
+    ["barbell", "dumbbell", "cable", "machine", "bodyweight", "kettlebell", "band", "other"];
+
+pub const DIFFICULTIES: [&str; 3] = ["beginner", "intermediate", "advanced"];
+
+pub const MUSCLE_INVOLVEMENTS: [&str; 3] = ["primary", "secondary", "stabilizer"];
+
+/// Segments understood by `compute_moving_segment_mass` and `compute_displacement`
+pub const BODY_SEGMENTS: [&str; 5] = ["upper_arm", "lower_arm", "upper_leg", "lower_leg", "torso"];
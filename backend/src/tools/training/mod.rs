@@ -1,14 +1,26 @@
 #![allow(dead_code)]
+pub mod body_composition;
+pub mod cardio;
 pub mod compute;
 pub mod constants;
+pub mod nutrition;
 pub mod plates;
+pub mod program;
+pub mod progression;
+pub mod records;
+pub mod summary;
 pub mod types;
+pub mod units;
+pub mod validation;
+pub mod warmup;
+pub mod wearable;
 
 pub use compute::*;
 #[allow(unused_imports)]
 pub use constants::*;
 pub use plates::*;
 pub use types::*;
+pub use units::*;
 
 // ============================================================================
 // TESTS
@@ -340,6 +352,13 @@ mod tests {
         assert!(result.is_empty());
     }
 
+    #[test]
+    fn test_muscle_set_credit() {
+        assert_eq!(muscle_set_credit("primary"), 1.0);
+        assert_eq!(muscle_set_credit("secondary"), 0.5);
+        assert_eq!(muscle_set_credit("stabilizer"), 0.0);
+    }
+
     #[test]
     fn test_1rm_estimation() {
         // Epley: 1RM = weight * (1 + reps/30)
@@ -353,6 +372,19 @@ mod tests {
         assert_eq!(rm, 150.0);
     }
 
+    #[test]
+    fn test_1rm_formulas() {
+        // 100 kg × 10: Brzycki 100*36/27, Lombardi 100*10^0.1
+        let epley = estimate_1rm_with(100.0, 10, OneRmFormula::Epley).unwrap();
+        let brzycki = estimate_1rm_with(100.0, 10, OneRmFormula::Brzycki).unwrap();
+        let lombardi = estimate_1rm_with(100.0, 10, OneRmFormula::Lombardi).unwrap();
+        assert!((epley - 133.33).abs() < 0.01);
+        assert!((brzycki - 133.33).abs() < 0.01);
+        assert!((lombardi - 125.89).abs() < 0.01);
+        assert!(estimate_1rm_with(100.0, 37, OneRmFormula::Brzycki).is_none());
+        assert_eq!(estimate_1rm_with(140.0, 1, OneRmFormula::Lombardi), Some(140.0));
+    }
+
     #[test]
     fn test_1rm_zero_weight() {
         assert!(estimate_1rm(0.0, 10).is_none());
@@ -389,25 +421,32 @@ mod tests {
 
     #[test]
     fn test_plate_calculator() {
-        let result = calculate_plates(100.0);
-        assert_eq!(result.barbell_weight_kg, 20.0);
+        let result = calculate_plates(100.0).unwrap();
+        assert_eq!(result.barbell_weight, 20.0);
         // 100 - 20 = 80kg total plates, 40kg per side
         // 40 = 20 + 15 + 5
-        assert_eq!(result.achievable_weight_kg, 100.0);
+        assert_eq!(result.achievable_weight, 100.0);
     }
 
     #[test]
     fn test_plate_calculator_empty_bar() {
-        let result = calculate_plates(20.0);
+        let result = calculate_plates(20.0).unwrap();
         assert!(result.plates_per_side.is_empty());
-        assert_eq!(result.achievable_weight_kg, 20.0);
+        assert_eq!(result.achievable_weight, 20.0);
     }
 
     #[test]
     fn test_plate_calculator_odd_weight() {
-        let result = calculate_plates(23.0);
+        let result = calculate_plates(23.0).unwrap();
         // Can only do 22.5 (bar + 1.25 per side)
-        assert!((result.achievable_weight_kg - 22.5).abs() < 0.01);
+        assert!((result.achievable_weight - 22.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_plate_calculator_out_of_range() {
+        assert!(calculate_plates(-5.0).is_err());
+        assert!(calculate_plates(1500.0).is_err());
+        assert!(calculate_plates(f64::NAN).is_err());
     }
 
     #[test]
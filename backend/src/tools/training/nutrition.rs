@@ -0,0 +1,240 @@
+use crate::tools::metabolism::{katch_mcardle_bmr, ActivityLevel};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// MACRO PLANNER
+// ============================================================================
+
+const KCAL_PER_G_PROTEIN: f64 = 4.0;
+const KCAL_PER_G_CARBS: f64 = 4.0;
+const KCAL_PER_G_FAT: f64 = 9.0;
+
+/// Share of rest-day calories given to fat
+const FAT_ENERGY_SHARE: f64 = 0.25;
+/// Lower bound on fat intake (g per kg body weight) for hormonal health
+const MIN_FAT_G_PER_KG: f64 = 0.6;
+/// Weekly volume (kg lifted) at which protein reaches the top of the goal's range
+const HIGH_WEEKLY_VOLUME_KG: f64 = 40_000.0;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    Cut,
+    Maintain,
+    Bulk,
+}
+
+impl Goal {
+    /// Fractional change applied to maintenance calories
+    #[must_use]
+    pub fn energy_adjustment(self) -> f64 {
+        match self {
+            Self::Cut => -0.20,
+            Self::Maintain => 0.0,
+            Self::Bulk => 0.10,
+        }
+    }
+
+    /// Protein range in g per kg lean mass (Helms et al., 2014 for the deficit)
+    #[must_use]
+    pub fn protein_range_g_per_kg_lean(self) -> (f64, f64) {
+        match self {
+            Self::Cut => (2.3, 3.1),
+            Self::Maintain => (1.8, 2.4),
+            Self::Bulk => (1.8, 2.6),
+        }
+    }
+}
+
+/// Completed training of the planned week
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainingWeek {
+    pub training_days: u32,
+    /// Sum of set energy from `compute_set_energy`
+    pub energy_kcal: f64,
+    /// Sum of weight × reps
+    pub volume_kg: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NutritionInputs {
+    pub lean_mass_kg: f64,
+    pub body_weight_kg: f64,
+    pub goal: Goal,
+    /// Non-exercise activity; training energy is added on top
+    pub activity_level: ActivityLevel,
+    pub week: TrainingWeek,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroTargets {
+    pub kcal: f64,
+    pub protein_g: f64,
+    pub fat_g: f64,
+    pub carbs_g: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NutritionPlan {
+    pub goal: Goal,
+    pub training_day: MacroTargets,
+    pub rest_day: MacroTargets,
+    pub weekly_average_kcal: f64,
+    /// How each number was derived, in calculation order
+    pub explanation: Vec<String>,
+}
+
+fn macros(kcal: f64, protein_g: f64, fat_g: f64) -> MacroTargets {
+    let carbs_g = ((kcal - protein_g * KCAL_PER_G_PROTEIN - fat_g * KCAL_PER_G_FAT)
+        / KCAL_PER_G_CARBS)
+        .max(0.0);
+    MacroTargets { kcal, protein_g, fat_g, carbs_g }
+}
+
+/// Daily protein, fat and carb targets for training and rest days.
+///
+/// Protein and fat stay the same on both day types; the extra energy of a training day goes to
+/// carbohydrates.
+pub fn plan_macros(inputs: &NutritionInputs) -> Result<NutritionPlan, String> {
+    if inputs.lean_mass_kg <= 0.0 || inputs.lean_mass_kg > inputs.body_weight_kg {
+        return Err("lean mass must be positive and not exceed body weight".to_string());
+    }
+    if inputs.week.training_days > 7 {
+        return Err("a week has at most 7 training days".to_string());
+    }
+    let week = &inputs.week;
+    let mut explanation = Vec::new();
+
+    let body_fat = (1.0 - inputs.lean_mass_kg / inputs.body_weight_kg) * 100.0;
+    let bmr = katch_mcardle_bmr(inputs.body_weight_kg, body_fat);
+    let pal = inputs.activity_level.pal();
+    let rest_maintenance = bmr * pal;
+    explanation.push(format!(
+        "Resting metabolic rate {bmr:.0} kcal (Katch-McArdle on {:.1} kg lean mass) × activity \
+         factor {pal} = {rest_maintenance:.0} kcal maintenance on rest days",
+        inputs.lean_mass_kg
+    ));
+
+    let session_kcal = if week.training_days == 0 {
+        0.0
+    } else {
+        week.energy_kcal / f64::from(week.training_days)
+    };
+    let training_maintenance = rest_maintenance + session_kcal;
+    explanation.push(format!(
+        "{:.0} kcal of set energy over {} training days adds {session_kcal:.0} kcal per training day",
+        week.energy_kcal, week.training_days
+    ));
+
+    let factor = 1.0 + inputs.goal.energy_adjustment();
+    let rest_kcal = rest_maintenance * factor;
+    let training_kcal = training_maintenance * factor;
+    explanation.push(format!(
+        "Goal {:?} adjusts maintenance by {:+.0}%",
+        inputs.goal,
+        inputs.goal.energy_adjustment() * 100.0
+    ));
+
+    let (low, high) = inputs.goal.protein_range_g_per_kg_lean();
+    let volume_share = (week.volume_kg / HIGH_WEEKLY_VOLUME_KG).clamp(0.0, 1.0);
+    let protein_per_kg = (high - low).mul_add(volume_share, low);
+    let protein_g = protein_per_kg * inputs.lean_mass_kg;
+    explanation.push(format!(
+        "Protein {protein_per_kg:.2} g/kg lean mass: {:.0} kg weekly volume places it at {:.0}% of \
+         the {low}-{high} g/kg range",
+        week.volume_kg,
+        volume_share * 100.0
+    ));
+
+    let fat_g = (rest_kcal * FAT_ENERGY_SHARE / KCAL_PER_G_FAT)
+        .max(MIN_FAT_G_PER_KG * inputs.body_weight_kg);
+    explanation.push(format!(
+        "Fat {fat_g:.0} g: {:.0}% of rest-day calories, at least {MIN_FAT_G_PER_KG} g/kg body weight",
+        FAT_ENERGY_SHARE * 100.0
+    ));
+    explanation.push(
+        "Carbohydrates fill the remaining calories, so training days get the extra energy as carbs"
+            .to_string(),
+    );
+
+    let rest_days = 7 - week.training_days;
+    let weekly_average_kcal =
+        (training_kcal * f64::from(week.training_days) + rest_kcal * f64::from(rest_days)) / 7.0;
+
+    Ok(NutritionPlan {
+        goal: inputs.goal,
+        training_day: macros(training_kcal, protein_g, fat_g),
+        rest_day: macros(rest_kcal, protein_g, fat_g),
+        weekly_average_kcal,
+        explanation,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(goal: Goal, week: TrainingWeek) -> NutritionInputs {
+        NutritionInputs {
+            lean_mass_kg: 64.0,
+            body_weight_kg: 80.0,
+            goal,
+            activity_level: ActivityLevel::Sedentary,
+            week,
+        }
+    }
+
+    #[test]
+    fn test_rest_week_maintenance() {
+        let plan = plan_macros(&inputs(Goal::Maintain, TrainingWeek::default())).unwrap();
+        // Katch-McArdle 1752.4 × 1.2
+        assert!((plan.rest_day.kcal - 2102.88).abs() < 1e-9);
+        assert!((plan.weekly_average_kcal - plan.rest_day.kcal).abs() < 1e-9);
+        assert!((plan.rest_day.protein_g - 1.8 * 64.0).abs() < 1e-9);
+
+        let r = &plan.rest_day;
+        let total = r.protein_g * 4.0 + r.carbs_g * 4.0 + r.fat_g * 9.0;
+        assert!((total - r.kcal).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_training_days_get_extra_carbs() {
+        let week = TrainingWeek { training_days: 4, energy_kcal: 800.0, volume_kg: 20_000.0 };
+        let plan = plan_macros(&inputs(Goal::Maintain, week)).unwrap();
+
+        assert!((plan.training_day.kcal - plan.rest_day.kcal - 200.0).abs() < 1e-9);
+        assert!((plan.training_day.carbs_g - plan.rest_day.carbs_g - 50.0).abs() < 1e-9);
+        assert_eq!(plan.training_day.protein_g, plan.rest_day.protein_g);
+        assert_eq!(plan.training_day.fat_g, plan.rest_day.fat_g);
+        // Half of the reference volume → middle of the range
+        assert!((plan.rest_day.protein_g - 2.1 * 64.0).abs() < 1e-9);
+        assert!(!plan.explanation.is_empty());
+    }
+
+    #[test]
+    fn test_goals_shift_energy_and_protein() {
+        let week = TrainingWeek { training_days: 3, energy_kcal: 600.0, volume_kg: 15_000.0 };
+        let cut = plan_macros(&inputs(Goal::Cut, week.clone())).unwrap();
+        let maintain = plan_macros(&inputs(Goal::Maintain, week.clone())).unwrap();
+        let bulk = plan_macros(&inputs(Goal::Bulk, week)).unwrap();
+
+        assert!(cut.weekly_average_kcal < maintain.weekly_average_kcal);
+        assert!(bulk.weekly_average_kcal > maintain.weekly_average_kcal);
+        assert!(cut.rest_day.protein_g > maintain.rest_day.protein_g);
+        // Fat floor of 0.6 g/kg applies in the deficit
+        assert!(cut.rest_day.fat_g >= 48.0);
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        let mut bad = inputs(Goal::Cut, TrainingWeek::default());
+        bad.lean_mass_kg = 90.0;
+        assert!(plan_macros(&bad).is_err());
+
+        let too_many = TrainingWeek { training_days: 8, ..TrainingWeek::default() };
+        assert!(plan_macros(&inputs(Goal::Cut, too_many)).is_err());
+    }
+}
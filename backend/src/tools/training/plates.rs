@@ -1,5 +1,7 @@
 #![allow(dead_code)]
+use super::units::WeightUnit;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 // ============================================================================
 // PLATE CALCULATOR
@@ -8,45 +10,413 @@ use serde::{Deserialize, Serialize};
 /// Standard plate weights in kg (per side)
 const STANDARD_PLATES_KG: [f64; 6] = [20.0, 15.0, 10.0, 5.0, 2.5, 1.25];
 const BARBELL_WEIGHT_KG: f64 = 20.0;
+/// Standard plate weights in lb (per side)
+const STANDARD_PLATES_LB: [f64; 6] = [45.0, 35.0, 25.0, 10.0, 5.0, 2.5];
+const BARBELL_WEIGHT_LB: f64 = 45.0;
+/// Loads are compared in grams (thousandths of a pound for lb) so fractional plates add up exactly
+const GRAMS_PER_KG: f64 = 1000.0;
+/// Heaviest load a plate calculation or a planned set may target
+pub const MAX_TARGET_KG: f64 = 1000.0;
+const MAX_PLATE_SIZES: usize = 20;
+const MAX_PAIRS: u32 = 50;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct PlateCalculation {
-    pub total_weight_kg: f64,
-    pub barbell_weight_kg: f64,
-    pub plates_per_side: Vec<PlateCount>,
-    pub achievable_weight_kg: f64,
+pub struct Bar {
+    pub name: String,
+    /// In the profile's unit; `weightKg` is accepted from clients predating units
+    #[serde(alias = "weightKg")]
+    pub weight: f64,
+}
+
+/// A plate size and how many pairs of it there are; None means as many as needed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlateInventory {
+    #[serde(alias = "weightKg")]
+    pub weight: f64,
+    pub pairs: Option<u32>,
 }
 
+/// The bars and plates of one gym, weighed in `unit`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct EquipmentProfile {
+    pub name: String,
+    #[serde(default)]
+    pub unit: WeightUnit,
+    pub bars: Vec<Bar>,
+    pub plates: Vec<PlateInventory>,
+}
+
+impl EquipmentProfile {
+    /// Olympic bar with unlimited standard plates of the unit
+    #[must_use]
+    pub fn standard(unit: WeightUnit) -> Self {
+        let (bar, plates) = match unit {
+            WeightUnit::Kg => (BARBELL_WEIGHT_KG, STANDARD_PLATES_KG),
+            WeightUnit::Lb => (BARBELL_WEIGHT_LB, STANDARD_PLATES_LB),
+        };
+        Self {
+            name: "Standard".to_string(),
+            unit,
+            bars: vec![Bar { name: "Olympic bar".to_string(), weight: bar }],
+            plates: plates.iter().map(|&weight| PlateInventory { weight, pairs: None }).collect(),
+        }
+    }
+}
+
+pub fn validate_profile(profile: &EquipmentProfile) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    if profile.bars.is_empty() {
+        return Err("at least one bar is required".to_string());
+    }
+    for bar in &profile.bars {
+        if bar.name.trim().is_empty() {
+            return Err("bar names must not be empty".to_string());
+        }
+        if !(0.0..=50.0).contains(&profile.unit.to_kg(bar.weight)) {
+            return Err(format!("{}: bar weight must be 0-50 kg", bar.name));
+        }
+    }
+    validate_plates(&profile.plates, profile.unit)
+}
+
+/// Limits are in kg whatever the unit of the plates
+pub fn validate_plates(plates: &[PlateInventory], unit: WeightUnit) -> Result<(), String> {
+    if plates.len() > MAX_PLATE_SIZES {
+        return Err(format!("at most {MAX_PLATE_SIZES} plate sizes"));
+    }
+    for plate in plates {
+        if !(0.1..=50.0).contains(&unit.to_kg(plate.weight)) {
+            return Err("plate weights must be 0.1-50 kg".to_string());
+        }
+        if plate.pairs.is_some_and(|p| p > MAX_PAIRS) {
+            return Err(format!("at most {MAX_PAIRS} pairs per plate size"));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct PlateCount {
+    pub weight: f64,
+    /// `weight` in kg, kept for clients predating units
     pub weight_kg: f64,
     pub count: u32,
 }
 
-/// Calculate plates needed per side to reach target weight on a barbell.
-#[must_use]
-pub fn calculate_plates(target_weight_kg: f64) -> PlateCalculation {
-    let weight_per_side = (target_weight_kg - BARBELL_WEIGHT_KG).max(0.0) / 2.0;
-    let mut remaining = weight_per_side;
-    let mut plates = Vec::new();
+impl PlateCount {
+    #[must_use]
+    pub fn new(weight: f64, count: u32, unit: WeightUnit) -> Self {
+        Self { weight, weight_kg: unit.to_kg(weight), count }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadDirection {
+    Exact,
+    Above,
+    Below,
+}
+
+impl LoadDirection {
+    /// Direction of an achieved load from its difference to the target
+    #[must_use]
+    pub fn of(difference: f64) -> Self {
+        if difference.abs() < 0.5 / GRAMS_PER_KG {
+            Self::Exact
+        } else if difference > 0.0 {
+            Self::Above
+        } else {
+            Self::Below
+        }
+    }
+}
+
+/// One way to load the bar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlateOption {
+    pub achievable_weight: f64,
+    pub achievable_weight_kg: f64,
+    pub plates_per_side: Vec<PlateCount>,
+}
+
+impl PlateOption {
+    #[must_use]
+    pub fn new(achievable_weight: f64, plates_per_side: Vec<PlateCount>, unit: WeightUnit) -> Self {
+        Self {
+            achievable_weight,
+            achievable_weight_kg: unit.to_kg(achievable_weight),
+            plates_per_side,
+        }
+    }
+}
+
+/// Weights are in `unit`; the `_kg` fields repeat them in kg for clients predating units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlateCalculation {
+    pub unit: WeightUnit,
+    pub total_weight: f64,
+    pub total_weight_kg: f64,
+    pub barbell_weight: f64,
+    pub barbell_weight_kg: f64,
+    /// Plates of the closest load, heaviest first
+    pub plates_per_side: Vec<PlateCount>,
+    pub achievable_weight: f64,
+    pub achievable_weight_kg: f64,
+    /// Where the closest load lies relative to the target
+    pub direction: LoadDirection,
+    pub difference: f64,
+    pub difference_kg: f64,
+    /// Heaviest load at or below the target
+    pub nearest_below: Option<PlateOption>,
+    /// Lightest load at or above the target
+    pub nearest_above: Option<PlateOption>,
+}
+
+fn grams(kg: f64) -> usize {
+    (kg * GRAMS_PER_KG).round().max(0.0) as usize
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Fewest plates per reachable per-side load with a bounded number of each size.
+/// Loads are in units of the plates' common divisor, up to `cap` units.
+struct PlateTable {
+    unit_g: usize,
+    sizes: Vec<(usize, u32)>,
+    /// Plates of each size used to reach a load, per size layer
+    choices: Vec<Vec<u16>>,
+    plate_count: Vec<Option<u32>>,
+}
+
+impl PlateTable {
+    fn build(plates: &[PlateInventory], cap_g: usize) -> Self {
+        let mut sizes: Vec<(usize, u32)> = plates
+            .iter()
+            .map(|p| (grams(p.weight), p.pairs.unwrap_or(u32::MAX)))
+            .filter(|&(g, pairs)| g > 0 && pairs > 0)
+            .collect();
+        sizes.sort_by(|a, b| b.0.cmp(&a.0));
+        let unit_g = sizes.iter().fold(0, |acc, &(g, _)| gcd(acc, g)).max(1);
+        let cap = cap_g / unit_g;
+
+        let mut plate_count: Vec<Option<u32>> = vec![None; cap + 1];
+        plate_count[0] = Some(0);
+        let mut choices = Vec::with_capacity(sizes.len());
+        for &(size_g, pairs) in &sizes {
+            let size = size_g / unit_g;
+            let mut next: Vec<Option<u32>> = vec![None; cap + 1];
+            let mut choice = vec![0u16; cap + 1];
+            // Loads with the same remainder form a chain; the best of the last `pairs`
+            // predecessors on the chain is a sliding-window minimum
+            for residue in 0..size.min(cap + 1) {
+                let mut window: VecDeque<(usize, i64)> = VecDeque::new();
+                for (step, load) in (residue..=cap).step_by(size).enumerate() {
+                    if let Some(prev) = plate_count[load] {
+                        let value = i64::from(prev) - step as i64;
+                        while window.back().is_some_and(|&(_, v)| v >= value) {
+                            window.pop_back();
+                        }
+                        window.push_back((step, value));
+                    }
+                    while window.front().is_some_and(|&(s, _)| step - s > pairs as usize) {
+                        window.pop_front();
+                    }
+                    if let Some(&(from, value)) = window.front() {
+                        next[load] = Some((value + step as i64) as u32);
+                        choice[load] = (step - from) as u16;
+                    }
+                }
+            }
+            plate_count = next;
+            choices.push(choice);
+        }
+        Self { unit_g, sizes, choices, plate_count }
+    }
+
+    fn reachable(&self, load: usize) -> bool {
+        self.plate_count.get(load).is_some_and(Option::is_some)
+    }
+
+    /// Plates per side for a reachable load, heaviest first
+    fn plates(&self, mut load: usize, unit: WeightUnit) -> Vec<PlateCount> {
+        let mut plates = Vec::new();
+        for (i, &(size_g, _)) in self.sizes.iter().enumerate().rev() {
+            let count = self.choices[i][load];
+            if count > 0 {
+                plates.push(PlateCount::new(size_g as f64 / GRAMS_PER_KG, u32::from(count), unit));
+                load -= usize::from(count) * size_g / self.unit_g;
+            }
+        }
+        plates.reverse();
+        plates
+    }
+}
+
+/// Closest achievable load with the given bar and plate inventory, all in `unit`.
+/// Ties between a lighter and a heavier load go to the lighter one.
+pub fn solve_plates(
+    target_weight: f64,
+    bar_weight: f64,
+    plates: &[PlateInventory],
+    unit: WeightUnit,
+) -> Result<PlateCalculation, String> {
+    if !(0.0..=MAX_TARGET_KG).contains(&unit.to_kg(target_weight)) {
+        return Err(format!("target weight must be 0-{MAX_TARGET_KG} kg"));
+    }
+    validate_plates(plates, unit)?;
+
+    let per_side_g = grams((target_weight - bar_weight).max(0.0) / 2.0);
+    let largest_g = plates.iter().map(|p| grams(p.weight)).max().unwrap_or(0);
+    let table = PlateTable::build(plates, per_side_g + largest_g);
+    let option = |load: usize| {
+        PlateOption::new(
+            bar_weight + 2.0 * (load * table.unit_g) as f64 / GRAMS_PER_KG,
+            table.plates(load, unit),
+            unit,
+        )
+    };
+
+    let target_units = per_side_g / table.unit_g;
+    let below = (0..=target_units).rev().find(|&l| table.reachable(l)).map(option);
+    let above = if per_side_g.is_multiple_of(table.unit_g) && table.reachable(target_units) {
+        below.clone()
+    } else {
+        (target_units + 1..table.plate_count.len()).find(|&l| table.reachable(l)).map(option)
+    };
 
-    for &plate in &STANDARD_PLATES_KG {
-        let count = (remaining / plate).floor() as u32;
-        if count > 0 {
-            plates.push(PlateCount { weight_kg: plate, count });
-            remaining -= plate * count as f64;
+    let closest = match (&below, &above) {
+        (Some(b), Some(a))
+            if a.achievable_weight - target_weight < target_weight - b.achievable_weight =>
+        {
+            a
         }
+        (Some(b), _) => b,
+        (None, Some(a)) => a,
+        (None, None) => unreachable!("an unloaded bar is always a solution"),
+    };
+    let difference = closest.achievable_weight - target_weight;
+    let direction = LoadDirection::of(difference);
+
+    Ok(PlateCalculation {
+        unit,
+        total_weight: target_weight,
+        total_weight_kg: unit.to_kg(target_weight),
+        barbell_weight: bar_weight,
+        barbell_weight_kg: unit.to_kg(bar_weight),
+        plates_per_side: closest.plates_per_side.clone(),
+        achievable_weight: closest.achievable_weight,
+        achievable_weight_kg: closest.achievable_weight_kg,
+        direction,
+        difference,
+        difference_kg: unit.to_kg(difference),
+        nearest_below: below.filter(|b| b.achievable_weight <= target_weight + 0.5 / GRAMS_PER_KG),
+        nearest_above: above,
+    })
+}
+
+/// Calculate plates per side for a 20 kg bar and unlimited standard plates.
+///
+/// Fails like [`solve_plates`] for targets outside 0-1000 kg.
+pub fn calculate_plates(target_weight_kg: f64) -> Result<PlateCalculation, String> {
+    let standard = EquipmentProfile::standard(WeightUnit::Kg);
+    solve_plates(target_weight_kg, BARBELL_WEIGHT_KG, &standard.plates, WeightUnit::Kg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plates(sizes: &[(f64, Option<u32>)]) -> Vec<PlateInventory> {
+        sizes.iter().map(|&(weight, pairs)| PlateInventory { weight, pairs }).collect()
+    }
+
+    #[test]
+    fn test_limited_inventory() {
+        // One pair of 20s and two of 10s: 100 kg needs 40 per side, 20 + 10 + 10 is all there is
+        let inventory = plates(&[(20.0, Some(1)), (10.0, Some(2))]);
+        let result = solve_plates(100.0, 20.0, &inventory, WeightUnit::Kg).unwrap();
+        assert_eq!(result.achievable_weight, 100.0);
+        assert_eq!(result.direction, LoadDirection::Exact);
+        assert_eq!(
+            result.plates_per_side,
+            vec![
+                PlateCount::new(20.0, 1, WeightUnit::Kg),
+                PlateCount::new(10.0, 2, WeightUnit::Kg)
+            ]
+        );
+
+        // Beyond the inventory only lighter loads remain
+        let result = solve_plates(140.0, 20.0, &inventory, WeightUnit::Kg).unwrap();
+        assert_eq!(result.achievable_weight, 100.0);
+        assert_eq!(result.direction, LoadDirection::Below);
+        assert!(result.nearest_above.is_none());
     }
 
-    let loaded_per_side: f64 = plates.iter().map(|p| p.weight_kg * p.count as f64).sum();
-    let achievable = BARBELL_WEIGHT_KG + loaded_per_side * 2.0;
+    #[test]
+    fn test_closest_load_above_or_below() {
+        // 15 kg bar with 5s and 2.5s: 31 kg sits between 30 and 35
+        let inventory = plates(&[(5.0, None), (2.5, None)]);
+        let result = solve_plates(31.0, 15.0, &inventory, WeightUnit::Kg).unwrap();
+        assert_eq!(result.achievable_weight, 30.0);
+        assert_eq!(result.direction, LoadDirection::Below);
+        assert_eq!(result.nearest_above.unwrap().achievable_weight, 35.0);
+
+        let result = solve_plates(34.0, 15.0, &inventory, WeightUnit::Kg).unwrap();
+        assert_eq!(result.achievable_weight, 35.0);
+        assert_eq!(result.direction, LoadDirection::Above);
+        assert!((result.difference - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fewest_plates_and_fractional_sizes() {
+        // 10 kg EZ bar, 0.5 kg fractional plates: 21 kg is 5 + 0.5 per side
+        let inventory = plates(&[(5.0, None), (2.5, None), (1.25, None), (0.5, Some(2))]);
+        let result = solve_plates(21.0, 10.0, &inventory, WeightUnit::Kg).unwrap();
+        assert_eq!(result.direction, LoadDirection::Exact);
+        assert_eq!(
+            result.plates_per_side,
+            vec![PlateCount::new(5.0, 1, WeightUnit::Kg), PlateCount::new(0.5, 1, WeightUnit::Kg)]
+        );
+    }
+
+    #[test]
+    fn test_pound_plates() {
+        // 225 lb is two 45s a side on a 45 lb bar; 227 lb is nearer 225 than 230
+        let standard = EquipmentProfile::standard(WeightUnit::Lb);
+        let result = solve_plates(225.0, 45.0, &standard.plates, WeightUnit::Lb).unwrap();
+        assert_eq!(result.direction, LoadDirection::Exact);
+        assert_eq!(result.plates_per_side, vec![PlateCount::new(45.0, 2, WeightUnit::Lb)]);
+        let result = solve_plates(227.0, 45.0, &standard.plates, WeightUnit::Lb).unwrap();
+        assert_eq!(result.achievable_weight, 225.0);
+        assert_eq!(result.nearest_above.unwrap().achievable_weight, 230.0);
+        // 120 lb plates are over the 50 kg limit
+        assert!(solve_plates(300.0, 45.0, &plates(&[(120.0, None)]), WeightUnit::Lb).is_err());
+    }
 
-    PlateCalculation {
-        total_weight_kg: target_weight_kg,
-        barbell_weight_kg: BARBELL_WEIGHT_KG,
-        plates_per_side: plates,
-        achievable_weight_kg: achievable,
+    #[test]
+    fn test_profile_validation() {
+        let mut profile = EquipmentProfile::standard(WeightUnit::Kg);
+        assert!(validate_profile(&profile).is_ok());
+        profile.bars.clear();
+        assert!(validate_profile(&profile).is_err());
+        assert!(solve_plates(50.0, 20.0, &plates(&[(0.0, None)]), WeightUnit::Kg).is_err());
+        assert!(solve_plates(-5.0, 20.0, &[], WeightUnit::Kg).is_err());
+        // No plates at all: just the bar
+        assert_eq!(solve_plates(50.0, 20.0, &[], WeightUnit::Kg).unwrap().achievable_weight, 20.0);
     }
 }
@@ -0,0 +1,369 @@
+use super::progression::TargetSet;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ============================================================================
+// TRAINING PROGRAMS (MESOCYCLES)
+// ============================================================================
+
+const MAX_PROGRAM_WEEKS: u32 = 52;
+/// Weekly ramp cap; the stored columns are DECIMAL(5,2)
+const MAX_RAMP_PERCENTAGE: f64 = 200.0;
+
+fn default_deload_volume_percentage() -> f64 {
+    50.0
+}
+
+fn default_deload_intensity_percentage() -> f64 {
+    90.0
+}
+
+/// Plans trained on one weekday; with several plans they rotate week by week
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleDay {
+    /// ISO weekday, Monday = 1
+    pub day_of_week: u32,
+    pub plan_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramDefinition {
+    pub start_date: NaiveDate,
+    pub weeks: u32,
+    /// 1-based week trained at reduced volume and intensity
+    pub deload_week: Option<u32>,
+    /// Sets added per week, as a percentage of week 1
+    #[serde(default)]
+    pub volume_ramp_percentage: f64,
+    /// Load added per week, as a percentage of week 1
+    #[serde(default)]
+    pub intensity_ramp_percentage: f64,
+    #[serde(default = "default_deload_volume_percentage")]
+    pub deload_volume_percentage: f64,
+    #[serde(default = "default_deload_intensity_percentage")]
+    pub deload_intensity_percentage: f64,
+    pub schedule: Vec<ScheduleDay>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekModifiers {
+    pub week: u32,
+    pub is_deload: bool,
+    /// Multiplier for the number of sets
+    pub volume_multiplier: f64,
+    /// Multiplier for the target weights
+    pub intensity_multiplier: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledWorkout {
+    pub date: NaiveDate,
+    pub plan_id: Uuid,
+    pub modifiers: WeekModifiers,
+}
+
+/// A completed session counted towards adherence
+#[derive(Debug, Clone)]
+pub struct CompletedWorkout {
+    pub date: NaiveDate,
+    pub plan_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekAdherence {
+    pub week: u32,
+    /// Workouts scheduled up to and including today
+    pub scheduled: u32,
+    pub completed: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Adherence {
+    pub scheduled: u32,
+    pub completed: u32,
+    pub missed: u32,
+    pub upcoming: u32,
+    /// None before the first scheduled workout
+    pub adherence_percentage: Option<f64>,
+    pub weeks: Vec<WeekAdherence>,
+}
+
+pub fn validate_program(def: &ProgramDefinition) -> Result<(), String> {
+    if def.weeks == 0 || def.weeks > MAX_PROGRAM_WEEKS {
+        return Err(format!("weeks must be between 1 and {MAX_PROGRAM_WEEKS}"));
+    }
+    if def.deload_week.is_some_and(|w| w == 0 || w > def.weeks) {
+        return Err("deloadWeek must be within the program".to_string());
+    }
+    let deload = |pct: f64| pct > 0.0 && pct <= 100.0;
+    if !deload(def.deload_volume_percentage) || !deload(def.deload_intensity_percentage) {
+        return Err("deload percentages must be above 0 and at most 100".to_string());
+    }
+    if !(0.0..=MAX_RAMP_PERCENTAGE).contains(&def.volume_ramp_percentage)
+        || !(0.0..=MAX_RAMP_PERCENTAGE).contains(&def.intensity_ramp_percentage)
+    {
+        return Err(format!("ramps must be between 0 and {MAX_RAMP_PERCENTAGE}"));
+    }
+    if def.schedule.is_empty() {
+        return Err("schedule needs at least one day".to_string());
+    }
+    for day in &def.schedule {
+        if !(1..=7).contains(&day.day_of_week) {
+            return Err("dayOfWeek must be between 1 (Monday) and 7 (Sunday)".to_string());
+        }
+        if day.plan_ids.is_empty() {
+            return Err("every scheduled day needs a plan".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// 1-based program week of `date`, or None outside the program
+#[must_use]
+pub fn program_week(def: &ProgramDefinition, date: NaiveDate) -> Option<u32> {
+    let days = (date - def.start_date).num_days();
+    let week = u32::try_from(days.div_euclid(7) + 1).ok()?;
+    (days >= 0 && week <= def.weeks).then_some(week)
+}
+
+/// Volume and intensity of a week. Ramps count loading weeks only, so the week after a deload
+/// continues where the one before it stopped.
+#[must_use]
+pub fn week_modifiers(def: &ProgramDefinition, week: u32) -> WeekModifiers {
+    if def.deload_week == Some(week) {
+        return WeekModifiers {
+            week,
+            is_deload: true,
+            volume_multiplier: def.deload_volume_percentage / 100.0,
+            intensity_multiplier: def.deload_intensity_percentage / 100.0,
+        };
+    }
+    let deloads_before = u32::from(def.deload_week.is_some_and(|d| d < week));
+    let step = f64::from(week.saturating_sub(1 + deloads_before));
+    WeekModifiers {
+        week,
+        is_deload: false,
+        volume_multiplier: 1.0 + step * def.volume_ramp_percentage / 100.0,
+        intensity_multiplier: 1.0 + step * def.intensity_ramp_percentage / 100.0,
+    }
+}
+
+/// Workouts scheduled on `date`
+#[must_use]
+pub fn scheduled_on(def: &ProgramDefinition, date: NaiveDate) -> Vec<ScheduledWorkout> {
+    let Some(week) = program_week(def, date) else {
+        return Vec::new();
+    };
+    let modifiers = week_modifiers(def, week);
+    def.schedule
+        .iter()
+        .filter(|d| d.day_of_week == date.weekday().number_from_monday())
+        .filter_map(|d| {
+            let plan_id = *d.plan_ids.get((week as usize - 1) % d.plan_ids.len())?;
+            Some(ScheduledWorkout { date, plan_id, modifiers })
+        })
+        .collect()
+}
+
+/// Every scheduled workout of the program, in date order
+#[must_use]
+pub fn full_schedule(def: &ProgramDefinition) -> Vec<ScheduledWorkout> {
+    (0..i64::from(def.weeks) * 7)
+        .flat_map(|offset| scheduled_on(def, def.start_date + Duration::days(offset)))
+        .collect()
+}
+
+/// Apply a week's ramps to a plan exercise's sets. Extra sets repeat the last prescribed set.
+#[must_use]
+pub fn apply_week(
+    sets: &[TargetSet],
+    modifiers: &WeekModifiers,
+    rounding_kg: f64,
+) -> Vec<TargetSet> {
+    let Some(last) = sets.last() else {
+        return Vec::new();
+    };
+    let count = ((sets.len() as f64) * modifiers.volume_multiplier).round().max(1.0) as usize;
+    (0..count)
+        .map(|i| {
+            let set = sets.get(i).unwrap_or(last);
+            let weight_kg = set.weight_kg.map(|w| {
+                let scaled = w * modifiers.intensity_multiplier;
+                if rounding_kg > 0.0 {
+                    (scaled / rounding_kg).round() * rounding_kg
+                } else {
+                    scaled
+                }
+            });
+            TargetSet { set_number: i as u32 + 1, weight_kg, ..set.clone() }
+        })
+        .collect()
+}
+
+/// Scheduled workouts matched with completed sessions of the same plan in the same week
+#[must_use]
+pub fn adherence(
+    def: &ProgramDefinition,
+    completed: &[CompletedWorkout],
+    today: NaiveDate,
+) -> Adherence {
+    let schedule = full_schedule(def);
+    let mut weeks: Vec<WeekAdherence> = Vec::new();
+    let mut upcoming = 0;
+    for week in 1..=def.weeks {
+        let due: Vec<&ScheduledWorkout> =
+            schedule.iter().filter(|s| s.modifiers.week == week).collect();
+        upcoming += due.iter().filter(|s| s.date > today).count() as u32;
+        let due: Vec<&&ScheduledWorkout> = due.iter().filter(|s| s.date <= today).collect();
+        if due.is_empty() {
+            continue;
+        }
+        let mut sessions: Vec<Option<Uuid>> = completed
+            .iter()
+            .filter(|c| program_week(def, c.date) == Some(week) && c.date <= today)
+            .map(|c| c.plan_id)
+            .collect();
+        let mut done = 0;
+        for workout in &due {
+            if let Some(i) = sessions.iter().position(|p| *p == Some(workout.plan_id)) {
+                sessions.swap_remove(i);
+                done += 1;
+            }
+        }
+        weeks.push(WeekAdherence { week, scheduled: due.len() as u32, completed: done });
+    }
+    let scheduled: u32 = weeks.iter().map(|w| w.scheduled).sum();
+    let completed: u32 = weeks.iter().map(|w| w.completed).sum();
+    Adherence {
+        scheduled,
+        completed,
+        missed: scheduled - completed,
+        upcoming,
+        adherence_percentage: (scheduled > 0)
+            .then(|| f64::from(completed) / f64::from(scheduled) * 100.0),
+        weeks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        // 2024-01-01 is a Monday
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    fn program() -> ProgramDefinition {
+        ProgramDefinition {
+            start_date: date(1),
+            weeks: 4,
+            deload_week: Some(4),
+            volume_ramp_percentage: 10.0,
+            intensity_ramp_percentage: 2.5,
+            deload_volume_percentage: 50.0,
+            deload_intensity_percentage: 90.0,
+            schedule: vec![
+                ScheduleDay {
+                    day_of_week: 1,
+                    plan_ids: vec![Uuid::from_u128(1), Uuid::from_u128(2)],
+                },
+                ScheduleDay { day_of_week: 4, plan_ids: vec![Uuid::from_u128(3)] },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_weekly_rotation() {
+        let def = program();
+        assert!(validate_program(&def).is_ok());
+        assert_eq!(scheduled_on(&def, date(1))[0].plan_id, Uuid::from_u128(1));
+        assert_eq!(scheduled_on(&def, date(8))[0].plan_id, Uuid::from_u128(2));
+        assert_eq!(scheduled_on(&def, date(15))[0].plan_id, Uuid::from_u128(1));
+        assert_eq!(scheduled_on(&def, date(4))[0].plan_id, Uuid::from_u128(3));
+        assert!(scheduled_on(&def, date(2)).is_empty());
+        // Past the fourth week
+        assert!(scheduled_on(&def, date(29)).is_empty());
+        assert_eq!(full_schedule(&def).len(), 8);
+    }
+
+    #[test]
+    fn test_ramps_and_deload() {
+        let def = program();
+        let week3 = week_modifiers(&def, 3);
+        assert!((week3.volume_multiplier - 1.2).abs() < 1e-9);
+        assert!((week3.intensity_multiplier - 1.05).abs() < 1e-9);
+        let deload = week_modifiers(&def, 4);
+        assert!(deload.is_deload);
+        assert_eq!(deload.volume_multiplier, 0.5);
+
+        let sets: Vec<TargetSet> = (1..=4)
+            .map(|set_number| TargetSet {
+                set_number,
+                weight_kg: Some(100.0),
+                reps: 8,
+                rpe: None,
+                is_amrap: false,
+            })
+            .collect();
+        let ramped = apply_week(&sets, &week3, 2.5);
+        // 4 × 1.2 = 4.8 → 5 sets at 105 kg
+        assert_eq!(ramped.len(), 5);
+        assert_eq!(ramped[4].set_number, 5);
+        assert_eq!(ramped[4].weight_kg, Some(105.0));
+        let deloaded = apply_week(&sets, &deload, 2.5);
+        assert_eq!(deloaded.len(), 2);
+        assert_eq!(deloaded[0].weight_kg, Some(90.0));
+    }
+
+    #[test]
+    fn test_adherence() {
+        let def = program();
+        let completed = vec![
+            CompletedWorkout { date: date(1), plan_id: Some(Uuid::from_u128(1)) },
+            // Thursday's plan done a day late still counts for the week
+            CompletedWorkout { date: date(5), plan_id: Some(Uuid::from_u128(3)) },
+            // Wrong plan in week 2
+            CompletedWorkout { date: date(8), plan_id: Some(Uuid::from_u128(1)) },
+        ];
+        let result = adherence(&def, &completed, date(10));
+        assert_eq!((result.scheduled, result.completed, result.missed), (3, 2, 1));
+        assert_eq!(result.upcoming, 5);
+        assert!((result.adherence_percentage.unwrap() - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(result.weeks.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_programs() {
+        let mut def = program();
+        def.deload_week = Some(5);
+        assert!(validate_program(&def).is_err());
+
+        let mut def = program();
+        def.schedule[0].day_of_week = 0;
+        assert!(validate_program(&def).is_err());
+
+        let mut def = program();
+        def.schedule[1].plan_ids.clear();
+        assert!(validate_program(&def).is_err());
+
+        let mut def = program();
+        def.volume_ramp_percentage = 1000.0;
+        assert!(validate_program(&def).is_err());
+
+        let mut def = program();
+        def.intensity_ramp_percentage = f64::NAN;
+        assert!(validate_program(&def).is_err());
+
+        let mut def = program();
+        def.deload_volume_percentage = 0.0;
+        assert!(validate_program(&def).is_err());
+    }
+}
@@ -0,0 +1,584 @@
+use super::compute::estimate_1rm;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// PROGRESSIVE OVERLOAD
+// ============================================================================
+
+/// Share of the estimated 1RM used as the default 5/3/1 training max
+const DEFAULT_TRAINING_MAX_FRACTION: f64 = 0.9;
+const DEFAULT_TARGET_RPE: f64 = 8.0;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressionScheme {
+    /// The plan's targets are repeated unchanged
+    #[default]
+    Fixed,
+    /// Add weight after every successful session
+    Linear,
+    /// Add reps up to the top of a range, then add weight and start at the bottom again
+    DoubleProgression,
+    /// Adjust the load so the top set lands on the target RPE
+    Rpe,
+    /// Percentages of a training max cycling through waves, e.g. 5/3/1
+    PercentageWave,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveSet {
+    /// Percentage of the training max
+    pub percentage: f64,
+    pub reps: u32,
+    /// As many reps as possible, with `reps` as the minimum
+    #[serde(default)]
+    pub amrap: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveWeek {
+    pub sets: Vec<WaveSet>,
+    #[serde(default)]
+    pub deload: bool,
+}
+
+/// Scheme parameters stored as JSON on the plan exercise; every field has a default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProgressionConfig {
+    /// Weight added after a successful session, or after a completed wave cycle
+    pub increment_kg: f64,
+    /// Targets are rounded to this load step
+    pub rounding_kg: f64,
+    /// Top of the double-progression rep range; the plan's target reps are the bottom
+    pub rep_range_max: Option<u32>,
+    /// Consecutive failed sessions before a deload
+    pub deload_after_failures: u32,
+    pub deload_percentage: f64,
+    /// Load change per RPE point between the last top set and the target
+    pub percent_per_rpe: f64,
+    /// Starting training max for waves; defaults to 90% of the plan's estimated 1RM
+    pub training_max_kg: Option<f64>,
+    pub wave: Vec<WaveWeek>,
+}
+
+impl Default for ProgressionConfig {
+    fn default() -> Self {
+        Self {
+            increment_kg: 2.5,
+            rounding_kg: 2.5,
+            rep_range_max: None,
+            deload_after_failures: 3,
+            deload_percentage: 10.0,
+            percent_per_rpe: 4.0,
+            training_max_kg: None,
+            wave: five_three_one(),
+        }
+    }
+}
+
+impl ProgressionConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let finite =
+            [self.increment_kg, self.rounding_kg, self.deload_percentage, self.percent_per_rpe]
+                .into_iter()
+                .chain(self.training_max_kg)
+                .chain(self.wave.iter().flat_map(|w| w.sets.iter().map(|s| s.percentage)))
+                .all(f64::is_finite);
+        if !finite {
+            return Err("progression settings must be finite numbers".to_string());
+        }
+        if self.increment_kg < 0.0 {
+            return Err("incrementKg must not be negative".to_string());
+        }
+        if self.rounding_kg <= 0.0 {
+            return Err("roundingKg must be positive".to_string());
+        }
+        if self.deload_percentage <= 0.0 || self.deload_percentage > 100.0 {
+            return Err("deloadPercentage must be above 0 and at most 100".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn wave_week(sets: [(f64, u32); 3], amrap_last: bool, deload: bool) -> WaveWeek {
+    WaveWeek {
+        sets: sets
+            .iter()
+            .enumerate()
+            .map(|(i, &(percentage, reps))| WaveSet {
+                percentage,
+                reps,
+                amrap: amrap_last && i == sets.len() - 1,
+            })
+            .collect(),
+        deload,
+    }
+}
+
+/// Wendler's 5/3/1: three loading weeks and a deload week
+#[must_use]
+pub fn five_three_one() -> Vec<WaveWeek> {
+    vec![
+        wave_week([(65.0, 5), (75.0, 5), (85.0, 5)], true, false),
+        wave_week([(70.0, 3), (80.0, 3), (90.0, 3)], true, false),
+        wave_week([(75.0, 5), (85.0, 3), (95.0, 1)], true, false),
+        wave_week([(40.0, 5), (50.0, 5), (60.0, 5)], false, true),
+    ]
+}
+
+/// The fixed targets stored on the plan exercise
+#[derive(Debug, Clone)]
+pub struct BaseTarget {
+    pub sets: u32,
+    pub reps: u32,
+    pub weight_kg: Option<f64>,
+    pub rpe: Option<f64>,
+}
+
+/// A working set logged in an earlier session
+#[derive(Debug, Clone)]
+pub struct PerformedSet {
+    pub weight_kg: f64,
+    pub reps: u32,
+    pub rpe: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetSet {
+    pub set_number: u32,
+    /// None until the user picks a starting weight
+    pub weight_kg: Option<f64>,
+    pub reps: u32,
+    pub rpe: Option<f64>,
+    pub is_amrap: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NextTarget {
+    pub scheme: ProgressionScheme,
+    pub sets: Vec<TargetSet>,
+    pub is_deload: bool,
+    /// How the prescription was derived
+    pub explanation: Vec<String>,
+}
+
+fn round_to(weight_kg: f64, step_kg: f64) -> f64 {
+    if step_kg > 0.0 {
+        (weight_kg / step_kg).round() * step_kg
+    } else {
+        weight_kg
+    }
+}
+
+fn top_weight(session: &[PerformedSet]) -> f64 {
+    session.iter().map(|s| s.weight_kg).fold(0.0, f64::max)
+}
+
+/// Whether at least `sets` sets reached `reps` at `weight_kg` or more
+fn completed(session: &[PerformedSet], sets: u32, reps: u32, weight_kg: f64) -> bool {
+    let done = session.iter().filter(|s| s.weight_kg >= weight_kg - 1e-6 && s.reps >= reps).count();
+    done >= sets as usize
+}
+
+/// Number of failed sessions at the end of the history
+fn trailing_failures(
+    history: &[Vec<PerformedSet>],
+    failed: impl Fn(&[PerformedSet]) -> bool,
+) -> u32 {
+    history.iter().rev().take_while(|s| failed(s)).count() as u32
+}
+
+fn straight_sets(
+    base: &BaseTarget,
+    weight_kg: Option<f64>,
+    reps: u32,
+    rpe: Option<f64>,
+) -> Vec<TargetSet> {
+    (1..=base.sets.max(1))
+        .map(|set_number| TargetSet { set_number, weight_kg, reps, rpe, is_amrap: false })
+        .collect()
+}
+
+/// Repeat the weight after a failure, or deload once every `deload_after_failures` failures
+fn after_failure(
+    weight_kg: f64,
+    failures: u32,
+    config: &ProgressionConfig,
+    explanation: &mut Vec<String>,
+) -> (f64, bool) {
+    let every = config.deload_after_failures.max(1);
+    if failures.is_multiple_of(every) {
+        let deloaded =
+            round_to(weight_kg * (1.0 - config.deload_percentage / 100.0), config.rounding_kg);
+        explanation.push(format!(
+            "{failures} failed sessions in a row: deload {}% to {deloaded} kg",
+            config.deload_percentage
+        ));
+        (deloaded, true)
+    } else {
+        explanation.push(format!("Missed the targets at {weight_kg} kg: repeat the weight"));
+        (weight_kg, false)
+    }
+}
+
+fn no_history(base: &BaseTarget, scheme: ProgressionScheme, rpe: Option<f64>) -> NextTarget {
+    NextTarget {
+        scheme,
+        sets: straight_sets(base, base.weight_kg, base.reps, rpe),
+        is_deload: false,
+        explanation: vec!["No logged sessions yet: start from the plan's targets".to_string()],
+    }
+}
+
+/// Prescription for the next session.
+///
+/// `history` holds the working sets of earlier sessions of this exercise, oldest first. A
+/// session fails when fewer than the target number of sets reach the target reps at its top
+/// weight; waves instead check the reps of the heaviest prescribed set.
+pub fn next_target(
+    scheme: ProgressionScheme,
+    config: &ProgressionConfig,
+    base: &BaseTarget,
+    history: &[Vec<PerformedSet>],
+) -> Result<NextTarget, String> {
+    config.validate()?;
+    let history: Vec<Vec<PerformedSet>> =
+        history.iter().filter(|s| !s.is_empty()).cloned().collect();
+    match scheme {
+        ProgressionScheme::Fixed => Ok(NextTarget {
+            scheme,
+            sets: straight_sets(base, base.weight_kg, base.reps, base.rpe),
+            is_deload: false,
+            explanation: vec!["Fixed targets".to_string()],
+        }),
+        ProgressionScheme::Linear => Ok(linear(config, base, &history)),
+        ProgressionScheme::DoubleProgression => double_progression(config, base, &history),
+        ProgressionScheme::Rpe => Ok(rpe_based(config, base, &history)),
+        ProgressionScheme::PercentageWave => percentage_wave(config, base, &history),
+    }
+}
+
+fn linear(
+    config: &ProgressionConfig,
+    base: &BaseTarget,
+    history: &[Vec<PerformedSet>],
+) -> NextTarget {
+    let scheme = ProgressionScheme::Linear;
+    let Some(last) = history.last() else {
+        return no_history(base, scheme, base.rpe);
+    };
+    let failed = |s: &[PerformedSet]| !completed(s, base.sets, base.reps, top_weight(s));
+    let weight = top_weight(last);
+    let mut explanation = Vec::new();
+    let (next, is_deload) = if failed(last) {
+        after_failure(weight, trailing_failures(history, failed), config, &mut explanation)
+    } else {
+        let next = round_to(weight + config.increment_kg, config.rounding_kg);
+        explanation.push(format!(
+            "Completed {}×{} at {weight} kg: add {} kg",
+            base.sets, base.reps, config.increment_kg
+        ));
+        (next, false)
+    };
+    NextTarget {
+        scheme,
+        sets: straight_sets(base, Some(next), base.reps, base.rpe),
+        is_deload,
+        explanation,
+    }
+}
+
+fn double_progression(
+    config: &ProgressionConfig,
+    base: &BaseTarget,
+    history: &[Vec<PerformedSet>],
+) -> Result<NextTarget, String> {
+    let scheme = ProgressionScheme::DoubleProgression;
+    let (min, max) = (base.reps, config.rep_range_max.unwrap_or(base.reps + 4));
+    if max < min {
+        return Err("repRangeMax must not be below the target reps".to_string());
+    }
+    let Some(last) = history.last() else {
+        return Ok(no_history(base, scheme, base.rpe));
+    };
+    let failed = |s: &[PerformedSet]| !completed(s, base.sets, min, top_weight(s));
+    let weight = top_weight(last);
+    let mut explanation = Vec::new();
+    let (next, reps, is_deload) = if completed(last, base.sets, max, weight) {
+        explanation.push(format!(
+            "All {} sets reached {max} reps at {weight} kg: add {} kg and restart at {min} reps",
+            base.sets, config.increment_kg
+        ));
+        (round_to(weight + config.increment_kg, config.rounding_kg), min, false)
+    } else if failed(last) {
+        let (next, is_deload) =
+            after_failure(weight, trailing_failures(history, failed), config, &mut explanation);
+        (next, min, is_deload)
+    } else {
+        let lowest = last
+            .iter()
+            .filter(|s| s.weight_kg >= weight - 1e-6)
+            .map(|s| s.reps)
+            .min()
+            .unwrap_or(min);
+        let reps = (lowest + 1).clamp(min, max);
+        explanation
+            .push(format!("Within the {min}-{max} rep range at {weight} kg: aim for {reps} reps"));
+        (weight, reps, false)
+    };
+    Ok(NextTarget {
+        scheme,
+        sets: straight_sets(base, Some(next), reps, base.rpe),
+        is_deload,
+        explanation,
+    })
+}
+
+fn rpe_based(
+    config: &ProgressionConfig,
+    base: &BaseTarget,
+    history: &[Vec<PerformedSet>],
+) -> NextTarget {
+    let scheme = ProgressionScheme::Rpe;
+    let target_rpe = base.rpe.unwrap_or(DEFAULT_TARGET_RPE);
+    let Some(last) = history.last() else {
+        return no_history(base, scheme, Some(target_rpe));
+    };
+    let failed = |s: &[PerformedSet]| !completed(s, base.sets, base.reps, top_weight(s));
+    let weight = top_weight(last);
+    let mut explanation = Vec::new();
+    let (next, is_deload) = if failed(last) {
+        after_failure(weight, trailing_failures(history, failed), config, &mut explanation)
+    } else if let Some(rpe) =
+        last.iter().rev().filter(|s| s.weight_kg >= weight - 1e-6).find_map(|s| s.rpe)
+    {
+        let change = config.percent_per_rpe * (target_rpe - rpe);
+        let next = round_to(weight * (1.0 + change / 100.0), config.rounding_kg);
+        explanation.push(format!(
+            "Top set at RPE {rpe} against a target of {target_rpe}: change the load {change:+.1}% to {next} kg"
+        ));
+        (next, false)
+    } else {
+        explanation.push(format!("No RPE logged at {weight} kg: repeat the weight"));
+        (weight, false)
+    };
+    NextTarget {
+        scheme,
+        sets: straight_sets(base, Some(next), base.reps, Some(target_rpe)),
+        is_deload,
+        explanation,
+    }
+}
+
+fn percentage_wave(
+    config: &ProgressionConfig,
+    base: &BaseTarget,
+    history: &[Vec<PerformedSet>],
+) -> Result<NextTarget, String> {
+    let scheme = ProgressionScheme::PercentageWave;
+    if config.wave.is_empty() || config.wave.iter().any(|w| w.sets.is_empty()) {
+        return Err("wave needs at least one week with sets".to_string());
+    }
+    let mut explanation = Vec::new();
+    let mut training_max = match config.training_max_kg {
+        Some(tm) => tm,
+        None => {
+            let e1rm = base.weight_kg.and_then(|w| estimate_1rm(w, base.reps)).or_else(|| {
+                history.first().and_then(|s| {
+                    s.iter().filter_map(|p| estimate_1rm(p.weight_kg, p.reps)).reduce(f64::max)
+                })
+            });
+            let Some(e1rm) = e1rm else {
+                return Err("set trainingMaxKg or a target weight to start a wave".to_string());
+            };
+            let tm = e1rm * DEFAULT_TRAINING_MAX_FRACTION;
+            explanation
+                .push(format!("Training max {tm:.1} kg: 90% of an estimated 1RM of {e1rm:.1} kg"));
+            tm
+        }
+    };
+
+    // Replay earlier cycles: a completed cycle raises the training max, a missed top set resets it
+    let weeks = config.wave.len();
+    let top_set = |week: &WaveWeek, tm: f64| {
+        week.sets
+            .iter()
+            .map(|s| (round_to(tm * s.percentage / 100.0, config.rounding_kg), s.reps))
+            .fold((0.0, 0), |best, set| if set.0 > best.0 { set } else { best })
+    };
+    for cycle in history.chunks(weeks).filter(|c| c.len() == weeks) {
+        let missed = cycle.iter().zip(&config.wave).any(|(session, week)| {
+            let (weight, reps) = top_set(week, training_max);
+            !week.deload && !completed(session, 1, reps, weight)
+        });
+        if missed {
+            training_max *= 1.0 - config.deload_percentage / 100.0;
+            explanation.push(format!(
+                "Missed a top set: training max reset by {}% to {training_max:.1} kg",
+                config.deload_percentage
+            ));
+        } else {
+            training_max += config.increment_kg;
+            explanation.push(format!(
+                "Completed a cycle: training max +{} kg to {training_max:.1} kg",
+                config.increment_kg
+            ));
+        }
+    }
+
+    let week_index = history.len() % weeks;
+    let week = &config.wave[week_index];
+    explanation.push(format!("Week {} of {weeks}", week_index + 1));
+    let sets = week
+        .sets
+        .iter()
+        .zip(1..)
+        .map(|(s, set_number)| TargetSet {
+            set_number,
+            weight_kg: Some(round_to(training_max * s.percentage / 100.0, config.rounding_kg)),
+            reps: s.reps,
+            rpe: None,
+            is_amrap: s.amrap,
+        })
+        .collect();
+    Ok(NextTarget { scheme, sets, is_deload: week.deload, explanation })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> BaseTarget {
+        BaseTarget { sets: 3, reps: 5, weight_kg: Some(100.0), rpe: None }
+    }
+
+    fn session(weight_kg: f64, reps: &[u32]) -> Vec<PerformedSet> {
+        reps.iter().map(|&reps| PerformedSet { weight_kg, reps, rpe: None }).collect()
+    }
+
+    fn weight(target: &NextTarget) -> f64 {
+        target.sets[0].weight_kg.unwrap()
+    }
+
+    #[test]
+    fn test_fixed_and_empty_history_use_plan_targets() {
+        let config = ProgressionConfig::default();
+        for scheme in [ProgressionScheme::Fixed, ProgressionScheme::Linear] {
+            let next = next_target(scheme, &config, &base(), &[]).unwrap();
+            assert_eq!(next.sets.len(), 3);
+            assert_eq!(weight(&next), 100.0);
+            assert_eq!(next.sets[0].reps, 5);
+        }
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        let configs = [
+            ProgressionConfig { deload_percentage: 0.0, ..Default::default() },
+            ProgressionConfig { deload_percentage: 120.0, ..Default::default() },
+            ProgressionConfig { increment_kg: -2.5, ..Default::default() },
+            ProgressionConfig { rounding_kg: 0.0, ..Default::default() },
+            ProgressionConfig { increment_kg: f64::NAN, ..Default::default() },
+            ProgressionConfig { training_max_kg: Some(f64::INFINITY), ..Default::default() },
+        ];
+        for config in configs {
+            assert!(config.validate().is_err());
+            assert!(next_target(ProgressionScheme::Fixed, &config, &base(), &[]).is_err());
+        }
+        assert!(ProgressionConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_linear_progression_and_deload() {
+        let config = ProgressionConfig::default();
+        let success = vec![session(100.0, &[5, 5, 5])];
+        let next = next_target(ProgressionScheme::Linear, &config, &base(), &success).unwrap();
+        assert_eq!(weight(&next), 102.5);
+
+        let mut history = vec![session(100.0, &[5, 5, 5]), session(102.5, &[5, 4, 3])];
+        let next = next_target(ProgressionScheme::Linear, &config, &base(), &history).unwrap();
+        assert_eq!(weight(&next), 102.5);
+        assert!(!next.is_deload);
+
+        history.push(session(102.5, &[5, 4, 4]));
+        history.push(session(102.5, &[5, 5, 4]));
+        let next = next_target(ProgressionScheme::Linear, &config, &base(), &history).unwrap();
+        assert!(next.is_deload);
+        // 102.5 × 0.9 = 92.25 → 92.5
+        assert_eq!(weight(&next), 92.5);
+    }
+
+    #[test]
+    fn test_double_progression() {
+        let config = ProgressionConfig { rep_range_max: Some(8), ..ProgressionConfig::default() };
+        let mid = vec![session(60.0, &[7, 6, 6])];
+        let next =
+            next_target(ProgressionScheme::DoubleProgression, &config, &base(), &mid).unwrap();
+        assert_eq!((weight(&next), next.sets[0].reps), (60.0, 7));
+
+        let top = vec![session(60.0, &[8, 8, 8])];
+        let next =
+            next_target(ProgressionScheme::DoubleProgression, &config, &base(), &top).unwrap();
+        assert_eq!((weight(&next), next.sets[0].reps), (62.5, 5));
+
+        let bad = ProgressionConfig { rep_range_max: Some(3), ..ProgressionConfig::default() };
+        assert!(next_target(ProgressionScheme::DoubleProgression, &bad, &base(), &top).is_err());
+    }
+
+    #[test]
+    fn test_rpe_autoregulation() {
+        let config = ProgressionConfig::default();
+        let mut easy = session(100.0, &[5, 5, 5]);
+        easy[2].rpe = Some(6.0);
+        let next = next_target(ProgressionScheme::Rpe, &config, &base(), &[easy]).unwrap();
+        // Two RPE points under the default target of 8 → +8%
+        assert_eq!(weight(&next), 107.5);
+        assert_eq!(next.sets[0].rpe, Some(8.0));
+
+        let mut hard = session(100.0, &[5, 5, 5]);
+        hard[2].rpe = Some(9.5);
+        let target = BaseTarget { rpe: Some(8.5), ..base() };
+        let next = next_target(ProgressionScheme::Rpe, &config, &target, &[hard]).unwrap();
+        assert_eq!(weight(&next), 95.0);
+    }
+
+    #[test]
+    fn test_five_three_one_wave() {
+        let config =
+            ProgressionConfig { training_max_kg: Some(100.0), ..ProgressionConfig::default() };
+        let week1 = next_target(ProgressionScheme::PercentageWave, &config, &base(), &[]).unwrap();
+        let weights: Vec<f64> = week1.sets.iter().map(|s| s.weight_kg.unwrap()).collect();
+        assert_eq!(weights, vec![65.0, 75.0, 85.0]);
+        assert!(week1.sets[2].is_amrap);
+
+        let cycle = vec![
+            session(85.0, &[5, 5, 8]),
+            session(90.0, &[3, 3, 5]),
+            session(95.0, &[5, 3, 3]),
+            session(60.0, &[5, 5, 5]),
+        ];
+        let deload =
+            next_target(ProgressionScheme::PercentageWave, &config, &base(), &cycle[..3]).unwrap();
+        assert!(deload.is_deload);
+
+        let next =
+            next_target(ProgressionScheme::PercentageWave, &config, &base(), &cycle).unwrap();
+        // Training max 102.5 → 85% = 87.125 → 87.5
+        assert_eq!(next.sets[2].weight_kg, Some(87.5));
+
+        let mut failed = cycle.clone();
+        failed[1] = [(70.0, 3), (80.0, 3), (90.0, 2)]
+            .iter()
+            .map(|&(weight_kg, reps)| PerformedSet { weight_kg, reps, rpe: None })
+            .collect();
+        let next =
+            next_target(ProgressionScheme::PercentageWave, &config, &base(), &failed).unwrap();
+        // Training max 90 → 85% = 76.5 → 77.5
+        assert_eq!(next.sets[2].weight_kg, Some(77.5));
+    }
+}
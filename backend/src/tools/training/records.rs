@@ -0,0 +1,362 @@
+use super::compute::estimate_1rm_with;
+use super::types::OneRmFormula;
+use super::units::{display_weight, EnteredWeight, WeightUnit};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+// ============================================================================
+// PERSONAL RECORDS
+// ============================================================================
+
+/// A logged working set of one exercise
+#[derive(Debug, Clone)]
+pub struct LoggedSet {
+    pub session_id: Uuid,
+    pub performed_at: DateTime<Utc>,
+    pub weight_kg: f64,
+    /// The weight as typed, when the set was logged with a unit
+    pub entered: Option<EnteredWeight>,
+    pub reps: u32,
+}
+
+impl LoggedSet {
+    fn weight_in(&self, unit: WeightUnit) -> f64 {
+        display_weight(self.weight_kg, self.entered, unit)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    HeaviestWeight,
+    BestE1rm,
+    BestRepsAtWeight,
+    BestSessionVolume,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetRecord {
+    pub weight_kg: f64,
+    /// `weight_kg` in the unit of the records
+    pub weight: f64,
+    pub reps: u32,
+    pub performed_at: DateTime<Utc>,
+    pub session_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct E1rmRecord {
+    pub e1rm_kg: f64,
+    pub e1rm: f64,
+    #[serde(flatten)]
+    pub set: SetRecord,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeRecord {
+    pub volume_kg: f64,
+    pub volume: f64,
+    pub session_id: Uuid,
+    pub performed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersonalRecords {
+    pub formula: OneRmFormula,
+    /// Unit of the unsuffixed weights, e1RMs and volumes
+    pub weight_unit: WeightUnit,
+    pub heaviest_weight: Option<SetRecord>,
+    pub best_e1rm: Option<E1rmRecord>,
+    /// Most reps per distinct weight, heaviest first
+    pub best_reps_at_weight: Vec<SetRecord>,
+    pub best_session_volume: Option<VolumeRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct E1rmPoint {
+    pub date: NaiveDate,
+    pub session_id: Uuid,
+    /// Best estimate of the session
+    pub e1rm_kg: f64,
+    pub e1rm: f64,
+}
+
+/// Weights are stored with two decimals; compare them in hundredths of a kg
+fn weight_key(weight_kg: f64) -> i64 {
+    (weight_kg * 100.0).round() as i64
+}
+
+fn set_record(set: &LoggedSet, unit: WeightUnit) -> SetRecord {
+    SetRecord {
+        weight_kg: set.weight_kg,
+        weight: set.weight_in(unit),
+        reps: set.reps,
+        performed_at: set.performed_at,
+        session_id: set.session_id,
+    }
+}
+
+/// Session volumes in first-seen order with the time of the session's first set.
+/// The volume in `unit` sums the sets' weights in that unit, so pounds typed as pounds
+/// add up exactly.
+fn session_volumes(sets: &[LoggedSet], unit: WeightUnit) -> Vec<VolumeRecord> {
+    let mut volumes: Vec<VolumeRecord> = Vec::new();
+    for set in sets {
+        let volume_kg = set.weight_kg * f64::from(set.reps);
+        let volume = set.weight_in(unit) * f64::from(set.reps);
+        match volumes.iter_mut().find(|v| v.session_id == set.session_id) {
+            Some(v) => {
+                v.volume_kg += volume_kg;
+                v.volume += volume;
+            }
+            None => volumes.push(VolumeRecord {
+                volume_kg,
+                volume,
+                session_id: set.session_id,
+                performed_at: set.performed_at,
+            }),
+        }
+    }
+    volumes
+}
+
+/// Current records over the given working sets, with weights also given in `unit`.
+/// Ties keep the earliest set.
+#[must_use]
+pub fn personal_records(
+    sets: &[LoggedSet],
+    formula: OneRmFormula,
+    unit: WeightUnit,
+) -> PersonalRecords {
+    let mut sorted: Vec<&LoggedSet> = sets.iter().filter(|s| s.reps > 0).collect();
+    sorted.sort_by_key(|s| s.performed_at);
+
+    let mut heaviest: Option<&LoggedSet> = None;
+    let mut best_e1rm: Option<(f64, &LoggedSet)> = None;
+    let mut reps_at_weight: BTreeMap<i64, &LoggedSet> = BTreeMap::new();
+    for &set in &sorted {
+        if set.weight_kg > heaviest.map_or(0.0, |h| h.weight_kg) {
+            heaviest = Some(set);
+        }
+        if let Some(e1rm) = estimate_1rm_with(set.weight_kg, set.reps, formula) {
+            if best_e1rm.is_none_or(|(best, _)| e1rm > best) {
+                best_e1rm = Some((e1rm, set));
+            }
+        }
+        let entry = reps_at_weight.entry(weight_key(set.weight_kg)).or_insert(set);
+        if set.reps > entry.reps {
+            *entry = set;
+        }
+    }
+
+    let owned: Vec<LoggedSet> = sorted.into_iter().cloned().collect();
+    let best_session_volume = session_volumes(&owned, unit)
+        .into_iter()
+        .filter(|v| v.volume_kg > 0.0)
+        .fold(None, |best: Option<VolumeRecord>, v| match best {
+            Some(b) if b.volume_kg >= v.volume_kg => Some(b),
+            _ => Some(v),
+        });
+
+    PersonalRecords {
+        formula,
+        weight_unit: unit,
+        heaviest_weight: heaviest.map(|s| set_record(s, unit)),
+        best_e1rm: best_e1rm.map(|(e1rm_kg, set)| E1rmRecord {
+            e1rm_kg,
+            e1rm: estimate_1rm_with(set.weight_in(unit), set.reps, formula).unwrap_or_default(),
+            set: set_record(set, unit),
+        }),
+        best_reps_at_weight: reps_at_weight.values().rev().map(|s| set_record(s, unit)).collect(),
+        best_session_volume,
+    }
+}
+
+/// Records `set` beats compared with the earlier working sets in `previous`.
+///
+/// `previous` may contain sets of the same session; they count towards its volume.
+#[must_use]
+pub fn new_records(
+    previous: &[LoggedSet],
+    set: &LoggedSet,
+    formula: OneRmFormula,
+) -> Vec<RecordKind> {
+    if set.reps == 0 {
+        return Vec::new();
+    }
+    let before = personal_records(previous, formula, WeightUnit::Kg);
+    let mut records = Vec::new();
+
+    if set.weight_kg > 0.0
+        && before.heaviest_weight.as_ref().is_none_or(|h| set.weight_kg > h.weight_kg)
+    {
+        records.push(RecordKind::HeaviestWeight);
+    }
+    if let Some(e1rm) = estimate_1rm_with(set.weight_kg, set.reps, formula) {
+        if before.best_e1rm.as_ref().is_none_or(|b| e1rm > b.e1rm_kg) {
+            records.push(RecordKind::BestE1rm);
+        }
+    }
+    let key = weight_key(set.weight_kg);
+    if before
+        .best_reps_at_weight
+        .iter()
+        .find(|r| weight_key(r.weight_kg) == key)
+        .is_none_or(|r| set.reps > r.reps)
+    {
+        records.push(RecordKind::BestRepsAtWeight);
+    }
+    let session_volume: f64 = previous
+        .iter()
+        .filter(|s| s.session_id == set.session_id)
+        .map(|s| s.weight_kg * f64::from(s.reps))
+        .sum::<f64>()
+        + set.weight_kg * f64::from(set.reps);
+    let other_sessions_best = session_volumes(previous, WeightUnit::Kg)
+        .into_iter()
+        .filter(|v| v.session_id != set.session_id)
+        .map(|v| v.volume_kg)
+        .fold(0.0, f64::max);
+    let session_was_record =
+        before.best_session_volume.as_ref().is_some_and(|b| b.session_id == set.session_id);
+    if session_volume > other_sessions_best && !session_was_record {
+        records.push(RecordKind::BestSessionVolume);
+    }
+    records
+}
+
+/// Best estimated 1RM per session, oldest first, also given in `unit`
+#[must_use]
+pub fn e1rm_trend(sets: &[LoggedSet], formula: OneRmFormula, unit: WeightUnit) -> Vec<E1rmPoint> {
+    let mut points: Vec<E1rmPoint> = Vec::new();
+    let mut sorted: Vec<&LoggedSet> = sets.iter().collect();
+    sorted.sort_by_key(|s| s.performed_at);
+    for set in sorted {
+        let Some(e1rm_kg) = estimate_1rm_with(set.weight_kg, set.reps, formula) else {
+            continue;
+        };
+        let e1rm = estimate_1rm_with(set.weight_in(unit), set.reps, formula).unwrap_or_default();
+        match points.iter_mut().find(|p| p.session_id == set.session_id) {
+            Some(p) => {
+                p.e1rm_kg = p.e1rm_kg.max(e1rm_kg);
+                p.e1rm = p.e1rm.max(e1rm);
+            }
+            None => points.push(E1rmPoint {
+                date: set.performed_at.date_naive(),
+                session_id: set.session_id,
+                e1rm_kg,
+                e1rm,
+            }),
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn set(session: u128, day: u32, weight_kg: f64, reps: u32) -> LoggedSet {
+        LoggedSet {
+            session_id: Uuid::from_u128(session),
+            performed_at: Utc.with_ymd_and_hms(2024, 3, day, 10, 0, 0).unwrap(),
+            weight_kg,
+            entered: None,
+            reps,
+        }
+    }
+
+    #[test]
+    fn test_personal_records() {
+        let sets = vec![
+            set(1, 1, 100.0, 5),
+            set(1, 1, 100.0, 6),
+            set(2, 8, 110.0, 2),
+            set(2, 8, 80.0, 12),
+        ];
+        let records = personal_records(&sets, OneRmFormula::Epley, WeightUnit::Kg);
+
+        assert_eq!(records.heaviest_weight.unwrap().weight_kg, 110.0);
+        // 80×12 → 112, 100×6 → 120, 110×2 → 117.3
+        let e1rm = records.best_e1rm.unwrap();
+        assert!((e1rm.e1rm_kg - 120.0).abs() < 1e-9);
+        assert_eq!(e1rm.set.reps, 6);
+        let reps: Vec<(f64, u32)> =
+            records.best_reps_at_weight.iter().map(|r| (r.weight_kg, r.reps)).collect();
+        assert_eq!(reps, vec![(110.0, 2), (100.0, 6), (80.0, 12)]);
+        let volume = records.best_session_volume.unwrap();
+        assert_eq!(volume.session_id, Uuid::from_u128(2));
+        assert!((volume.volume_kg - 1180.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_new_records_against_history() {
+        let history = vec![set(1, 1, 100.0, 5), set(1, 1, 90.0, 8)];
+
+        let heavier = set(2, 8, 105.0, 1);
+        assert_eq!(
+            new_records(&history, &heavier, OneRmFormula::Epley),
+            vec![RecordKind::HeaviestWeight, RecordKind::BestRepsAtWeight]
+        );
+
+        // 90×9 → 117 beats 100×5 → 116.7
+        let more_reps = set(2, 8, 90.0, 9);
+        assert_eq!(
+            new_records(&history, &more_reps, OneRmFormula::Epley),
+            vec![RecordKind::BestE1rm, RecordKind::BestRepsAtWeight]
+        );
+
+        let lighter = set(2, 8, 90.0, 7);
+        assert!(new_records(&history, &lighter, OneRmFormula::Epley).is_empty());
+    }
+
+    #[test]
+    fn test_session_volume_record_is_reported_once() {
+        // Session 1: 1220 kg. Session 2 passes it with its third set
+        let mut history = vec![set(1, 1, 100.0, 5), set(1, 1, 90.0, 8)];
+        history.push(set(2, 8, 100.0, 5));
+        history.push(set(2, 8, 100.0, 5));
+        let third = set(2, 8, 50.0, 5);
+        assert!(new_records(&history, &third, OneRmFormula::Epley)
+            .contains(&RecordKind::BestSessionVolume));
+
+        history.push(third);
+        let fourth = set(2, 8, 50.0, 5);
+        assert!(!new_records(&history, &fourth, OneRmFormula::Epley)
+            .contains(&RecordKind::BestSessionVolume));
+    }
+
+    #[test]
+    fn test_records_in_entered_unit() {
+        let mut pounds = set(1, 1, 102.06, 5);
+        pounds.entered = Some(EnteredWeight { value: 225.0, unit: WeightUnit::Lb });
+        let sets = vec![pounds, set(1, 1, 100.0, 5)];
+
+        let records = personal_records(&sets, OneRmFormula::Epley, WeightUnit::Lb);
+        assert_eq!(records.heaviest_weight.unwrap().weight, 225.0);
+        assert_eq!(records.best_reps_at_weight[1].weight, 220.46);
+        assert!(
+            (records.best_session_volume.unwrap().volume - (225.0 + 220.46) * 5.0).abs() < 1e-9
+        );
+        let trend = e1rm_trend(&sets, OneRmFormula::Epley, WeightUnit::Lb);
+        assert!((trend[0].e1rm - 225.0 * (1.0 + 5.0 / 30.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_e1rm_trend_per_session() {
+        let sets = vec![set(1, 1, 100.0, 5), set(1, 1, 100.0, 3), set(2, 8, 100.0, 8)];
+        let trend = e1rm_trend(&sets, OneRmFormula::Brzycki, WeightUnit::Kg);
+        assert_eq!(trend.len(), 2);
+        assert!((trend[0].e1rm_kg - 100.0 * 36.0 / 32.0).abs() < 1e-9);
+        assert!(trend[1].e1rm_kg > trend[0].e1rm_kg);
+        assert_eq!(trend[1].date, NaiveDate::from_ymd_opt(2024, 3, 8).unwrap());
+    }
+}
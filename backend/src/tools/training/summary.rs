@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// ============================================================================
+// PLANNED VS. PERFORMED
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlannedSetStatus {
+    Pending,
+    Completed,
+    Skipped,
+}
+
+/// A prescribed set of the session
+#[derive(Debug, Clone)]
+pub struct PlannedSet {
+    pub exercise_id: Uuid,
+    pub exercise_name: String,
+    pub target_weight_kg: Option<f64>,
+    pub target_reps: u32,
+    pub status: PlannedSetStatus,
+}
+
+/// A logged working set of the session
+#[derive(Debug, Clone)]
+pub struct SessionSet {
+    pub exercise_id: Uuid,
+    pub exercise_name: String,
+    pub weight_kg: f64,
+    pub reps: u32,
+    /// Whether the set checked off a planned set
+    pub is_planned: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExerciseSummary {
+    pub exercise_id: Uuid,
+    pub exercise_name: String,
+    pub planned_sets: u32,
+    pub completed_sets: u32,
+    pub skipped_sets: u32,
+    /// Working sets logged without a planned set
+    pub extra_sets: u32,
+    pub planned_reps: u32,
+    pub performed_reps: u32,
+    /// Only planned sets with a target weight count
+    pub planned_volume_kg: f64,
+    pub performed_volume_kg: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    /// Planned exercises in plan order, then unplanned ones in logging order
+    pub exercises: Vec<ExerciseSummary>,
+    pub planned_volume_kg: f64,
+    pub performed_volume_kg: f64,
+    /// Completed share of the planned sets; None without a plan
+    pub completion_percentage: Option<f64>,
+}
+
+fn entry<'a>(
+    exercises: &'a mut Vec<ExerciseSummary>,
+    exercise_id: Uuid,
+    exercise_name: &str,
+) -> &'a mut ExerciseSummary {
+    let index = match exercises.iter().position(|e| e.exercise_id == exercise_id) {
+        Some(index) => index,
+        None => {
+            exercises.push(ExerciseSummary {
+                exercise_id,
+                exercise_name: exercise_name.to_string(),
+                planned_sets: 0,
+                completed_sets: 0,
+                skipped_sets: 0,
+                extra_sets: 0,
+                planned_reps: 0,
+                performed_reps: 0,
+                planned_volume_kg: 0.0,
+                performed_volume_kg: 0.0,
+            });
+            exercises.len() - 1
+        }
+    };
+    &mut exercises[index]
+}
+
+/// Compare a session's prescription with what was logged
+#[must_use]
+pub fn summarize_session(planned: &[PlannedSet], performed: &[SessionSet]) -> SessionSummary {
+    let mut exercises: Vec<ExerciseSummary> = Vec::new();
+    for set in planned {
+        let e = entry(&mut exercises, set.exercise_id, &set.exercise_name);
+        e.planned_sets += 1;
+        e.planned_reps += set.target_reps;
+        e.planned_volume_kg += set.target_weight_kg.unwrap_or(0.0) * f64::from(set.target_reps);
+        match set.status {
+            PlannedSetStatus::Completed => e.completed_sets += 1,
+            PlannedSetStatus::Skipped => e.skipped_sets += 1,
+            PlannedSetStatus::Pending => {}
+        }
+    }
+    for set in performed {
+        let e = entry(&mut exercises, set.exercise_id, &set.exercise_name);
+        if !set.is_planned {
+            e.extra_sets += 1;
+        }
+        e.performed_reps += set.reps;
+        e.performed_volume_kg += set.weight_kg * f64::from(set.reps);
+    }
+
+    let planned_sets: u32 = exercises.iter().map(|e| e.planned_sets).sum();
+    let completed_sets: u32 = exercises.iter().map(|e| e.completed_sets).sum();
+    SessionSummary {
+        planned_volume_kg: exercises.iter().map(|e| e.planned_volume_kg).sum(),
+        performed_volume_kg: exercises.iter().map(|e| e.performed_volume_kg).sum(),
+        completion_percentage: (planned_sets > 0)
+            .then(|| f64::from(completed_sets) / f64::from(planned_sets) * 100.0),
+        exercises,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned(exercise: u128, weight: f64, reps: u32, status: PlannedSetStatus) -> PlannedSet {
+        PlannedSet {
+            exercise_id: Uuid::from_u128(exercise),
+            exercise_name: format!("exercise {exercise}"),
+            target_weight_kg: Some(weight),
+            target_reps: reps,
+            status,
+        }
+    }
+
+    fn performed(exercise: u128, weight: f64, reps: u32, is_planned: bool) -> SessionSet {
+        SessionSet {
+            exercise_id: Uuid::from_u128(exercise),
+            exercise_name: format!("exercise {exercise}"),
+            weight_kg: weight,
+            reps,
+            is_planned,
+        }
+    }
+
+    #[test]
+    fn test_planned_vs_performed() {
+        let plan = vec![
+            planned(1, 100.0, 5, PlannedSetStatus::Completed),
+            planned(1, 100.0, 5, PlannedSetStatus::Completed),
+            planned(2, 50.0, 10, PlannedSetStatus::Skipped),
+            planned(2, 50.0, 10, PlannedSetStatus::Pending),
+        ];
+        let logged = vec![
+            performed(1, 100.0, 5, true),
+            performed(1, 100.0, 4, true),
+            performed(3, 20.0, 15, false),
+        ];
+        let summary = summarize_session(&plan, &logged);
+
+        let ids: Vec<u128> = summary.exercises.iter().map(|e| e.exercise_id.as_u128()).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        let first = &summary.exercises[0];
+        assert_eq!((first.planned_reps, first.performed_reps), (10, 9));
+        assert_eq!(summary.exercises[1].skipped_sets, 1);
+        assert_eq!(summary.exercises[2].extra_sets, 1);
+        assert!((summary.planned_volume_kg - 2000.0).abs() < 1e-9);
+        assert!((summary.performed_volume_kg - 1200.0).abs() < 1e-9);
+        assert_eq!(summary.completion_percentage, Some(50.0));
+    }
+
+    #[test]
+    fn test_session_without_plan() {
+        let summary = summarize_session(&[], &[performed(1, 60.0, 10, false)]);
+        assert_eq!(summary.completion_percentage, None);
+        assert_eq!(summary.exercises[0].extra_sets, 1);
+    }
+}
@@ -5,6 +5,16 @@ use serde::{Deserialize, Serialize};
 // DATA STRUCTURES
 // ============================================================================
 
+/// Formula used to estimate a one-rep max from a submaximal set
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OneRmFormula {
+    #[default]
+    Epley,
+    Brzycki,
+    Lombardi,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BodyMeasurements {
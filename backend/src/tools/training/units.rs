@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// WEIGHT UNITS
+// ============================================================================
+
+/// Exact by definition (International Yard and Pound Agreement, 1959)
+pub const KG_PER_LB: f64 = 0.453_592_37;
+/// Weights shown in another unit than they were entered in are rounded to this
+const DISPLAY_DECIMALS: i32 = 2;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightUnit {
+    #[default]
+    Kg,
+    Lb,
+}
+
+impl WeightUnit {
+    #[must_use]
+    pub fn to_kg(self, value: f64) -> f64 {
+        match self {
+            Self::Kg => value,
+            Self::Lb => value * KG_PER_LB,
+        }
+    }
+
+    #[must_use]
+    pub fn kg_to(self, kg: f64) -> f64 {
+        match self {
+            Self::Kg => kg,
+            Self::Lb => kg / KG_PER_LB,
+        }
+    }
+
+    /// Convert a value between units
+    #[must_use]
+    pub fn convert(self, value: f64, to: Self) -> f64 {
+        if self == to {
+            value
+        } else {
+            to.kg_to(self.to_kg(value))
+        }
+    }
+}
+
+/// A weight as the user entered it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct EnteredWeight {
+    pub value: f64,
+    pub unit: WeightUnit,
+}
+
+/// A stored weight in the requested unit. The entered value is returned unchanged when
+/// the units match, so a weight logged in pounds reads back as exactly what was typed
+/// even though the canonical kg column is rounded.
+#[must_use]
+pub fn display_weight(kg: f64, entered: Option<EnteredWeight>, unit: WeightUnit) -> f64 {
+    match entered {
+        Some(entered) if entered.unit == unit => entered.value,
+        Some(entered) => round_display(entered.unit.convert(entered.value, unit)),
+        None => round_display(unit.kg_to(kg)),
+    }
+}
+
+fn round_display(value: f64) -> f64 {
+    let factor = 10f64.powi(DISPLAY_DECIMALS);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion() {
+        assert!((WeightUnit::Lb.to_kg(225.0) - 102.058_283_25).abs() < 1e-9);
+        assert!((WeightUnit::Lb.kg_to(100.0) - 220.462_262_185).abs() < 1e-6);
+        assert_eq!(WeightUnit::Kg.convert(80.0, WeightUnit::Kg), 80.0);
+        // Many round trips through kg do not move a pound value
+        let mut lb = 315.0;
+        for _ in 0..1000 {
+            lb = WeightUnit::Kg.convert(WeightUnit::Lb.convert(lb, WeightUnit::Kg), WeightUnit::Lb);
+        }
+        assert!((lb - 315.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_display_weight() {
+        // 225 lb is stored as 102.06 kg; it still reads back as 225 lb
+        let entered = Some(EnteredWeight { value: 225.0, unit: WeightUnit::Lb });
+        assert_eq!(display_weight(102.06, entered, WeightUnit::Lb), 225.0);
+        assert_eq!(display_weight(102.06, entered, WeightUnit::Kg), 102.06);
+        // Rows without an entered value convert from kg
+        assert_eq!(display_weight(100.0, None, WeightUnit::Lb), 220.46);
+        assert_eq!(display_weight(100.0, None, WeightUnit::Kg), 100.0);
+    }
+}
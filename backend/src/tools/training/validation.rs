@@ -0,0 +1,183 @@
+use super::constants::{
+    BODY_SEGMENTS, DIFFICULTIES, EQUIPMENT_TYPES, MOVEMENT_PATTERNS, MUSCLE_INVOLVEMENTS,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// ============================================================================
+// EXERCISE DEFINITIONS
+// ============================================================================
+
+const MAX_EXERCISE_NAME_LEN: usize = 100;
+
+fn default_equipment() -> String {
+    "barbell".to_string()
+}
+
+fn default_difficulty() -> String {
+    "intermediate".to_string()
+}
+
+fn default_rom_degrees() -> f64 {
+    90.0
+}
+
+fn default_activation_fraction() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExerciseMuscleInput {
+    /// `muscle_groups.name`
+    pub name: String,
+    pub involvement: String,
+    #[serde(default = "default_activation_fraction")]
+    pub activation_fraction: f64,
+}
+
+/// A user-authored exercise, as accepted by create and update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExerciseDefinition {
+    pub name: String,
+    pub description: Option<String>,
+    pub movement_pattern: String,
+    #[serde(default = "default_equipment")]
+    pub equipment: String,
+    #[serde(default = "default_difficulty")]
+    pub difficulty: String,
+    #[serde(default)]
+    pub is_bodyweight: bool,
+    #[serde(default)]
+    pub is_unilateral: bool,
+    #[serde(default)]
+    pub primary_segments_moved: Vec<String>,
+    #[serde(default = "default_rom_degrees")]
+    pub rom_degrees: f64,
+    #[serde(default)]
+    pub body_mass_fraction_moved: f64,
+    #[serde(default)]
+    pub muscles: Vec<ExerciseMuscleInput>,
+}
+
+fn check_one_of(field: &str, value: &str, allowed: &[&str]) -> Result<(), String> {
+    if allowed.contains(&value) {
+        Ok(())
+    } else {
+        Err(format!("{field} must be one of: {}", allowed.join(", ")))
+    }
+}
+
+/// Check an exercise against the table constraints.
+///
+/// Muscle names are only checked for duplicates here; whether they exist in `muscle_groups` is
+/// up to the caller.
+pub fn validate_exercise(def: &ExerciseDefinition) -> Result<(), String> {
+    let name = def.name.trim();
+    if name.is_empty() || name.len() > MAX_EXERCISE_NAME_LEN {
+        return Err(format!("name must be 1-{MAX_EXERCISE_NAME_LEN} characters"));
+    }
+    check_one_of("movementPattern", &def.movement_pattern, &MOVEMENT_PATTERNS)?;
+    check_one_of("equipment", &def.equipment, &EQUIPMENT_TYPES)?;
+    check_one_of("difficulty", &def.difficulty, &DIFFICULTIES)?;
+    for segment in &def.primary_segments_moved {
+        check_one_of("primarySegmentsMoved", segment, &BODY_SEGMENTS)?;
+    }
+    if !(0.0..=360.0).contains(&def.rom_degrees) {
+        return Err("romDegrees must be between 0 and 360".to_string());
+    }
+    if !(0.0..=1.0).contains(&def.body_mass_fraction_moved) {
+        return Err("bodyMassFractionMoved must be between 0 and 1".to_string());
+    }
+    if def.is_bodyweight && def.body_mass_fraction_moved == 0.0 {
+        return Err("bodyweight exercises need a bodyMassFractionMoved above 0".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    for muscle in &def.muscles {
+        check_one_of("involvement", &muscle.involvement, &MUSCLE_INVOLVEMENTS)?;
+        if !(muscle.activation_fraction > 0.0 && muscle.activation_fraction <= 1.0) {
+            return Err("activationFraction must be in (0, 1]".to_string());
+        }
+        if !seen.insert(muscle.name.to_lowercase()) {
+            return Err(format!("muscle {} is listed twice", muscle.name));
+        }
+    }
+    if !def.muscles.iter().any(|m| m.involvement == "primary") {
+        return Err("at least one primary muscle is required".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curl() -> ExerciseDefinition {
+        serde_json::from_value(serde_json::json!({
+            "name": "Spider Curl",
+            "movementPattern": "isolation_upper",
+            "equipment": "dumbbell",
+            "primarySegmentsMoved": ["lower_arm"],
+            "romDegrees": 130.0,
+            "muscles": [
+                {"name": "biceps", "involvement": "primary"},
+                {"name": "forearms", "involvement": "secondary", "activationFraction": 0.4}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_valid_definition_with_defaults() {
+        let def = curl();
+        assert_eq!(def.difficulty, "intermediate");
+        assert_eq!(def.muscles[0].activation_fraction, 1.0);
+        assert!(validate_exercise(&def).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_values_outside_check_enums() {
+        let mut def = curl();
+        def.movement_pattern = "curl".to_string();
+        assert!(validate_exercise(&def).unwrap_err().contains("movementPattern"));
+
+        let mut def = curl();
+        def.equipment = "smith".to_string();
+        assert!(validate_exercise(&def).is_err());
+
+        let mut def = curl();
+        def.primary_segments_moved.push("head".to_string());
+        assert!(validate_exercise(&def).is_err());
+
+        let mut def = curl();
+        def.muscles[1].involvement = "synergist".to_string();
+        assert!(validate_exercise(&def).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_numbers_and_muscle_lists() {
+        let mut def = curl();
+        def.body_mass_fraction_moved = 1.5;
+        assert!(validate_exercise(&def).is_err());
+
+        let mut def = curl();
+        def.is_bodyweight = true;
+        assert!(validate_exercise(&def).is_err());
+
+        let mut def = curl();
+        def.muscles[1].name = "biceps".to_string();
+        assert!(validate_exercise(&def).unwrap_err().contains("twice"));
+        def.muscles[1].name = "Biceps".to_string();
+        assert!(validate_exercise(&def).unwrap_err().contains("twice"));
+
+        let mut def = curl();
+        def.muscles.remove(0);
+        assert!(validate_exercise(&def).unwrap_err().contains("primary"));
+
+        let mut def = curl();
+        def.name = "  ".to_string();
+        assert!(validate_exercise(&def).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use super::plates::{
+    solve_plates, LoadDirection, PlateCalculation, PlateCount, PlateInventory, PlateOption,
+};
+use super::units::WeightUnit;
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// WARM-UP SETS
+// ============================================================================
+
+/// Ramp as (share of the working weight, reps); None is the empty bar
+const WARMUP_RAMP: [(Option<f64>, u32); 4] =
+    [(None, 10), (Some(0.4), 5), (Some(0.6), 3), (Some(0.8), 1)];
+/// Working sets of this many reps or more skip the heavy single
+const HIGH_REP_WORKING_SET: u32 = 8;
+/// A warm-up may miss its target by this share of the working weight more than the
+/// closest loadout when that saves stripping plates; warm-ups need not be exact
+const STACKING_TOLERANCE: f64 = 0.025;
+const WEIGHT_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarmupSet {
+    pub set_number: u32,
+    /// Share of the working weight; None for the empty bar
+    pub percentage: Option<f64>,
+    pub target_weight: f64,
+    pub reps: u32,
+    pub loadout: PlateCalculation,
+    /// Plates added or removed per side since the previous set
+    pub plate_changes: u32,
+}
+
+/// Warm-up ramp up to a working set, all weights in `unit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarmupPlan {
+    pub unit: WeightUnit,
+    pub bar_weight: f64,
+    pub working_weight: f64,
+    pub working_reps: u32,
+    pub sets: Vec<WarmupSet>,
+    pub working: PlateCalculation,
+    pub working_plate_changes: u32,
+}
+
+fn side_weight(plates: &[PlateCount]) -> f64 {
+    plates.iter().map(|p| p.weight * f64::from(p.count)).sum()
+}
+
+/// Plates added plus plates removed per side to go from one loadout to the next
+#[must_use]
+pub fn plate_changes(from: &[PlateCount], to: &[PlateCount]) -> u32 {
+    let count = |plates: &[PlateCount], weight: f64| {
+        plates
+            .iter()
+            .filter(|p| (p.weight - weight).abs() < WEIGHT_EPSILON)
+            .map(|p| p.count)
+            .sum::<u32>()
+    };
+    let mut weights: Vec<f64> = from.iter().chain(to).map(|p| p.weight).collect();
+    weights.sort_by(|a, b| b.total_cmp(a));
+    weights.dedup_by(|a, b| (*a - *b).abs() < WEIGHT_EPSILON);
+    weights.iter().map(|&w| count(from, w).abs_diff(count(to, w))).sum()
+}
+
+/// Inventory left once `loaded` is on the bar
+fn remaining_inventory(plates: &[PlateInventory], loaded: &[PlateCount]) -> Vec<PlateInventory> {
+    plates
+        .iter()
+        .map(|p| {
+            let used: u32 = loaded
+                .iter()
+                .filter(|l| (l.weight - p.weight).abs() < WEIGHT_EPSILON)
+                .map(|l| l.count)
+                .sum();
+            PlateInventory { weight: p.weight, pairs: p.pairs.map(|n| n.saturating_sub(used)) }
+        })
+        .collect()
+}
+
+/// Both loadouts together, heaviest plates first
+fn merge_plates(base: &[PlateCount], extra: &[PlateCount]) -> Vec<PlateCount> {
+    let mut merged: Vec<PlateCount> = base.to_vec();
+    for plate in extra {
+        match merged.iter_mut().find(|p| (p.weight - plate.weight).abs() < WEIGHT_EPSILON) {
+            Some(existing) => existing.count += plate.count,
+            None => merged.push(plate.clone()),
+        }
+    }
+    merged.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    merged
+}
+
+/// Closest loadout for the target, or the plates already on the bar plus more when
+/// that is at most `tolerance` further from the target
+fn loadout(
+    target: f64,
+    bar_weight: f64,
+    plates: &[PlateInventory],
+    unit: WeightUnit,
+    loaded: &[PlateCount],
+    tolerance: f64,
+) -> Result<PlateCalculation, String> {
+    let fresh = solve_plates(target, bar_weight, plates, unit)?;
+    let loaded_side = side_weight(loaded);
+    let stacked_target = target - 2.0 * loaded_side;
+    if loaded.is_empty() || stacked_target < bar_weight {
+        return Ok(fresh);
+    }
+    let extra =
+        solve_plates(stacked_target, bar_weight, &remaining_inventory(plates, loaded), unit)?;
+    let difference = extra.achievable_weight + 2.0 * loaded_side - target;
+    if difference.abs() > fresh.difference.abs() + tolerance + WEIGHT_EPSILON {
+        return Ok(fresh);
+    }
+    let stack = |option: PlateOption| {
+        PlateOption::new(
+            option.achievable_weight + 2.0 * loaded_side,
+            merge_plates(loaded, &option.plates_per_side),
+            unit,
+        )
+    };
+    Ok(PlateCalculation {
+        unit,
+        total_weight: target,
+        total_weight_kg: unit.to_kg(target),
+        barbell_weight: bar_weight,
+        barbell_weight_kg: unit.to_kg(bar_weight),
+        plates_per_side: merge_plates(loaded, &extra.plates_per_side),
+        achievable_weight: target + difference,
+        achievable_weight_kg: unit.to_kg(target + difference),
+        direction: LoadDirection::of(difference),
+        difference,
+        difference_kg: unit.to_kg(difference),
+        nearest_below: extra.nearest_below.map(stack),
+        nearest_above: extra.nearest_above.map(stack),
+    })
+}
+
+/// Warm-up sets from the empty bar up to the working weight. Steps that round to the
+/// bar, to the previous step or to the working weight are dropped, and each step keeps
+/// the previous plates on when that stays close to its target.
+pub fn generate_warmup(
+    working_weight: f64,
+    working_reps: u32,
+    bar_weight: f64,
+    plates: &[PlateInventory],
+    unit: WeightUnit,
+) -> Result<WarmupPlan, String> {
+    if working_reps == 0 {
+        return Err("working reps must be positive".to_string());
+    }
+    let working_fresh = solve_plates(working_weight, bar_weight, plates, unit)?;
+    let tolerance = STACKING_TOLERANCE * working_weight;
+
+    let mut sets: Vec<WarmupSet> = Vec::new();
+    let mut loaded: Vec<PlateCount> = Vec::new();
+    for (percentage, reps) in WARMUP_RAMP {
+        if percentage.is_some_and(|p| p >= 0.8) && working_reps >= HIGH_REP_WORKING_SET {
+            continue;
+        }
+        let target_weight = percentage.map_or(bar_weight, |p| working_weight * p);
+        if percentage.is_some() && target_weight <= bar_weight {
+            continue;
+        }
+        let step = loadout(target_weight, bar_weight, plates, unit, &loaded, tolerance)?;
+        let previous = sets.last().map(|s| s.loadout.achievable_weight);
+        if previous.is_some_and(|w| step.achievable_weight <= w + WEIGHT_EPSILON)
+            || step.achievable_weight >= working_fresh.achievable_weight - WEIGHT_EPSILON
+        {
+            continue;
+        }
+        let changes = plate_changes(&loaded, &step.plates_per_side);
+        loaded = step.plates_per_side.clone();
+        sets.push(WarmupSet {
+            set_number: sets.len() as u32 + 1,
+            percentage,
+            target_weight,
+            reps,
+            loadout: step,
+            plate_changes: changes,
+        });
+    }
+
+    // The working set only keeps the warm-up plates if that loses nothing
+    let working = loadout(working_weight, bar_weight, plates, unit, &loaded, 0.0)?;
+    let working_plate_changes = plate_changes(&loaded, &working.plates_per_side);
+    Ok(WarmupPlan {
+        unit,
+        bar_weight,
+        working_weight,
+        working_reps,
+        sets,
+        working,
+        working_plate_changes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::training::EquipmentProfile;
+
+    #[test]
+    fn test_standard_ramp() {
+        // 100 kg x 5: bar x 10, 40 x 5, 60 x 3, 80 x 1
+        let standard = EquipmentProfile::standard(WeightUnit::Kg);
+        let plan = generate_warmup(100.0, 5, 20.0, &standard.plates, WeightUnit::Kg).unwrap();
+        let loads: Vec<(f64, u32)> =
+            plan.sets.iter().map(|s| (s.loadout.achievable_weight, s.reps)).collect();
+        assert_eq!(loads, vec![(20.0, 10), (40.0, 5), (60.0, 3), (80.0, 1)]);
+        assert_eq!(plan.working.achievable_weight, 100.0);
+        assert!(plan.sets.iter().all(|s| s.set_number > 0));
+        // 40 kg is a 10 a side; 60 stacks a 10 on top rather than swapping for a 20
+        assert_eq!(plan.sets[2].plate_changes, 1);
+        assert_eq!(
+            plan.sets[2].loadout.plates_per_side,
+            vec![PlateCount::new(10.0, 2, WeightUnit::Kg)]
+        );
+    }
+
+    #[test]
+    fn test_light_and_high_rep_working_sets() {
+        let standard = EquipmentProfile::standard(WeightUnit::Kg);
+        // 40 kg: 40 % and 60 % are at or below the bar, 80 % is 32.5
+        let plan = generate_warmup(40.0, 5, 20.0, &standard.plates, WeightUnit::Kg).unwrap();
+        let loads: Vec<f64> = plan.sets.iter().map(|s| s.loadout.achievable_weight).collect();
+        assert_eq!(loads, vec![20.0, 25.0, 32.5]);
+        // Ten-rep working sets skip the heavy single
+        let plan = generate_warmup(100.0, 10, 20.0, &standard.plates, WeightUnit::Kg).unwrap();
+        assert_eq!(plan.sets.len(), 3);
+        // The empty bar needs no warm-up
+        let plan = generate_warmup(20.0, 5, 20.0, &standard.plates, WeightUnit::Kg).unwrap();
+        assert!(plan.sets.is_empty());
+        assert!(generate_warmup(100.0, 0, 20.0, &standard.plates, WeightUnit::Kg).is_err());
+    }
+
+    #[test]
+    fn test_plate_changes() {
+        let plates = |list: &[(f64, u32)]| -> Vec<PlateCount> {
+            list.iter()
+                .map(|&(weight, count)| PlateCount::new(weight, count, WeightUnit::Kg))
+                .collect()
+        };
+        assert_eq!(plate_changes(&[], &plates(&[(20.0, 1), (5.0, 1)])), 2);
+        assert_eq!(plate_changes(&plates(&[(20.0, 1)]), &plates(&[(20.0, 2)])), 1);
+        assert_eq!(plate_changes(&plates(&[(10.0, 2)]), &plates(&[(20.0, 1)])), 3);
+    }
+}
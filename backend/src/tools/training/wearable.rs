@@ -0,0 +1,449 @@
+use super::types::Tempo;
+use crate::tools::metabolism::Sex;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// WEARABLE HEART-RATE IMPORT
+// ============================================================================
+
+/// Seconds from the Unix epoch to the FIT epoch, 1989-12-31T00:00:00Z
+const FIT_EPOCH_OFFSET_S: i64 = 631_065_600;
+/// Global message number of FIT `record` messages
+const FIT_RECORD_MESSAGE: u16 = 20;
+const FIT_FIELD_TIMESTAMP: u8 = 253;
+const FIT_FIELD_HEART_RATE: u8 = 3;
+/// Readings outside this range are sensor dropouts or noise
+const PLAUSIBLE_BPM: std::ops::RangeInclusive<u32> = 25..=250;
+/// Samples further apart than this are a recording gap, not a steady heart rate
+const MAX_SAMPLE_GAP_S: i64 = 60;
+/// Shortest window a set is aligned with, for sets of very few fast reps
+const MIN_SET_DURATION_S: f64 = 10.0;
+const KJ_PER_KCAL: f64 = 4.184;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WearableFormat {
+    Fit,
+    Tcx,
+    Gpx,
+}
+
+impl WearableFormat {
+    /// Guess the format from the file contents
+    #[must_use]
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.get(8..12) == Some(b".FIT") {
+            return Some(Self::Fit);
+        }
+        let text = std::str::from_utf8(data).ok()?;
+        if text.contains("<TrainingCenterDatabase") {
+            Some(Self::Tcx)
+        } else if text.contains("<gpx") {
+            Some(Self::Gpx)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartRateSample {
+    pub time: DateTime<Utc>,
+    pub bpm: u32,
+}
+
+/// Heart-rate samples of a wearable file, oldest first with one sample per timestamp.
+/// Points without a plausible heart rate are skipped.
+pub fn parse_heart_rate(
+    format: WearableFormat,
+    data: &[u8],
+) -> Result<Vec<HeartRateSample>, String> {
+    let mut samples = match format {
+        WearableFormat::Fit => parse_fit(data)?,
+        WearableFormat::Tcx => parse_tcx(utf8(data)?),
+        WearableFormat::Gpx => parse_gpx(utf8(data)?),
+    };
+    samples.retain(|s| PLAUSIBLE_BPM.contains(&s.bpm));
+    samples.sort_by_key(|s| s.time);
+    samples.dedup_by_key(|s| s.time);
+    if samples.is_empty() {
+        return Err("file contains no heart-rate samples".to_string());
+    }
+    Ok(samples)
+}
+
+fn utf8(data: &[u8]) -> Result<&str, String> {
+    std::str::from_utf8(data).map_err(|_| "file is not valid UTF-8 text".to_string())
+}
+
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text.trim()).ok().map(|t| t.with_timezone(&Utc))
+}
+
+/// Contents of each element whose local name (namespace prefix ignored) is `name`.
+/// Only as much XML as the track points of TCX and GPX need: no CDATA, no nesting of
+/// the same element.
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let Some(tag_end) = rest.find('>') else { break };
+        let tag = &rest[..tag_end];
+        if tag.starts_with(['/', '?', '!']) || tag.ends_with('/') {
+            continue;
+        }
+        let tag_name = tag.split(char::is_whitespace).next().unwrap_or_default();
+        if tag_name.rsplit(':').next() != Some(name) {
+            continue;
+        }
+        let body = &rest[tag_end + 1..];
+        let close = format!("</{tag_name}>");
+        if let Some(end) = body.find(&close) {
+            found.push(&body[..end]);
+            rest = &body[end + close.len()..];
+        }
+    }
+    found
+}
+
+fn first_text<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    elements(xml, name).first().map(|s| s.trim())
+}
+
+fn parse_tcx(xml: &str) -> Vec<HeartRateSample> {
+    elements(xml, "Trackpoint")
+        .into_iter()
+        .filter_map(|point| {
+            let time = parse_time(first_text(point, "Time")?)?;
+            let heart_rate = *elements(point, "HeartRateBpm").first()?;
+            let bpm = first_text(heart_rate, "Value")?.parse().ok()?;
+            Some(HeartRateSample { time, bpm })
+        })
+        .collect()
+}
+
+/// Heart rate sits in a Garmin `TrackPointExtension`, under whatever prefix the writer chose
+fn parse_gpx(xml: &str) -> Vec<HeartRateSample> {
+    elements(xml, "trkpt")
+        .into_iter()
+        .filter_map(|point| {
+            let time = parse_time(first_text(point, "time")?)?;
+            let bpm = first_text(point, "hr")?.parse().ok()?;
+            Some(HeartRateSample { time, bpm })
+        })
+        .collect()
+}
+
+/// FIT checksum (CRC-16 with the nibble table from the FIT SDK)
+fn fit_crc(data: &[u8]) -> u16 {
+    const TABLE: [u16; 16] = [
+        0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800,
+        0xB401, 0x5000, 0x9C01, 0x8801, 0x4400,
+    ];
+    data.iter().fold(0u16, |mut crc, &byte| {
+        for nibble in [byte & 0x0F, byte >> 4] {
+            let tmp = TABLE[usize::from(crc & 0x0F)];
+            crc = ((crc >> 4) & 0x0FFF) ^ tmp ^ TABLE[usize::from(nibble)];
+        }
+        crc
+    })
+}
+
+struct FitDefinition {
+    global: u16,
+    big_endian: bool,
+    /// Field number and size in bytes
+    fields: Vec<(u8, usize)>,
+    developer_size: usize,
+}
+
+fn take<'a>(data: &'a [u8], pos: &mut usize, end: usize, len: usize) -> Result<&'a [u8], String> {
+    if *pos + len > end {
+        return Err("truncated FIT message".to_string());
+    }
+    let bytes = &data[*pos..*pos + len];
+    *pos += len;
+    Ok(bytes)
+}
+
+/// Timestamp and heart rate of a data message, if it has them
+fn read_fit_message(
+    definition: &FitDefinition,
+    data: &[u8],
+    pos: &mut usize,
+    end: usize,
+) -> Result<(Option<u32>, Option<u32>), String> {
+    let mut timestamp = None;
+    let mut heart_rate = None;
+    for &(number, size) in &definition.fields {
+        let bytes = take(data, pos, end, size)?;
+        if number == FIT_FIELD_TIMESTAMP && size == 4 {
+            let raw = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            let value = if definition.big_endian {
+                u32::from_be_bytes(raw)
+            } else {
+                u32::from_le_bytes(raw)
+            };
+            timestamp = (value != u32::MAX).then_some(value);
+        } else if number == FIT_FIELD_HEART_RATE
+            && size == 1
+            && definition.global == FIT_RECORD_MESSAGE
+            && bytes[0] != u8::MAX
+        {
+            heart_rate = Some(u32::from(bytes[0]));
+        }
+    }
+    take(data, pos, end, definition.developer_size)?;
+    Ok((timestamp, heart_rate))
+}
+
+fn parse_fit(data: &[u8]) -> Result<Vec<HeartRateSample>, String> {
+    let header_size = usize::from(*data.first().ok_or("empty FIT file")?);
+    if header_size < 12 || data.get(8..12) != Some(b".FIT") {
+        return Err("not a FIT file".to_string());
+    }
+    let data_size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let end = header_size + data_size;
+    if data.len() < end + 2 {
+        return Err("truncated FIT file".to_string());
+    }
+    if fit_crc(&data[..end]) != u16::from_le_bytes([data[end], data[end + 1]]) {
+        return Err("FIT checksum mismatch".to_string());
+    }
+
+    let mut definitions: Vec<Option<FitDefinition>> = (0..16).map(|_| None).collect();
+    let mut last_timestamp: Option<u32> = None;
+    let mut samples = Vec::new();
+    let mut pos = header_size;
+    while pos < end {
+        let header = take(data, &mut pos, end, 1)?[0];
+        if header & 0x40 != 0 && header & 0x80 == 0 {
+            let fixed = take(data, &mut pos, end, 5)?;
+            let big_endian = fixed[1] == 1;
+            let global_bytes = [fixed[2], fixed[3]];
+            let fields = take(data, &mut pos, end, usize::from(fixed[4]) * 3)?
+                .chunks(3)
+                .map(|f| (f[0], usize::from(f[1])))
+                .collect();
+            let developer_size = if header & 0x20 != 0 {
+                let count = usize::from(take(data, &mut pos, end, 1)?[0]);
+                take(data, &mut pos, end, count * 3)?.chunks(3).map(|f| usize::from(f[1])).sum()
+            } else {
+                0
+            };
+            definitions[usize::from(header & 0x0F)] = Some(FitDefinition {
+                global: if big_endian {
+                    u16::from_be_bytes(global_bytes)
+                } else {
+                    u16::from_le_bytes(global_bytes)
+                },
+                big_endian,
+                fields,
+                developer_size,
+            });
+            continue;
+        }
+
+        // Compressed headers carry a 5-bit rollover offset from the last full timestamp
+        let (local, compressed) = if header & 0x80 != 0 {
+            let last = last_timestamp.ok_or("compressed timestamp before any timestamp")?;
+            let offset = u32::from(header & 0x1F);
+            let mut timestamp = (last & !0x1F) | offset;
+            if offset < last & 0x1F {
+                timestamp = timestamp.wrapping_add(0x20);
+            }
+            (usize::from((header >> 5) & 0x03), Some(timestamp))
+        } else {
+            (usize::from(header & 0x0F), None)
+        };
+        let definition =
+            definitions[local].as_ref().ok_or("FIT data message without a definition")?;
+        let (field_timestamp, heart_rate) = read_fit_message(definition, data, &mut pos, end)?;
+        let timestamp = compressed.or(field_timestamp);
+        if timestamp.is_some() {
+            last_timestamp = timestamp;
+        }
+        if let (Some(timestamp), Some(bpm)) = (timestamp, heart_rate) {
+            if let Some(time) =
+                DateTime::from_timestamp(FIT_EPOCH_OFFSET_S + i64::from(timestamp), 0)
+            {
+                samples.push(HeartRateSample { time, bpm });
+            }
+        }
+    }
+    Ok(samples)
+}
+
+// ============================================================================
+// HEART-RATE ENERGY AND SET ALIGNMENT
+// ============================================================================
+
+/// Energy expenditure from heart rate (Keytel et al., 2005) in kcal/min, never negative.
+/// The equation is fitted on steady exercise; at resting heart rates it drops to zero.
+#[must_use]
+pub fn keytel_kcal_per_min(sex: Sex, age_years: f64, body_weight_kg: f64, bpm: f64) -> f64 {
+    let kj_per_min = match sex {
+        Sex::Male => -55.0969 + 0.6309 * bpm + 0.1988 * body_weight_kg + 0.2017 * age_years,
+        Sex::Female => -20.4022 + 0.4472 * bpm - 0.1263 * body_weight_kg + 0.074 * age_years,
+    };
+    (kj_per_min / KJ_PER_KCAL).max(0.0)
+}
+
+/// Heart-rate energy of a recording. Each sample holds until the next one; gaps longer
+/// than a minute are left out.
+#[must_use]
+pub fn heart_rate_energy_kcal(
+    samples: &[HeartRateSample],
+    sex: Sex,
+    age_years: f64,
+    body_weight_kg: f64,
+) -> f64 {
+    samples
+        .windows(2)
+        .map(|pair| {
+            let seconds = (pair[1].time - pair[0].time).num_seconds();
+            if seconds > MAX_SAMPLE_GAP_S {
+                return 0.0;
+            }
+            let per_min =
+                keytel_kcal_per_min(sex, age_years, body_weight_kg, f64::from(pair[0].bpm));
+            per_min * seconds as f64 / 60.0
+        })
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Time under tension of a set ending when it was logged
+#[must_use]
+pub fn set_window(performed_at: DateTime<Utc>, reps: u32, tempo: &Tempo) -> SetWindow {
+    let rep_s = tempo.eccentric_s + tempo.pause_bottom_s + tempo.concentric_s + tempo.pause_top_s;
+    let seconds = (rep_s * f64::from(reps)).max(MIN_SET_DURATION_S);
+    SetWindow {
+        start: performed_at - Duration::milliseconds((seconds * 1000.0) as i64),
+        end: performed_at,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeartRateSummary {
+    pub avg_bpm: f64,
+    pub max_bpm: u32,
+    pub samples: usize,
+}
+
+/// Average and peak of the samples inside the window; None without samples
+#[must_use]
+pub fn summarize_window(
+    samples: &[HeartRateSample],
+    window: &SetWindow,
+) -> Option<HeartRateSummary> {
+    let inside: Vec<u32> = samples
+        .iter()
+        .filter(|s| s.time >= window.start && s.time <= window.end)
+        .map(|s| s.bpm)
+        .collect();
+    let max_bpm = *inside.iter().max()?;
+    Some(HeartRateSummary {
+        avg_bpm: f64::from(inside.iter().sum::<u32>()) / inside.len() as f64,
+        max_bpm,
+        samples: inside.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const FIT: &[u8] = include_bytes!("../../../tests/fixtures/sample.fit");
+    const TCX: &[u8] = include_bytes!("../../../tests/fixtures/sample.tcx");
+    const GPX: &[u8] = include_bytes!("../../../tests/fixtures/sample.gpx");
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap() + Duration::seconds(seconds)
+    }
+
+    fn bpm_by_offset(samples: &[HeartRateSample]) -> Vec<(i64, u32)> {
+        samples.iter().map(|s| ((s.time - at(0)).num_seconds(), s.bpm)).collect()
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(WearableFormat::detect(FIT), Some(WearableFormat::Fit));
+        assert_eq!(WearableFormat::detect(TCX), Some(WearableFormat::Tcx));
+        assert_eq!(WearableFormat::detect(GPX), Some(WearableFormat::Gpx));
+        assert_eq!(WearableFormat::detect(b"hello"), None);
+    }
+
+    #[test]
+    fn test_parse_fit_fixture() {
+        // Little- and big-endian records, a developer field, an invalid reading
+        // and two compressed timestamps
+        let samples = parse_heart_rate(WearableFormat::Fit, FIT).unwrap();
+        assert_eq!(bpm_by_offset(&samples), vec![(0, 90), (20, 120), (25, 140), (40, 150)]);
+    }
+
+    #[test]
+    fn test_parse_fit_rejects_corruption() {
+        let mut corrupt = FIT.to_vec();
+        corrupt[20] ^= 0xFF;
+        assert_eq!(
+            parse_heart_rate(WearableFormat::Fit, &corrupt).unwrap_err(),
+            "FIT checksum mismatch"
+        );
+        assert!(parse_heart_rate(WearableFormat::Fit, &FIT[..40]).is_err());
+    }
+
+    #[test]
+    fn test_parse_tcx_fixture() {
+        let samples = parse_heart_rate(WearableFormat::Tcx, TCX).unwrap();
+        assert_eq!(bpm_by_offset(&samples), vec![(0, 92), (10, 118), (20, 135), (30, 128)]);
+    }
+
+    #[test]
+    fn test_parse_gpx_fixture() {
+        let samples = parse_heart_rate(WearableFormat::Gpx, GPX).unwrap();
+        assert_eq!(bpm_by_offset(&samples), vec![(0, 95), (10, 121)]);
+        assert!(parse_heart_rate(WearableFormat::Gpx, b"<gpx></gpx>").is_err());
+    }
+
+    #[test]
+    fn test_keytel_energy() {
+        // 30-year-old 80 kg man at 140 bpm: (-55.0969 + 88.326 + 15.904 + 6.051) / 4.184
+        let per_min = keytel_kcal_per_min(Sex::Male, 30.0, 80.0, 140.0);
+        assert!((per_min - 13.19).abs() < 0.01, "{per_min}");
+        assert_eq!(keytel_kcal_per_min(Sex::Female, 30.0, 60.0, 40.0), 0.0);
+
+        // One minute at 140 bpm, then a gap that is left out
+        let samples = [
+            HeartRateSample { time: at(0), bpm: 140 },
+            HeartRateSample { time: at(60), bpm: 140 },
+            HeartRateSample { time: at(600), bpm: 140 },
+        ];
+        let kcal = heart_rate_energy_kcal(&samples, Sex::Male, 30.0, 80.0);
+        assert!((kcal - per_min).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_set_alignment() {
+        let samples = parse_heart_rate(WearableFormat::Fit, FIT).unwrap();
+        // 5 reps of a 3 s tempo logged at +40 s cover +25 s to +40 s
+        let window = set_window(at(40), 5, &Tempo::standard());
+        assert_eq!(window.start, at(25));
+        let summary = summarize_window(&samples, &window).unwrap();
+        assert_eq!((summary.avg_bpm, summary.max_bpm, summary.samples), (145.0, 150, 2));
+
+        // A single rep still spans the minimum window
+        assert_eq!(set_window(at(40), 1, &Tempo::standard()).start, at(30));
+        assert!(summarize_window(&samples, &set_window(at(100), 1, &Tempo::standard())).is_none());
+    }
+}
@@ -0,0 +1,315 @@
+use crate::tools::metabolism::{mifflin_st_jeor_bmr, ActivityLevel, Sex};
+use serde::{Deserialize, Serialize};
+
+/// Energy density of fat tissue (kcal/kg)
+const RHO_FAT: f64 = 9440.0;
+/// Energy density of lean tissue (kcal/kg)
+const RHO_LEAN: f64 = 1816.0;
+/// Maintenance cost of fat mass (kcal/kg/day)
+const GAMMA_FAT: f64 = 3.2;
+/// Maintenance cost of lean mass (kcal/kg/day)
+const GAMMA_LEAN: f64 = 22.0;
+/// Cost of depositing or mobilising fat (kcal/kg)
+const ETA_FAT: f64 = 180.0;
+/// Cost of depositing or mobilising lean tissue (kcal/kg)
+const ETA_LEAN: f64 = 230.0;
+/// Thermic effect of food as a fraction of the intake change
+const BETA_TEF: f64 = 0.1;
+/// Adaptive thermogenesis as a fraction of the intake change
+const BETA_AT: f64 = 0.14;
+/// Time constant of adaptive thermogenesis (days)
+const TAU_AT: f64 = 14.0;
+/// Forbes constant for the fat/lean partition of weight change (kg)
+const FORBES_C: f64 = 10.4 * RHO_LEAN / RHO_FAT;
+
+/// Longest simulation the API will run
+pub const MAX_SIMULATION_DAYS: u32 = 3650;
+
+/// Constant daily intake held for a number of days
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntakePhase {
+    pub days: u32,
+    pub intake_kcal: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationRequest {
+    pub weight_kg: f64,
+    pub body_fat_percentage: f64,
+    pub height_cm: f64,
+    pub age_years: f64,
+    pub sex: Sex,
+    pub activity_level: ActivityLevel,
+    /// Phases are applied one after another starting on day 0
+    pub intake_schedule: Vec<IntakePhase>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationPoint {
+    pub day: u32,
+    pub weight_kg: f64,
+    pub fat_mass_kg: f64,
+    pub lean_mass_kg: f64,
+    pub body_fat_percentage: f64,
+    pub intake_kcal: f64,
+    pub expenditure_kcal: f64,
+    /// Change in expenditure from metabolic adaptation (negative while dieting)
+    pub adaptive_thermogenesis_kcal: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimulationResult {
+    pub baseline_bmr_kcal: f64,
+    /// Intake that keeps the starting weight stable
+    pub maintenance_kcal: f64,
+    pub points: Vec<SimulationPoint>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct State {
+    fat: f64,
+    lean: f64,
+    adaptive_thermogenesis: f64,
+}
+
+/// Parameters fixed at the start of a simulation
+struct Model {
+    baseline_intake: f64,
+    /// Energy cost of physical activity per kg body weight (kcal/kg/day)
+    delta: f64,
+    /// Baseline expenditure not explained by tissue maintenance or activity
+    k: f64,
+}
+
+impl Model {
+    fn new(fat: f64, lean: f64, rmr: f64, pal: f64) -> Self {
+        let weight = fat + lean;
+        let baseline_intake = pal * rmr;
+        let delta = (1.0 - BETA_TEF).mul_add(pal, -1.0) * rmr / weight;
+        let k = baseline_intake - GAMMA_FAT * fat - GAMMA_LEAN * lean - delta * weight;
+        Self { baseline_intake, delta, k }
+    }
+
+    /// Expenditure without the tissue deposition costs
+    fn base_expenditure(&self, state: State, intake: f64) -> f64 {
+        let weight = state.fat + state.lean;
+        self.k
+            + GAMMA_FAT * state.fat
+            + GAMMA_LEAN * state.lean
+            + self.delta * weight
+            + BETA_TEF * (intake - self.baseline_intake)
+            + state.adaptive_thermogenesis
+    }
+
+    /// Daily rates of change and the total expenditure at `state`
+    fn derivatives(&self, state: State, intake: f64) -> (State, f64) {
+        let p = FORBES_C / (FORBES_C + state.fat.max(0.0));
+        let imbalance_before_deposition = intake - self.base_expenditure(state, intake);
+        // Deposition costs depend on the rates themselves, so solve for the net imbalance
+        let imbalance = imbalance_before_deposition
+            / (1.0 + ETA_FAT * (1.0 - p) / RHO_FAT + ETA_LEAN * p / RHO_LEAN);
+        let d_fat = (1.0 - p) * imbalance / RHO_FAT;
+        let d_lean = p * imbalance / RHO_LEAN;
+        let d_at =
+            (BETA_AT * (intake - self.baseline_intake) - state.adaptive_thermogenesis) / TAU_AT;
+        (State { fat: d_fat, lean: d_lean, adaptive_thermogenesis: d_at }, intake - imbalance)
+    }
+
+    /// Advance one day with fourth-order Runge-Kutta
+    fn step(&self, s: State, intake: f64) -> State {
+        let add = |s: State, d: State, h: f64| State {
+            fat: d.fat.mul_add(h, s.fat),
+            lean: d.lean.mul_add(h, s.lean),
+            adaptive_thermogenesis: d.adaptive_thermogenesis.mul_add(h, s.adaptive_thermogenesis),
+        };
+        let (k1, _) = self.derivatives(s, intake);
+        let (k2, _) = self.derivatives(add(s, k1, 0.5), intake);
+        let (k3, _) = self.derivatives(add(s, k2, 0.5), intake);
+        let (k4, _) = self.derivatives(add(s, k3, 1.0), intake);
+        let weighted = |f: fn(State) -> f64| (f(k1) + 2.0 * f(k2) + 2.0 * f(k3) + f(k4)) / 6.0;
+        State {
+            fat: s.fat + weighted(|d| d.fat),
+            lean: s.lean + weighted(|d| d.lean),
+            adaptive_thermogenesis: s.adaptive_thermogenesis
+                + weighted(|d| d.adaptive_thermogenesis),
+        }
+    }
+}
+
+fn validate(request: &SimulationRequest) -> Result<u32, String> {
+    let positive = [
+        ("weight_kg", request.weight_kg),
+        ("height_cm", request.height_cm),
+        ("age_years", request.age_years),
+    ];
+    if let Some((name, _)) = positive.iter().find(|(_, v)| !(v.is_finite() && *v > 0.0)) {
+        return Err(format!("{name} must be positive"));
+    }
+    if !(1.0..=70.0).contains(&request.body_fat_percentage) {
+        return Err("body_fat_percentage must be between 1 and 70".to_string());
+    }
+    if request.intake_schedule.is_empty() {
+        return Err("intake_schedule must contain at least one phase".to_string());
+    }
+    if request.intake_schedule.iter().any(|p| !(p.intake_kcal.is_finite() && p.intake_kcal >= 0.0))
+    {
+        return Err("intake_kcal must be zero or positive".to_string());
+    }
+    request
+        .intake_schedule
+        .iter()
+        .try_fold(0u32, |total, p| total.checked_add(p.days))
+        .filter(|days| (1..=MAX_SIMULATION_DAYS).contains(days))
+        .ok_or_else(|| format!("schedule must cover 1 to {MAX_SIMULATION_DAYS} days"))
+}
+
+/// Project body weight and composition day by day for a planned intake schedule.
+///
+/// Follows the Hall (2011) dynamic model: the energy imbalance is split between fat and lean
+/// tissue with the Forbes partition, expenditure tracks body composition, the thermic effect
+/// of food and an adaptive thermogenesis term that lags intake changes.
+pub fn simulate_weight(request: &SimulationRequest) -> Result<SimulationResult, String> {
+    let days = validate(request)?;
+
+    let fat = request.weight_kg * request.body_fat_percentage / 100.0;
+    let lean = request.weight_kg - fat;
+    let bmr =
+        mifflin_st_jeor_bmr(request.weight_kg, request.height_cm, request.age_years, request.sex);
+    let model = Model::new(fat, lean, bmr, request.activity_level.pal());
+
+    let intakes = request
+        .intake_schedule
+        .iter()
+        .flat_map(|phase| std::iter::repeat_n(phase.intake_kcal, phase.days as usize));
+
+    let mut state = State { fat, lean, adaptive_thermogenesis: 0.0 };
+    let mut points = Vec::with_capacity(days as usize + 1);
+    for (day, intake) in (0..).zip(intakes) {
+        let (_, expenditure) = model.derivatives(state, intake);
+        points.push(point(day, state, intake, expenditure));
+        state = model.step(state, intake);
+        // Also catches a state that overflowed to infinity or NaN
+        let viable = |mass: f64| mass.is_finite() && mass > 0.0;
+        if !(viable(state.fat) && viable(state.lean)) {
+            return Err(format!("body composition is not viable after day {day}"));
+        }
+    }
+    // Final state after the last day, evaluated at the last planned intake
+    let last_intake = request.intake_schedule.last().map_or(0.0, |p| p.intake_kcal);
+    let (_, expenditure) = model.derivatives(state, last_intake);
+    points.push(point(days, state, last_intake, expenditure));
+
+    Ok(SimulationResult { baseline_bmr_kcal: bmr, maintenance_kcal: model.baseline_intake, points })
+}
+
+fn point(day: u32, state: State, intake: f64, expenditure: f64) -> SimulationPoint {
+    let weight = state.fat + state.lean;
+    SimulationPoint {
+        day,
+        weight_kg: weight,
+        fat_mass_kg: state.fat,
+        lean_mass_kg: state.lean,
+        body_fat_percentage: state.fat / weight * 100.0,
+        intake_kcal: intake,
+        expenditure_kcal: expenditure,
+        adaptive_thermogenesis_kcal: state.adaptive_thermogenesis,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(intake_schedule: Vec<IntakePhase>) -> SimulationRequest {
+        SimulationRequest {
+            weight_kg: 90.0,
+            body_fat_percentage: 30.0,
+            height_cm: 180.0,
+            age_years: 35.0,
+            sex: Sex::Male,
+            activity_level: ActivityLevel::Moderate,
+            intake_schedule,
+        }
+    }
+
+    fn maintenance() -> f64 {
+        simulate_weight(&request(vec![IntakePhase { days: 1, intake_kcal: 2000.0 }]))
+            .unwrap()
+            .maintenance_kcal
+    }
+
+    #[test]
+    fn test_maintenance_intake_keeps_weight_stable() {
+        let kcal = maintenance();
+        let result =
+            simulate_weight(&request(vec![IntakePhase { days: 365, intake_kcal: kcal }])).unwrap();
+        assert_eq!(result.points.len(), 366);
+        let last = result.points.last().unwrap();
+        assert!((last.weight_kg - 90.0).abs() < 1e-6);
+        assert!(last.adaptive_thermogenesis_kcal.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_deficit_loses_mostly_fat_and_slows_down() {
+        let kcal = maintenance() - 500.0;
+        let result =
+            simulate_weight(&request(vec![IntakePhase { days: 720, intake_kcal: kcal }])).unwrap();
+        let start = &result.points[0];
+        let month = &result.points[30];
+        let year = &result.points[365];
+        let end = result.points.last().unwrap();
+
+        assert!(month.weight_kg < start.weight_kg);
+        let fat_lost = start.fat_mass_kg - year.fat_mass_kg;
+        let lean_lost = start.lean_mass_kg - year.lean_mass_kg;
+        assert!(fat_lost > 2.0 * lean_lost, "fat {fat_lost} lean {lean_lost}");
+        assert!(year.adaptive_thermogenesis_kcal < 0.0);
+
+        // The second year loses less than the first as expenditure falls towards intake
+        let first_year = start.weight_kg - year.weight_kg;
+        let second_year = year.weight_kg - end.weight_kg;
+        assert!(second_year < first_year);
+        // Much slower than the 7700 kcal/kg rule of thumb would predict
+        assert!(first_year < 500.0 * 365.0 / 7700.0);
+    }
+
+    #[test]
+    fn test_phases_run_in_order() {
+        let kcal = maintenance();
+        let result = simulate_weight(&request(vec![
+            IntakePhase { days: 10, intake_kcal: kcal - 800.0 },
+            IntakePhase { days: 10, intake_kcal: kcal + 800.0 },
+        ]))
+        .unwrap();
+        assert_eq!(result.points[5].intake_kcal, kcal - 800.0);
+        assert_eq!(result.points[15].intake_kcal, kcal + 800.0);
+        assert!(result.points[10].weight_kg < result.points[0].weight_kg);
+        assert!(result.points[20].weight_kg > result.points[10].weight_kg);
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        assert!(simulate_weight(&request(vec![])).is_err());
+        assert!(
+            simulate_weight(&request(vec![IntakePhase { days: 0, intake_kcal: 2000.0 }])).is_err()
+        );
+        assert!(simulate_weight(&request(vec![IntakePhase {
+            days: MAX_SIMULATION_DAYS + 1,
+            intake_kcal: 2000.0
+        }]))
+        .is_err());
+
+        let mut bad = request(vec![IntakePhase { days: 10, intake_kcal: 2000.0 }]);
+        bad.body_fat_percentage = 0.0;
+        assert!(simulate_weight(&bad).is_err());
+        bad.body_fat_percentage = 20.0;
+        bad.weight_kg = f64::NAN;
+        assert!(simulate_weight(&bad).is_err());
+        bad.weight_kg = 90.0;
+        bad.age_years = f64::NAN;
+        assert!(simulate_weight(&bad).is_err());
+        bad.age_years = 35.0;
+        bad.intake_schedule[0].intake_kcal = f64::NAN;
+        assert!(simulate_weight(&bad).is_err());
+    }
+}
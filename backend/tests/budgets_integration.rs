@@ -0,0 +1,143 @@
+use axum_test::TestServer;
+use sqlx::PgPool;
+use std::env;
+use std::sync::Arc;
+use tools_backend::tools::auth as auth_tools;
+
+async fn setup_test_server() -> Option<(TestServer, String)> {
+    let db_url = match env::var("TEST_DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("TEST_DATABASE_URL not set — skipping budgets integration test");
+            return None;
+        }
+    };
+
+    let redis_url = env::var("REDIS_URL").ok()?;
+
+    let pool = PgPool::connect(&db_url).await.expect("connect db");
+
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap_or_else(|e| {
+        let msg = e.to_string();
+        if msg.contains("duplicate key value") || msg.contains("_sqlx_migrations_pkey") {
+            eprintln!("Notice: migrations appear already applied. Treating as success.");
+        } else {
+            panic!("Migration failed: {}", e);
+        }
+    });
+
+    let pool = Arc::new(pool);
+
+    let session_store = {
+        let s = tools_backend::tools::session::SessionStore::new(&redis_url, "tools_test")
+            .await
+            .expect("create store");
+        Arc::new(tokio::sync::Mutex::new(s))
+    };
+
+    let app = tools_backend::app::build_app(pool.clone(), Some(session_store.clone()));
+    let server = TestServer::new(app);
+
+    let email = format!("budget_test_{}@example.com", uuid::Uuid::new_v4());
+    let user_id = auth_tools::register_user(&pool, &email, "password123", Some("Budget Tester"))
+        .await
+        .expect("register user");
+
+    let sid = {
+        let mut store_guard = session_store.lock().await;
+        store_guard.create_session(user_id, 3600).await.expect("create session")
+    };
+
+    Some((server, format!("sid={sid}; HttpOnly; Path=/")))
+}
+
+#[tokio::test]
+async fn test_budgets_crud_and_compare() {
+    let (server, cookie_str) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    // 1. Create a budget with carry-over
+    let resp = server
+        .post("/api/tools/budgets")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "category": "cardTransactions",
+            "monthlyAmount": 100.0,
+            "carryOver": true,
+            "startsOn": "2024-01"
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Create budget failed: {}", resp.text());
+    let id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+
+    // 2. A second budget for the same category conflicts
+    let resp_dup = server
+        .post("/api/tools/budgets")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"category": "cardTransactions", "monthlyAmount": 50.0, "startsOn": "2024-01"}))
+        .await;
+    assert_eq!(resp_dup.status_code(), 409);
+
+    // 3. Negative or oversized amounts, unknown categories and a missing start month are rejected
+    for body in [
+        serde_json::json!({"category": "cash26Data", "monthlyAmount": -1.0, "startsOn": "2024-01"}),
+        serde_json::json!({"category": "groceries", "monthlyAmount": 10.0, "startsOn": "2024-01"}),
+        serde_json::json!({"category": "cash26Data", "monthlyAmount": 10.0}),
+        serde_json::json!({"category": "cash26Data", "monthlyAmount": 1e10, "startsOn": "2024-01"}),
+    ] {
+        let resp_invalid =
+            server.post("/api/tools/budgets").add_header("Cookie", &cookie_str).json(&body).await;
+        assert_eq!(resp_invalid.status_code(), 400, "accepted {body}");
+    }
+
+    // 4. List
+    let list: serde_json::Value =
+        server.get("/api/tools/budgets").add_header("Cookie", &cookie_str).await.json();
+    let budgets = list["budgets"].as_array().expect("budgets array");
+    assert_eq!(budgets.len(), 1);
+    assert_eq!(budgets[0]["startsOn"], "2024-01-01");
+    assert_eq!(budgets[0]["monthlyAmount"].as_str().unwrap().parse::<f64>().unwrap(), 100.0);
+
+    // 5. Compare: January leaves 40 unspent, February has 140 available
+    let data = serde_json::json!({
+        "id": "export",
+        "created": "2024-02-10",
+        "data": {
+            "cardTransactions": [
+                {"end_amount": 60.0, "transaction_date": "2024-01-15", "merchant_name": "Shop"},
+                {"end_amount": 70.0, "transaction_date": "2024-02-10", "merchant_name": "Shop"}
+            ]
+        }
+    });
+    let resp_compare = server
+        .post("/api/tools/budgets/compare")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"data": data, "month": "2024-02"}))
+        .await;
+    assert!(resp_compare.status_code().is_success(), "Compare failed: {}", resp_compare.text());
+    let report: serde_json::Value = resp_compare.json();
+    let status = &report["budgets"][0];
+    assert_eq!(status["carriedOver"].as_f64().unwrap(), 40.0);
+    assert_eq!(status["spent"].as_f64().unwrap(), 70.0);
+    // 70 spent in 10 of 29 days projects to 203 against 140 available
+    assert_eq!(status["state"], "at_risk");
+    assert!(report["import_report"].is_object());
+
+    // 6. Update and delete
+    let resp_update = server
+        .put(&format!("/api/tools/budgets/{id}"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"category": "cardTransactions", "monthlyAmount": 250.0}))
+        .await;
+    assert!(resp_update.status_code().is_success());
+
+    let resp_delete =
+        server.delete(&format!("/api/tools/budgets/{id}")).add_header("Cookie", &cookie_str).await;
+    assert!(resp_delete.status_code().is_success());
+
+    let resp_missing =
+        server.delete(&format!("/api/tools/budgets/{id}")).add_header("Cookie", &cookie_str).await;
+    assert_eq!(resp_missing.status_code(), 404);
+}
@@ -0,0 +1,169 @@
+use axum_test::TestServer;
+use sqlx::PgPool;
+use std::env;
+use std::sync::Arc;
+use tools_backend::tools::auth as auth_tools;
+
+async fn setup_test_server() -> Option<(TestServer, String)> {
+    let db_url = match env::var("TEST_DATABASE_URL") {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("TEST_DATABASE_URL not set — skipping energy journal integration test");
+            return None;
+        }
+    };
+
+    let redis_url = env::var("REDIS_URL").ok()?;
+
+    let pool = PgPool::connect(&db_url).await.expect("connect db");
+
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap_or_else(|e| {
+        let msg = e.to_string();
+        if msg.contains("duplicate key value") || msg.contains("_sqlx_migrations_pkey") {
+            eprintln!("Notice: migrations appear already applied. Treating as success.");
+        } else {
+            panic!("Migration failed: {}", e);
+        }
+    });
+
+    let pool = Arc::new(pool);
+
+    let session_store = {
+        let s = tools_backend::tools::session::SessionStore::new(&redis_url, "tools_test")
+            .await
+            .expect("create store");
+        Arc::new(tokio::sync::Mutex::new(s))
+    };
+
+    let app = tools_backend::app::build_app(pool.clone(), Some(session_store.clone()));
+    let server = TestServer::new(app);
+
+    let email = format!("journal_test_{}@example.com", uuid::Uuid::new_v4());
+    let user_id = auth_tools::register_user(&pool, &email, "password123", Some("Journal Tester"))
+        .await
+        .expect("register user");
+
+    let sid = {
+        let mut store_guard = session_store.lock().await;
+        store_guard.create_session(user_id, 3600).await.expect("create session")
+    };
+
+    Some((server, format!("sid={sid}; HttpOnly; Path=/")))
+}
+
+#[tokio::test]
+async fn test_energy_journal_summary() {
+    let (server, cookie_str) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    // 1. Weight in the morning, intake in the evening merge into one day
+    let resp = server
+        .post("/api/tools/energy-journal")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"date": "2024-03-01", "bodyWeightKg": 82.0}))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Create entry failed: {}", resp.text());
+    let id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+
+    let resp = server
+        .post("/api/tools/energy-journal")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"date": "2024-03-01", "intakeKcal": 1500}))
+        .await;
+    assert_eq!(resp.status_code(), 200);
+    assert_eq!(resp.json::<serde_json::Value>()["id"].as_str().unwrap(), id);
+
+    for day in 2..=28 {
+        let mut entry =
+            serde_json::json!({"date": format!("2024-03-{day:02}"), "intakeKcal": 1500});
+        if day == 28 {
+            entry["bodyWeightKg"] = serde_json::json!(80.0);
+        }
+        let resp = server
+            .post("/api/tools/energy-journal")
+            .add_header("Cookie", &cookie_str)
+            .json(&entry)
+            .await;
+        assert!(resp.status_code().is_success());
+    }
+
+    // 2. Empty entries and values the columns cannot hold are rejected
+    for body in [
+        serde_json::json!({"date": "2024-03-02"}),
+        serde_json::json!({"date": "2024-03-02", "bodyFatPercentage": 99.96}),
+        serde_json::json!({"date": "2024-03-02", "intakeKcal": 1.0e7}),
+    ] {
+        let resp_invalid = server
+            .post("/api/tools/energy-journal")
+            .add_header("Cookie", &cookie_str)
+            .json(&body)
+            .await;
+        assert_eq!(resp_invalid.status_code(), 400, "accepted {body}");
+    }
+
+    // 3. List
+    let list: serde_json::Value = server
+        .get("/api/tools/energy-journal?from=2024-03-01&to=2024-03-31")
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    let entries = list["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 28);
+    let first = entries.last().unwrap();
+    assert_eq!(first["intakeKcal"].as_str().unwrap().parse::<f64>().unwrap(), 1500.0);
+    assert_eq!(first["bodyWeightKg"].as_str().unwrap().parse::<f64>().unwrap(), 82.0);
+
+    // 4. Summary needs a height when no body measurement has one
+    let resp_no_height = server
+        .get(
+            "/api/tools/energy-journal/summary?from=2024-03-01&to=2024-03-31&sex=male&age_years=30",
+        )
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert_eq!(resp_no_height.status_code(), 400);
+    assert_eq!(
+        resp_no_height.json::<serde_json::Value>()["error"],
+        "height_cm is required when no body measurement has a height"
+    );
+
+    let resp_summary = server
+        .get("/api/tools/energy-journal/summary?from=2024-03-01&to=2024-03-31&sex=male&age_years=30&height_cm=180")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert!(resp_summary.status_code().is_success(), "Summary failed: {}", resp_summary.text());
+    let body: serde_json::Value = resp_summary.json();
+    assert_eq!(body["formula"], "mifflin_st_jeor");
+    let summary = &body["summary"];
+    assert_eq!(summary["daysWithIntake"], 28);
+    assert_eq!(summary["weightLossKg"].as_f64().unwrap(), 2.0);
+    assert!(summary["cumulativeDeficitKcal"].as_f64().unwrap() > 0.0);
+    assert!(summary["fatLoss"]["is_valid"].is_boolean());
+
+    // 5. Adaptive TDEE from the same entries
+    let resp_tdee = server
+        .get("/api/tools/energy-journal/tdee?from=2024-03-01&to=2024-03-31&smoothing=kalman&target_weekly_change_kg=-0.5")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert!(resp_tdee.status_code().is_success(), "TDEE failed: {}", resp_tdee.text());
+    let tdee: serde_json::Value = resp_tdee.json();
+    assert_eq!(tdee["smoothing"], "kalman");
+    assert_eq!(tdee["history"].as_array().unwrap().len(), 28);
+    assert!(tdee["current"]["tdeeKcal"].as_f64().unwrap() > 1500.0);
+    assert!(tdee["target"]["dailyKcal"].is_number());
+
+    let resp_window = server
+        .get("/api/tools/energy-journal/tdee?from=2024-03-01&to=2024-03-31&window_days=1")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert_eq!(resp_window.status_code(), 400);
+    assert_eq!(resp_window.json::<serde_json::Value>()["error"], "window_days must be at least 2");
+
+    // 6. Delete
+    let resp_delete = server
+        .delete(&format!("/api/tools/energy-journal/{id}"))
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert!(resp_delete.status_code().is_success());
+}
@@ -97,6 +97,24 @@ async fn test_training_measurements_crud() {
         .await;
 
     assert_eq!(resp_invalid.status_code(), 400, "Should reject negative weight");
+    assert_eq!(resp_invalid.json::<serde_json::Value>()["error"], "bodyWeightKg must be positive");
+
+    // Exactly one of bodyWeightKg and bodyWeight
+    for body in [
+        serde_json::json!({"heightCm": 180.0}),
+        serde_json::json!({"bodyWeightKg": 80.0, "bodyWeight": 176.0}),
+        // Beyond what the columns hold
+        serde_json::json!({"bodyWeightKg": 20000.0}),
+        serde_json::json!({"bodyWeightKg": 80.0, "waistCm": 12000.0}),
+        serde_json::json!({"bodyWeightKg": 80.0, "skinfoldThighMm": 1500.0}),
+    ] {
+        let resp = server
+            .post("/api/tools/training/measurements")
+            .add_header("Cookie", &cookie_str)
+            .json(&body)
+            .await;
+        assert_eq!(resp.status_code(), 400);
+    }
 
     // 3. List measurements
     let resp_list =
@@ -174,6 +192,66 @@ async fn test_training_measurements_crud() {
     );
 }
 
+#[tokio::test]
+async fn test_body_composition_history() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    // Height only on the first measurement; the second reuses it
+    for body in [
+        serde_json::json!({
+            "bodyWeightKg": 82.0,
+            "heightCm": 180.0,
+            "neckCm": 38.0,
+            "waistCm": 88.0,
+            "measuredAt": "2024-03-01T08:00:00Z"
+        }),
+        serde_json::json!({
+            "bodyWeightKg": 80.0,
+            "neckCm": 38.0,
+            "waistCm": 85.0,
+            "skinfoldChestMm": 15.0,
+            "skinfoldAbdominalMm": 25.0,
+            "skinfoldThighMm": 20.0,
+            "measuredAt": "2024-04-01T08:00:00Z"
+        }),
+        serde_json::json!({ "bodyWeightKg": 79.0, "measuredAt": "2024-04-15T08:00:00Z" }),
+    ] {
+        let resp = server
+            .post("/api/tools/training/measurements")
+            .add_header("Cookie", &cookie_str)
+            .json(&body)
+            .await;
+        assert!(resp.status_code().is_success(), "Create measurement failed: {}", resp.text());
+    }
+
+    let resp = server
+        .get("/api/tools/training/measurements/body-composition?sex=male&age_years=30")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert!(resp.status_code().is_success(), "Body composition failed: {}", resp.text());
+    let json: serde_json::Value = resp.json();
+    let history = json["history"].as_array().unwrap();
+    assert_eq!(history.len(), 2, "measurement without tape or skinfolds is skipped");
+
+    assert_eq!(history[0]["estimates"].as_array().unwrap().len(), 1);
+    let latest = history[1]["estimates"].as_array().unwrap();
+    assert_eq!(latest[0]["method"], "navy");
+    assert_eq!(latest[1]["method"], "jackson_pollock3");
+    let navy_bf = latest[0]["bodyFatPercentage"].as_f64().unwrap();
+    assert!((navy_bf - 16.11).abs() < 0.01, "navy: {navy_bf}");
+    let lean = latest[0]["leanMassKg"].as_f64().unwrap();
+    assert!((latest[0]["ffmi"].as_f64().unwrap() - lean / 3.24).abs() < 1e-9);
+
+    let resp_bad = server
+        .get("/api/tools/training/measurements/body-composition")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert_eq!(resp_bad.status_code(), 400, "sex is required");
+}
+
 #[tokio::test]
 async fn test_training_plans_crud() {
     let (server, cookie_str, _) = match setup_test_server().await {
@@ -278,3 +356,1559 @@ async fn test_training_sessions_crud() {
     assert_eq!(first_item.get("id").unwrap().as_str().unwrap(), id);
     assert_eq!(first_item.get("name").unwrap().as_str().unwrap(), "Morning Workout");
 }
+
+#[tokio::test]
+async fn test_nutrition_plan() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let url = "/api/tools/training/nutrition-plan?goal=cut&sex=male&week_of=2024-04-03";
+    let resp_missing = server.get(url).add_header("Cookie", &cookie_str).await;
+    assert_eq!(resp_missing.status_code(), 400, "needs a body composition measurement");
+
+    let body = serde_json::json!({
+        "bodyWeightKg": 80.0,
+        "heightCm": 180.0,
+        "neckCm": 38.0,
+        "waistCm": 85.0,
+        "measuredAt": "2024-04-01T08:00:00Z"
+    });
+    let resp = server
+        .post("/api/tools/training/measurements")
+        .add_header("Cookie", &cookie_str)
+        .json(&body)
+        .await;
+    assert!(resp.status_code().is_success(), "Create measurement failed: {}", resp.text());
+
+    let resp = server.get(url).add_header("Cookie", &cookie_str).await;
+    assert!(resp.status_code().is_success(), "Nutrition plan failed: {}", resp.text());
+    let json: serde_json::Value = resp.json();
+    assert_eq!(json["weekStart"], "2024-04-01");
+    assert_eq!(json["weekEnd"], "2024-04-07");
+    assert_eq!(json["bodyComposition"]["method"], "navy");
+    assert_eq!(json["trainingWeek"]["trainingDays"], 0);
+
+    let plan = &json["plan"];
+    assert_eq!(plan["goal"], "cut");
+    let lean = json["bodyComposition"]["leanMassKg"].as_f64().unwrap();
+    assert!((plan["restDay"]["proteinG"].as_f64().unwrap() - 2.3 * lean).abs() < 1e-6);
+    assert!(!plan["explanation"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_custom_exercise_authoring() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let mut body = serde_json::json!({
+        "name": "Spider Curl",
+        "movementPattern": "isolation_upper",
+        "equipment": "dumbbell",
+        "primarySegmentsMoved": ["lower_arm"],
+        "romDegrees": 130.0,
+        "muscles": [
+            {"name": "biceps", "involvement": "primary"},
+            {"name": "forearms", "involvement": "secondary", "activationFraction": 0.4}
+        ]
+    });
+
+    // 1. Create
+    let resp = server
+        .post("/api/tools/training/exercises")
+        .add_header("Cookie", &cookie_str)
+        .json(&body)
+        .await;
+    assert_eq!(resp.status_code(), 201, "Create exercise failed: {}", resp.text());
+    let id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let url = format!("/api/tools/training/exercises/{id}");
+
+    let resp = server.get(&url).add_header("Cookie", &cookie_str).await;
+    let exercise: serde_json::Value = resp.json();
+    assert_eq!(exercise["isSystemDefault"], false);
+    assert_eq!(exercise["muscles"].as_array().unwrap().len(), 2);
+
+    // 2. Validation against CHECK enums and muscle_groups
+    let mut invalid = body.clone();
+    invalid["equipment"] = serde_json::json!("smith_machine");
+    let resp = server
+        .post("/api/tools/training/exercises")
+        .add_header("Cookie", &cookie_str)
+        .json(&invalid)
+        .await;
+    assert_eq!(resp.status_code(), 400);
+
+    let mut unknown = body.clone();
+    unknown["muscles"][1]["name"] = serde_json::json!("pinky");
+    let resp = server.put(&url).add_header("Cookie", &cookie_str).json(&unknown).await;
+    assert_eq!(resp.status_code(), 400);
+    assert!(resp.text().contains("pinky"));
+
+    // 3. Update replaces fields and muscles
+    body["name"] = serde_json::json!("Incline Spider Curl");
+    body["muscles"] = serde_json::json!([{"name": "Biceps", "involvement": "primary"}]);
+    let resp = server.put(&url).add_header("Cookie", &cookie_str).json(&body).await;
+    assert!(resp.status_code().is_success(), "Update exercise failed: {}", resp.text());
+    let exercise: serde_json::Value =
+        server.get(&url).add_header("Cookie", &cookie_str).await.json();
+    assert_eq!(exercise["name"], "Incline Spider Curl");
+    assert_eq!(exercise["muscles"].as_array().unwrap().len(), 1);
+    assert_eq!(exercise["muscles"][0]["name"], "biceps");
+
+    // Muscles differing only in case are duplicates
+    let mut duplicate = body.clone();
+    duplicate["muscles"] = serde_json::json!([
+        {"name": "Biceps", "involvement": "primary"},
+        {"name": "biceps", "involvement": "secondary"}
+    ]);
+    let resp = server.put(&url).add_header("Cookie", &cookie_str).json(&duplicate).await;
+    assert_eq!(resp.status_code(), 400, "{}", resp.text());
+
+    // A private exercise is hidden from other users
+    let (other_server, other_cookie, _) = setup_test_server().await.unwrap();
+    let resp = other_server.get(&url).add_header("Cookie", &other_cookie).await;
+    assert_eq!(resp.status_code(), 404);
+
+    // 4. Clone a system exercise; the original stays read-only
+    let list: serde_json::Value =
+        server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await.json();
+    let system = list["exercises"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["isSystemDefault"] == true)
+        .expect("seeded exercises")
+        .clone();
+    let system_url = format!("/api/tools/training/exercises/{}", system["id"].as_str().unwrap());
+    let resp = server.put(&system_url).add_header("Cookie", &cookie_str).json(&body).await;
+    assert_eq!(resp.status_code(), 404, "system exercises are not editable");
+
+    let resp = server.post(&format!("{system_url}/clone")).add_header("Cookie", &cookie_str).await;
+    assert_eq!(resp.status_code(), 201, "Clone failed: {}", resp.text());
+    let clone_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let original: serde_json::Value =
+        server.get(&system_url).add_header("Cookie", &cookie_str).await.json();
+    let clone: serde_json::Value = server
+        .get(&format!("/api/tools/training/exercises/{clone_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    assert_eq!(clone["isSystemDefault"], false);
+    assert_eq!(clone["movementPattern"], original["movementPattern"]);
+    assert_eq!(clone["muscles"], original["muscles"]);
+
+    // 5. Delete, once no plan refers to the exercise
+    let plan_id = server
+        .post("/api/tools/training/plans")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Arms"}))
+        .await
+        .json::<serde_json::Value>()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let resp = server
+        .post(&format!("/api/tools/training/plans/{plan_id}/exercises"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"exerciseId": id, "targetReps": 10}))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Add plan exercise failed: {}", resp.text());
+    let plan_exercise_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let resp = server.delete(&url).add_header("Cookie", &cookie_str).await;
+    assert_eq!(resp.status_code(), 409);
+    server
+        .delete(&format!("/api/tools/training/plans/{plan_id}/exercises/{plan_exercise_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await;
+
+    let resp = server.delete(&url).add_header("Cookie", &cookie_str).await;
+    assert!(resp.status_code().is_success(), "Delete exercise failed");
+    let resp = server.delete(&url).add_header("Cookie", &cookie_str).await;
+    assert_eq!(resp.status_code(), 404);
+    let resp = server.delete(&system_url).add_header("Cookie", &cookie_str).await;
+    assert_eq!(resp.status_code(), 404);
+}
+
+#[tokio::test]
+async fn test_personal_records() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let list: serde_json::Value =
+        server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await.json();
+    let exercise_id = list["exercises"][0]["id"].as_str().expect("seeded exercises").to_string();
+
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Heavy day"}))
+        .await;
+    let session_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let sets_url = format!("/api/tools/training/sessions/{session_id}/sets");
+
+    // 1. The first working set sets every record; warm-ups never do
+    let log = |weight: f64, reps: i32, warmup: bool| {
+        serde_json::json!({
+            "exerciseId": exercise_id,
+            "setNumber": 1,
+            "weightKg": weight,
+            "reps": reps,
+            "isWarmup": warmup
+        })
+    };
+    let resp =
+        server.post(&sets_url).add_header("Cookie", &cookie_str).json(&log(60.0, 10, true)).await;
+    assert_eq!(resp.status_code(), 201, "Log set failed: {}", resp.text());
+    assert_eq!(resp.json::<serde_json::Value>()["isRecord"], false);
+
+    let resp =
+        server.post(&sets_url).add_header("Cookie", &cookie_str).json(&log(100.0, 5, false)).await;
+    let json: serde_json::Value = resp.json();
+    assert_eq!(json["isRecord"], true);
+    assert_eq!(json["newRecords"].as_array().unwrap().len(), 4);
+
+    // 2. A lighter set with fewer reps only adds session volume, which is already the best
+    let resp =
+        server.post(&sets_url).add_header("Cookie", &cookie_str).json(&log(100.0, 3, false)).await;
+    assert_eq!(resp.json::<serde_json::Value>()["isRecord"], false);
+
+    // 3. Records and e1RM trend
+    let url = format!("/api/tools/training/exercises/{exercise_id}/records?formula=brzycki");
+    let json: serde_json::Value = server.get(&url).add_header("Cookie", &cookie_str).await.json();
+    let records = &json["records"];
+    assert_eq!(records["formula"], "brzycki");
+    assert_eq!(records["heaviestWeight"]["reps"], 5);
+    assert!((records["bestE1rm"]["e1rmKg"].as_f64().unwrap() - 112.5).abs() < 1e-9);
+    assert!((records["bestSessionVolume"]["volumeKg"].as_f64().unwrap() - 800.0).abs() < 1e-9);
+
+    let url = format!("/api/tools/training/exercises/{exercise_id}/e1rm-trend");
+    let json: serde_json::Value = server.get(&url).add_header("Cookie", &cookie_str).await.json();
+    assert_eq!(json["formula"], "epley");
+    assert_eq!(json["trend"].as_array().unwrap().len(), 1);
+
+    let resp = server
+        .get("/api/tools/training/exercises/not-a-uuid/records")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert_eq!(resp.status_code(), 400);
+}
+
+#[tokio::test]
+async fn test_plan_progression() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let list: serde_json::Value =
+        server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await.json();
+    let exercise_id = list["exercises"][0]["id"].as_str().expect("seeded exercises").to_string();
+
+    let resp = server
+        .post("/api/tools/training/plans")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Strength"}))
+        .await;
+    let plan_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let exercises_url = format!("/api/tools/training/plans/{plan_id}/exercises");
+
+    // 1. Invalid scheme settings are rejected up front
+    let resp = server
+        .post(&exercises_url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "exerciseId": exercise_id,
+            "targetReps": 8,
+            "progressionScheme": "double_progression",
+            "progressionConfig": {"repRangeMax": 6}
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 400);
+    let resp = server
+        .post(&exercises_url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "exerciseId": exercise_id,
+            "progressionScheme": "linear",
+            "progressionConfig": {"deloadPercentage": 150.0}
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 400);
+
+    let resp = server
+        .post(&exercises_url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "exerciseId": exercise_id,
+            "targetSets": 3,
+            "targetReps": 5,
+            "targetWeightKg": 100.0,
+            "progressionScheme": "linear"
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Add plan exercise failed: {}", resp.text());
+
+    // 2. Without history the plan's targets are prescribed
+    let next_url = format!("/api/tools/training/plans/{plan_id}/next");
+    let json: serde_json::Value =
+        server.get(&next_url).add_header("Cookie", &cookie_str).await.json();
+    let next = &json["exercises"][0]["next"];
+    assert_eq!(next["scheme"], "linear");
+    assert_eq!(next["sets"].as_array().unwrap().len(), 3);
+    assert_eq!(next["sets"][0]["weightKg"], 100.0);
+
+    // 3. A completed session at the target adds the increment
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Strength A", "planId": plan_id}))
+        .await;
+    let session_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    for set_number in 1..=3 {
+        let resp = server
+            .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+            .add_header("Cookie", &cookie_str)
+            .json(&serde_json::json!({
+                "exerciseId": exercise_id,
+                "setNumber": set_number,
+                "weightKg": 100.0,
+                "reps": 5
+            }))
+            .await;
+        assert_eq!(resp.status_code(), 201);
+    }
+    server
+        .put(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"status": "completed"}))
+        .await;
+
+    let json: serde_json::Value =
+        server.get(&next_url).add_header("Cookie", &cookie_str).await.json();
+    assert_eq!(json["exercises"][0]["sessionsLogged"], 1);
+    assert_eq!(json["exercises"][0]["next"]["sets"][0]["weightKg"], 102.5);
+}
+
+#[tokio::test]
+async fn test_session_from_plan() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let list: serde_json::Value =
+        server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await.json();
+    let first = list["exercises"][0]["id"].as_str().expect("seeded exercises").to_string();
+    let second = list["exercises"][1]["id"].as_str().expect("seeded exercises").to_string();
+
+    let resp = server
+        .post("/api/tools/training/plans")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Upper"}))
+        .await;
+    let plan_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    for (sort_order, exercise_id, sets) in [(0, &first, 3), (1, &second, 2)] {
+        server
+            .post(&format!("/api/tools/training/plans/{plan_id}/exercises"))
+            .add_header("Cookie", &cookie_str)
+            .json(&serde_json::json!({
+                "exerciseId": exercise_id,
+                "sortOrder": sort_order,
+                "targetSets": sets,
+                "targetReps": 8,
+                "targetWeightKg": 50.0,
+                "restSeconds": 120,
+                "supersetGroup": 1
+            }))
+            .await;
+    }
+
+    // 1. Starting from the plan pre-fills the prescribed sets in plan order
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Upper A", "planId": plan_id}))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Start session failed: {}", resp.text());
+    let json: serde_json::Value = resp.json();
+    let session_id = json["id"].as_str().unwrap().to_string();
+    let planned = json["plannedSets"].as_array().unwrap().clone();
+    assert_eq!(planned.len(), 5);
+    assert_eq!(planned[0]["exerciseId"], first.as_str());
+    assert_eq!(planned[4]["exerciseId"], second.as_str());
+    assert_eq!(planned[0]["restSeconds"], 120);
+    assert_eq!(planned[0]["supersetGroup"], 1);
+    assert_eq!(planned[0]["status"], "pending");
+
+    // 2. Check off one set, skip another, adjust a third
+    let resp = server
+        .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "exerciseId": first,
+            "setNumber": 1,
+            "weightKg": 50.0,
+            "reps": 7,
+            "plannedSetId": planned[0]["id"]
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Log set failed: {}", resp.text());
+
+    // A planned set is checked off once, and only by its own exercise
+    for (i, status) in [(0, 409), (4, 400)] {
+        let resp = server
+            .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+            .add_header("Cookie", &cookie_str)
+            .json(&serde_json::json!({
+                "exerciseId": first,
+                "setNumber": 2,
+                "weightKg": 50.0,
+                "reps": 7,
+                "plannedSetId": planned[i]["id"]
+            }))
+            .await;
+        assert_eq!(resp.status_code(), status, "{}", resp.text());
+    }
+
+    let planned_url = |i: usize| {
+        format!(
+            "/api/tools/training/sessions/{session_id}/planned-sets/{}",
+            planned[i]["id"].as_str().unwrap()
+        )
+    };
+    let resp = server
+        .put(&planned_url(4))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"status": "skipped"}))
+        .await;
+    assert!(resp.status_code().is_success(), "Skip failed: {}", resp.text());
+    let resp = server
+        .put(&planned_url(1))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"targetWeightKg": 45.0}))
+        .await;
+    assert!(resp.status_code().is_success());
+    for body in [
+        serde_json::json!({"targetWeightKg": 5000.0}),
+        serde_json::json!({"targetWeightKg": -5.0}),
+        serde_json::json!({"targetReps": 0}),
+    ] {
+        let resp = server.put(&planned_url(1)).add_header("Cookie", &cookie_str).json(&body).await;
+        assert_eq!(resp.status_code(), 400, "accepted {body}");
+    }
+    let resp = server
+        .put(&planned_url(1))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"status": "completed"}))
+        .await;
+    assert_eq!(resp.status_code(), 400);
+
+    let session: serde_json::Value = server
+        .get(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    let planned_now = session["plannedSets"].as_array().unwrap();
+    assert_eq!(planned_now[0]["status"], "completed");
+    assert_eq!(planned_now[1]["targetWeightKg"].as_str().unwrap().parse::<f64>().unwrap(), 45.0);
+
+    // 3. Summary compares planned and performed
+    let summary: serde_json::Value = server
+        .get(&format!("/api/tools/training/sessions/{session_id}/summary"))
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    let exercises = summary["exercises"].as_array().unwrap();
+    assert_eq!(exercises[0]["plannedSets"], 3);
+    assert_eq!(exercises[0]["completedSets"], 1);
+    assert_eq!(exercises[0]["performedReps"], 7);
+    assert_eq!(exercises[1]["skippedSets"], 1);
+    assert_eq!(summary["completionPercentage"], 20.0);
+}
+
+#[tokio::test]
+async fn test_training_program_schedule() {
+    use chrono::Datelike;
+
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let list: serde_json::Value =
+        server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await.json();
+    let exercise_id = list["exercises"][0]["id"].as_str().expect("seeded exercises").to_string();
+    let resp = server
+        .post("/api/tools/training/plans")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Full body"}))
+        .await;
+    let plan_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    server
+        .post(&format!("/api/tools/training/plans/{plan_id}/exercises"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "exerciseId": exercise_id,
+            "targetSets": 2,
+            "targetReps": 8,
+            "targetWeightKg": 100.0
+        }))
+        .await;
+
+    // 1. A program that started a week ago, training on today's weekday
+    let today = chrono::Utc::now().date_naive();
+    let body = serde_json::json!({
+        "name": "Hypertrophy block",
+        "startDate": today - chrono::Duration::days(7),
+        "weeks": 4,
+        "deloadWeek": 4,
+        "intensityRampPercentage": 10.0,
+        "schedule": [{"dayOfWeek": today.weekday().number_from_monday(), "planIds": [plan_id]}]
+    });
+    let resp = server
+        .post("/api/tools/training/programs")
+        .add_header("Cookie", &cookie_str)
+        .json(&body)
+        .await;
+    assert_eq!(resp.status_code(), 201, "Create program failed: {}", resp.text());
+    let program_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+
+    let mut invalid = body.clone();
+    invalid["deloadWeek"] = serde_json::json!(6);
+    let resp = server
+        .post("/api/tools/training/programs")
+        .add_header("Cookie", &cookie_str)
+        .json(&invalid)
+        .await;
+    assert_eq!(resp.status_code(), 400);
+
+    // 2. Today's workout is the plan at week 2 intensity
+    let json: serde_json::Value =
+        server.get("/api/tools/training/today").add_header("Cookie", &cookie_str).await.json();
+    let workout = &json["workouts"][0];
+    assert_eq!(workout["planId"], plan_id.as_str());
+    assert_eq!(workout["week"], 2);
+    assert_eq!(workout["exercises"][0]["sets"][0]["weightKg"], 110.0);
+    assert!(workout["sessionId"].is_null());
+
+    // 3. Starting the session through the program applies the same ramp
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Week 2", "planId": plan_id, "programId": program_id}))
+        .await;
+    let json: serde_json::Value = resp.json();
+    let session_id = json["id"].as_str().unwrap().to_string();
+    let weight = json["plannedSets"][0]["targetWeightKg"].as_str().unwrap();
+    assert_eq!(weight.parse::<f64>().unwrap(), 110.0);
+    server
+        .put(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"status": "completed"}))
+        .await;
+
+    // 4. Adherence: last week's workout was missed, today's is done
+    let json: serde_json::Value = server
+        .get(&format!("/api/tools/training/programs/{program_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    assert_eq!(json["calendar"].as_array().unwrap().len(), 4);
+    assert_eq!(json["adherence"]["scheduled"], 2);
+    assert_eq!(json["adherence"]["completed"], 1);
+    assert_eq!(json["adherence"]["upcoming"], 2);
+
+    let json: serde_json::Value =
+        server.get("/api/tools/training/today").add_header("Cookie", &cookie_str).await.json();
+    assert_eq!(json["workouts"][0]["sessionId"], session_id.as_str());
+
+    // 5. A deactivated program stops scheduling workouts but is kept
+    let resp = server
+        .patch(&format!("/api/tools/training/programs/{program_id}"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"isActive": false}))
+        .await;
+    assert!(resp.status_code().is_success(), "Deactivate failed: {}", resp.text());
+    let json: serde_json::Value =
+        server.get("/api/tools/training/today").add_header("Cookie", &cookie_str).await.json();
+    assert!(json["workouts"].as_array().unwrap().is_empty());
+    let json: serde_json::Value = server
+        .get(&format!("/api/tools/training/programs/{program_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    assert_eq!(json["isActive"], false);
+
+    // 6. Starting a session through a paused program keeps the plan's own targets
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Paused", "planId": plan_id, "programId": program_id}))
+        .await;
+    let json: serde_json::Value = resp.json();
+    let weight = json["plannedSets"][0]["targetWeightKg"].as_str().unwrap();
+    assert_eq!(weight.parse::<f64>().unwrap(), 100.0);
+
+    for body in [
+        serde_json::json!({"name": "Bad", "planId": "not-a-uuid"}),
+        serde_json::json!({"name": "Bad", "planId": plan_id, "programId": "not-a-uuid"}),
+    ] {
+        let resp = server
+            .post("/api/tools/training/sessions")
+            .add_header("Cookie", &cookie_str)
+            .json(&body)
+            .await;
+        assert_eq!(resp.status_code(), 400);
+    }
+}
+
+#[tokio::test]
+async fn test_stats_filters() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let list: serde_json::Value =
+        server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await.json();
+    let first = list["exercises"][0]["id"].as_str().expect("seeded exercises").to_string();
+    let second = list["exercises"][1]["id"].as_str().expect("seeded exercises").to_string();
+
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Stats day"}))
+        .await;
+    let session_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    for (exercise_id, weight, reps, warmup) in
+        [(&first, 40.0, 10, true), (&first, 100.0, 5, false), (&second, 20.0, 10, false)]
+    {
+        server
+            .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+            .add_header("Cookie", &cookie_str)
+            .json(&serde_json::json!({
+                "exerciseId": exercise_id,
+                "setNumber": 1,
+                "weightKg": weight,
+                "reps": reps,
+                "isWarmup": warmup
+            }))
+            .await;
+    }
+    server
+        .put(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"status": "completed"}))
+        .await;
+
+    let volume = |query: String| {
+        let server = &server;
+        let cookie_str = &cookie_str;
+        async move {
+            let resp = server
+                .get(&format!("/api/tools/training/stats/volume?{query}"))
+                .add_header("Cookie", cookie_str)
+                .await;
+            assert!(resp.status_code().is_success(), "Stats failed: {}", resp.text());
+            let json: serde_json::Value = resp.json();
+            json["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|d| d["totalVolumeKg"].as_str().unwrap().parse::<f64>().unwrap())
+                .sum::<f64>()
+        }
+    };
+
+    // 1. Everything, then without warm-ups, then one exercise
+    assert_eq!(volume(String::new()).await, 1100.0);
+    assert_eq!(volume("exclude_warmups=true".to_string()).await, 700.0);
+    assert_eq!(volume(format!("exercise_id={first}&exclude_warmups=true")).await, 500.0);
+
+    // 2. Date bounds and grouping
+    let today = chrono::Utc::now().date_naive();
+    let tomorrow = today + chrono::Duration::days(1);
+    assert_eq!(volume(format!("from={today}&to={today}&group_by=week")).await, 1100.0);
+    assert_eq!(volume(format!("from={tomorrow}")).await, 0.0);
+
+    let json: serde_json::Value = server
+        .get("/api/tools/training/stats/energy?group_by=month")
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    assert_eq!(json["groupBy"], "month");
+    let month_start = chrono::NaiveDate::from_ymd_opt(
+        chrono::Datelike::year(&today),
+        chrono::Datelike::month(&today),
+        1,
+    )
+    .unwrap();
+    assert_eq!(json["data"][0]["date"], month_start.to_string());
+
+    // 3. Invalid filters are rejected instead of ignored
+    let resp = server
+        .get("/api/tools/training/stats/muscle-energy?from=yesterday")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert_eq!(resp.status_code(), 400);
+    let resp = server
+        .get("/api/tools/training/stats/volume?plan_id=nope")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert_eq!(resp.status_code(), 400);
+
+    // 4. The session list takes the same filters
+    let sessions = |query: String| {
+        let server = &server;
+        let cookie_str = &cookie_str;
+        async move {
+            let resp = server
+                .get(&format!("/api/tools/training/sessions?{query}"))
+                .add_header("Cookie", cookie_str)
+                .await;
+            (resp.status_code(), resp.json::<serde_json::Value>())
+        }
+    };
+    let (status, json) = sessions(format!("from={today}&to={today}&exercise_id={second}")).await;
+    assert_eq!(status, 200);
+    assert_eq!(json["sessions"][0]["id"], session_id.as_str());
+    let (_, json) = sessions(format!("from={tomorrow}")).await;
+    assert!(json["sessions"].as_array().unwrap().is_empty());
+    let (status, _) = sessions("to=tomorrow".to_string()).await;
+    assert_eq!(status, 400);
+}
+
+#[tokio::test]
+async fn test_muscle_energy_attribution() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let list: serde_json::Value =
+        server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await.json();
+    let bench = list["exercises"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["name"] == "Barbell Bench Press (Flat)")
+        .expect("seeded bench press")["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Push"}))
+        .await;
+    let session_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let mut logged_energy = 0.0;
+    for set_number in 1..=2 {
+        let resp = server
+            .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+            .add_header("Cookie", &cookie_str)
+            .json(&serde_json::json!({
+                "exerciseId": bench,
+                "setNumber": set_number,
+                "weightKg": 100.0,
+                "reps": 5
+            }))
+            .await;
+        logged_energy += resp.json::<serde_json::Value>()["energyKcal"].as_f64().unwrap();
+    }
+    server
+        .put(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"status": "completed"}))
+        .await;
+
+    let resp = server
+        .get("/api/tools/training/stats/muscle-energy")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert!(resp.status_code().is_success(), "Muscle stats failed: {}", resp.text());
+    let json: serde_json::Value = resp.json();
+    let muscles = json["muscles"].as_array().unwrap();
+    let muscle = |name: &str| muscles.iter().find(|m| m["muscleName"] == name).unwrap().clone();
+
+    // 1. Energy is split across the muscles instead of counted once per muscle
+    let attributed: f64 = muscles.iter().map(|m| m["energyKcal"].as_f64().unwrap()).sum();
+    assert!((attributed - logged_energy).abs() < 0.01, "{attributed} vs {logged_energy}");
+    let chest = muscle("chest");
+    assert!((chest["energyKcal"].as_f64().unwrap() - logged_energy * 0.6 / 0.9).abs() < 0.01);
+
+    // 2. Primary muscles get full sets, secondary ones half
+    assert_eq!(chest["directSets"], 2);
+    assert_eq!(chest["sets"].as_f64().unwrap(), 2.0);
+    assert_eq!(chest["volumeKg"].as_f64().unwrap(), 1000.0);
+    let triceps = muscle("triceps");
+    assert_eq!(triceps["directSets"], 0);
+    assert_eq!(triceps["sets"].as_f64().unwrap(), 1.0);
+    assert_eq!(triceps["volumeKg"].as_f64().unwrap(), 500.0);
+
+    // 3. Weekly breakdown by default
+    assert_eq!(json["groupBy"], "week");
+    let today = chrono::Utc::now().date_naive();
+    let monday = today
+        - chrono::Duration::days(i64::from(
+            chrono::Datelike::weekday(&today).num_days_from_monday(),
+        ));
+    assert_eq!(json["periods"][0]["date"], monday.to_string());
+}
+
+#[tokio::test]
+async fn test_exercise_search() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+    let search = |query: &str| {
+        let server = &server;
+        let cookie_str = &cookie_str;
+        let url = format!("/api/tools/training/exercises?{query}");
+        async move {
+            let resp = server.get(&url).add_header("Cookie", cookie_str).await;
+            assert!(resp.status_code().is_success(), "Search failed: {}", resp.text());
+            resp.json::<serde_json::Value>()
+        }
+    };
+
+    // 1. Without paging parameters everything is listed; pages report the full count
+    let all = search("").await;
+    let total = all["total"].as_i64().unwrap();
+    assert_eq!(all["exercises"].as_array().unwrap().len() as i64, total);
+    assert!(all["limit"].is_null());
+    let page = search("limit=2&offset=2").await;
+    assert_eq!(page["total"], total);
+    assert_eq!(page["exercises"][0]["id"], all["exercises"][2]["id"]);
+    assert_eq!(search(&format!("offset={total}")).await["total"], total);
+
+    // 2. Name and full-text search are bound, not interpolated
+    let bench = search("search=bench%20press").await;
+    let names: Vec<&str> = bench["exercises"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+    assert!(!names.is_empty() && names.iter().all(|n| n.to_lowercase().contains("bench")));
+    assert_eq!(search("search=%27%3B%20DROP%20TABLE%20exercises%3B--").await["total"], 0);
+
+    // 3. Muscle and involvement filters
+    let chest = search("muscle=chest&involvement=primary&equipment=barbell").await;
+    let chest_names: Vec<&str> = chest["exercises"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+    assert!(chest_names.contains(&"Barbell Bench Press (Flat)"));
+    assert!(chest["exercises"].as_array().unwrap().iter().all(|e| e["equipment"] == "barbell"));
+    let triceps = search("muscle=triceps&involvement=primary").await;
+    assert!(!triceps["exercises"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|e| e["name"] == "Barbell Bench Press (Flat)"));
+
+    // 4. The equipment facet ignores its own filter
+    let barbell_count = chest["total"].as_i64().unwrap();
+    let facet = chest["facets"]["equipment"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["value"] == "barbell")
+        .unwrap();
+    assert_eq!(facet["count"].as_i64().unwrap(), barbell_count);
+    assert!(chest["facets"]["equipment"].as_array().unwrap().len() > 1);
+
+    let resp = server
+        .get("/api/tools/training/exercises?involvement=major")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert_eq!(resp.status_code(), 400);
+}
+
+#[tokio::test]
+async fn test_heart_rate_import() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let list: serde_json::Value =
+        server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await.json();
+    let exercise_id = list["exercises"][0]["id"].as_str().expect("seeded exercises").to_string();
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Monitored"}))
+        .await;
+    let session_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    server
+        .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "exerciseId": exercise_id,
+            "setNumber": 1,
+            "weightKg": 60.0,
+            "reps": 10
+        }))
+        .await;
+
+    // A TCX recording around the set: one sample every 5 s, climbing during it
+    let now = chrono::Utc::now();
+    let trackpoints: String = (-12..=4)
+        .map(|i| {
+            let time = (now + chrono::Duration::seconds(i * 5)).to_rfc3339();
+            format!(
+                "<Trackpoint><Time>{time}</Time><HeartRateBpm><Value>{}</Value></HeartRateBpm></Trackpoint>",
+                120 + i
+            )
+        })
+        .collect();
+    let tcx = format!(
+        "<?xml version=\"1.0\"?><TrainingCenterDatabase><Activities><Activity><Lap><Track>{trackpoints}</Track></Lap></Activity></Activities></TrainingCenterDatabase>"
+    );
+    let url = format!("/api/tools/training/sessions/{session_id}/heart-rate");
+
+    // 1. Import, detecting the format from the contents
+    let resp = server
+        .post(&format!("{url}?sex=male&age_years=30"))
+        .add_header("Cookie", &cookie_str)
+        .bytes(axum::body::Bytes::from(tcx))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Import failed: {}", resp.text());
+    let report: serde_json::Value = resp.json();
+    assert_eq!(report["source"], "tcx");
+    assert_eq!(report["sampleCount"], 17);
+    assert!(report["heartRateEnergyKcal"].as_f64().unwrap() > 0.0);
+    assert!(report["mechanicalEnergyKcal"].as_f64().unwrap() > 0.0);
+
+    // 2. The set covers the 30 s before it was logged
+    let report: serde_json::Value = server.get(&url).add_header("Cookie", &cookie_str).await.json();
+    let set = &report["sets"][0];
+    let max = set["maxHeartRate"].as_u64().expect("heart rate aligned with the set");
+    assert!((112..=121).contains(&max), "max {max}");
+    assert!(set["avgHeartRate"].as_f64().unwrap() <= max as f64);
+
+    // 3. A FIT file replaces the recording; unknown files are rejected
+    let resp = server
+        .post(&format!("{url}?sex=female&age_years=40&format=fit"))
+        .add_header("Cookie", &cookie_str)
+        .bytes(axum::body::Bytes::from_static(include_bytes!("fixtures/sample.fit")))
+        .await;
+    assert_eq!(resp.status_code(), 201, "FIT import failed: {}", resp.text());
+    assert_eq!(resp.json::<serde_json::Value>()["sampleCount"], 4);
+    let resp = server
+        .post(&format!("{url}?sex=male&age_years=30"))
+        .add_header("Cookie", &cookie_str)
+        .bytes(axum::body::Bytes::from_static(b"not a recording"))
+        .await;
+    assert_eq!(resp.status_code(), 400);
+
+    // 4. Recordings may exceed the 1 MB limit of the other routes
+    let resp = server
+        .post(&format!("{url}?sex=male&age_years=30"))
+        .add_header("Cookie", &cookie_str)
+        .bytes(axum::body::Bytes::from(vec![b' '; 4 * 1024 * 1024]))
+        .await;
+    assert_eq!(resp.status_code(), 400, "{}", resp.text());
+    let resp = server
+        .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+        .add_header("Cookie", &cookie_str)
+        .content_type("application/json")
+        .bytes(axum::body::Bytes::from(vec![b' '; 2 * 1024 * 1024]))
+        .await;
+    assert_eq!(resp.status_code(), 413);
+}
+
+#[tokio::test]
+async fn test_cardio_activities() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Conditioning"}))
+        .await;
+    let session_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let url = format!("/api/tools/training/sessions/{session_id}/cardio");
+
+    // 1. A run is estimated from its speed, a ride from its power
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "activity": "run",
+            "durationS": 1500,
+            "distanceM": 5000.0,
+            "avgHeartRate": 160,
+            "athleteMaxHeartRate": 190,
+            "zoneSeconds": [0, 60, 400, 900, 140]
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Run failed: {}", resp.text());
+    let run: serde_json::Value = resp.json();
+    assert_eq!(run["energyMethod"], "met");
+    assert_eq!(run["paceSPerKm"].as_f64().unwrap(), 300.0);
+    assert_eq!(run["avgHeartRateZone"], 4);
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"activity": "cycle", "durationS": 600, "avgPowerW": 200.0}))
+        .await;
+    let ride: serde_json::Value = resp.json();
+    assert_eq!(ride["energyMethod"], "power");
+    let cardio_kcal = run["energyKcal"].as_f64().unwrap() + ride["energyKcal"].as_f64().unwrap();
+
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"activity": "row", "durationS": 0}))
+        .await;
+    assert_eq!(resp.status_code(), 400);
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "activity": "run",
+            "durationS": 600,
+            "zoneSeconds": [0, 0, 0, 0, 3_000_000_000u32]
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 400);
+
+    // 2. Activities show in the session and count toward its energy
+    let session: serde_json::Value = server
+        .get(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    assert_eq!(session["cardio"].as_array().unwrap().len(), 2);
+    assert_eq!(session["cardio"][0]["zoneSeconds"][3], 900);
+    server
+        .put(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"status": "completed"}))
+        .await;
+    let session: serde_json::Value = server
+        .get(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    let total: f64 = session["totalEnergyKcal"].as_str().unwrap().parse().unwrap();
+    assert!((total - cardio_kcal).abs() < 0.02, "{total} vs {cardio_kcal}");
+
+    let stats: serde_json::Value = server
+        .get("/api/tools/training/stats/energy")
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    let stats_total: f64 = stats["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["totalEnergyKcal"].as_str().unwrap().parse::<f64>().unwrap())
+        .sum();
+    assert!((stats_total - cardio_kcal).abs() < 0.02);
+
+    // 3. Deleting an activity takes it out of the session total
+    let id = session["cardio"][1]["id"].as_str().unwrap();
+    let resp = server.delete(&format!("{url}/{id}")).add_header("Cookie", &cookie_str).await;
+    assert_eq!(resp.status_code(), 200);
+    let resp = server.delete(&format!("{url}/{id}")).add_header("Cookie", &cookie_str).await;
+    assert_eq!(resp.status_code(), 404);
+    let session: serde_json::Value = server
+        .get(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await
+        .json();
+    let total: f64 = session["totalEnergyKcal"].as_str().unwrap().parse().unwrap();
+    let run_kcal = run["energyKcal"].as_f64().unwrap();
+    assert!((total - run_kcal).abs() < 0.02, "{total} vs {run_kcal}");
+}
+
+#[tokio::test]
+async fn test_equipment_profiles() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    // 1. The public calculator takes an inline inventory
+    let resp = server
+        .post("/api/tools/training/calculate-plates")
+        .json(&serde_json::json!({
+            "targetWeight": 62.0,
+            "barWeight": 15.0,
+            "plates": [{"weight": 10.0, "pairs": 1}, {"weight": 5.0, "pairs": 2}]
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 200, "Calculate failed: {}", resp.text());
+    let calc: serde_json::Value = resp.json();
+    assert_eq!(calc["achievableWeight"].as_f64().unwrap(), 55.0);
+    assert_eq!(calc["direction"], "below");
+    assert!(calc["nearestAbove"].is_null());
+    // Targets the calculator used to clamp are rejected
+    let resp = server
+        .post("/api/tools/training/calculate-plates")
+        .json(&serde_json::json!({"targetWeight": 1500.0}))
+        .await;
+    assert_eq!(resp.status_code(), 400);
+    // Requests and responses written before units still work
+    let resp = server
+        .post("/api/tools/training/calculate-plates")
+        .json(&serde_json::json!({"targetWeightKg": 100.0}))
+        .await;
+    let calc: serde_json::Value = resp.json();
+    assert_eq!(calc["achievableWeight"].as_f64().unwrap(), 100.0);
+    assert_eq!(calc["achievableWeightKg"].as_f64().unwrap(), 100.0);
+    assert_eq!(calc["totalWeightKg"].as_f64().unwrap(), 100.0);
+    assert_eq!(calc["barbellWeightKg"].as_f64().unwrap(), 20.0);
+    assert_eq!(calc["platesPerSide"][0]["weightKg"], calc["platesPerSide"][0]["weight"]);
+
+    // 2. A home gym with a women's bar, an EZ bar and one pair of each plate
+    let resp = server
+        .post("/api/tools/training/equipment-profiles")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "name": "Home",
+            "bars": [{"name": "Women's bar", "weight": 15.0}, {"name": "EZ bar", "weight": 10.0}],
+            "plates": [
+                {"weight": 20.0, "pairs": 1},
+                {"weight": 10.0, "pairs": 1},
+                {"weight": 2.5, "pairs": 1},
+                {"weight": 0.5, "pairs": 2}
+            ],
+            "isDefault": true
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Create failed: {}", resp.text());
+    let profile_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+
+    let resp = server
+        .post("/api/tools/training/equipment-profiles")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "name": "Home",
+            "bars": [{"name": "Bar", "weight": 20.0}],
+            "plates": []
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 409);
+
+    // 3. 50 kg on the EZ bar is a 20 per side
+    let url = format!("/api/tools/training/equipment-profiles/{profile_id}/calculate-plates");
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"targetWeight": 50.0, "bar": "ez bar"}))
+        .await;
+    assert_eq!(resp.status_code(), 200, "Profile calculate failed: {}", resp.text());
+    let calc: serde_json::Value = resp.json();
+    assert_eq!(calc["bar"], "EZ bar");
+    assert_eq!(calc["direction"], "exact");
+    assert_eq!(calc["platesPerSide"][0]["weight"].as_f64().unwrap(), 20.0);
+
+    // 4. 44 kg on the women's bar needs 14.5 per side; 13.5 is closest, 20 is the next heavier
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"targetWeight": 44.0}))
+        .await;
+    let calc: serde_json::Value = resp.json();
+    assert_eq!(calc["barbellWeight"].as_f64().unwrap(), 15.0);
+    assert_eq!(calc["achievableWeight"].as_f64().unwrap(), 42.0);
+    assert_eq!(calc["direction"], "below");
+    assert_eq!(calc["nearestAbove"]["achievableWeight"].as_f64().unwrap(), 55.0);
+
+    // 5. Updates replace the inventory; deleting removes the profile
+    let resp = server
+        .put(&format!("/api/tools/training/equipment-profiles/{profile_id}"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "name": "Home",
+            "bars": [{"name": "Trap bar", "weight": 25.0}],
+            "plates": [{"weight": 25.0, "pairs": null}],
+            "isDefault": true
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 200, "Update failed: {}", resp.text());
+    let resp = server
+        .get("/api/tools/training/equipment-profiles")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    let list: serde_json::Value = resp.json();
+    assert_eq!(list["profiles"][0]["bars"][0]["name"], "Trap bar");
+    assert_eq!(list["profiles"][0]["isDefault"], true);
+
+    let resp = server
+        .delete(&format!("/api/tools/training/equipment-profiles/{profile_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await;
+    assert_eq!(resp.status_code(), 200);
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"targetWeight": 100.0}))
+        .await;
+    assert_eq!(resp.status_code(), 404);
+}
+
+#[tokio::test]
+async fn test_pound_units() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    // 1. kg until the user switches to pounds
+    let resp =
+        server.get("/api/tools/training/preferences").add_header("Cookie", &cookie_str).await;
+    assert_eq!(resp.json::<serde_json::Value>()["weightUnit"], "kg");
+    let resp = server
+        .put("/api/tools/training/preferences")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"weightUnit": "lb"}))
+        .await;
+    assert_eq!(resp.status_code(), 200, "Preferences failed: {}", resp.text());
+
+    // 2. Weights without a unit are in pounds and read back exactly
+    let resp = server
+        .post("/api/tools/training/measurements")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"bodyWeight": 181.0}))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Measurement failed: {}", resp.text());
+    let resp = server
+        .get("/api/tools/training/measurements/latest")
+        .add_header("Cookie", &cookie_str)
+        .await;
+    let latest: serde_json::Value = resp.json();
+    assert_eq!(latest["bodyWeight"].as_f64().unwrap(), 181.0);
+    assert_eq!(latest["bodyWeightKg"].as_str().unwrap().parse::<f64>().unwrap(), 82.1);
+
+    let resp = server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await;
+    let list: serde_json::Value = resp.json();
+    let exercise_id = list["exercises"][0]["id"].as_str().unwrap().to_string();
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Pounds"}))
+        .await;
+    let session_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let resp = server
+        .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"exerciseId": exercise_id, "setNumber": 1, "weight": 225.0, "reps": 5}))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Log set failed: {}", resp.text());
+    let set: serde_json::Value = resp.json();
+    assert_eq!(set["weightUnit"], "lb");
+    assert!((set["weightKg"].as_f64().unwrap() - 102.058_283_25).abs() < 1e-6);
+
+    // Exactly one of weightKg and weight is accepted
+    for body in [
+        serde_json::json!({"exerciseId": exercise_id, "setNumber": 9, "reps": 5}),
+        serde_json::json!({"exerciseId": exercise_id, "setNumber": 9, "weightKg": 100.0, "weight": 225.0, "reps": 5}),
+    ] {
+        let resp = server
+            .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+            .add_header("Cookie", &cookie_str)
+            .json(&body)
+            .await;
+        assert_eq!(resp.status_code(), 400, "{}", resp.text());
+    }
+
+    // A kg entry is shown in pounds, converted from what was typed
+    server
+        .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"exerciseId": exercise_id, "setNumber": 2, "weight": 100.0, "weightUnit": "kg", "reps": 5}))
+        .await;
+    let resp = server
+        .get(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await;
+    let session: serde_json::Value = resp.json();
+    assert_eq!(session["sets"][0]["weight"].as_f64().unwrap(), 225.0);
+    assert_eq!(session["sets"][0]["weightUnit"], "lb");
+    assert_eq!(session["sets"][0]["weightKg"].as_str().unwrap().parse::<f64>().unwrap(), 102.06);
+    assert_eq!(session["sets"][1]["weight"].as_f64().unwrap(), 220.46);
+    assert_eq!(session["weightUnit"], "lb");
+    assert!((session["totalVolume"].as_f64().unwrap() - (225.0 + 220.46) * 5.0).abs() < 1e-9);
+
+    // Records and the session list follow the preference too
+    let resp = server
+        .get(&format!("/api/tools/training/exercises/{exercise_id}/records"))
+        .add_header("Cookie", &cookie_str)
+        .await;
+    let records: serde_json::Value = resp.json();
+    assert_eq!(records["records"]["weightUnit"], "lb");
+    assert_eq!(records["records"]["heaviestWeight"]["weight"].as_f64().unwrap(), 225.0);
+    let resp = server.get("/api/tools/training/sessions").add_header("Cookie", &cookie_str).await;
+    let list: serde_json::Value = resp.json();
+    assert_eq!(list["weightUnit"], "lb");
+    assert!(
+        (list["sessions"][0]["totalVolume"].as_f64().unwrap() - (225.0 + 220.46) * 5.0).abs()
+            < 1e-9
+    );
+
+    // 3. Pound plates on a 45 lb bar
+    let resp = server
+        .post("/api/tools/training/calculate-plates")
+        .json(&serde_json::json!({"targetWeight": 315.0, "unit": "lb"}))
+        .await;
+    let calc: serde_json::Value = resp.json();
+    assert_eq!(calc["unit"], "lb");
+    assert_eq!(calc["direction"], "exact");
+    assert_eq!(calc["platesPerSide"][0]["weight"].as_f64().unwrap(), 45.0);
+    assert!((calc["platesPerSide"][0]["weightKg"].as_f64().unwrap() - 20.411_656_65).abs() < 1e-9);
+    assert_eq!(calc["platesPerSide"][0]["count"], 3);
+}
+
+#[tokio::test]
+async fn test_warmup_generator() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    let resp = server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await;
+    let list: serde_json::Value = resp.json();
+    let exercise_id = list["exercises"][0]["id"].as_str().unwrap().to_string();
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Heavy day"}))
+        .await;
+    let session_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let url = format!("/api/tools/training/sessions/{session_id}/warmup");
+
+    // 1. 100 kg x 5 ramps bar x 10, 40 x 5, 60 x 3, 80 x 1 with plates stacked on
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"exerciseId": exercise_id, "workingWeight": 100.0, "workingReps": 5}))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Warm-up failed: {}", resp.text());
+    let plan: serde_json::Value = resp.json();
+    let sets = plan["sets"].as_array().unwrap();
+    let loads: Vec<(f64, i64)> = sets
+        .iter()
+        .map(|s| (s["loadout"]["achievableWeight"].as_f64().unwrap(), s["reps"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(loads, vec![(20.0, 10), (40.0, 5), (60.0, 3), (80.0, 1)]);
+    assert!(sets[1..].iter().all(|s| s["plateChanges"] == 1));
+    assert_eq!(plan["working"]["achievableWeight"].as_f64().unwrap(), 100.0);
+
+    // 2. Generating again replaces the pending warm-ups
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"exerciseId": exercise_id, "workingWeight": 100.0, "workingReps": 5}))
+        .await;
+    let plan: serde_json::Value = resp.json();
+    let first_id = plan["sets"][0]["plannedSetId"].as_str().unwrap().to_string();
+    let resp = server
+        .get(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await;
+    let session: serde_json::Value = resp.json();
+    let planned = session["plannedSets"].as_array().unwrap();
+    assert_eq!(planned.len(), 4);
+    assert!(planned.iter().all(|p| p["isWarmup"] == true));
+    assert_eq!(planned[1]["plateLoadout"]["unit"], "kg");
+    assert_eq!(planned[1]["plateLoadout"]["plates"][0]["weight"].as_f64().unwrap(), 10.0);
+
+    // 3. Logging against a planned warm-up flags the set
+    let resp = server
+        .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({
+            "exerciseId": exercise_id, "setNumber": 1, "weightKg": 20.0, "reps": 10,
+            "plannedSetId": first_id
+        }))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Log set failed: {}", resp.text());
+    let resp = server
+        .get(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await;
+    let session: serde_json::Value = resp.json();
+    assert_eq!(session["sets"][0]["isWarmup"], true);
+    assert_eq!(session["plannedSets"][0]["status"], "completed");
+
+    // 4. Pound plates on request, and bad input
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"exerciseId": exercise_id, "workingWeight": 315.0, "workingReps": 3, "unit": "lb"}))
+        .await;
+    let plan: serde_json::Value = resp.json();
+    assert_eq!(plan["unit"], "lb");
+    assert_eq!(plan["barWeight"].as_f64().unwrap(), 45.0);
+    let resp = server
+        .post(&url)
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"exerciseId": exercise_id, "workingWeight": 100.0, "workingReps": 0}))
+        .await;
+    assert_eq!(resp.status_code(), 400);
+}
+
+#[tokio::test]
+async fn test_energy_recompute() {
+    let (server, cookie_str, _) = match setup_test_server().await {
+        Some(s) => s,
+        None => return,
+    };
+
+    // 1. A bodyweight set logged before any measurement uses the default body weight
+    let resp = server.get("/api/tools/training/exercises").add_header("Cookie", &cookie_str).await;
+    let list: serde_json::Value = resp.json();
+    let exercise_id = list["exercises"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["isBodyweight"] == true)
+        .expect("a bodyweight exercise")["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let resp = server
+        .post("/api/tools/training/sessions")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"name": "Recompute"}))
+        .await;
+    let session_id = resp.json::<serde_json::Value>()["id"].as_str().unwrap().to_string();
+    let resp = server
+        .post(&format!("/api/tools/training/sessions/{session_id}/sets"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"exerciseId": exercise_id, "setNumber": 1, "weightKg": 0.0, "reps": 10}))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Log set failed: {}", resp.text());
+    // ...as do a run and a heart-rate recording
+    let resp = server
+        .post(&format!("/api/tools/training/sessions/{session_id}/cardio"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"activity": "run", "durationS": 1500, "distanceM": 5000.0}))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Run failed: {}", resp.text());
+    let now = chrono::Utc::now();
+    let trackpoints: String = (0..10)
+        .map(|i| {
+            let time = (now + chrono::Duration::seconds(i * 5)).to_rfc3339();
+            format!("<Trackpoint><Time>{time}</Time><HeartRateBpm><Value>130</Value></HeartRateBpm></Trackpoint>")
+        })
+        .collect();
+    let heart_rate_url = format!("/api/tools/training/sessions/{session_id}/heart-rate");
+    let resp = server
+        .post(&format!("{heart_rate_url}?sex=male&age_years=30"))
+        .add_header("Cookie", &cookie_str)
+        .bytes(axum::body::Bytes::from(format!(
+            "<?xml version=\"1.0\"?><TrainingCenterDatabase><Activities><Activity><Lap><Track>{trackpoints}</Track></Lap></Activity></Activities></TrainingCenterDatabase>"
+        )))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Import failed: {}", resp.text());
+    server
+        .put(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"status": "completed"}))
+        .await;
+
+    // 2. The real body weight is much heavier
+    let resp = server
+        .post("/api/tools/training/measurements")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"bodyWeightKg": 120.0}))
+        .await;
+    assert_eq!(resp.status_code(), 201, "Measurement failed: {}", resp.text());
+
+    // 3. A dry run reports the delta and stores nothing
+    let resp = server
+        .post("/api/tools/training/recompute-energy")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"dryRun": true}))
+        .await;
+    assert_eq!(resp.status_code(), 200, "Recompute failed: {}", resp.text());
+    let dry: serde_json::Value = resp.json();
+    assert_eq!(dry["setsRecomputed"], 1);
+    assert_eq!(dry["setsChanged"], 1);
+    assert_eq!(dry["sessions"][0]["sessionId"], session_id.as_str());
+    assert_eq!(dry["cardioChanged"], 1);
+    assert_eq!(dry["heartRateChanged"], 1);
+    let dry_session = &dry["sessions"][0];
+    assert!(
+        dry_session["cardioEnergyKcal"].as_f64() > dry_session["previousCardioEnergyKcal"].as_f64()
+    );
+    assert!(
+        dry_session["heartRateEnergyKcal"].as_f64()
+            > dry_session["previousHeartRateEnergyKcal"].as_f64()
+    );
+    let delta = dry["deltaKcal"].as_f64().unwrap();
+    assert!(delta > 0.0, "heavier body should cost more energy: {dry}");
+    let session_kcal = |session: &serde_json::Value| {
+        session["totalEnergyKcal"].as_str().unwrap().parse::<f64>().unwrap()
+    };
+    let resp = server
+        .get(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await;
+    let before = session_kcal(&resp.json());
+    assert!((before - dry["previousEnergyKcal"].as_f64().unwrap()).abs() < 0.01);
+
+    // 4. The real run stores the new energy, version and session total
+    let resp = server
+        .post("/api/tools/training/recompute-energy")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"from": "2000-01-01"}))
+        .await;
+    let report: serde_json::Value = resp.json();
+    assert_eq!(report["dryRun"], false);
+    assert_eq!(report["energyModelVersion"], 1);
+    assert_eq!(report["deltaKcal"], dry["deltaKcal"]);
+    let resp = server
+        .get(&format!("/api/tools/training/sessions/{session_id}"))
+        .add_header("Cookie", &cookie_str)
+        .await;
+    let session: serde_json::Value = resp.json();
+    assert!((session_kcal(&session) - (before + delta)).abs() < 0.01);
+    let cardio_kcal = session["cardio"][0]["energyKcal"].as_str().unwrap().parse::<f64>().unwrap();
+    assert!((cardio_kcal - dry_session["cardioEnergyKcal"].as_f64().unwrap()).abs() < 0.01);
+    let heart_rate: serde_json::Value =
+        server.get(&heart_rate_url).add_header("Cookie", &cookie_str).await.json();
+    assert_eq!(heart_rate["heartRateEnergyKcal"], dry_session["heartRateEnergyKcal"]);
+
+    // 5. Nothing left to change; a range before the session has no sets
+    let resp = server
+        .post("/api/tools/training/recompute-energy")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({}))
+        .await;
+    let again: serde_json::Value = resp.json();
+    assert_eq!(again["setsChanged"], 0);
+    assert_eq!(again["cardioChanged"], 0);
+    assert_eq!(again["sessionsChanged"], 0);
+    let resp = server
+        .post("/api/tools/training/recompute-energy")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"to": "2000-01-01"}))
+        .await;
+    assert_eq!(resp.json::<serde_json::Value>()["setsRecomputed"], 0);
+    let resp = server
+        .post("/api/tools/training/recompute-energy")
+        .add_header("Cookie", &cookie_str)
+        .json(&serde_json::json!({"from": "yesterday"}))
+        .await;
+    assert_eq!(resp.status_code(), 400);
+}